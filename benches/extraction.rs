@@ -0,0 +1,63 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+use typedb_examples::{
+    extract_function_metadata, extract_functions_from_source,
+    extract_functions_from_source_with_stats, extract_signature,
+};
+
+/// Exercise files with a mix of scalar, stream, relation and reducing functions
+const SOURCES: &[(&str, &str)] = &[
+    (
+        "define-functions",
+        include_str!("../exercises/00d3-define-functions.tqls"),
+    ),
+    (
+        "functions-with-relations",
+        include_str!("../exercises/00d4-functions-with-relations.tqls"),
+    ),
+];
+
+fn bench_sources(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extract_functions_from_source");
+    for (name, source) in SOURCES {
+        group.throughput(Throughput::Bytes(source.len() as u64));
+        group.bench_function(*name, |b| {
+            b.iter(|| extract_functions_from_source(black_box(source)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_single_function(c: &mut Criterion) {
+    let (_, source) = SOURCES[0];
+    let function = extract_functions_from_source(source)
+        .expect("benchmark source should parse")
+        .remove(0);
+    let text = function.spans.definition.unwrap().slice(source);
+
+    c.bench_function("extract_function_metadata", |b| {
+        b.iter(|| extract_function_metadata(black_box(text)))
+    });
+    c.bench_function("extract_signature", |b| {
+        b.iter(|| extract_signature(black_box(text)))
+    });
+}
+
+/// Print where extraction spends its time once, alongside the timed benchmarks
+fn report_stats(_: &mut Criterion) {
+    for (name, source) in SOURCES {
+        if let Ok((_, stats)) = extract_functions_from_source_with_stats(source) {
+            println!(
+                "{}: {} functions, {} bytes, parse {:?}, extraction {:?}",
+                name,
+                stats.function_count,
+                stats.input_bytes,
+                stats.parse_time,
+                stats.extraction_time
+            );
+        }
+    }
+}
+
+criterion_group!(benches, report_stats, bench_sources, bench_single_function);
+criterion_main!(benches);
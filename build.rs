@@ -0,0 +1,5 @@
+fn main() {
+    // Node.js addons link against symbols the host process provides
+    #[cfg(feature = "napi")]
+    napi_build::setup();
+}
@@ -0,0 +1,39 @@
+use anyhow::{bail, Result};
+use arbitrary::{Arbitrary, Unstructured};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use typedb_examples::{extract_function_metadata, ArbitraryFunction};
+
+/// Generate random function definitions, extract each one and check the metadata matches
+/// what was generated.
+///
+/// Usage: `cargo run --example fuzz_extraction --features arbitrary -- [iterations] [seed]`
+fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    let iterations: usize = args.next().map_or(Ok(1000), |arg| arg.parse())?;
+    let seed: u64 = args.next().map_or(Ok(0), |arg| arg.parse())?;
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut failures = 0;
+    for iteration in 0..iterations {
+        let mut bytes = [0u8; 1024];
+        rng.fill(&mut bytes[..]);
+        let function = ArbitraryFunction::arbitrary(&mut Unstructured::new(&bytes))?;
+        let result = extract_function_metadata(&function.source)
+            .map_err(|error| error.to_string())
+            .and_then(|metadata| function.check(&metadata));
+        if let Err(error) = result {
+            failures += 1;
+            println!("❌ iteration {}: {}\n{}", iteration, error, function.source);
+        }
+    }
+
+    if failures > 0 {
+        bail!("{} of {} generated functions failed", failures, iterations);
+    }
+    println!(
+        "✅ {} generated functions extracted as expected",
+        iterations
+    );
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use std::fs;
-use typedb_examples::{extract_function_metadata, FunctionMetadata};
+use typedb_examples::{extract_function_metadata, to_metadata_json, FunctionMetadata};
 
 fn main() -> Result<()> {
     println!("TypeQL Function Metadata Extractor Example");
@@ -36,6 +36,11 @@ fn main() -> Result<()> {
                 $f1 isa friendship, links (friend: $p1, friend: $pm);
                 $f2 isa friendship, links (friend: $p2, friend: $pm);
             return { $pm };"#,
+        
+        r#"fun income_summary($taxpayer: taxpayer) -> double, integer:
+            match
+                $income (earner: $taxpayer) isa income_source, has amount $amt;
+            return sum($amt), count($amt);"#,
     ];
     
     let mut all_metadata = Vec::new();
@@ -46,8 +51,8 @@ fn main() -> Result<()> {
                 print_function_metadata(&metadata);
                 all_metadata.push(metadata);
             }
-            Err(e) => {
-                eprintln!("❌ Error parsing function: {}", e);
+            Err(error) => {
+                eprintln!("❌ Error parsing function:\n{}", error);
             }
         }
     }
@@ -62,44 +67,11 @@ fn main() -> Result<()> {
 
 fn print_function_metadata(metadata: &FunctionMetadata) {
     println!("{}", "=".repeat(60));
-    println!("Function Name: {}", metadata.name);
-    println!();
-    
-    println!("Parameters:");
-    if metadata.parameters.is_empty() {
-        println!("  (none)");
-    } else {
-        for param in &metadata.parameters {
-            println!("  ${}: {}", param.name, param.type_name);
-        }
-    }
-    println!();
-    
-    println!("Output: {}", metadata.output);
-    if let Some(ref return_expr) = metadata.return_expression {
-        println!("Return Expression: {}", return_expr);
-    }
-    println!();
-    
-    println!("Referenced Functions:");
-    if metadata.referenced_functions.is_empty() {
-        println!("  (none)");
-    } else {
-        for func_ref in &metadata.referenced_functions {
-            println!("  - {}", func_ref);
-        }
-    }
-    println!();
-    
-    println!("Code Block:");
-    for line in metadata.code_block.lines() {
-        println!("  {}", line);
-    }
-    println!();
+    println!("{}", metadata);
 }
 
 fn save_metadata_to_json(metadata: &[FunctionMetadata]) -> Result<()> {
-    let json_output = serde_json::to_string_pretty(metadata)?;
+    let json_output = to_metadata_json(metadata)?;
     fs::write("function_metadata.json", json_output)?;
     println!("{}", "=".repeat(60));
     println!("✅ All metadata successfully extracted and saved to function_metadata.json");
@@ -109,11 +81,7 @@ fn save_metadata_to_json(metadata: &[FunctionMetadata]) -> Result<()> {
     println!("📊 Summary:");
     println!("   Total functions parsed: {}", metadata.len());
     for meta in metadata {
-        println!("   - {} ({} params, returns {})", 
-            meta.name, 
-            meta.parameters.len(),
-            meta.output
-        );
+        println!("   - {}", meta.signature());
     }
     
     Ok(())
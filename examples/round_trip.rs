@@ -0,0 +1,61 @@
+use anyhow::Result;
+use serde_json::Value;
+use typedb_examples::{extract_function_metadata, FunctionMetadata};
+
+/// Parse a function, regenerate it with `to_typeql`, parse the output again and check that
+/// nothing but source positions changed.
+fn main() -> Result<()> {
+    let functions = [
+        r#"fun calculate_total_income($taxpayer: taxpayer) -> double:
+            match
+                $income (earner: $taxpayer, type: $type) isa income_source, has amount $amt;
+            return sum($amt);"#,
+        r#"fun get_tax_bracket($income: double, $year: tax_year, $status: filing_status) -> bracket_min, bracket_max, rate, base_tax:
+            match
+                (applicable_year: $year,
+                 applicable_status: $status,
+                 bracket: $bracket) isa tax_bracket_rule;
+                $bracket has bracket_min $min, has bracket_max $max, has rate $rate, has base_tax $base;
+                $income >= $min;
+                $income <= $max;
+            return first $min, $max, $rate, $base;"#,
+        r#"fun mutual_friends($p1: person, $p2: person) -> { person }:
+            match
+                $f1 isa friendship, links (friend: $p1, friend: $pm);
+                $f2 isa friendship, links (friend: $p2, friend: $pm);
+            return { $pm };"#,
+    ];
+
+    for function_text in functions {
+        let original = extract_function_metadata(function_text)?;
+        let generated = original.to_typeql();
+        let reparsed = extract_function_metadata(&generated)?;
+
+        assert_eq!(
+            comparable(&original)?,
+            comparable(&reparsed)?,
+            "round trip changed `{}`:\n{}",
+            original.name,
+            generated
+        );
+        println!("✅ {} round-trips\n{}\n", original.name, generated);
+    }
+
+    Ok(())
+}
+
+/// The metadata as JSON without spans, which legitimately move when the text is regenerated
+fn comparable(metadata: &FunctionMetadata) -> Result<Value> {
+    let mut value = serde_json::to_value(metadata)?;
+    if let Value::Object(fields) = &mut value {
+        fields.remove("spans");
+        if let Some(Value::Array(parameters)) = fields.get_mut("parameters") {
+            for parameter in parameters {
+                if let Value::Object(parameter) = parameter {
+                    parameter.remove("span");
+                }
+            }
+        }
+    }
+    Ok(value)
+}
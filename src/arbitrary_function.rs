@@ -0,0 +1,265 @@
+//! Random valid function definitions for fuzzing the extractor (requires the `arbitrary`
+//! feature)
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use std::fmt::Write;
+
+use crate::extract_function_metadata::FunctionMetadata;
+
+const WORDS: &[&str] = &[
+    "get", "total", "income", "tax", "friend", "count", "rate", "bracket", "year", "person",
+    "amount", "find", "all", "owed",
+];
+const LABELS: &[&str] = &[
+    "person",
+    "taxpayer",
+    "income-source",
+    "tax_year",
+    "employment",
+];
+const NUMERIC: &[&str] = &["integer", "double", "decimal"];
+const BUILTINS: &[&str] = &[
+    "integer", "double", "decimal", "string", "boolean", "date", "datetime", "duration",
+];
+
+/// A generated `fun` definition and what extracting it should give.
+///
+/// Definitions vary in parameters, stream and single outputs, optional output types,
+/// `first`/`last`/reduce/stream returns, calls to other functions, nested `or`, `not` and
+/// `try` blocks, and extra stages before the return.
+#[derive(Debug, Clone)]
+pub struct ArbitraryFunction {
+    pub source: String,
+    pub name: String,
+    /// Name without `$` and declared type of each parameter
+    pub parameters: Vec<(String, String)>,
+    pub stream: bool,
+    /// Output types as written, e.g. `double?`
+    pub output_types: Vec<String>,
+    /// Functions called in the body
+    pub calls: Vec<String>,
+}
+
+impl<'a> Arbitrary<'a> for ArbitraryFunction {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let name = identifier(u, "_")?;
+
+        let mut parameters: Vec<(String, String)> = Vec::new();
+        for _ in 0..u.int_in_range(0..=3)? {
+            let parameter = identifier(u, "-")?;
+            if parameters.iter().any(|(name, _)| *name == parameter) {
+                continue;
+            }
+            let type_name = if u.arbitrary()? {
+                u.choose(LABELS)?
+            } else {
+                u.choose(BUILTINS)?
+            };
+            parameters.push((parameter, type_name.to_string()));
+        }
+
+        let stream: bool = u.arbitrary()?;
+        let reduce = !stream && u.ratio(1, 4)?;
+        let mut output_types = Vec::new();
+        if reduce {
+            output_types.push(u.choose(NUMERIC)?.to_string());
+        } else {
+            for _ in 0..u.int_in_range(1..=3)? {
+                let mut type_name = if u.arbitrary()? {
+                    u.choose(LABELS)?.to_string()
+                } else {
+                    u.choose(NUMERIC)?.to_string()
+                };
+                if !stream && u.ratio(1, 4)? {
+                    type_name.push('?');
+                }
+                output_types.push(type_name);
+            }
+        }
+
+        let mut body = Vec::new();
+        for (parameter, type_name) in &parameters {
+            if LABELS.contains(&type_name.as_str()) && u.arbitrary()? {
+                body.push(format!("${} isa {};", parameter, type_name));
+            }
+        }
+        let mut calls = Vec::new();
+        let mut outputs = Vec::new();
+        for (position, type_name) in output_types.iter().enumerate() {
+            let var = format!("$out{}", position);
+            let label = type_name.trim_end_matches('?');
+            if LABELS.contains(&label) {
+                body.push(format!("{} isa {};", var, label));
+            } else if u.ratio(1, 3)? {
+                let callee = identifier(u, "_")?;
+                let arguments = numeric_parameters(&parameters);
+                body.push(format!(
+                    "let {} = {}({});",
+                    var,
+                    callee,
+                    arguments.join(", ")
+                ));
+                calls.push(callee);
+            } else {
+                let expression = expression(u, &parameters, 3)?;
+                body.push(format!("let {} = {};", var, expression));
+            }
+            outputs.push(var);
+        }
+        let mut fresh = 0;
+        for _ in 0..u.int_in_range(0..=2)? {
+            body.push(nested(u, &mut fresh, 2)?);
+        }
+
+        let mut stages = String::new();
+        if stream && !LABELS.contains(&output_types[0].as_str()) && u.arbitrary()? {
+            writeln!(stages, "    sort {};", outputs[0]).unwrap();
+        }
+        if stream && u.arbitrary()? {
+            writeln!(stages, "    limit {};", u.int_in_range(1..=100u32)?).unwrap();
+        }
+        let return_statement = if stream {
+            format!("return {{ {} }};", outputs.join(", "))
+        } else if reduce {
+            let reducer = u.choose(&["sum", "max", "min"])?;
+            format!("return {}({});", reducer, outputs[0])
+        } else {
+            let selector = u.choose(&["first", "last"])?;
+            format!("return {} {};", selector, outputs.join(", "))
+        };
+
+        let signature_parameters: Vec<String> = parameters
+            .iter()
+            .map(|(name, type_name)| format!("${}: {}", name, type_name))
+            .collect();
+        let output = if stream {
+            format!("{{ {} }}", output_types.join(", "))
+        } else {
+            output_types.join(", ")
+        };
+        let mut source = format!(
+            "fun {}({}) -> {}:\n    match\n",
+            name,
+            signature_parameters.join(", "),
+            output
+        );
+        for statement in body {
+            writeln!(source, "        {}", statement).unwrap();
+        }
+        source.push_str(&stages);
+        writeln!(source, "    {}", return_statement).unwrap();
+
+        Ok(ArbitraryFunction {
+            source,
+            name,
+            parameters,
+            stream,
+            output_types,
+            calls,
+        })
+    }
+}
+
+impl ArbitraryFunction {
+    /// Compare extracted metadata with what was generated, describing the first difference
+    pub fn check(&self, metadata: &FunctionMetadata) -> std::result::Result<(), String> {
+        if metadata.name != self.name {
+            return Err(format!(
+                "name {:?}, expected {:?}",
+                metadata.name, self.name
+            ));
+        }
+        let parameters: Vec<(String, String)> = metadata
+            .parameters
+            .iter()
+            .map(|parameter| (parameter.name.clone(), parameter.type_name.clone()))
+            .collect();
+        if parameters != self.parameters {
+            return Err(format!(
+                "parameters {:?}, expected {:?}",
+                parameters, self.parameters
+            ));
+        }
+        if metadata.output.is_stream() != self.stream {
+            return Err(format!("stream output is {}", metadata.output.is_stream()));
+        }
+        let output_types: Vec<String> = metadata
+            .output
+            .types()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        if output_types != self.output_types {
+            return Err(format!(
+                "output types {:?}, expected {:?}",
+                output_types, self.output_types
+            ));
+        }
+        for call in &self.calls {
+            if !metadata.referenced_functions.contains(call) {
+                return Err(format!("call to {} not referenced", call));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Words joined by `separator`, with a numeric suffix so it is never a keyword
+fn identifier(u: &mut Unstructured, separator: &str) -> Result<String> {
+    let mut words = Vec::new();
+    for _ in 0..u.int_in_range(1..=3)? {
+        words.push(*u.choose(WORDS)?);
+    }
+    Ok(format!(
+        "{}{}{}",
+        words.join(separator),
+        separator,
+        u.int_in_range(0..=99u8)?
+    ))
+}
+
+fn numeric_parameters(parameters: &[(String, String)]) -> Vec<String> {
+    parameters
+        .iter()
+        .filter(|(_, type_name)| NUMERIC.contains(&type_name.as_str()))
+        .map(|(name, _)| format!("${}", name))
+        .collect()
+}
+
+/// An arithmetic expression over numeric parameters and literals, nested up to `depth`
+fn expression(u: &mut Unstructured, parameters: &[(String, String)], depth: u32) -> Result<String> {
+    let operands = numeric_parameters(parameters);
+    if depth == 0 || u.ratio(1, 3)? {
+        return Ok(if !operands.is_empty() && u.arbitrary()? {
+            u.choose(&operands)?.clone()
+        } else {
+            u.int_in_range(0..=1000u32)?.to_string()
+        });
+    }
+    let left = expression(u, parameters, depth - 1)?;
+    let right = expression(u, parameters, depth - 1)?;
+    let operator = u.choose(&["+", "-", "*"])?;
+    Ok(format!("({} {} {})", left, operator, right))
+}
+
+/// An `or`, `not` or `try` block, nested up to `depth`, binding fresh variables
+fn nested(u: &mut Unstructured, fresh: &mut usize, depth: u32) -> Result<String> {
+    let branch = |u: &mut Unstructured, fresh: &mut usize| -> Result<String> {
+        if depth > 1 && u.arbitrary()? {
+            nested(u, fresh, depth - 1)
+        } else {
+            *fresh += 1;
+            Ok(format!("$nested{} isa {};", fresh, u.choose(LABELS)?))
+        }
+    };
+    let block = match u.int_in_range(0..=2u8)? {
+        0 => format!(
+            "{{ {} }} or {{ {} }};",
+            branch(u, fresh)?,
+            branch(u, fresh)?
+        ),
+        1 => format!("not {{ {} }};", branch(u, fresh)?),
+        _ => format!("try {{ {} }};", branch(u, fresh)?),
+    };
+    Ok(block)
+}
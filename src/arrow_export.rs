@@ -0,0 +1,128 @@
+//! Writing function metadata as Arrow record batches and Parquet files (requires the
+//! `parquet` feature)
+
+use arrow_array::builder::{BooleanBuilder, ListBuilder, StringBuilder, UInt32Builder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use parquet::arrow::ArrowWriter;
+use std::io::Write;
+use std::sync::Arc;
+
+use crate::error::ExtractError;
+use crate::extract_function_metadata::FunctionMetadata;
+
+/// Arrow schema of the table written by [`write_parquet`], one row per function
+pub fn function_schema() -> SchemaRef {
+    let list = |name: &str| {
+        Field::new(
+            name,
+            DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+            false,
+        )
+    };
+    Arc::new(Schema::new(vec![
+        Field::new("name", DataType::Utf8, false),
+        Field::new("source_file", DataType::Utf8, true),
+        Field::new("doc", DataType::Utf8, true),
+        Field::new("deprecated", DataType::Boolean, false),
+        Field::new("parameter_count", DataType::UInt32, false),
+        list("parameter_names"),
+        list("parameter_types"),
+        Field::new("output", DataType::Utf8, false),
+        Field::new("output_is_stream", DataType::Boolean, false),
+        Field::new("return_expression", DataType::Utf8, false),
+        list("referenced_functions"),
+        list("referenced_types"),
+        Field::new("code_block", DataType::Utf8, false),
+    ]))
+}
+
+/// Convert `functions` into a single record batch with the [`function_schema`] columns
+pub fn to_record_batch(functions: &[FunctionMetadata]) -> Result<RecordBatch, ExtractError> {
+    let strings = |values: &dyn Fn(&FunctionMetadata) -> String| -> ArrayRef {
+        let mut builder = StringBuilder::new();
+        for function in functions {
+            builder.append_value(values(function));
+        }
+        Arc::new(builder.finish())
+    };
+    let lists = |values: &dyn Fn(&FunctionMetadata) -> Vec<&str>| -> ArrayRef {
+        let mut builder = ListBuilder::new(StringBuilder::new());
+        for function in functions {
+            for value in values(function) {
+                builder.values().append_value(value);
+            }
+            builder.append(true);
+        }
+        Arc::new(builder.finish())
+    };
+    let booleans = |values: &dyn Fn(&FunctionMetadata) -> bool| -> ArrayRef {
+        let mut builder = BooleanBuilder::new();
+        for function in functions {
+            builder.append_value(values(function));
+        }
+        Arc::new(builder.finish())
+    };
+
+    let mut source_file = StringBuilder::new();
+    let mut doc = StringBuilder::new();
+    let mut parameter_count = UInt32Builder::new();
+    for function in functions {
+        source_file.append_option(function.source_file.as_deref());
+        doc.append_option(function.doc.as_deref());
+        parameter_count.append_value(function.parameters.len() as u32);
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        strings(&|function| function.name.clone()),
+        Arc::new(source_file.finish()),
+        Arc::new(doc.finish()),
+        booleans(&FunctionMetadata::is_deprecated),
+        Arc::new(parameter_count.finish()),
+        lists(&|function| {
+            function
+                .parameters
+                .iter()
+                .map(|parameter| parameter.name.as_str())
+                .collect()
+        }),
+        lists(&|function| {
+            function
+                .parameters
+                .iter()
+                .map(|parameter| parameter.type_name.as_str())
+                .collect()
+        }),
+        strings(&|function| function.output.to_string()),
+        booleans(&|function| function.output.is_stream()),
+        strings(&|function| function.return_expression.to_string()),
+        lists(&|function| {
+            function
+                .referenced_functions
+                .iter()
+                .map(String::as_str)
+                .collect()
+        }),
+        lists(&|function| {
+            function
+                .referenced_types
+                .iter()
+                .map(String::as_str)
+                .collect()
+        }),
+        strings(&|function| function.code_block.clone()),
+    ];
+    Ok(RecordBatch::try_new(function_schema(), columns)?)
+}
+
+/// Write `functions` to `writer` as a Parquet file, one row per function
+pub fn write_parquet<W: Write + Send>(
+    functions: &[FunctionMetadata],
+    writer: W,
+) -> Result<(), ExtractError> {
+    let batch = to_record_batch(functions)?;
+    let mut writer = ArrowWriter::try_new(writer, batch.schema(), None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostic::Diagnostic;
+
+/// Findings accepted when a linter was adopted, so that only new ones are reported.
+///
+/// Entries are matched on rule code, function and message, not on position, so editing
+/// unrelated code above a known finding does not make it new again. Saved baselines are
+/// sorted so they diff cleanly.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    pub findings: Vec<BaselineEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub code: String,
+    pub function: Option<String>,
+    pub message: String,
+}
+
+impl BaselineEntry {
+    fn of(diagnostic: &Diagnostic) -> Self {
+        BaselineEntry {
+            code: diagnostic.code.clone(),
+            function: diagnostic.function.clone(),
+            message: diagnostic.message.clone(),
+        }
+    }
+}
+
+impl Baseline {
+    /// Record every unsuppressed diagnostic
+    pub fn from_diagnostics(diagnostics: &[Diagnostic]) -> Self {
+        let mut findings: Vec<BaselineEntry> = diagnostics
+            .iter()
+            .filter(|diagnostic| !diagnostic.suppressed)
+            .map(BaselineEntry::of)
+            .collect();
+        findings.sort();
+        Baseline { findings }
+    }
+
+    /// The diagnostics not covered by the baseline.
+    ///
+    /// Each entry covers one diagnostic, so a finding that now occurs more often than when
+    /// the baseline was recorded is reported for the extra occurrences.
+    pub fn new_findings(&self, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        let mut remaining: Vec<&BaselineEntry> = self.findings.iter().collect();
+        diagnostics
+            .into_iter()
+            .filter(|diagnostic| {
+                let entry = BaselineEntry::of(diagnostic);
+                match remaining.iter().position(|known| **known == entry) {
+                    Some(position) => {
+                        remaining.swap_remove(position);
+                        false
+                    }
+                    None => true,
+                }
+            })
+            .collect()
+    }
+
+    /// Entries with no matching diagnostic any more, i.e. findings that have been fixed
+    pub fn fixed_findings(&self, diagnostics: &[Diagnostic]) -> Vec<&BaselineEntry> {
+        let mut current: Vec<BaselineEntry> = diagnostics.iter().map(BaselineEntry::of).collect();
+        self.findings
+            .iter()
+            .filter(
+                |entry| match current.iter().position(|found| found == *entry) {
+                    Some(position) => {
+                        current.swap_remove(position);
+                        false
+                    }
+                    None => true,
+                },
+            )
+            .collect()
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
@@ -0,0 +1,1316 @@
+use anyhow::{anyhow, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::fs;
+use std::io;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+use typedb_examples::{
+    bundle, catalog_inserts, check_dialect, check_snapshot, convert_rules, detect_dialect,
+    diff_schemas, expand_paths, explain, extract_from_file, extract_from_paths, extract_literals,
+    extract_query_metadata, extract_rules, extract_schema_metadata, find_duplicates,
+    generate_inserts, generate_synthetic_data, inline_function, literal_to_parameter,
+    plan_migration, recursion_report, render_call_graph_html_with_namespaces, render_csv,
+    render_graphql_schema, render_markdown, render_markdown_with_namespaces, render_python_client,
+    render_rust_client, render_rust_types, render_sarif, render_typescript_definitions,
+    to_metadata_json, tree_shake, typeql_files, watch, write_json_line, Baseline, CallGraph,
+    DataMapping, Dialect, FunctionMetadata, FunctionRegistry, IncompatibleTypes, LintConfig,
+    Linter, NamespaceConfig, Namespaces, SchemaMetadata, Severity, SnapshotMode, SyntheticConfig,
+    UnknownTypes, WatchEvent, CATALOG_SCHEMA, DEFAULT_SIMILARITY_THRESHOLD,
+};
+
+/// Inspect TypeQL function libraries
+#[derive(Parser)]
+#[command(name = "typedb-meta", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Extract metadata for every function in the given files, directories or globs
+    Extract {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        #[arg(long, value_enum, default_value_t = ExtractFormat::Json)]
+        format: ExtractFormat,
+        /// Only functions with this category, from `# @tag: ...` doc comments
+        #[arg(long)]
+        tag: Option<String>,
+        /// Namespaces to group the Markdown docs by
+        #[command(flatten)]
+        namespaces: NamespaceArgs,
+    },
+    /// Extract stages, variables, types and called functions of standalone queries, one
+    /// query per file
+    Query {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Show the call graph: direct calls, definition order and cycles
+    Graph {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Print the graph in Graphviz DOT format
+        #[arg(long, conflicts_with_all = ["mermaid", "html"])]
+        dot: bool,
+        /// Print the graph as a Mermaid flowchart
+        #[arg(long, conflicts_with = "html")]
+        mermaid: bool,
+        /// Print a standalone HTML page with a zoomable graph and details for each function
+        #[arg(long)]
+        html: bool,
+        /// List the functions that call FUNCTION, directly or transitively
+        #[arg(long, value_name = "FUNCTION", conflicts_with_all = ["dot", "mermaid", "html"])]
+        callers_of: Option<String>,
+        /// List the chains of calls from one function to another
+        #[arg(
+            long,
+            num_args = 2,
+            value_names = ["FROM", "TO"],
+            conflicts_with_all = ["dot", "mermaid", "html", "callers_of"]
+        )]
+        path: Vec<String>,
+        /// Longest chain of calls `--path` lists
+        #[arg(long, default_value_t = 10, requires = "path")]
+        max_depth: usize,
+        /// Namespaces to cluster the graph by
+        #[command(flatten)]
+        namespaces: NamespaceArgs,
+    },
+    /// Lint functions for undefined calls, unbound variables, missing return values, unused
+    /// parameters, calls to deprecated functions, naming conventions and, given a schema,
+    /// unknown types and parameters matched as types, attributes or roles they cannot have
+    Validate {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Schema file with the `define` blocks the functions run against
+        #[arg(long)]
+        schema: Option<PathBuf>,
+        /// Functions defined outside the given files that may be called
+        #[arg(long = "allow", value_name = "FUNCTION")]
+        allowed: Vec<String>,
+        /// JSON lint configuration, e.g.
+        /// `{"disabled": [rule, ...], "severities": {rule: "info"}, "verb_prefixes": ["get_"]}`
+        #[arg(long)]
+        config: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = ValidateFormat::Text)]
+        format: ValidateFormat,
+        /// Only report findings that are not recorded in this baseline file
+        #[arg(long, conflicts_with = "write_baseline")]
+        baseline: Option<PathBuf>,
+        /// Record the current findings to this baseline file instead of reporting them
+        #[arg(long, value_name = "FILE")]
+        write_baseline: Option<PathBuf>,
+    },
+    /// Report functions that duplicate each other up to variable names and statement order,
+    /// and pairs that share most of their body
+    Duplicates {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// Share of body statements, from 0 to 1, from which a pair is reported as similar
+        #[arg(long, default_value_t = DEFAULT_SIMILARITY_THRESHOLD)]
+        threshold: f64,
+    },
+    /// Explore functions interactively: paste definitions to see their metadata, lint
+    /// findings and explanation, and query the functions entered so far
+    Repl {
+        /// Files, directories or globs with functions to start the session with
+        paths: Vec<String>,
+        /// JSON lint configuration, as for `validate`
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+    /// Describe what each function does in plain English
+    Explain {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Find functions by a few words from their name, doc comment or body, best first
+    Search {
+        /// Words describing the function, e.g. `bracket rates`
+        query: String,
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// Print at most this many functions
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Print a content hash per function that only changes when the function does, not
+    /// when it is reformatted or its comments are edited
+    Fingerprint {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Print the catalog meta-schema followed by `insert` queries storing the metadata of
+    /// every function, so the library can be queried with TypeQL
+    Catalog {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Generate client bindings calling each function
+    Codegen {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        #[arg(long, value_enum)]
+        language: CodegenLanguage,
+    },
+    /// Generate a GraphQL schema with a query field per function
+    Graphql {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// Schema file, to generate object types for entities and relations
+        #[arg(long)]
+        schema: Option<PathBuf>,
+    },
+    /// Generate Rust types with serde derives for the types in a schema
+    Types {
+        /// Schema file with the `define` blocks
+        schema: PathBuf,
+    },
+    /// Draw the entities, relations, roles, attributes and inheritance of a schema as a
+    /// Graphviz DOT entity-relationship diagram
+    Diagram {
+        /// Schema file with the `define` blocks
+        schema: PathBuf,
+        /// Print a Mermaid ER diagram instead
+        #[arg(long)]
+        mermaid: bool,
+    },
+    /// Generate `insert` queries from JSON records
+    Inserts {
+        /// Schema file with the `define` blocks
+        #[arg(long)]
+        schema: PathBuf,
+        /// JSON mapping of each collection to its type, key, attributes and roles, e.g.
+        /// `{"people": {"type": "person", "key": "id", "attributes": {"id": "person-id"}}}`
+        #[arg(long)]
+        mapping: PathBuf,
+        /// JSON object of collections, each an array of records
+        data: PathBuf,
+    },
+    /// Generate an `insert` query with random instances of every type in a schema
+    Synthesize {
+        /// Schema file with the `define` blocks
+        schema: PathBuf,
+        /// JSON configuration, e.g.
+        /// `{"default_count": 10, "counts": {type: 5}, "values": {attribute: {"min": 0, "max": 100}}}`
+        #[arg(long)]
+        config: Option<PathBuf>,
+        /// Seed for the random generator, overriding the configuration
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Compare the metadata of each file with its golden file, `<golden>/<file name>.json`;
+    /// fails on any difference
+    Snapshot {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Directory of golden files
+        #[arg(long)]
+        golden: PathBuf,
+        /// Write the golden files instead of comparing
+        #[arg(long)]
+        update: bool,
+    },
+    /// Print every function in one `define` document, each after the functions it calls
+    Bundle {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Only include FUNCTION and the functions it needs; may be repeated
+        #[arg(long = "entry", value_name = "FUNCTION")]
+        entries: Vec<String>,
+    },
+    /// List the functions no other function calls and that are not entry points; fails if
+    /// there are any
+    Orphans {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Function called by queries rather than other functions; may be repeated
+        #[arg(long = "entry", value_name = "FUNCTION")]
+        entries: Vec<String>,
+    },
+    /// List recursive and mutually recursive functions; fails if any recurse through a
+    /// negation or an aggregate, which TypeDB cannot stratify
+    Recursion {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Print the recursive groups as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a function with its `let ... in` calls to another function replaced by that
+    /// function's body
+    Inline {
+        /// Function whose body is inlined
+        function: String,
+        /// Function calling it, to rewrite
+        #[arg(long, value_name = "FUNCTION")]
+        into: String,
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Print a define/redefine/undefine script migrating one function corpus to another
+    Migrate {
+        /// Directory (or file) with the current functions
+        #[arg(long)]
+        from: PathBuf,
+        /// Directory (or file) with the target functions
+        #[arg(long)]
+        to: PathBuf,
+    },
+    /// Report TypeQL 2.x constructs, such as rules and `match ... get` queries, with their
+    /// TypeQL 3 replacements; fails if any file is TypeQL 2.x
+    Dialect {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Convert the TypeQL 2.x rules in a file into TypeQL 3 functions, printed as one
+    /// `define` document; what could not be converted is reported on stderr
+    ConvertRules {
+        file: PathBuf,
+        /// Schema file, to type role players the rules do not match with `isa`
+        #[arg(long)]
+        schema: Option<PathBuf>,
+    },
+    /// Report types added, removed or changed between two schemas and the functions
+    /// affected; fails on breaking changes
+    SchemaDiff {
+        /// Schema file before the change
+        old: PathBuf,
+        /// Schema file after the change
+        new: PathBuf,
+        /// Files, directories or globs with the functions that run against the schema
+        paths: Vec<String>,
+        /// Print the differences and affected functions as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the namespaces of the functions in the given files, directories or globs
+    Namespaces {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        #[command(flatten)]
+        namespaces: NamespaceArgs,
+    },
+    /// List the literal values hardcoded in function bodies, with where they are and the
+    /// statement using them
+    Literals {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// Print the literals as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a function with one of its literals turned into a new parameter, and the
+    /// calls to update so they keep passing the literal
+    ExtractParameter {
+        /// File defining the function
+        file: PathBuf,
+        /// Position of the literal, as printed by `literals`
+        #[arg(long, value_name = "LINE:COLUMN")]
+        at: String,
+        /// Name of the new parameter
+        #[arg(long)]
+        name: String,
+        /// Files, directories or globs with the functions calling it; defaults to `file`
+        paths: Vec<String>,
+    },
+    /// Watch files, directories or globs and print functions as they change
+    Watch {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// How often to check for modified files, in milliseconds
+        #[arg(long, default_value_t = 500)]
+        interval: u64,
+    },
+    /// Print the JSON Schema of the metadata produced by `extract --format json`
+    #[cfg(feature = "json-schema")]
+    Schema,
+    /// Write metadata for every function in the given files or directories to a Parquet file
+    #[cfg(feature = "parquet")]
+    Parquet {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        /// File to write
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Update a SQLite store with the functions in the given files, directories or globs,
+    /// extracting only files that changed since the last run, and print the stored functions
+    #[cfg(feature = "sqlite")]
+    Index {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// Store file, created if missing
+        #[arg(long)]
+        store: PathBuf,
+        #[arg(long, value_enum, default_value_t = ExtractFormat::Json)]
+        format: ExtractFormat,
+    },
+    /// Extract metadata for every function defined in a database on a TypeDB server
+    #[cfg(feature = "client")]
+    Pull {
+        /// Database to read functions from
+        database: String,
+        #[command(flatten)]
+        server: ServerArgs,
+        #[arg(long, value_enum, default_value_t = ExtractFormat::Json)]
+        format: ExtractFormat,
+    },
+    /// Define, redefine and undefine functions so a database matches the given files
+    #[cfg(feature = "client")]
+    Sync {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Database to update
+        #[arg(long)]
+        database: String,
+        #[command(flatten)]
+        server: ServerArgs,
+        /// Print the planned statements without running them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Report functions that are missing, extra or changed in a database; fails on drift
+    #[cfg(feature = "client")]
+    Drift {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Database to compare against
+        #[arg(long)]
+        database: String,
+        #[command(flatten)]
+        server: ServerArgs,
+    },
+    /// Replace the function catalog in a database with the metadata of the given files,
+    /// directories or globs
+    #[cfg(feature = "client")]
+    Publish {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// Database to store the catalog in
+        #[arg(long)]
+        database: String,
+        #[command(flatten)]
+        server: ServerArgs,
+    },
+    /// Call functions deployed to a database with sample arguments and check their
+    /// answers; fails if any test fails
+    #[cfg(feature = "client")]
+    Test {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// JSON array of tests, e.g.
+        /// `[{"function": f, "arguments": {param: value}, "expected": [{var: value}]}]`
+        #[arg(long)]
+        tests: PathBuf,
+        /// Database to run the functions in
+        #[arg(long)]
+        database: String,
+        #[command(flatten)]
+        server: ServerArgs,
+    },
+    /// Run a language server on stdio, for hover, go to definition and diagnostics in
+    /// editors
+    #[cfg(feature = "lsp")]
+    Lsp {
+        /// JSON lint configuration, as for `validate`
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+    /// Run a Model Context Protocol server on stdio, letting coding assistants look up
+    /// and search the functions in the given files, directories or glob patterns
+    #[cfg(feature = "mcp")]
+    Mcp {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+}
+
+#[derive(clap::Args)]
+struct NamespaceArgs {
+    /// Group functions whose names start with PREFIX, directly or after a verb such as
+    /// `get_`, in a namespace named after it
+    #[arg(long = "namespace-prefix", value_name = "PREFIX")]
+    prefixes: Vec<String>,
+    /// Group the functions no prefix matches by the directory of their file
+    #[arg(long)]
+    namespace_by_directory: bool,
+    /// Add the name prefixes shared by at least N functions
+    #[arg(long, value_name = "N")]
+    infer_namespaces: Option<usize>,
+}
+
+impl NamespaceArgs {
+    fn namespaces(&self, functions: &[FunctionMetadata]) -> Namespaces {
+        let mut config = NamespaceConfig {
+            prefixes: self.prefixes.clone(),
+            by_directory: self.namespace_by_directory,
+        };
+        if let Some(min_functions) = self.infer_namespaces {
+            for prefix in NamespaceConfig::infer(functions, min_functions).prefixes {
+                if !config.prefixes.contains(&prefix) {
+                    config.prefixes.push(prefix);
+                }
+            }
+        }
+        config.assign(functions)
+    }
+}
+
+#[cfg(feature = "client")]
+#[derive(clap::Args)]
+struct ServerArgs {
+    /// Server address as host:port
+    #[arg(long, default_value = "localhost:1729")]
+    address: String,
+    #[arg(long, default_value = "admin")]
+    username: String,
+    #[arg(long, env = "TYPEDB_PASSWORD", default_value = "password")]
+    password: String,
+    /// Connect over TLS
+    #[arg(long)]
+    tls: bool,
+}
+
+#[cfg(feature = "client")]
+impl ServerArgs {
+    fn config(&self) -> typedb_examples::ServerConfig {
+        typedb_examples::ServerConfig {
+            address: self.address.clone(),
+            username: self.username.clone(),
+            password: self.password.clone(),
+            tls: self.tls,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExtractFormat {
+    Json,
+    /// One JSON object per function per line, written as files are processed
+    JsonLines,
+    Markdown,
+    /// One row per function with its parameter types, output and call count
+    Csv,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ValidateFormat {
+    /// One line per finding on stderr
+    Text,
+    /// A SARIF 2.1.0 log on stdout, for code review annotations
+    Sarif,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum CodegenLanguage {
+    /// Structs and query builders for the TypeDB Rust driver
+    Rust,
+    /// A `.d.ts` declaration file with parameter and answer interfaces
+    Typescript,
+    /// Dataclasses, TypedDicts and query helpers for the TypeDB Python driver
+    Python,
+}
+
+fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Extract {
+            paths,
+            format: ExtractFormat::JsonLines,
+            tag,
+            namespaces: _,
+        } => {
+            // Print each file's functions as soon as they are extracted
+            let mut stdout = io::stdout().lock();
+            for file in expand_paths(&patterns(&paths))? {
+                for function in extract_from_file(&file)? {
+                    if tag.as_deref().is_none_or(|tag| function.has_tag(tag)) {
+                        write_json_line(&mut stdout, &function)?;
+                    }
+                }
+            }
+        }
+        Command::Extract {
+            paths,
+            format,
+            tag,
+            namespaces,
+        } => {
+            let mut functions = extract_from_paths(&patterns(&paths))?;
+            if let Some(tag) = &tag {
+                functions.retain(|function| function.has_tag(tag));
+            }
+            if matches!(format, ExtractFormat::Markdown) {
+                let namespaces = namespaces.namespaces(&functions);
+                print!(
+                    "{}",
+                    render_markdown_with_namespaces(&functions, &namespaces)
+                );
+            } else {
+                print_functions(&functions, format)?;
+            }
+        }
+        Command::Query { files } => {
+            let mut queries = Vec::new();
+            for file in &files {
+                let text = read(file)?;
+                let query = extract_query_metadata(&text).map_err(|diagnostic| {
+                    anyhow!(diagnostic.render(&file.display().to_string(), &text))
+                })?;
+                queries.push(query);
+            }
+            println!("{}", serde_json::to_string_pretty(&queries)?);
+        }
+        Command::Graph {
+            files,
+            dot,
+            mermaid,
+            html,
+            callers_of,
+            path,
+            max_depth,
+            namespaces,
+        } => {
+            let functions = load_functions(&files)?;
+            let namespaces = namespaces.namespaces(&functions);
+            let graph = CallGraph::from_functions(&functions).with_namespaces(namespaces.clone());
+            if let Some(name) = callers_of {
+                print_callers(&graph, &name);
+            } else if let [from, to] = path.as_slice() {
+                let paths = graph.paths(from, to, max_depth);
+                if paths.is_empty() {
+                    eprintln!(
+                        "{} does not reach {} in {} calls or fewer",
+                        from, to, max_depth
+                    );
+                }
+                for path in paths {
+                    println!("{}", path.join(" -> "));
+                }
+            } else if dot {
+                print!("{}", graph.to_dot());
+            } else if mermaid {
+                print!("{}", graph.to_mermaid());
+            } else if html {
+                print!(
+                    "{}",
+                    render_call_graph_html_with_namespaces(&functions, &namespaces)
+                );
+            } else {
+                print_graph(&graph);
+            }
+        }
+        Command::Validate {
+            files,
+            schema,
+            allowed,
+            config,
+            format,
+            baseline,
+            write_baseline,
+        } => {
+            let functions = load_functions(&files)?;
+            let config = load_lint_config(config.as_deref())?;
+            let allowed: Vec<&str> = allowed.iter().map(String::as_str).collect();
+            let mut linter = Linter::new(config, &allowed);
+            if let Some(schema_path) = schema {
+                let schema = load_schema(&schema_path)?;
+                linter = linter
+                    .with_rule(UnknownTypes::from_schema(&schema))
+                    .with_rule(IncompatibleTypes::from_schema(&schema));
+            }
+            let mut diagnostics = linter.run(&functions);
+
+            if let Some(baseline_path) = write_baseline {
+                let baseline = Baseline::from_diagnostics(&diagnostics);
+                fs::write(&baseline_path, baseline.to_json()?)
+                    .with_context(|| format!("failed to write {}", baseline_path.display()))?;
+                eprintln!(
+                    "Recorded {} findings in {}",
+                    baseline.findings.len(),
+                    baseline_path.display()
+                );
+                return Ok(ExitCode::SUCCESS);
+            }
+            if let Some(baseline_path) = baseline {
+                let baseline = Baseline::from_json(&read(&baseline_path)?)
+                    .with_context(|| format!("invalid baseline {}", baseline_path.display()))?;
+                let fixed = baseline.fixed_findings(&diagnostics).len();
+                if fixed > 0 && format == ValidateFormat::Text {
+                    eprintln!(
+                        "{} baseline findings are fixed; rerun with --write-baseline to drop them",
+                        fixed
+                    );
+                }
+                diagnostics = baseline.new_findings(diagnostics);
+            }
+
+            match format {
+                ValidateFormat::Text => {
+                    for diagnostic in &diagnostics {
+                        eprintln!("{}", diagnostic);
+                    }
+                }
+                ValidateFormat::Sarif => println!("{}", render_sarif(&diagnostics, &functions)),
+            }
+            if diagnostics
+                .iter()
+                .any(|diagnostic| diagnostic.severity == Severity::Error && !diagnostic.suppressed)
+            {
+                return Ok(ExitCode::FAILURE);
+            }
+            if format == ValidateFormat::Text {
+                eprintln!("✅ {} functions checked", functions.len());
+            }
+        }
+        Command::Duplicates { paths, threshold } => {
+            let functions = extract_from_paths(&patterns(&paths))?;
+            let report = find_duplicates(&functions, threshold);
+            for group in &report.exact {
+                println!("duplicates: {}", group.join(", "));
+            }
+            for pair in &report.similar {
+                println!(
+                    "{:.0}% similar: {}, {}",
+                    pair.similarity * 100.0,
+                    pair.first,
+                    pair.second
+                );
+            }
+            if report.exact.is_empty() && report.similar.is_empty() {
+                eprintln!("✅ no duplicates among {} functions", functions.len());
+            }
+        }
+        Command::Repl { paths, config } => {
+            let registry: FunctionRegistry = if paths.is_empty() {
+                FunctionRegistry::new()
+            } else {
+                extract_from_paths(&patterns(&paths))?.into_iter().collect()
+            };
+            let config = load_lint_config(config.as_deref())?;
+            typedb_examples::repl::run(io::stdin().lock(), io::stdout().lock(), registry, config)?;
+        }
+        Command::Explain { paths } => {
+            for function in extract_from_paths(&patterns(&paths))? {
+                println!("{}: {}", function.name, explain(&function));
+            }
+        }
+        Command::Search {
+            query,
+            paths,
+            limit,
+        } => {
+            let registry: FunctionRegistry =
+                extract_from_paths(&patterns(&paths))?.into_iter().collect();
+            for hit in registry.search(&query).into_iter().take(limit) {
+                println!("{:6.2}  {}", hit.score, hit.function.signature());
+            }
+        }
+        Command::Fingerprint { paths } => {
+            for function in extract_from_paths(&patterns(&paths))? {
+                println!("{}  {}", function.fingerprint(), function.name);
+            }
+        }
+        Command::Catalog { paths } => {
+            let functions = extract_from_paths(&patterns(&paths))?;
+            print!("{}", CATALOG_SCHEMA);
+            for query in catalog_inserts(&functions) {
+                print!("\n{}", query);
+            }
+        }
+        Command::Codegen { paths, language } => {
+            let functions = extract_from_paths(&patterns(&paths))?;
+            match language {
+                CodegenLanguage::Rust => print!("{}", render_rust_client(&functions)),
+                CodegenLanguage::Python => print!("{}", render_python_client(&functions)),
+                CodegenLanguage::Typescript => {
+                    print!("{}", render_typescript_definitions(&functions))
+                }
+            }
+        }
+        Command::Graphql { paths, schema } => {
+            let functions = extract_from_paths(&patterns(&paths))?;
+            let schema = match schema {
+                Some(schema_path) => load_schema(&schema_path)?,
+                None => SchemaMetadata::default(),
+            };
+            print!("{}", render_graphql_schema(&functions, &schema));
+        }
+        Command::Types {
+            schema: schema_path,
+        } => {
+            let schema = load_schema(&schema_path)?;
+            print!("{}", render_rust_types(&schema));
+        }
+        Command::Diagram { schema, mermaid } => {
+            let schema = load_schema(&schema)?;
+            if mermaid {
+                print!("{}", schema.to_mermaid());
+            } else {
+                print!("{}", schema.to_dot());
+            }
+        }
+        Command::Inserts {
+            schema,
+            mapping,
+            data,
+        } => {
+            let schema = load_schema(&schema)?;
+            let mapping: DataMapping = serde_json::from_str(&read(&mapping)?)
+                .with_context(|| format!("invalid mapping {}", mapping.display()))?;
+            let data: serde_json::Value = serde_json::from_str(&read(&data)?)
+                .with_context(|| format!("invalid data {}", data.display()))?;
+            print!("{}", generate_inserts(&schema, &mapping, &data)?);
+        }
+        Command::Synthesize {
+            schema,
+            config,
+            seed,
+        } => {
+            let schema = load_schema(&schema)?;
+            let mut config: SyntheticConfig = match config {
+                Some(config_path) => serde_json::from_str(&read(&config_path)?)
+                    .with_context(|| format!("invalid config {}", config_path.display()))?,
+                None => SyntheticConfig::default(),
+            };
+            if let Some(seed) = seed {
+                config.seed = seed;
+            }
+            print!("{}", generate_synthetic_data(&schema, &config));
+        }
+        Command::Snapshot {
+            files,
+            golden,
+            update,
+        } => {
+            let mode = if update {
+                SnapshotMode::Update
+            } else {
+                SnapshotMode::from_env()
+            };
+            let mut failed = 0;
+            for file in &files {
+                let functions = extract_from_file(file)?;
+                let name = file
+                    .file_name()
+                    .ok_or_else(|| anyhow!("{} is not a file", file.display()))?;
+                let golden_file = golden.join(format!("{}.json", name.to_string_lossy()));
+                match check_snapshot(&golden_file, &functions, mode) {
+                    Ok(outcome) => eprintln!("{} {}", outcome, golden_file.display()),
+                    Err(error) => {
+                        eprintln!("{:#}", error);
+                        failed += 1;
+                    }
+                }
+            }
+            if failed > 0 {
+                eprintln!("❌ {} of {} snapshots differ", failed, files.len());
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Bundle { files, entries } => {
+            let mut functions = load_functions(&files)?;
+            if !entries.is_empty() {
+                let entries: Vec<&str> = entries.iter().map(String::as_str).collect();
+                functions = tree_shake(functions, &entries)?;
+            }
+            print!(
+                "{}",
+                bundle(&functions).context("cannot order functions for bundling")?
+            );
+        }
+        Command::Orphans { files, entries } => {
+            let registry: FunctionRegistry = load_functions(&files)?.into_iter().collect();
+            if let Some(missing) = entries.iter().find(|entry| !registry.contains(entry)) {
+                return Err(anyhow!("entry function `{}` is not defined", missing));
+            }
+            let entries: Vec<&str> = entries.iter().map(String::as_str).collect();
+            let orphans = registry.orphans(&entries);
+            for orphan in &orphans {
+                match &orphan.source_file {
+                    Some(file) => println!("{} ({})", orphan.name, file),
+                    None => println!("{}", orphan.name),
+                }
+            }
+            if !orphans.is_empty() {
+                eprintln!(
+                    "❌ {} of {} functions are never called",
+                    orphans.len(),
+                    registry.len()
+                );
+                return Ok(ExitCode::FAILURE);
+            }
+            eprintln!("✅ every function is called or an entry point");
+        }
+        Command::Recursion { files, json } => {
+            let functions = load_functions(&files)?;
+            let groups = recursion_report(&functions);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&groups)?);
+            } else {
+                for group in &groups {
+                    println!("{}", group);
+                }
+            }
+            let unstratified = groups.iter().filter(|group| !group.is_stratified()).count();
+            if unstratified > 0 {
+                eprintln!(
+                    "❌ {} of {} recursive groups recurse through a negation or an aggregate",
+                    unstratified,
+                    groups.len()
+                );
+                return Ok(ExitCode::FAILURE);
+            }
+            eprintln!("✅ {} recursive groups, all stratified", groups.len());
+        }
+        Command::Inline {
+            function,
+            into,
+            paths,
+        } => {
+            let registry: FunctionRegistry =
+                extract_from_paths(&patterns(&paths))?.into_iter().collect();
+            let find = |name: &str| {
+                registry
+                    .get(name)
+                    .ok_or_else(|| anyhow!("no function named `{}`", name))
+            };
+            print!("{}", inline_function(find(&into)?, find(&function)?)?);
+        }
+        Command::Migrate { from, to } => {
+            let old = load_functions(&typeql_files(&from)?)?;
+            let new = load_functions(&typeql_files(&to)?)?;
+            print!("{}", plan_migration(&old, &new));
+        }
+        Command::Dialect { paths } => {
+            let files = expand_paths(&patterns(&paths))?;
+            let mut legacy = 0;
+            for file in &files {
+                let text = read(file)?;
+                let source_name = file.display().to_string();
+                if detect_dialect(&text) == Dialect::TypeQl3 {
+                    println!("{}: TypeQL 3", source_name);
+                    continue;
+                }
+                legacy += 1;
+                let rules = extract_rules(&text)
+                    .map_err(|diagnostic| anyhow!(diagnostic.render(&source_name, &text)))?;
+                println!("{}: TypeQL 2.x, {} rules", source_name, rules.len());
+                for diagnostic in check_dialect(&text) {
+                    print!("{}", diagnostic.render(&source_name, &text));
+                }
+            }
+            if legacy > 0 {
+                eprintln!("❌ {} of {} files are TypeQL 2.x", legacy, files.len());
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::ConvertRules { file, schema } => {
+            let schema = schema.as_deref().map(load_schema).transpose()?;
+            let text = read(&file)?;
+            let conversions = convert_rules(&text, schema.as_ref()).map_err(|diagnostic| {
+                anyhow!(diagnostic.render(&file.display().to_string(), &text))
+            })?;
+            let converted = conversions
+                .iter()
+                .filter(|conversion| conversion.function.is_some())
+                .count();
+            if converted > 0 {
+                println!("define\n");
+            }
+            for conversion in &conversions {
+                if let Some(function) = &conversion.function {
+                    println!("{}", function);
+                }
+                for issue in &conversion.issues {
+                    eprintln!("⚠️ {}: {}", conversion.rule, issue);
+                }
+            }
+            eprintln!("converted {} of {} rules", converted, conversions.len());
+        }
+        Command::Namespaces { paths, namespaces } => {
+            let functions = extract_from_paths(&patterns(&paths))?;
+            let namespaces = namespaces.namespaces(&functions);
+            for (namespace, members) in namespaces.groups() {
+                println!("{}: {}", namespace, members.join(", "));
+            }
+            let ungrouped: Vec<&str> = functions
+                .iter()
+                .map(|function| function.name.as_str())
+                .filter(|name| namespaces.namespace_of(name).is_none())
+                .collect();
+            if !ungrouped.is_empty() {
+                eprintln!("no namespace: {}", ungrouped.join(", "));
+            }
+        }
+        Command::Literals { paths, json } => {
+            let mut literals = Vec::new();
+            for file in expand_paths(&patterns(&paths))? {
+                let text = read(&file)?;
+                let source_name = file.display().to_string();
+                literals.extend(
+                    extract_literals(&text)
+                        .into_iter()
+                        .map(|literal| (source_name.clone(), literal)),
+                );
+            }
+            if json {
+                let literals: Vec<serde_json::Value> = literals
+                    .iter()
+                    .map(|(file, literal)| {
+                        let mut value = serde_json::to_value(literal)?;
+                        value["file"] = file.as_str().into();
+                        Ok(value)
+                    })
+                    .collect::<Result<_>>()?;
+                println!("{}", serde_json::to_string_pretty(&literals)?);
+            } else {
+                for (file, literal) in &literals {
+                    println!(
+                        "{}:{}:{}: {}: {}",
+                        file, literal.span.line, literal.span.column, literal.function, literal
+                    );
+                }
+                eprintln!("{} literals", literals.len());
+            }
+        }
+        Command::ExtractParameter {
+            file,
+            at,
+            name,
+            paths,
+        } => {
+            let (line, column): (usize, usize) = at
+                .split_once(':')
+                .and_then(|(line, column)| Some((line.parse().ok()?, column.parse().ok()?)))
+                .ok_or_else(|| anyhow!("expected LINE:COLUMN, got `{}`", at))?;
+            let text = read(&file)?;
+            let literal = extract_literals(&text)
+                .into_iter()
+                .find(|literal| literal.span.line == line && literal.span.column == column)
+                .ok_or_else(|| anyhow!("no literal at {}:{}:{}", file.display(), line, column))?;
+            let registry: FunctionRegistry = if paths.is_empty() {
+                extract_from_file(&file)?.into_iter().collect()
+            } else {
+                extract_from_paths(&patterns(&paths))?.into_iter().collect()
+            };
+            let parameterized = literal_to_parameter(&text, &literal, &name, &registry)?;
+            println!("{}", parameterized.function);
+            for site in &parameterized.call_sites {
+                println!("{}: {} -> {}", site.caller, site.call, site.updated);
+            }
+        }
+        Command::SchemaDiff {
+            old,
+            new,
+            paths,
+            json,
+        } => {
+            let diff = diff_schemas(&load_schema(&old)?, &load_schema(&new)?);
+            let functions = if paths.is_empty() {
+                Vec::new()
+            } else {
+                extract_from_paths(&patterns(&paths))?
+            };
+            let impacted = diff.impacted_functions(&functions);
+            if json {
+                let report = serde_json::json!({ "diff": diff, "impacted_functions": impacted });
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!("{}", diff);
+                for function in &impacted {
+                    println!("! {} uses {}", function.function, function.types.join(", "));
+                }
+            }
+            if diff.is_breaking() {
+                eprintln!(
+                    "❌ breaking schema changes affecting {} functions",
+                    impacted.len()
+                );
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Watch { paths, interval } => {
+            watch(
+                &patterns(&paths),
+                Duration::from_millis(interval),
+                |event| {
+                    print_watch_event(event);
+                    ControlFlow::Continue(())
+                },
+            );
+        }
+        #[cfg(feature = "json-schema")]
+        Command::Schema => {
+            let schema = typedb_examples::MetadataDocument::json_schema();
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+        }
+        #[cfg(feature = "parquet")]
+        Command::Parquet { paths, output } => {
+            let mut files = Vec::new();
+            for path in &paths {
+                files.extend(typeql_files(path)?);
+            }
+            let functions = load_functions(&files)?;
+            let file = fs::File::create(&output)
+                .with_context(|| format!("failed to create {}", output.display()))?;
+            typedb_examples::write_parquet(&functions, file)?;
+        }
+        #[cfg(feature = "sqlite")]
+        Command::Index {
+            paths,
+            store,
+            format,
+        } => {
+            let mut store = typedb_examples::FunctionStore::open(&store)?;
+            let summary = store.refresh(&patterns(&paths))?;
+            for path in &summary.extracted {
+                eprintln!("extracted {}", path);
+            }
+            for path in &summary.removed {
+                eprintln!("removed {}", path);
+            }
+            eprintln!("{} files unchanged", summary.unchanged);
+            print_functions(&store.functions()?, format)?;
+        }
+        #[cfg(feature = "client")]
+        Command::Pull {
+            database,
+            server,
+            format,
+        } => {
+            let runtime = tokio::runtime::Runtime::new()?;
+            let functions = runtime.block_on(async {
+                let driver = typedb_examples::connect(&server.config()).await?;
+                typedb_examples::fetch_functions(&driver, &database).await
+            })?;
+            print_functions(&functions, format)?;
+        }
+        #[cfg(feature = "client")]
+        Command::Sync {
+            files,
+            database,
+            server,
+            dry_run,
+        } => {
+            let functions = load_functions(&files)?;
+            let mode = if dry_run {
+                typedb_examples::SyncMode::DryRun
+            } else {
+                typedb_examples::SyncMode::Apply
+            };
+            let runtime = tokio::runtime::Runtime::new()?;
+            let plan = runtime.block_on(async {
+                let driver = typedb_examples::connect(&server.config()).await?;
+                typedb_examples::sync_functions(&driver, &database, &functions, mode).await
+            })?;
+            if dry_run {
+                print!("{}", plan);
+            } else {
+                for statement in &plan.statements {
+                    eprintln!("{} {}", statement.action, statement.function);
+                }
+                eprintln!("✅ {} statements applied", plan.statements.len());
+            }
+        }
+        #[cfg(feature = "client")]
+        Command::Drift {
+            files,
+            database,
+            server,
+        } => {
+            let functions = load_functions(&files)?;
+            let runtime = tokio::runtime::Runtime::new()?;
+            let report = runtime.block_on(async {
+                let driver = typedb_examples::connect(&server.config()).await?;
+                typedb_examples::check_drift(&driver, &database, &functions).await
+            })?;
+            if !report.is_clean() {
+                print!("{}", report);
+                return Ok(ExitCode::FAILURE);
+            }
+            eprintln!("✅ {} matches {} functions", database, functions.len());
+        }
+        #[cfg(feature = "client")]
+        Command::Publish {
+            paths,
+            database,
+            server,
+        } => {
+            let functions = extract_from_paths(&patterns(&paths))?;
+            let runtime = tokio::runtime::Runtime::new()?;
+            let queries = runtime.block_on(async {
+                let driver = typedb_examples::connect(&server.config()).await?;
+                typedb_examples::store_catalog(&driver, &database, &functions).await
+            })?;
+            eprintln!(
+                "✅ {} functions stored in {} ({} queries)",
+                functions.len(),
+                database,
+                queries
+            );
+        }
+        #[cfg(feature = "client")]
+        Command::Test {
+            files,
+            tests,
+            database,
+            server,
+        } => {
+            let functions = load_functions(&files)?;
+            let tests: Vec<typedb_examples::FunctionTest> = serde_json::from_str(&read(&tests)?)
+                .with_context(|| format!("invalid tests {}", tests.display()))?;
+            let runtime = tokio::runtime::Runtime::new()?;
+            let outcomes = runtime.block_on(async {
+                let driver = typedb_examples::connect(&server.config()).await?;
+                typedb_examples::run_function_tests(&driver, &database, &functions, &tests).await
+            })?;
+            for outcome in &outcomes {
+                println!("{}", outcome);
+            }
+            let failed = outcomes.iter().filter(|outcome| !outcome.passed).count();
+            if failed > 0 {
+                eprintln!("❌ {} of {} tests failed", failed, outcomes.len());
+                return Ok(ExitCode::FAILURE);
+            }
+            eprintln!("✅ {} tests passed", outcomes.len());
+        }
+        #[cfg(feature = "lsp")]
+        Command::Lsp { config } => {
+            let config = load_lint_config(config.as_deref())?;
+            typedb_examples::lsp::serve(io::stdin().lock(), io::stdout().lock(), config)?;
+        }
+        #[cfg(feature = "mcp")]
+        Command::Mcp { paths } => {
+            typedb_examples::mcp::serve(
+                io::stdin().lock(),
+                io::stdout().lock(),
+                &patterns(&paths),
+            )?;
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn read(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))
+}
+
+fn patterns(paths: &[String]) -> Vec<&str> {
+    paths.iter().map(String::as_str).collect()
+}
+
+fn load_schema(path: &Path) -> Result<SchemaMetadata> {
+    let text = read(path)?;
+    extract_schema_metadata(&text)
+        .map_err(|diagnostic| anyhow!(diagnostic.render(&path.display().to_string(), &text)))
+}
+
+fn load_lint_config(path: Option<&Path>) -> Result<LintConfig> {
+    match path {
+        Some(path) => serde_json::from_str(&read(path)?)
+            .with_context(|| format!("invalid lint config {}", path.display())),
+        None => Ok(LintConfig::default()),
+    }
+}
+
+fn load_functions(files: &[PathBuf]) -> Result<Vec<FunctionMetadata>> {
+    let mut functions = Vec::new();
+    for file in files {
+        functions.extend(extract_from_file(file)?);
+    }
+    Ok(functions)
+}
+
+fn print_functions(functions: &[FunctionMetadata], format: ExtractFormat) -> Result<()> {
+    match format {
+        ExtractFormat::Json => println!("{}", to_metadata_json(functions)?),
+        ExtractFormat::Markdown => print!("{}", render_markdown(functions)),
+        ExtractFormat::Csv => print!("{}", render_csv(functions)),
+        ExtractFormat::JsonLines => {
+            let mut stdout = io::stdout().lock();
+            for function in functions {
+                write_json_line(&mut stdout, function)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_watch_event(event: &WatchEvent) {
+    match event {
+        WatchEvent::Changed(change) => {
+            println!("{}:", change.path.display());
+            for function in &change.updated {
+                match change.diffs.iter().find(|diff| diff.name == function.name) {
+                    Some(diff) => {
+                        println!("  ~ {}", function.name);
+                        for line in diff.to_string().lines() {
+                            println!("    {}", line);
+                        }
+                    }
+                    None => println!("  + {}", function.name),
+                }
+            }
+            for name in &change.removed {
+                println!("  - {}", name);
+            }
+        }
+        WatchEvent::Failed { path, error } => {
+            eprintln!("{}: {}", path.display(), error);
+        }
+    }
+}
+
+fn print_graph(graph: &CallGraph) {
+    println!("Calls:");
+    for function in graph.functions() {
+        let callees = graph.callees(function);
+        if callees.is_empty() {
+            println!("  {}", function);
+        } else {
+            println!("  {} -> {}", function, callees.join(", "));
+        }
+    }
+
+    println!();
+    match graph.topological_order() {
+        Ok(order) => {
+            println!("Definition order:");
+            for (position, function) in order.iter().enumerate() {
+                println!("  {}. {}", position + 1, function);
+            }
+        }
+        Err(_) => {
+            println!("Cycles:");
+            for cycle in graph.cycles() {
+                println!("  {}", cycle.join(" -> "));
+            }
+        }
+    }
+}
+
+fn print_callers(graph: &CallGraph, name: &str) {
+    let direct = graph.callers_of(name);
+    println!("Direct callers of {}:", name);
+    for caller in &direct {
+        println!("  {}", caller);
+    }
+
+    let indirect: Vec<&str> = graph
+        .all_callers_of(name)
+        .into_iter()
+        .filter(|caller| !direct.contains(caller))
+        .collect();
+    println!("Indirect callers:");
+    for caller in indirect {
+        println!("  {}", caller);
+    }
+}
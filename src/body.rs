@@ -0,0 +1,371 @@
+use serde::{Deserialize, Serialize};
+use typeql::expression::Expression;
+use typeql::pattern::Pattern;
+use typeql::query::stage::Stage;
+use typeql::statement::thing::isa::IsaKind;
+use typeql::statement::thing::{self, HasValue, Head, RolePlayer as AstRolePlayer};
+use typeql::statement::{AssignmentPattern, Statement};
+use typeql::Variable;
+
+use crate::expression::Expr;
+use crate::extract_function_metadata::{
+    for_each_statement, type_ref_any_label, type_ref_label, variable_name, visit_expression,
+};
+
+/// One constraint from the function body together with the variables it mentions.
+///
+/// A statement such as `$x isa person, has name $n;` yields one constraint per part.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct Constraint {
+    #[serde(flatten)]
+    pub kind: ConstraintKind,
+    /// Named variables involved, without `$`, in order of appearance
+    pub variables: Vec<String>,
+}
+
+/// Variables are written without `$`; anonymous variables and relations are `_`.
+/// Expressions are kept as TypeQL text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConstraintKind {
+    /// `$x isa person` (`exact` for `isa!`)
+    Isa {
+        var: String,
+        type_name: String,
+        exact: bool,
+    },
+    /// `$x has amount $a`, `$x has amount > 10`
+    Has {
+        owner: String,
+        attribute: Option<String>,
+        value: String,
+    },
+    /// `$r links (earner: $x)` or `(earner: $x) isa income_source`
+    Links {
+        relation: String,
+        role_players: Vec<RolePlayer>,
+    },
+    /// `$x iid 0x...`
+    Iid { var: String, iid: String },
+    /// `$a > $b`
+    Comparison {
+        lhs: String,
+        comparator: String,
+        rhs: String,
+    },
+    /// `let $x = expr`
+    Let {
+        vars: Vec<String>,
+        expression: String,
+        /// `expression` parsed into operators, operands and calls
+        tree: Expr,
+    },
+    /// `let $x in expr`
+    LetIn {
+        vars: Vec<String>,
+        expression: String,
+        tree: Expr,
+    },
+    /// `$a is $b`
+    Is { lhs: String, rhs: String },
+    /// Type and attribute-value statements, kept as written
+    Other { statement: String },
+}
+
+/// A body pattern with its nesting kept, so conditional logic can be analysed
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BodyPattern {
+    /// A single statement, split into its constraints
+    Statement { constraints: Vec<Constraint> },
+    /// `{ ... }` grouping patterns without changing their meaning
+    Conjunction { patterns: Vec<BodyPattern> },
+    /// `not { ... }`
+    Negation { patterns: Vec<BodyPattern> },
+    /// `{ ... } or { ... }`, one entry per branch
+    Disjunction { branches: Vec<Vec<BodyPattern>> },
+    /// `try { ... }`
+    Optional { patterns: Vec<BodyPattern> },
+}
+
+impl BodyPattern {
+    /// Every constraint in this pattern and the patterns nested in it, in source order
+    pub fn constraints(&self) -> Vec<&Constraint> {
+        let mut constraints = Vec::new();
+        self.collect_constraints(&mut constraints);
+        constraints
+    }
+
+    fn collect_constraints<'a>(&'a self, out: &mut Vec<&'a Constraint>) {
+        match self {
+            BodyPattern::Statement { constraints } => out.extend(constraints),
+            BodyPattern::Conjunction { patterns }
+            | BodyPattern::Negation { patterns }
+            | BodyPattern::Optional { patterns } => {
+                for pattern in patterns {
+                    pattern.collect_constraints(out);
+                }
+            }
+            BodyPattern::Disjunction { branches } => {
+                for pattern in branches.iter().flatten() {
+                    pattern.collect_constraints(out);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct RolePlayer {
+    /// `None` for untyped role players such as `($x, $y)`
+    pub role: Option<String>,
+    pub player: String,
+}
+
+impl Constraint {
+    fn new(kind: ConstraintKind) -> Self {
+        Constraint {
+            kind,
+            variables: Vec::new(),
+        }
+    }
+
+    fn with_variable(mut self, var: &Variable) -> Self {
+        add_variable(&mut self.variables, var);
+        self
+    }
+
+    fn with_expression(mut self, expression: &Expression) -> Self {
+        visit_expression(expression, &mut |inner| {
+            if let Expression::Variable(var) = inner {
+                add_variable(&mut self.variables, var);
+            }
+        });
+        self
+    }
+}
+
+/// Flatten every statement in the body, including nested blocks, into constraints
+pub(crate) fn extract_constraints(stages: &[Stage]) -> Vec<Constraint> {
+    let mut constraints = Vec::new();
+    for_each_statement(stages, &mut |statement| {
+        statement_constraints(statement, &mut constraints)
+    });
+    constraints
+}
+
+/// The body's patterns with `not`, `or` and `try` blocks kept as nested structures.
+///
+/// Statements of write stages (`insert`, `put`, `update`) are included as plain statements.
+pub(crate) fn extract_patterns(stages: &[Stage]) -> Vec<BodyPattern> {
+    let mut patterns = Vec::new();
+    for stage in stages {
+        match stage {
+            Stage::Match(match_) => patterns.extend(match_.patterns.iter().map(body_pattern)),
+            Stage::Insert(insert) => {
+                patterns.extend(insert.statements.iter().map(statement_pattern))
+            }
+            Stage::Put(put) => patterns.extend(put.statements.iter().map(statement_pattern)),
+            Stage::Update(update) => {
+                patterns.extend(update.statements.iter().map(statement_pattern))
+            }
+            Stage::Delete(_) | Stage::Fetch(_) | Stage::Operator(_) => {}
+        }
+    }
+    patterns
+}
+
+fn body_pattern(pattern: &Pattern) -> BodyPattern {
+    let nested = |patterns: &[Pattern]| patterns.iter().map(body_pattern).collect();
+    match pattern {
+        Pattern::Statement(statement) => statement_pattern(statement),
+        Pattern::Conjunction(conjunction) => BodyPattern::Conjunction {
+            patterns: nested(&conjunction.patterns),
+        },
+        Pattern::Negation(negation) => BodyPattern::Negation {
+            patterns: nested(&negation.patterns),
+        },
+        Pattern::Optional(optional) => BodyPattern::Optional {
+            patterns: nested(&optional.patterns),
+        },
+        Pattern::Disjunction(disjunction) => BodyPattern::Disjunction {
+            branches: disjunction
+                .branches
+                .iter()
+                .map(|branch| nested(branch))
+                .collect(),
+        },
+    }
+}
+
+fn statement_pattern(statement: &Statement) -> BodyPattern {
+    let mut constraints = Vec::new();
+    statement_constraints(statement, &mut constraints);
+    BodyPattern::Statement { constraints }
+}
+
+fn statement_constraints(statement: &Statement, out: &mut Vec<Constraint>) {
+    match statement {
+        Statement::Thing(thing) => thing_constraints(thing, out),
+        Statement::Comparison(comparison) => out.push(
+            Constraint::new(ConstraintKind::Comparison {
+                lhs: comparison.lhs.to_string(),
+                comparator: comparison.comparison.comparator.to_string(),
+                rhs: comparison.comparison.rhs.to_string(),
+            })
+            .with_expression(&comparison.lhs)
+            .with_expression(&comparison.comparison.rhs),
+        ),
+        Statement::Assignment(assignment) => {
+            let vars = match &assignment.lhs {
+                AssignmentPattern::Variables(vars) => vars.iter().collect(),
+                AssignmentPattern::Deconstruct(_) => Vec::new(),
+            };
+            let mut constraint = Constraint::new(ConstraintKind::Let {
+                vars: vars.iter().map(|var| var_text(var)).collect(),
+                expression: assignment.rhs.to_string(),
+                tree: Expr::from(&assignment.rhs),
+            });
+            for var in vars {
+                constraint = constraint.with_variable(var);
+            }
+            out.push(constraint.with_expression(&assignment.rhs));
+        }
+        Statement::InIterable(in_iterable) => {
+            let mut constraint = Constraint::new(ConstraintKind::LetIn {
+                vars: in_iterable.lhs.iter().map(var_text).collect(),
+                expression: in_iterable.rhs.to_string(),
+                tree: Expr::from(&in_iterable.rhs),
+            });
+            for var in &in_iterable.lhs {
+                constraint = constraint.with_variable(var);
+            }
+            out.push(constraint.with_expression(&in_iterable.rhs));
+        }
+        Statement::Is(is) => out.push(
+            Constraint::new(ConstraintKind::Is {
+                lhs: var_text(&is.lhs),
+                rhs: var_text(&is.rhs),
+            })
+            .with_variable(&is.lhs)
+            .with_variable(&is.rhs),
+        ),
+        Statement::AttributeValue(_) | Statement::AttributeComparison(_) | Statement::Type(_) => {
+            out.push(Constraint::new(ConstraintKind::Other {
+                statement: statement.to_string(),
+            }))
+        }
+    }
+}
+
+fn thing_constraints(thing: &thing::Thing, out: &mut Vec<Constraint>) {
+    let subject = match &thing.head {
+        Head::Variable(var) => Some(var),
+        Head::Relation(type_ref, relation) => {
+            // `friendship ($x, $y)` or `(friend: $x)` without a relation variable
+            if let Some(type_name) = type_ref.as_ref().and_then(type_ref_label) {
+                out.push(Constraint::new(ConstraintKind::Isa {
+                    var: "_".to_string(),
+                    type_name,
+                    exact: false,
+                }));
+            }
+            out.push(links_constraint(
+                "_".to_string(),
+                None,
+                &relation.role_players,
+            ));
+            None
+        }
+    };
+    let subject_text = subject.map_or_else(|| "_".to_string(), var_text);
+    let with_subject = |constraint: Constraint| match subject {
+        Some(var) => constraint.with_variable(var),
+        None => constraint,
+    };
+
+    for constraint in &thing.constraints {
+        let extracted = match constraint {
+            thing::Constraint::Isa(isa) => with_subject(Constraint::new(ConstraintKind::Isa {
+                var: subject_text.clone(),
+                type_name: type_ref_any_label(&isa.type_).unwrap_or_else(|| isa.type_.to_string()),
+                exact: isa.kind == IsaKind::Exact,
+            })),
+            thing::Constraint::Has(has) => {
+                let value = match &has.value {
+                    HasValue::Variable(var) => format!("${}", var_text(var)),
+                    HasValue::Expression(expression) => expression.to_string(),
+                    HasValue::Comparison(comparison) => comparison.to_string(),
+                };
+                let constraint = with_subject(Constraint::new(ConstraintKind::Has {
+                    owner: subject_text.clone(),
+                    attribute: has.type_.as_ref().and_then(type_ref_any_label),
+                    value,
+                }));
+                match &has.value {
+                    HasValue::Variable(var) => constraint.with_variable(var),
+                    HasValue::Expression(expression) => constraint.with_expression(expression),
+                    HasValue::Comparison(comparison) => constraint.with_expression(&comparison.rhs),
+                }
+            }
+            thing::Constraint::Links(links) => {
+                links_constraint(subject_text.clone(), subject, &links.relation.role_players)
+            }
+            thing::Constraint::Iid(iid) => with_subject(Constraint::new(ConstraintKind::Iid {
+                var: subject_text.clone(),
+                iid: iid.iid.to_string(),
+            })),
+        };
+        out.push(extracted);
+    }
+}
+
+fn links_constraint(
+    relation: String,
+    subject: Option<&Variable>,
+    role_players: &[AstRolePlayer],
+) -> Constraint {
+    let mut constraint = Constraint::new(ConstraintKind::Links {
+        relation,
+        role_players: role_players
+            .iter()
+            .map(|role_player| match role_player {
+                AstRolePlayer::Typed(role, player) => RolePlayer {
+                    role: Some(type_ref_any_label(role).unwrap_or_else(|| role.to_string())),
+                    player: var_text(player),
+                },
+                AstRolePlayer::Untyped(player) => RolePlayer {
+                    role: None,
+                    player: var_text(player),
+                },
+            })
+            .collect(),
+    });
+    if let Some(subject) = subject {
+        constraint = constraint.with_variable(subject);
+    }
+    for role_player in role_players {
+        match role_player {
+            AstRolePlayer::Typed(_, player) | AstRolePlayer::Untyped(player) => {
+                constraint = constraint.with_variable(player)
+            }
+        }
+    }
+    constraint
+}
+
+fn var_text(var: &Variable) -> String {
+    variable_name(var).unwrap_or("_").to_string()
+}
+
+fn add_variable(variables: &mut Vec<String>, var: &Variable) {
+    if let Some(name) = variable_name(var) {
+        if !variables.iter().any(|existing| existing == name) {
+            variables.push(name.to_string());
+        }
+    }
+}
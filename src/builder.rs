@@ -0,0 +1,303 @@
+//! Constructing [`FunctionMetadata`] in code rather than by parsing TypeQL.
+//!
+//! ```
+//! use typedb_examples::FunctionMetadata;
+//!
+//! let function = FunctionMetadata::builder("get_total_income")
+//!     .doc("Total income of a taxpayer in a year")
+//!     .parameter("taxpayer", "person")
+//!     .parameter("year", "integer")
+//!     .single_output(["double"])
+//!     .code_block("match\n$i isa income, has amount $amount;\nreduce $total = sum($amount);")
+//!     .return_first(["total"])
+//!     .build()
+//!     .unwrap();
+//! assert!(function.to_typeql().starts_with("# Total income"));
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::body::{BodyPattern, Constraint};
+use crate::doc_comment::{categories, DocTag};
+use crate::error::ExtractError;
+use crate::expression::Expr;
+use crate::extract_function_metadata::{
+    CallSite, FunctionMetadata, FunctionSpans, OutputType, Parameter, Reduction, ReturnExpr,
+    Selector, TypeKind, TypeName, VALUE_TYPES,
+};
+use crate::query::StageKind;
+
+/// Builds a [`FunctionMetadata`] field by field; start one with
+/// [`FunctionMetadata::builder`].
+///
+/// Types are written as in a signature, e.g. `double`, `person[]` or `string?`, and
+/// variables without `$`. The builder does not parse `code_block`, so the references,
+/// call sites, constraints and patterns extraction would find in it are empty unless added
+/// with [`referenced_function`], [`call_site`], [`referenced_type`], [`constraint`] and
+/// [`pattern`].
+///
+/// [`referenced_function`]: FunctionMetadataBuilder::referenced_function
+/// [`call_site`]: FunctionMetadataBuilder::call_site
+/// [`referenced_type`]: FunctionMetadataBuilder::referenced_type
+/// [`constraint`]: FunctionMetadataBuilder::constraint
+/// [`pattern`]: FunctionMetadataBuilder::pattern
+#[derive(Debug, Clone)]
+pub struct FunctionMetadataBuilder {
+    metadata: FunctionMetadata,
+    output: Option<OutputType>,
+    return_expression: Option<ReturnExpr>,
+}
+
+impl FunctionMetadata {
+    /// A [`FunctionMetadataBuilder`] for a function called `name`
+    pub fn builder(name: impl Into<String>) -> FunctionMetadataBuilder {
+        FunctionMetadataBuilder::new(name)
+    }
+}
+
+impl FunctionMetadataBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        FunctionMetadataBuilder {
+            metadata: FunctionMetadata {
+                name: name.into(),
+                source_file: None,
+                doc: None,
+                doc_tags: Vec::new(),
+                lint_ignores: Vec::new(),
+                tags: Vec::new(),
+                parameters: Vec::new(),
+                // Replaced in `build`, which fails if no output was given
+                output: OutputType::Single(Vec::new()),
+                return_expression: ReturnExpr::Check,
+                code_block: String::new(),
+                referenced_functions: Vec::new(),
+                call_sites: Vec::new(),
+                referenced_types: Vec::new(),
+                constraints: Vec::new(),
+                patterns: Vec::new(),
+                stages: Vec::new(),
+                spans: FunctionSpans::default(),
+                extensions: BTreeMap::new(),
+            },
+            output: None,
+            return_expression: None,
+        }
+    }
+
+    pub fn source_file(mut self, path: impl Into<String>) -> Self {
+        self.metadata.source_file = Some(path.into());
+        self
+    }
+
+    /// The doc comment, without `#` markers
+    pub fn doc(mut self, doc: impl Into<String>) -> Self {
+        self.metadata.doc = Some(doc.into());
+        self
+    }
+
+    /// Add a doc tag, e.g. `doc_tag("deprecated", "use get_income instead")`
+    pub fn doc_tag(mut self, name: impl Into<String>, text: impl Into<String>) -> Self {
+        self.metadata.doc_tags.push(DocTag {
+            name: name.into(),
+            text: text.into(),
+        });
+        self
+    }
+
+    /// Add a category, as a `@tag` doc tag
+    pub fn tag(self, tag: impl Into<String>) -> Self {
+        self.doc_tag("tag", tag)
+    }
+
+    /// Suppress a lint rule for this function
+    pub fn lint_ignore(mut self, rule: impl Into<String>) -> Self {
+        self.metadata.lint_ignores.push(rule.into());
+        self
+    }
+
+    /// Add a parameter; `type_name` is a built-in value type or a schema label
+    pub fn parameter(mut self, name: impl Into<String>, type_name: impl Into<String>) -> Self {
+        let type_name = type_name.into();
+        self.metadata.parameters.push(Parameter {
+            name: name.into(),
+            kind: parse_type(&type_name).kind,
+            type_name,
+            span: None,
+        });
+        self
+    }
+
+    /// Declare the output `-> a, b`
+    pub fn single_output<T: AsRef<str>>(mut self, types: impl IntoIterator<Item = T>) -> Self {
+        self.output = Some(OutputType::Single(parse_types(types)));
+        self
+    }
+
+    /// Declare the output `-> { a, b }`
+    pub fn stream_output<T: AsRef<str>>(mut self, types: impl IntoIterator<Item = T>) -> Self {
+        self.output = Some(OutputType::Stream(parse_types(types)));
+        self
+    }
+
+    /// The body before the return statement, e.g. `match $p isa person;`
+    pub fn code_block(mut self, code_block: impl Into<String>) -> Self {
+        self.metadata.code_block = code_block.into();
+        self
+    }
+
+    pub fn return_expression(mut self, return_expression: ReturnExpr) -> Self {
+        self.return_expression = Some(return_expression);
+        self
+    }
+
+    /// `return first $a, $b;`
+    pub fn return_first<T: Into<String>>(self, vars: impl IntoIterator<Item = T>) -> Self {
+        self.return_expression(ReturnExpr::Single {
+            selector: Selector::First,
+            vars: vars.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    /// `return last $a, $b;`
+    pub fn return_last<T: Into<String>>(self, vars: impl IntoIterator<Item = T>) -> Self {
+        self.return_expression(ReturnExpr::Single {
+            selector: Selector::Last,
+            vars: vars.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    /// `return { $a, $b };`
+    pub fn return_stream<T: Into<String>>(self, vars: impl IntoIterator<Item = T>) -> Self {
+        self.return_expression(ReturnExpr::Stream {
+            vars: vars.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    /// `return sum($amount), count;` from `(op, var)` pairs
+    pub fn return_reduce<O: Into<String>, V: Into<String>>(
+        self,
+        reducers: impl IntoIterator<Item = (O, Option<V>)>,
+    ) -> Self {
+        let reducers = reducers
+            .into_iter()
+            .map(|(op, var)| Reduction {
+                op: op.into(),
+                var: var.map(Into::into),
+            })
+            .collect();
+        self.return_expression(ReturnExpr::Reduce { reducers })
+    }
+
+    /// `return check;`
+    pub fn return_check(self) -> Self {
+        self.return_expression(ReturnExpr::Check)
+    }
+
+    pub fn referenced_function(mut self, name: impl Into<String>) -> Self {
+        self.metadata.referenced_functions.push(name.into());
+        self
+    }
+
+    /// Add a call to `function` with the given arguments, also referencing the function
+    pub fn call_site(mut self, function: impl Into<String>, arguments: Vec<Expr>) -> Self {
+        let function = function.into();
+        if !self.metadata.referenced_functions.contains(&function) {
+            self.metadata.referenced_functions.push(function.clone());
+        }
+        self.metadata.call_sites.push(CallSite {
+            function,
+            arguments,
+            span: None,
+            negation_depth: 0,
+        });
+        self
+    }
+
+    pub fn referenced_type(mut self, label: impl Into<String>) -> Self {
+        self.metadata.referenced_types.push(label.into());
+        self
+    }
+
+    pub fn constraint(mut self, constraint: Constraint) -> Self {
+        self.metadata.constraints.push(constraint);
+        self
+    }
+
+    pub fn pattern(mut self, pattern: BodyPattern) -> Self {
+        self.metadata.patterns.push(pattern);
+        self
+    }
+
+    /// Add a stage of the body, e.g. `stage(StageKind::Reduce)`
+    pub fn stage(mut self, stage: StageKind) -> Self {
+        self.metadata.stages.push(stage);
+        self
+    }
+
+    /// The finished metadata.
+    ///
+    /// Fails when the name is empty, a parameter is repeated, no output was declared, no
+    /// return statement was given, or the return statement gives a different number of
+    /// values than the output declares.
+    pub fn build(self) -> Result<FunctionMetadata, ExtractError> {
+        let invalid = |message: String| ExtractError::InvalidMetadata { message };
+        let mut metadata = self.metadata;
+        if metadata.name.is_empty() {
+            return Err(invalid("function has no name".to_string()));
+        }
+        for (position, parameter) in metadata.parameters.iter().enumerate() {
+            if metadata.parameters[..position]
+                .iter()
+                .any(|earlier| earlier.name == parameter.name)
+            {
+                return Err(invalid(format!(
+                    "{} declares parameter ${} twice",
+                    metadata.name, parameter.name
+                )));
+            }
+        }
+        metadata.output = match self.output {
+            Some(output) if !output.types().is_empty() => output,
+            _ => return Err(invalid(format!("{} declares no output", metadata.name))),
+        };
+        metadata.tags = categories(&metadata.doc_tags);
+        metadata.return_expression = self
+            .return_expression
+            .ok_or_else(|| invalid(format!("{} has no return statement", metadata.name)))?;
+
+        let returned = metadata.returned_values().len();
+        let declared = metadata.output.types().len();
+        if !matches!(metadata.return_expression, ReturnExpr::Check) && returned != declared {
+            return Err(invalid(format!(
+                "{} returns {} values but its output declares {}",
+                metadata.name, returned, declared
+            )));
+        }
+        Ok(metadata)
+    }
+}
+
+fn parse_types<T: AsRef<str>>(types: impl IntoIterator<Item = T>) -> Vec<TypeName> {
+    types
+        .into_iter()
+        .map(|type_name| parse_type(type_name.as_ref()))
+        .collect()
+}
+
+/// A type written as in a signature, with `[]` and `?` read into `list` and `optional`
+fn parse_type(text: &str) -> TypeName {
+    let list = text.ends_with("[]");
+    let optional = text.ends_with('?');
+    let name = text.trim_end_matches(['[', ']', '?']).to_string();
+    let kind = if VALUE_TYPES.contains(&name.as_str()) {
+        TypeKind::Builtin
+    } else {
+        TypeKind::Label
+    };
+    TypeName {
+        name,
+        kind,
+        list,
+        optional,
+    }
+}
@@ -0,0 +1,621 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+
+use crate::error::ExtractError;
+use crate::extract_function_metadata::FunctionMetadata;
+use crate::namespace::Namespaces;
+
+/// Directed graph of function calls built from `referenced_functions`.
+///
+/// Nodes are the functions that were passed in, kept in their original order. Calls to
+/// functions outside that set are kept as edges (see [`CallGraph::callees`]) but are
+/// ignored when ordering functions or looking for cycles.
+#[derive(Debug, Clone)]
+pub struct CallGraph {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    callees: Vec<Vec<String>>,
+    /// Drawn as clusters by the renderers
+    namespaces: Namespaces,
+}
+
+impl CallGraph {
+    /// Build the call graph for a set of functions
+    pub fn from_functions(functions: &[FunctionMetadata]) -> Self {
+        let mut graph = CallGraph {
+            names: Vec::new(),
+            index: HashMap::new(),
+            callees: Vec::new(),
+            namespaces: Namespaces::default(),
+        };
+
+        for function in functions {
+            // A function defined twice keeps the first definition's position
+            let node = *graph.index.entry(function.name.clone()).or_insert_with(|| {
+                graph.names.push(function.name.clone());
+                graph.callees.push(Vec::new());
+                graph.names.len() - 1
+            });
+            for callee in &function.referenced_functions {
+                if !graph.callees[node].contains(callee) {
+                    graph.callees[node].push(callee.clone());
+                }
+            }
+        }
+
+        graph
+    }
+
+    /// Draw the functions of each namespace as a cluster in [`CallGraph::to_dot`] and
+    /// [`CallGraph::to_mermaid`]
+    pub fn with_namespaces(mut self, namespaces: Namespaces) -> Self {
+        self.namespaces = namespaces;
+        self
+    }
+
+    /// Names of all functions in the graph, in input order
+    pub fn functions(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(String::as_str)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.index.contains_key(name)
+    }
+
+    /// Functions called directly by `name`, including calls to unknown functions
+    pub fn callees(&self, name: &str) -> &[String] {
+        match self.index.get(name) {
+            Some(&node) => &self.callees[node],
+            None => &[],
+        }
+    }
+
+    /// Whether `caller` calls `callee` directly
+    pub fn calls(&self, caller: &str, callee: &str) -> bool {
+        self.callees(caller).iter().any(|name| name == callee)
+    }
+
+    /// Functions in the graph that call `name` directly, in input order
+    pub fn callers_of(&self, name: &str) -> Vec<&str> {
+        self.names
+            .iter()
+            .zip(&self.callees)
+            .filter(|(_, callees)| callees.iter().any(|callee| callee == name))
+            .map(|(caller, _)| caller.as_str())
+            .collect()
+    }
+
+    /// Every function that reaches `name` through one or more calls, nearest callers first.
+    ///
+    /// `name` itself is included only if it is recursive.
+    pub fn all_callers_of(&self, name: &str) -> Vec<&str> {
+        let mut callers: Vec<&str> = Vec::new();
+        let mut queue = VecDeque::from([name]);
+
+        while let Some(callee) = queue.pop_front() {
+            for caller in self.callers_of(callee) {
+                if !callers.contains(&caller) {
+                    callers.push(caller);
+                    queue.push_back(caller);
+                }
+            }
+        }
+
+        callers
+    }
+
+    /// Every chain of calls from `from` to `to` of at most `max_depth` calls, shortest
+    /// first, each listing the functions from `from` to `to`.
+    ///
+    /// Chains never pass through a function twice, so recursion does not repeat them,
+    /// except that `from` and `to` may be the same function to find its cycles. `to` may
+    /// be a function called but not in the graph.
+    pub fn paths(&self, from: &str, to: &str, max_depth: usize) -> Vec<Vec<&str>> {
+        let mut paths = Vec::new();
+        if let Some((name, _)) = self.index.get_key_value(from) {
+            self.collect_paths(vec![name.as_str()], to, max_depth, &mut paths);
+        }
+        // Stable, so chains of equal length keep the order calls are written in
+        paths.sort_by_key(Vec::len);
+        paths
+    }
+
+    /// `entries` and every function in the graph they reach through calls, in input order
+    pub fn reachable_from(&self, entries: &[&str]) -> Vec<&str> {
+        let mut reached = vec![false; self.names.len()];
+        let mut stack: Vec<usize> = entries
+            .iter()
+            .filter_map(|entry| self.index.get(*entry).copied())
+            .collect();
+
+        while let Some(node) = stack.pop() {
+            if !std::mem::replace(&mut reached[node], true) {
+                stack.extend(self.known_callees(node));
+            }
+        }
+
+        self.names
+            .iter()
+            .zip(reached)
+            .filter(|(_, reached)| *reached)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Functions in the graph that no other function calls, leaving out `entries`, in
+    /// input order. A function calling only itself is still an orphan.
+    pub fn orphans(&self, entries: &[&str]) -> Vec<&str> {
+        let mut called = vec![false; self.names.len()];
+        for (caller, _) in self.names.iter().enumerate() {
+            for callee in self.known_callees(caller) {
+                if callee != caller {
+                    called[callee] = true;
+                }
+            }
+        }
+
+        self.names
+            .iter()
+            .zip(called)
+            .filter(|(name, called)| !called && !entries.contains(&name.as_str()))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Order functions so that every function comes after the functions it calls.
+    ///
+    /// Fails with [`ExtractError::CyclicCalls`] if the graph contains a cycle.
+    pub fn topological_order(&self) -> Result<Vec<&str>, ExtractError> {
+        let mut order = Vec::with_capacity(self.names.len());
+        let mut state = vec![Visit::New; self.names.len()];
+
+        for node in 0..self.names.len() {
+            if let Some(cycle) = self.post_order(node, &mut state, &mut order, &mut Vec::new()) {
+                return Err(ExtractError::CyclicCalls { cycle });
+            }
+        }
+
+        Ok(order
+            .into_iter()
+            .map(|node| self.names[node].as_str())
+            .collect())
+    }
+
+    /// A call cycle for each call back into the current path of a depth-first search, each
+    /// listed from its entry function back to itself.
+    ///
+    /// Self-recursive functions appear as `[f, f]`. Not every cycle is listed, so a
+    /// function may be recursive without appearing in any; use
+    /// [`recursive_components`](CallGraph::recursive_components) to tell which functions
+    /// are.
+    pub fn cycles(&self) -> Vec<Vec<&str>> {
+        let mut cycles = Vec::new();
+        let mut state = vec![Visit::New; self.names.len()];
+        let mut path = Vec::new();
+
+        for node in 0..self.names.len() {
+            self.collect_cycles(node, &mut state, &mut path, &mut cycles);
+        }
+
+        cycles
+    }
+
+    /// The strongly connected components: groups of functions that all reach each other
+    /// through calls, with every function in exactly one group.
+    ///
+    /// A group comes after the groups it calls into, and lists its functions in input
+    /// order.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<&str>> {
+        let mut tarjan = Tarjan {
+            index: vec![None; self.names.len()],
+            low: vec![0; self.names.len()],
+            on_stack: vec![false; self.names.len()],
+            stack: Vec::new(),
+            next_index: 0,
+            components: Vec::new(),
+        };
+        for node in 0..self.names.len() {
+            if tarjan.index[node].is_none() {
+                self.connect(node, &mut tarjan);
+            }
+        }
+
+        tarjan
+            .components
+            .into_iter()
+            .map(|mut component| {
+                component.sort_unstable();
+                component
+                    .into_iter()
+                    .map(|node| self.names[node].as_str())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The strongly connected components that are recursive: several mutually recursive
+    /// functions, or one function calling itself
+    pub fn recursive_components(&self) -> Vec<Vec<&str>> {
+        self.strongly_connected_components()
+            .into_iter()
+            .filter(|component| component.len() > 1 || self.calls(component[0], component[0]))
+            .collect()
+    }
+
+    pub fn has_cycles(&self) -> bool {
+        !self.recursive_components().is_empty()
+    }
+
+    /// Whether `name` can reach itself through its calls: it calls itself, or shares a
+    /// strongly connected component with other functions
+    pub fn is_recursive(&self, name: &str) -> bool {
+        self.recursive_components()
+            .iter()
+            .any(|component| component.contains(&name))
+    }
+
+    /// Render the graph in Graphviz DOT format.
+    ///
+    /// Functions and calls that are part of a cycle are drawn in red; calls to functions
+    /// outside the graph point at dashed nodes. Functions in a namespace are boxed in a
+    /// cluster labelled with it.
+    pub fn to_dot(&self) -> String {
+        let (cyclic_nodes, cyclic_edges) = self.cycle_members();
+        let mut out = String::new();
+
+        writeln!(out, "digraph functions {{").unwrap();
+        writeln!(out, "    rankdir=LR;").unwrap();
+        writeln!(out, "    node [shape=box, fontname=\"Helvetica\"];").unwrap();
+
+        for (namespace, members) in self.clusters() {
+            writeln!(
+                out,
+                "    subgraph {} {{",
+                dot_id(&format!("cluster_{}", namespace))
+            )
+            .unwrap();
+            writeln!(
+                out,
+                "        label={}; style=\"rounded,filled\"; fillcolor=\"#eef3fb\";",
+                dot_id(namespace)
+            )
+            .unwrap();
+            for name in members {
+                let style = if cyclic_nodes.contains(name) {
+                    " [color=red, fontcolor=red]"
+                } else {
+                    ""
+                };
+                writeln!(out, "        {}{};", dot_id(name), style).unwrap();
+            }
+            writeln!(out, "    }}").unwrap();
+        }
+        for name in &self.names {
+            if self.namespaces.namespace_of(name).is_some() {
+                continue;
+            }
+            let style = if cyclic_nodes.contains(name.as_str()) {
+                " [color=red, fontcolor=red]"
+            } else {
+                ""
+            };
+            writeln!(out, "    {}{};", dot_id(name), style).unwrap();
+        }
+        for name in self.unknown_callees() {
+            writeln!(out, "    {} [style=dashed];", dot_id(name)).unwrap();
+        }
+
+        for (caller, callees) in self.names.iter().zip(&self.callees) {
+            for callee in callees {
+                let style = if cyclic_edges.contains(&(caller.as_str(), callee.as_str())) {
+                    " [color=red]"
+                } else {
+                    ""
+                };
+                writeln!(
+                    out,
+                    "    {} -> {}{};",
+                    dot_id(caller),
+                    dot_id(callee),
+                    style
+                )
+                .unwrap();
+            }
+        }
+
+        writeln!(out, "}}").unwrap();
+        out
+    }
+
+    /// Render the graph as a Mermaid flowchart for embedding in Markdown.
+    ///
+    /// Uses the same conventions as [`CallGraph::to_dot`]: cycles in red, unknown
+    /// functions dashed, namespaces as subgraphs.
+    pub fn to_mermaid(&self) -> String {
+        let (cyclic_nodes, cyclic_edges) = self.cycle_members();
+        let unknown = self.unknown_callees();
+        let ids: HashMap<&str, String> = self
+            .names
+            .iter()
+            .map(String::as_str)
+            .chain(unknown.iter().copied())
+            .enumerate()
+            .map(|(position, name)| (name, format!("f{}", position)))
+            .collect();
+        let mut out = String::new();
+
+        writeln!(out, "flowchart LR").unwrap();
+        for (position, (namespace, members)) in self.clusters().into_iter().enumerate() {
+            writeln!(
+                out,
+                "    subgraph ns{}[\"{}\"]",
+                position,
+                namespace.replace('"', "#quot;")
+            )
+            .unwrap();
+            for name in members {
+                writeln!(
+                    out,
+                    "        {}[\"{}\"]",
+                    ids[name],
+                    name.replace('"', "#quot;")
+                )
+                .unwrap();
+            }
+            writeln!(out, "    end").unwrap();
+        }
+        for name in self
+            .names
+            .iter()
+            .map(String::as_str)
+            .filter(|name| self.namespaces.namespace_of(name).is_none())
+            .chain(unknown.iter().copied())
+        {
+            writeln!(
+                out,
+                "    {}[\"{}\"]",
+                ids[name],
+                name.replace('"', "#quot;")
+            )
+            .unwrap();
+        }
+
+        let mut cyclic_links = Vec::new();
+        let mut link = 0;
+        for (caller, callees) in self.names.iter().zip(&self.callees) {
+            for callee in callees {
+                writeln!(
+                    out,
+                    "    {} --> {}",
+                    ids[caller.as_str()],
+                    ids[callee.as_str()]
+                )
+                .unwrap();
+                if cyclic_edges.contains(&(caller.as_str(), callee.as_str())) {
+                    cyclic_links.push(link.to_string());
+                }
+                link += 1;
+            }
+        }
+
+        if !cyclic_nodes.is_empty() {
+            writeln!(out, "    classDef cycle stroke:red,color:red").unwrap();
+            let members: Vec<&str> = self
+                .names
+                .iter()
+                .filter(|name| cyclic_nodes.contains(name.as_str()))
+                .map(|name| ids[name.as_str()].as_str())
+                .collect();
+            writeln!(out, "    class {} cycle", members.join(",")).unwrap();
+            writeln!(out, "    linkStyle {} stroke:red", cyclic_links.join(",")).unwrap();
+        }
+        if !unknown.is_empty() {
+            writeln!(out, "    classDef external stroke-dasharray:5 5").unwrap();
+            let members: Vec<&str> = unknown.iter().map(|name| ids[name].as_str()).collect();
+            writeln!(out, "    class {} external", members.join(",")).unwrap();
+        }
+
+        out
+    }
+
+    /// Each namespace with its functions in the graph, in input order
+    fn clusters(&self) -> Vec<(&str, Vec<&str>)> {
+        let mut clusters: Vec<(&str, Vec<&str>)> = Vec::new();
+        for namespace in self.namespaces.names() {
+            let members: Vec<&str> = self
+                .names
+                .iter()
+                .map(String::as_str)
+                .filter(|name| self.namespaces.namespace_of(name) == Some(namespace))
+                .collect();
+            if !members.is_empty() {
+                clusters.push((namespace, members));
+            }
+        }
+        clusters
+    }
+
+    /// Called functions that are not part of the graph, in first-seen order
+    fn unknown_callees(&self) -> Vec<&str> {
+        let mut unknown: Vec<&str> = Vec::new();
+        for callee in self.callees.iter().flatten() {
+            if !self.contains(callee) && !unknown.contains(&callee.as_str()) {
+                unknown.push(callee);
+            }
+        }
+        unknown
+    }
+
+    /// Functions and call edges that take part in at least one cycle: members of a
+    /// recursive component and the calls between members of the same component
+    fn cycle_members(&self) -> (HashSet<&str>, HashSet<(&str, &str)>) {
+        let mut nodes = HashSet::new();
+        let mut edges = HashSet::new();
+        for component in self.recursive_components() {
+            for &caller in &component {
+                for callee in self.callees(caller) {
+                    if component.contains(&callee.as_str()) {
+                        edges.insert((caller, callee.as_str()));
+                    }
+                }
+            }
+            nodes.extend(component);
+        }
+        (nodes, edges)
+    }
+
+    fn known_callees(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        self.callees[node]
+            .iter()
+            .filter_map(|callee| self.index.get(callee).copied())
+    }
+
+    /// Depth-first post-order; returns the first cycle encountered, if any
+    fn post_order(
+        &self,
+        node: usize,
+        state: &mut [Visit],
+        order: &mut Vec<usize>,
+        path: &mut Vec<usize>,
+    ) -> Option<Vec<String>> {
+        match state[node] {
+            Visit::Done => return None,
+            Visit::InProgress => return Some(self.cycle_names(path, node)),
+            Visit::New => {}
+        }
+
+        state[node] = Visit::InProgress;
+        path.push(node);
+        for callee in self.known_callees(node) {
+            if let Some(cycle) = self.post_order(callee, state, order, path) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        state[node] = Visit::Done;
+        order.push(node);
+        None
+    }
+
+    /// Tarjan's algorithm from `node`, adding each component once all of it is visited
+    fn connect(&self, node: usize, tarjan: &mut Tarjan) {
+        tarjan.index[node] = Some(tarjan.next_index);
+        tarjan.low[node] = tarjan.next_index;
+        tarjan.next_index += 1;
+        tarjan.stack.push(node);
+        tarjan.on_stack[node] = true;
+
+        for callee in self.known_callees(node) {
+            match tarjan.index[callee] {
+                None => {
+                    self.connect(callee, tarjan);
+                    tarjan.low[node] = tarjan.low[node].min(tarjan.low[callee]);
+                }
+                Some(index) if tarjan.on_stack[callee] => {
+                    tarjan.low[node] = tarjan.low[node].min(index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if Some(tarjan.low[node]) == tarjan.index[node] {
+            let mut component = Vec::new();
+            while let Some(member) = tarjan.stack.pop() {
+                tarjan.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            tarjan.components.push(component);
+        }
+    }
+
+    fn collect_paths<'a>(
+        &'a self,
+        path: Vec<&'a str>,
+        to: &str,
+        max_depth: usize,
+        paths: &mut Vec<Vec<&'a str>>,
+    ) {
+        if path.len() > max_depth {
+            return;
+        }
+        for callee in self.callees(path[path.len() - 1]) {
+            if callee == to {
+                let mut found = path.clone();
+                found.push(callee);
+                paths.push(found);
+            } else if !path.contains(&callee.as_str()) {
+                let mut next = path.clone();
+                next.push(callee);
+                self.collect_paths(next, to, max_depth, paths);
+            }
+        }
+    }
+
+    fn collect_cycles<'a>(
+        &'a self,
+        node: usize,
+        state: &mut [Visit],
+        path: &mut Vec<usize>,
+        cycles: &mut Vec<Vec<&'a str>>,
+    ) {
+        if state[node] != Visit::New {
+            return;
+        }
+
+        state[node] = Visit::InProgress;
+        path.push(node);
+        for callee in self.known_callees(node) {
+            match state[callee] {
+                Visit::New => self.collect_cycles(callee, state, path, cycles),
+                Visit::InProgress => {
+                    let start = path.iter().position(|&n| n == callee).unwrap_or(0);
+                    let mut cycle: Vec<&str> = path[start..]
+                        .iter()
+                        .map(|&n| self.names[n].as_str())
+                        .collect();
+                    cycle.push(self.names[callee].as_str());
+                    cycles.push(cycle);
+                }
+                Visit::Done => {}
+            }
+        }
+        path.pop();
+        state[node] = Visit::Done;
+    }
+
+    fn cycle_names(&self, path: &[usize], repeated: usize) -> Vec<String> {
+        let start = path.iter().position(|&n| n == repeated).unwrap_or(0);
+        path[start..]
+            .iter()
+            .chain(std::iter::once(&repeated))
+            .map(|&n| self.names[n].clone())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    New,
+    InProgress,
+    Done,
+}
+
+/// State of a strongly connected components search
+struct Tarjan {
+    /// Visit order of each node, `None` until visited
+    index: Vec<Option<usize>>,
+    /// Lowest visit order reachable from each node within its component
+    low: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next_index: usize,
+    components: Vec<Vec<usize>>,
+}
+
+/// Quote a function name as a DOT identifier
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\\\""))
+}
@@ -0,0 +1,220 @@
+//! A meta-schema for storing function metadata in TypeDB, so a function library can be
+//! explored with TypeQL itself.
+//!
+//! Define [`CATALOG_SCHEMA`] in a database, run the queries from [`catalog_inserts`], then
+//! ask for example which functions read people and what they call:
+//!
+//! ```typeql
+//! match
+//!     $t isa schema-type, has type-label "person";
+//!     references-type (function: $f, schema-type: $t);
+//!     calls (caller: $f, callee: $g);
+//!     $f has function-name $name;
+//!     $g has function-name $callee;
+//! ```
+//!
+//! Functions that are called but not among those inserted get a `function` with only its
+//! name, so `not { $f has code-block $c; }` finds calls to undefined functions.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::data::quote;
+use crate::extract_function_metadata::{FunctionMetadata, TypeKind};
+
+/// TypeQL `define` for the catalog: functions, their parameters, the calls between them
+/// and the schema types they refer to
+pub const CATALOG_SCHEMA: &str = "\
+define
+    attribute function-name, value string;
+    attribute signature, value string;
+    attribute output-type, value string;
+    attribute streams, value boolean;
+    attribute doc, value string;
+    attribute code-block, value string;
+    attribute source-file, value string;
+    attribute fingerprint, value string;
+    attribute parameter-name, value string;
+    attribute type-name, value string;
+    attribute position, value integer;
+    attribute type-label, value string;
+
+    entity function,
+        owns function-name @key,
+        owns signature,
+        owns output-type,
+        owns streams,
+        owns doc,
+        owns code-block,
+        owns source-file,
+        owns fingerprint,
+        plays parameter-of:function,
+        plays calls:caller,
+        plays calls:callee,
+        plays references-type:function;
+
+    entity parameter,
+        owns parameter-name,
+        owns type-name,
+        owns position,
+        plays parameter-of:parameter;
+
+    entity schema-type,
+        owns type-label @key,
+        plays references-type:schema-type;
+
+    relation parameter-of,
+        relates function,
+        relates parameter;
+
+    relation calls,
+        relates caller,
+        relates callee;
+
+    relation references-type,
+        relates function,
+        relates schema-type;
+";
+
+/// `insert` queries storing `functions` under [`CATALOG_SCHEMA`], to run in order in one
+/// write transaction.
+///
+/// Each function is inserted with its parameters, then every schema type they refer to in
+/// their body, parameters or output, then the `calls` and `references-type` relations
+/// between them. Calls to built-in functions are left out. Only the first of several
+/// functions with the same name is stored, as names are keys.
+pub fn catalog_inserts(functions: &[FunctionMetadata]) -> Vec<String> {
+    let mut queries = Vec::new();
+    let mut stored = HashSet::new();
+    let functions: Vec<&FunctionMetadata> = functions
+        .iter()
+        .filter(|function| stored.insert(function.name.as_str()))
+        .collect();
+
+    for function in &functions {
+        queries.push(function_insert(function));
+    }
+
+    let mut placeholders = HashSet::new();
+    for function in &functions {
+        for callee in &function.referenced_functions {
+            if !stored.contains(callee.as_str()) && placeholders.insert(callee.as_str()) {
+                queries.push(format!(
+                    "insert\n    $f isa function, has function-name {};\n",
+                    quote(callee)
+                ));
+            }
+        }
+    }
+
+    let mut labels = HashSet::new();
+    let mut references = Vec::new();
+    for function in &functions {
+        let mut referenced = HashSet::new();
+        for label in type_labels(function) {
+            if labels.insert(label) {
+                queries.push(format!(
+                    "insert\n    $t isa schema-type, has type-label {};\n",
+                    quote(label)
+                ));
+            }
+            if referenced.insert(label) {
+                references.push((function.name.as_str(), label));
+            }
+        }
+    }
+
+    for function in &functions {
+        let mut called = HashSet::new();
+        for callee in &function.referenced_functions {
+            if !called.insert(callee.as_str()) {
+                continue;
+            }
+            queries.push(format!(
+                "match\n    $caller isa function, has function-name {};\n    \
+                 $callee isa function, has function-name {};\n\
+                 insert\n    $call isa calls, links (caller: $caller, callee: $callee);\n",
+                quote(&function.name),
+                quote(callee)
+            ));
+        }
+    }
+
+    for (name, label) in references {
+        queries.push(format!(
+            "match\n    $f isa function, has function-name {};\n    \
+             $t isa schema-type, has type-label {};\n\
+             insert\n    $r isa references-type, links (function: $f, schema-type: $t);\n",
+            quote(name),
+            quote(label)
+        ));
+    }
+
+    queries
+}
+
+fn function_insert(function: &FunctionMetadata) -> String {
+    let output = function
+        .output
+        .types()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut statement = format!(
+        "$f isa function, has function-name {}, has signature {}, has output-type {}, \
+         has streams {}, has code-block {}, has fingerprint {}",
+        quote(&function.name),
+        quote(&function.signature()),
+        quote(&output),
+        function.output.is_stream(),
+        quote(&function.code_block),
+        quote(&function.fingerprint())
+    );
+    if let Some(doc) = &function.doc {
+        write!(statement, ", has doc {}", quote(doc)).unwrap();
+    }
+    if let Some(source_file) = &function.source_file {
+        write!(statement, ", has source-file {}", quote(source_file)).unwrap();
+    }
+
+    let mut out = String::from("insert\n");
+    writeln!(out, "    {};", statement).unwrap();
+    for (position, parameter) in function.parameters.iter().enumerate() {
+        writeln!(
+            out,
+            "    $p{} isa parameter, has parameter-name {}, has type-name {}, has position {};",
+            position,
+            quote(&parameter.name),
+            quote(&parameter.type_name),
+            position
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    $l{} isa parameter-of, links (function: $f, parameter: $p{});",
+            position, position
+        )
+        .unwrap();
+    }
+    out
+}
+
+/// Schema labels the function refers to, in order of appearance and possibly repeated
+fn type_labels(function: &FunctionMetadata) -> impl Iterator<Item = &str> {
+    let parameters = function
+        .parameters
+        .iter()
+        .filter(|parameter| parameter.kind == TypeKind::Label)
+        .map(|parameter| parameter.type_name.as_str());
+    let outputs = function
+        .output
+        .types()
+        .iter()
+        .filter(|output| output.kind == TypeKind::Label)
+        .map(|output| output.name.as_str());
+    parameters
+        .chain(outputs)
+        .chain(function.referenced_types.iter().map(String::as_str))
+}
@@ -0,0 +1,153 @@
+//! Reading function libraries from a running TypeDB server (requires the `client` feature)
+
+use typedb_driver::{Credentials, DriverOptions, TransactionType, TypeDBDriver};
+
+use crate::catalog::{catalog_inserts, CATALOG_SCHEMA};
+use crate::error::ExtractError;
+use crate::extract_function_metadata::{extract_functions_in, FunctionMetadata};
+use crate::sync::{detect_drift, plan_sync, DriftReport, SyncMode, SyncPlan};
+
+/// Connection settings for a TypeDB server
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// `host:port`, e.g. `localhost:1729`
+    pub address: String,
+    pub username: String,
+    pub password: String,
+    pub tls: bool,
+}
+
+impl Default for ServerConfig {
+    /// A local development server: `localhost:1729`, `admin`/`password`, no TLS
+    fn default() -> Self {
+        ServerConfig {
+            address: "localhost:1729".to_string(),
+            username: "admin".to_string(),
+            password: "password".to_string(),
+            tls: false,
+        }
+    }
+}
+
+/// Open a driver connection to the server described by `config`
+pub async fn connect(config: &ServerConfig) -> Result<TypeDBDriver, ExtractError> {
+    let options = DriverOptions::new(config.tls, None)?;
+    TypeDBDriver::new(
+        &config.address,
+        Credentials::new(&config.username, &config.password),
+        options,
+    )
+    .await
+    .map_err(|error| {
+        ExtractError::driver(
+            format!("failed to connect to TypeDB at {}", config.address),
+            error,
+        )
+    })
+}
+
+/// The full schema of `database` as TypeQL `define` text, functions included
+pub async fn fetch_schema(driver: &TypeDBDriver, database: &str) -> Result<String, ExtractError> {
+    let database = driver.databases().get(database).await.map_err(|error| {
+        ExtractError::driver(format!("failed to open database `{}`", database), error)
+    })?;
+    Ok(database.schema().await?)
+}
+
+/// Extract metadata for every function defined in `database`.
+///
+/// Functions are returned in the order the server lists them, with spans relative to the
+/// schema text returned by [`fetch_schema`].
+pub async fn fetch_functions(
+    driver: &TypeDBDriver,
+    database: &str,
+) -> Result<Vec<FunctionMetadata>, ExtractError> {
+    let schema = fetch_schema(driver, database).await?;
+    extract_functions_in(&schema).map_err(|diagnostic| {
+        ExtractError::from_diagnostic(diagnostic, &format!("<{}>", database), &schema)
+    })
+}
+
+/// Make the functions in `database` match `functions`: define new ones, redefine changed
+/// ones and undefine those no longer present.
+///
+/// All statements run in a single schema transaction, so a failure leaves the database
+/// untouched. In [`SyncMode::DryRun`] nothing is sent; either way the plan is returned.
+pub async fn sync_functions(
+    driver: &TypeDBDriver,
+    database: &str,
+    functions: &[FunctionMetadata],
+    mode: SyncMode,
+) -> Result<SyncPlan, ExtractError> {
+    let deployed = fetch_functions(driver, database).await?;
+    let plan = plan_sync(functions, &deployed);
+    if mode == SyncMode::DryRun || plan.is_empty() {
+        return Ok(plan);
+    }
+
+    let transaction = driver
+        .transaction(database, TransactionType::Schema)
+        .await?;
+    for statement in &plan.statements {
+        transaction.query(&statement.query).await.map_err(|error| {
+            ExtractError::driver(
+                format!("failed to {} `{}`", statement.action, statement.function),
+                error,
+            )
+        })?;
+    }
+    transaction.commit().await?;
+
+    Ok(plan)
+}
+
+/// Compare `functions` with those deployed to `database`
+pub async fn check_drift(
+    driver: &TypeDBDriver,
+    database: &str,
+    functions: &[FunctionMetadata],
+) -> Result<DriftReport, ExtractError> {
+    let deployed = fetch_functions(driver, database).await?;
+    Ok(detect_drift(functions, &deployed))
+}
+
+/// Replace the function catalog in `database` with `functions`, defining
+/// [`CATALOG_SCHEMA`] first if needed.
+///
+/// Existing catalog functions, parameters and schema types are deleted and the new ones
+/// inserted in a single write transaction. Returns the number of insert queries run.
+pub async fn store_catalog(
+    driver: &TypeDBDriver,
+    database: &str,
+    functions: &[FunctionMetadata],
+) -> Result<usize, ExtractError> {
+    let transaction = driver
+        .transaction(database, TransactionType::Schema)
+        .await?;
+    transaction
+        .query(CATALOG_SCHEMA)
+        .await
+        .map_err(|error| ExtractError::driver("failed to define the catalog schema", error))?;
+    transaction.commit().await?;
+
+    let transaction = driver.transaction(database, TransactionType::Write).await?;
+    // Relations left without players are removed along with them
+    for label in ["parameter", "function", "schema-type"] {
+        transaction
+            .query(&format!("match $x isa {};\ndelete $x;\n", label))
+            .await
+            .map_err(|error| {
+                ExtractError::driver(format!("failed to delete catalog `{}`s", label), error)
+            })?;
+    }
+    let queries = catalog_inserts(functions);
+    for query in &queries {
+        transaction
+            .query(query)
+            .await
+            .map_err(|error| ExtractError::driver(format!("failed to run\n{}", query), error))?;
+    }
+    transaction.commit().await?;
+
+    Ok(queries.len())
+}
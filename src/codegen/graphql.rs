@@ -0,0 +1,281 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use super::{identifier, type_identifier, Binding, Field};
+use crate::extract_function_metadata::{FunctionMetadata, TypeKind, TypeName};
+use crate::extract_schema_metadata::{SchemaKind, SchemaMetadata, TypeDefinition};
+
+/// Render a GraphQL schema (SDL) with a `Query` field per function and an object type per
+/// entity and relation type in `schema`.
+///
+/// Functions returning a stream become list fields, and functions returning a single
+/// answer nullable fields, since they may have no answer. Functions returning several
+/// values get a `...Row` object type with a field per value. Parameters of schema types
+/// are `ID`s holding the IID of an instance. Answers of entity and relation types resolve
+/// to their object type, and answers of attribute types to their value; without the
+/// schema they are `ID`s too. Integers are a custom `Long` scalar, since GraphQL's `Int`
+/// is 32-bit, and decimals, dates, datetimes and durations custom scalars of their own.
+pub fn render_graphql_schema(functions: &[FunctionMetadata], schema: &SchemaMetadata) -> String {
+    let mut scalars = BTreeSet::new();
+    let mut body = String::new();
+
+    for type_def in schema.entities().chain(schema.relations()) {
+        writeln!(body).unwrap();
+        render_object(&mut body, &mut scalars, schema, type_def);
+    }
+
+    let bindings: Vec<Binding> = functions.iter().map(Binding::new).collect();
+    for binding in &bindings {
+        if binding.columns.len() > 1 {
+            writeln!(body).unwrap();
+            render_row(&mut body, &mut scalars, schema, binding);
+        }
+    }
+
+    if !bindings.is_empty() {
+        writeln!(body).unwrap();
+        writeln!(body, "type Query {{").unwrap();
+        for binding in &bindings {
+            render_query_field(&mut body, &mut scalars, schema, binding);
+        }
+        writeln!(body, "}}").unwrap();
+    }
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "# Generated by typedb-meta from TypeQL function signatures and schema. Do not edit."
+    )
+    .unwrap();
+    if !scalars.is_empty() {
+        writeln!(out).unwrap();
+        for scalar in &scalars {
+            writeln!(out, "scalar {}", scalar).unwrap();
+        }
+    }
+    out.push_str(&body);
+    out
+}
+
+fn render_object(
+    out: &mut String,
+    scalars: &mut BTreeSet<&'static str>,
+    schema: &SchemaMetadata,
+    type_def: &TypeDefinition,
+) {
+    let kind = match type_def.kind {
+        SchemaKind::Relation => "relation",
+        _ => "entity",
+    };
+    render_description(out, "", &format!("The `{}` {}", type_def.label, kind));
+    writeln!(out, "type {} {{", type_identifier(&type_def.label)).unwrap();
+    writeln!(out, "  iid: ID!").unwrap();
+
+    let mut fields: Vec<&str> = Vec::new();
+    for ancestor in schema.lineage(type_def) {
+        for label in &ancestor.owns {
+            if fields.contains(&label.as_str()) {
+                continue;
+            }
+            fields.push(label);
+            let scalar = match schema.get(label) {
+                Some(attribute) => attribute_scalar(schema, attribute),
+                None => "String",
+            };
+            note_scalar(scalars, scalar);
+            writeln!(out, "  {}: {}", identifier(label), scalar).unwrap();
+        }
+    }
+    for ancestor in schema.lineage(type_def) {
+        for role in &ancestor.relates {
+            if fields.contains(&role.as_str()) {
+                continue;
+            }
+            fields.push(role);
+            render_description(
+                out,
+                "  ",
+                &format!("IIDs of the players of `{}:{}`", type_def.label, role),
+            );
+            writeln!(out, "  {}: [ID!]!", identifier(role)).unwrap();
+        }
+    }
+    writeln!(out, "}}").unwrap();
+}
+
+fn render_row(
+    out: &mut String,
+    scalars: &mut BTreeSet<&'static str>,
+    schema: &SchemaMetadata,
+    binding: &Binding,
+) {
+    let function = binding.function;
+    let answer = if function.output.is_stream() {
+        "An"
+    } else {
+        "The"
+    };
+    render_description(
+        out,
+        "",
+        &format!("{} answer of `{}`", answer, function.name),
+    );
+    writeln!(out, "type {}Row {{", type_identifier(&function.name)).unwrap();
+    for column in &binding.columns {
+        writeln!(
+            out,
+            "  {}: {}",
+            column.name,
+            column_type(scalars, schema, column)
+        )
+        .unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+}
+
+fn render_query_field(
+    out: &mut String,
+    scalars: &mut BTreeSet<&'static str>,
+    schema: &SchemaMetadata,
+    binding: &Binding,
+) {
+    let function = binding.function;
+    let mut description = String::new();
+    if let Some(doc) = &function.doc {
+        writeln!(description, "{}", doc).unwrap();
+        writeln!(description).unwrap();
+    }
+    write!(description, "Calls `{}`", function.signature()).unwrap();
+    render_description(out, "  ", &description);
+
+    let arguments: Vec<String> = binding
+        .parameters
+        .iter()
+        .map(|parameter| {
+            format!(
+                "{}: {}",
+                parameter.name,
+                parameter_type(scalars, &parameter.type_name)
+            )
+        })
+        .collect();
+    let answer = if binding.columns.len() == 1 {
+        column_type(scalars, schema, &binding.columns[0])
+    } else {
+        format!("{}Row!", type_identifier(&function.name))
+    };
+    let result = if function.output.is_stream() {
+        format!("[{}]!", answer)
+    } else {
+        answer.trim_end_matches('!').to_string()
+    };
+
+    write!(out, "  {}", identifier(&function.name)).unwrap();
+    if !arguments.is_empty() {
+        write!(out, "({})", arguments.join(", ")).unwrap();
+    }
+    write!(out, ": {}", result).unwrap();
+    if let Some(reason) = function.doc_tag("deprecated") {
+        write!(out, " @deprecated(reason: {:?})", reason).unwrap();
+    }
+    writeln!(out).unwrap();
+}
+
+fn parameter_type(scalars: &mut BTreeSet<&'static str>, type_name: &TypeName) -> String {
+    let scalar = match type_name.kind {
+        TypeKind::Label => "ID",
+        TypeKind::Builtin => builtin_scalar(&type_name.name),
+    };
+    note_scalar(scalars, scalar);
+    wrap(scalar, type_name)
+}
+
+fn column_type(
+    scalars: &mut BTreeSet<&'static str>,
+    schema: &SchemaMetadata,
+    column: &Field,
+) -> String {
+    let type_name = &column.type_name;
+    let scalar = match (type_name.kind, schema.get(&type_name.name)) {
+        (TypeKind::Label, Some(type_def)) if type_def.kind != SchemaKind::Attribute => {
+            type_identifier(&type_def.label)
+        }
+        (TypeKind::Label, Some(attribute)) => {
+            let scalar = attribute_scalar(schema, attribute);
+            note_scalar(scalars, scalar);
+            scalar.to_string()
+        }
+        (TypeKind::Label, None) => "ID".to_string(),
+        (TypeKind::Builtin, _) => {
+            let scalar = builtin_scalar(&type_name.name);
+            note_scalar(scalars, scalar);
+            scalar.to_string()
+        }
+    };
+    wrap(&scalar, type_name)
+}
+
+/// `scalar` with the list and optional markers of `type_name`
+fn wrap(scalar: &str, type_name: &TypeName) -> String {
+    if type_name.list {
+        format!("[{}!]!", scalar)
+    } else if type_name.optional {
+        scalar.to_string()
+    } else {
+        format!("{}!", scalar)
+    }
+}
+
+const CUSTOM_SCALARS: &[&str] = &[
+    "Long",
+    "Decimal",
+    "Date",
+    "DateTime",
+    "DateTimeTZ",
+    "Duration",
+];
+
+fn builtin_scalar(name: &str) -> &'static str {
+    match name {
+        "boolean" => "Boolean",
+        "integer" => "Long",
+        "double" => "Float",
+        "decimal" => "Decimal",
+        "date" => "Date",
+        "datetime" => "DateTime",
+        "datetime-tz" => "DateTimeTZ",
+        "duration" => "Duration",
+        _ => "String",
+    }
+}
+
+/// The scalar holding values of an attribute type, `String` if it declares no value type
+fn attribute_scalar(schema: &SchemaMetadata, attribute: &TypeDefinition) -> &'static str {
+    schema
+        .value_type(attribute)
+        .map_or("String", |value_type| builtin_scalar(value_type))
+}
+
+fn note_scalar(scalars: &mut BTreeSet<&'static str>, scalar: &'static str) {
+    if CUSTOM_SCALARS.contains(&scalar) {
+        scalars.insert(scalar);
+    }
+}
+
+/// A `"""` description, indented by `indent`
+fn render_description(out: &mut String, indent: &str, text: &str) {
+    let text = text.replace("\"\"\"", "\\\"\"\"");
+    if text.contains('\n') {
+        writeln!(out, "{}\"\"\"", indent).unwrap();
+        for line in text.lines() {
+            if line.is_empty() {
+                writeln!(out).unwrap();
+            } else {
+                writeln!(out, "{}{}", indent, line).unwrap();
+            }
+        }
+        writeln!(out, "{}\"\"\"", indent).unwrap();
+    } else {
+        writeln!(out, "{}\"\"\"{}\"\"\"", indent, text).unwrap();
+    }
+}
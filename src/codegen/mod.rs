@@ -0,0 +1,196 @@
+//! Code generated from function signatures and schemas.
+//!
+//! Each function gets a parameter type, a row type for its answers and a helper that
+//! builds the read query calling it:
+//!
+//! ```typeql
+//! match
+//!     $taxpayer iid 0x1e00000000000000000000;
+//!     let $total = calculate_tax($taxpayer, 2024);
+//! ```
+//!
+//! Value parameters are written as literals. Parameters of schema types cannot be, so the
+//! caller passes the IID of an instance and the query binds it first.
+
+mod graphql;
+mod python;
+mod rust;
+mod rust_types;
+mod typescript;
+
+pub use graphql::render_graphql_schema;
+pub use python::render_python_client;
+pub use rust::render_rust_client;
+pub use rust_types::render_rust_types;
+pub use typescript::render_typescript_definitions;
+
+use crate::extract_function_metadata::{FunctionMetadata, Parameter, TypeKind, TypeName};
+
+/// What a generated binding knows about one function
+pub(crate) struct Binding<'a> {
+    pub function: &'a FunctionMetadata,
+    pub parameters: Vec<Field>,
+    /// Answer columns, one per output type
+    pub columns: Vec<Field>,
+}
+
+pub(crate) struct Field {
+    /// snake_case identifier, e.g. `tax_year` for `$tax-year`
+    pub name: String,
+    /// TypeQL variable without `$`
+    pub var: String,
+    pub type_name: TypeName,
+}
+
+/// How a value is written in the generated query
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ValueKind {
+    Boolean,
+    Integer,
+    Double,
+    String,
+    /// A TypeQL literal passed through as written, e.g. `12.50dec` or `2024-04-05`
+    Literal,
+    /// An instance of a schema type, passed by IID
+    Instance,
+}
+
+/// A piece of the query calling a function
+pub(crate) enum QueryPart {
+    Text(String),
+    /// The value of the parameter at this position
+    Argument(usize),
+}
+
+impl<'a> Binding<'a> {
+    pub fn new(function: &'a FunctionMetadata) -> Self {
+        let parameters: Vec<Field> = function
+            .parameters
+            .iter()
+            .map(|parameter| Field {
+                name: identifier(&parameter.name),
+                var: parameter.name.clone(),
+                type_name: parameter_type(parameter),
+            })
+            .collect();
+
+        let mut columns: Vec<Field> = Vec::new();
+        let returned = function.returned_values();
+        for (position, type_name) in function.output.types().iter().enumerate() {
+            let returned_var = returned
+                .get(position)
+                .and_then(|(value, _)| value.strip_prefix('$'))
+                .filter(|var| !var.contains(['(', ' ']));
+            let mut var = match returned_var {
+                Some(var) => var.to_string(),
+                None if function.output.types().len() == 1 => "result".to_string(),
+                None => format!("result_{}", position),
+            };
+            while parameters.iter().any(|parameter| parameter.var == var)
+                || columns.iter().any(|column| column.var == var)
+            {
+                var.push_str("_result");
+            }
+            columns.push(Field {
+                name: identifier(&var),
+                var,
+                type_name: type_name.clone(),
+            });
+        }
+
+        Binding {
+            function,
+            parameters,
+            columns,
+        }
+    }
+
+    /// The query text with an argument marker for each parameter value
+    pub fn query(&self) -> Vec<QueryPart> {
+        let mut parts = vec![QueryPart::Text("match\n".to_string())];
+        for (position, parameter) in self.parameters.iter().enumerate() {
+            if value_kind(&parameter.type_name) == ValueKind::Instance {
+                parts.push(QueryPart::Text(format!("    ${} iid ", parameter.var)));
+                parts.push(QueryPart::Argument(position));
+                parts.push(QueryPart::Text(";\n".to_string()));
+            }
+        }
+
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| format!("${}", column.var))
+            .collect();
+        let assign = if self.function.output.is_stream() {
+            "in"
+        } else {
+            "="
+        };
+        parts.push(QueryPart::Text(format!(
+            "    let {} {} {}(",
+            columns.join(", "),
+            assign,
+            self.function.name
+        )));
+        for (position, parameter) in self.parameters.iter().enumerate() {
+            if position > 0 {
+                parts.push(QueryPart::Text(", ".to_string()));
+            }
+            if value_kind(&parameter.type_name) == ValueKind::Instance {
+                parts.push(QueryPart::Text(format!("${}", parameter.var)));
+            } else {
+                parts.push(QueryPart::Argument(position));
+            }
+        }
+        parts.push(QueryPart::Text(");\n".to_string()));
+        parts
+    }
+}
+
+/// How values of `type_name` are written; lists of instances cannot be bound by IID, so
+/// they are passed as TypeQL
+pub(crate) fn value_kind(type_name: &TypeName) -> ValueKind {
+    match (type_name.kind, type_name.name.as_str()) {
+        (TypeKind::Label, _) if type_name.list => ValueKind::Literal,
+        (TypeKind::Label, _) => ValueKind::Instance,
+        (TypeKind::Builtin, "boolean") => ValueKind::Boolean,
+        (TypeKind::Builtin, "integer") => ValueKind::Integer,
+        (TypeKind::Builtin, "double") => ValueKind::Double,
+        (TypeKind::Builtin, "string") => ValueKind::String,
+        (TypeKind::Builtin, _) => ValueKind::Literal,
+    }
+}
+
+/// A parameter's declared type, with `[]` and `?` read from its type name
+fn parameter_type(parameter: &Parameter) -> TypeName {
+    let list = parameter.type_name.ends_with("[]");
+    let optional = parameter.type_name.ends_with('?');
+    TypeName {
+        name: parameter
+            .type_name
+            .trim_end_matches(['[', ']', '?'])
+            .to_string(),
+        kind: parameter.kind,
+        list,
+        optional,
+    }
+}
+
+/// A TypeQL name as a snake_case identifier, e.g. `tax-year` as `tax_year`
+pub(crate) fn identifier(name: &str) -> String {
+    name.replace(['-', ':'], "_")
+}
+
+/// A TypeQL name as a PascalCase type name, e.g. `get_tax-year` as `GetTaxYear`
+pub(crate) fn type_identifier(name: &str) -> String {
+    name.split(['_', '-', ':'])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
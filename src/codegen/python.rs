@@ -0,0 +1,313 @@
+use std::fmt::Write;
+
+use super::{identifier, type_identifier, value_kind, Binding, Field, QueryPart, ValueKind};
+use crate::extract_function_metadata::{FunctionMetadata, TypeKind, TypeName};
+
+const KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// Helpers shared by every generated binding
+const PRELUDE: &str = r#"from __future__ import annotations
+
+from dataclasses import dataclass
+from datetime import date, datetime
+from decimal import Decimal
+from typing import Any, Callable, List, Optional, TypedDict
+
+
+def _typeql_string(value: str) -> str:
+    return '"' + value.replace("\\", "\\\\").replace('"', '\\"') + '"'
+
+
+def _typeql_boolean(value: bool) -> str:
+    return "true" if value else "false"
+
+
+def _typeql_double(value: float) -> str:
+    return repr(float(value))
+
+
+def _typeql_list(values: List[Any], write: Callable[[Any], str]) -> str:
+    return "[" + ", ".join(write(value) for value in values) + "]"
+
+
+def _decode(concept: Any) -> Any:
+    if concept is None:
+        return None
+    if concept.is_value():
+        return concept.try_get_value()
+    return concept.try_get_iid()
+
+
+def _rows(tx: Any, query: str, columns: List[tuple]) -> List[dict]:
+    answer = tx.query(query).resolve()
+    return [
+        {key: _decode(row.get(var)) for key, var in columns}
+        for row in answer.as_concept_rows()
+    ]
+"#;
+
+/// Render a Python module with typed bindings per function, for use with the TypeDB
+/// Python driver.
+///
+/// Each function `calculate_tax` gets a `CalculateTaxParams` dataclass whose `query()`
+/// builds the read query calling it, a `CalculateTaxRow` TypedDict for one answer, and a
+/// `calculate_tax(tx, params)` helper that runs the query in the given transaction and
+/// returns the rows, or the single row or `None` for functions that do not stream. The
+/// types mirror [`render_typescript_definitions`]: instances of schema types are IID
+/// strings, and decimals, dates, datetimes and durations are passed as TypeQL literals.
+///
+/// [`render_typescript_definitions`]: super::render_typescript_definitions
+pub fn render_python_client(functions: &[FunctionMetadata]) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "# Generated by typedb-meta from TypeQL function signatures. Do not edit."
+    )
+    .unwrap();
+    writeln!(out).unwrap();
+    out.push_str(PRELUDE);
+
+    for function in functions {
+        writeln!(out).unwrap();
+        writeln!(out).unwrap();
+        render_binding(&mut out, &Binding::new(function));
+    }
+    out
+}
+
+fn render_binding(out: &mut String, binding: &Binding) {
+    let function = binding.function;
+    let name = type_identifier(&function.name);
+
+    writeln!(out, "@dataclass").unwrap();
+    writeln!(out, "class {}Params:", name).unwrap();
+    writeln!(out, "    \"\"\"").unwrap();
+    if let Some(doc) = &function.doc {
+        for line in doc.lines() {
+            writeln!(out, "    {}", line).unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+    writeln!(out, "    Parameters of ``{}``", function.signature()).unwrap();
+    if let Some(reason) = function.doc_tag("deprecated") {
+        writeln!(out).unwrap();
+        writeln!(out, "    Deprecated: {}", reason).unwrap();
+    }
+    writeln!(out, "    \"\"\"").unwrap();
+    writeln!(out).unwrap();
+
+    for parameter in &binding.parameters {
+        writeln!(
+            out,
+            "    {}: {}",
+            field_name(parameter),
+            parameter_type(&parameter.type_name)
+        )
+        .unwrap();
+        match value_kind(&parameter.type_name) {
+            ValueKind::Instance => writeln!(
+                out,
+                "    \"\"\"IID of a ``{}`` instance\"\"\"",
+                parameter.type_name.name
+            )
+            .unwrap(),
+            ValueKind::Literal => writeln!(
+                out,
+                "    \"\"\"``{}`` as TypeQL text\"\"\"",
+                parameter.type_name
+            )
+            .unwrap(),
+            _ => {}
+        }
+    }
+    if !binding.parameters.is_empty() {
+        writeln!(out).unwrap();
+    }
+
+    let columns: Vec<String> = binding
+        .columns
+        .iter()
+        .map(|column| format!("({:?}, {:?})", field_name(column), column.var))
+        .collect();
+    writeln!(out, "    COLUMNS = [{}]", columns.join(", ")).unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "    def query(self) -> str:").unwrap();
+    writeln!(
+        out,
+        "        \"\"\"The read query calling ``{}``\"\"\"",
+        function.name
+    )
+    .unwrap();
+    // Without parameters there is nothing to interpolate, and no braces to escape
+    let prefix = if binding.parameters.is_empty() {
+        ""
+    } else {
+        "f"
+    };
+    writeln!(
+        out,
+        "        return {}\"{}\"",
+        prefix,
+        query_template(binding)
+    )
+    .unwrap();
+    writeln!(out).unwrap();
+    writeln!(out).unwrap();
+
+    let answer = if function.output.is_stream() {
+        "An"
+    } else {
+        "The"
+    };
+    writeln!(out, "class {}Row(TypedDict):", name).unwrap();
+    writeln!(
+        out,
+        "    \"\"\"{} answer of ``{}``\"\"\"",
+        answer, function.name
+    )
+    .unwrap();
+    writeln!(out).unwrap();
+    for column in &binding.columns {
+        writeln!(
+            out,
+            "    {}: {}",
+            field_name(column),
+            column_type(&column.type_name)
+        )
+        .unwrap();
+    }
+    writeln!(out).unwrap();
+    writeln!(out).unwrap();
+
+    let helper = python_name(&identifier(&function.name));
+    if function.output.is_stream() {
+        writeln!(
+            out,
+            "def {}(tx: Any, params: {}Params) -> List[{}Row]:",
+            helper, name, name
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    return _rows(tx, params.query(), {}Params.COLUMNS)  # type: ignore[return-value]",
+            name
+        )
+        .unwrap();
+    } else {
+        writeln!(
+            out,
+            "def {}(tx: Any, params: {}Params) -> Optional[{}Row]:",
+            helper, name, name
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    rows = _rows(tx, params.query(), {}Params.COLUMNS)",
+            name
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    return rows[0] if rows else None  # type: ignore[return-value]"
+        )
+        .unwrap();
+    }
+}
+
+/// The body of an f-string building the query
+fn query_template(binding: &Binding) -> String {
+    let mut template = String::new();
+    for part in binding.query() {
+        match part {
+            QueryPart::Text(text) => {
+                for c in text.chars() {
+                    match c {
+                        '\n' => template.push_str("\\n"),
+                        '"' => template.push_str("\\\""),
+                        '\\' => template.push_str("\\\\"),
+                        '{' => template.push_str("{{"),
+                        '}' => template.push_str("}}"),
+                        c => template.push(c),
+                    }
+                }
+            }
+            QueryPart::Argument(position) => {
+                let parameter = &binding.parameters[position];
+                let value = format!("self.{}", field_name(parameter));
+                let kind = value_kind(&parameter.type_name);
+                let expression = if parameter.type_name.list && kind != ValueKind::Literal {
+                    format!("_typeql_list({}, {})", value, writer(kind))
+                } else {
+                    format!("{}({})", writer(kind), value)
+                };
+                write!(template, "{{{}}}", expression).unwrap();
+            }
+        }
+    }
+    template
+}
+
+/// Function turning a value of this kind into TypeQL
+fn writer(kind: ValueKind) -> &'static str {
+    match kind {
+        ValueKind::Boolean => "_typeql_boolean",
+        ValueKind::Double => "_typeql_double",
+        ValueKind::String => "_typeql_string",
+        ValueKind::Integer | ValueKind::Literal | ValueKind::Instance => "str",
+    }
+}
+
+fn parameter_type(type_name: &TypeName) -> String {
+    let kind = value_kind(type_name);
+    let scalar = match kind {
+        ValueKind::Boolean => "bool",
+        ValueKind::Integer => "int",
+        ValueKind::Double => "float",
+        ValueKind::String | ValueKind::Literal | ValueKind::Instance => "str",
+    };
+    if type_name.list && kind != ValueKind::Literal {
+        format!("List[{}]", scalar)
+    } else {
+        scalar.to_string()
+    }
+}
+
+/// The Python type the driver decodes answers of this type to
+fn column_type(type_name: &TypeName) -> String {
+    let scalar = match (type_name.kind, type_name.name.as_str()) {
+        (TypeKind::Label, _) => "str",
+        (TypeKind::Builtin, "boolean") => "bool",
+        (TypeKind::Builtin, "integer") => "int",
+        (TypeKind::Builtin, "double") => "float",
+        (TypeKind::Builtin, "decimal") => "Decimal",
+        (TypeKind::Builtin, "string") => "str",
+        (TypeKind::Builtin, "date") => "date",
+        (TypeKind::Builtin, "datetime" | "datetime-tz") => "datetime",
+        (TypeKind::Builtin, _) => "Any",
+    };
+    if type_name.list {
+        format!("List[{}]", scalar)
+    } else if type_name.optional {
+        format!("Optional[{}]", scalar)
+    } else {
+        scalar.to_string()
+    }
+}
+
+fn field_name(field: &Field) -> String {
+    python_name(&field.name)
+}
+
+/// `name`, with `_` appended if it is a keyword
+fn python_name(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
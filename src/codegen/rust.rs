@@ -0,0 +1,252 @@
+use std::fmt::Write;
+
+use super::{type_identifier, value_kind, Binding, Field, QueryPart, ValueKind};
+use crate::extract_function_metadata::{FunctionMetadata, TypeKind, TypeName};
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try",
+    "type", "unsafe", "use", "where", "while", "yield",
+];
+
+/// Helper quoting string arguments, included when a function takes one
+const TYPEQL_STRING: &str = r#"/// Quote `value` as a TypeQL string literal
+fn typeql_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+"#;
+
+/// Render a Rust module with a typed wrapper per function, for use with the TypeDB Rust
+/// driver.
+///
+/// Each function `calculate_tax` gets a `CalculateTax` struct holding its arguments, whose
+/// `query()` builds the read query calling it, and a `CalculateTaxRow` struct describing
+/// one answer, with the driver method that decodes each column. `COLUMNS` lists the
+/// answer variables in row order. Arguments of schema types are IIDs, such as
+/// `0x1e00000000000000000000`; decimals, dates, datetimes and durations are TypeQL
+/// literals.
+pub fn render_rust_client(functions: &[FunctionMetadata]) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// Generated by typedb-meta from TypeQL function signatures. Do not edit."
+    )
+    .unwrap();
+
+    let bindings: Vec<Binding> = functions.iter().map(Binding::new).collect();
+    let quotes_strings = bindings.iter().any(|binding| {
+        binding
+            .parameters
+            .iter()
+            .any(|parameter| value_kind(&parameter.type_name) == ValueKind::String)
+    });
+    if quotes_strings {
+        writeln!(out).unwrap();
+        out.push_str(TYPEQL_STRING);
+    }
+
+    for binding in &bindings {
+        writeln!(out).unwrap();
+        render_binding(&mut out, binding);
+    }
+    out
+}
+
+fn render_binding(out: &mut String, binding: &Binding) {
+    let function = binding.function;
+    let name = type_identifier(&function.name);
+
+    if let Some(doc) = &function.doc {
+        for line in doc.lines() {
+            writeln!(out, "/// {}", line).unwrap();
+        }
+        writeln!(out, "///").unwrap();
+    }
+    writeln!(out, "/// Calls `{}`.", function.signature()).unwrap();
+    if let Some(reason) = function.doc_tag("deprecated") {
+        writeln!(out, "///").unwrap();
+        writeln!(out, "/// **Deprecated.** {}", reason).unwrap();
+    }
+    writeln!(out, "#[derive(Debug, Clone)]").unwrap();
+    if binding.parameters.is_empty() {
+        writeln!(out, "pub struct {} {{}}", name).unwrap();
+    } else {
+        writeln!(out, "pub struct {} {{", name).unwrap();
+    }
+    for parameter in &binding.parameters {
+        if value_kind(&parameter.type_name) == ValueKind::Instance {
+            writeln!(
+                out,
+                "    /// IID of a `{}` instance",
+                parameter.type_name.name
+            )
+            .unwrap();
+        } else if value_kind(&parameter.type_name) == ValueKind::Literal {
+            writeln!(out, "    /// `{}` written as TypeQL", parameter.type_name).unwrap();
+        }
+        writeln!(
+            out,
+            "    pub {}: {},",
+            field_name(parameter),
+            parameter_type(&parameter.type_name)
+        )
+        .unwrap();
+    }
+    if !binding.parameters.is_empty() {
+        writeln!(out, "}}").unwrap();
+    }
+    writeln!(out).unwrap();
+
+    let answers = if function.output.is_stream() {
+        "Each answer"
+    } else {
+        "The answer"
+    };
+    writeln!(out, "/// {} of [`{}`], one field per column", answers, name).unwrap();
+    writeln!(out, "#[derive(Debug, Clone)]").unwrap();
+    writeln!(out, "pub struct {}Row {{", name).unwrap();
+    for column in &binding.columns {
+        writeln!(
+            out,
+            "    /// `{}`, decoded with `Concept::{}`",
+            column.type_name,
+            decoder(&column.type_name)
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    pub {}: {},",
+            field_name(column),
+            column_type(&column.type_name)
+        )
+        .unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+    writeln!(out).unwrap();
+
+    let columns: Vec<String> = binding
+        .columns
+        .iter()
+        .map(|column| format!("{:?}", column.var))
+        .collect();
+    writeln!(out, "impl {} {{", name).unwrap();
+    writeln!(
+        out,
+        "    /// Answer variables, in the order of the row fields"
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    pub const COLUMNS: &'static [&'static str] = &[{}];",
+        columns.join(", ")
+    )
+    .unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "    /// The read query calling `{}`", function.name).unwrap();
+    writeln!(out, "    pub fn query(&self) -> String {{").unwrap();
+    let (template, arguments) = format_template(binding);
+    if arguments.is_empty() {
+        let text = template.replace("{{", "{").replace("}}", "}");
+        writeln!(out, "        String::from({:?})", text).unwrap();
+    } else {
+        writeln!(
+            out,
+            "        format!({:?}, {})",
+            template,
+            arguments.join(", ")
+        )
+        .unwrap();
+    }
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+}
+
+/// A `format!` string for the query and the argument expressions filling it
+fn format_template(binding: &Binding) -> (String, Vec<String>) {
+    let mut template = String::new();
+    let mut arguments = Vec::new();
+    for part in binding.query() {
+        match part {
+            QueryPart::Text(text) => template.push_str(&text.replace('{', "{{").replace('}', "}}")),
+            QueryPart::Argument(position) => {
+                let parameter = &binding.parameters[position];
+                let value = format!("self.{}", field_name(parameter));
+                let type_name = &parameter.type_name;
+                let kind = value_kind(type_name);
+                if type_name.list && kind != ValueKind::Literal {
+                    template.push_str("[{}]");
+                    arguments.push(format!(
+                        "{}.iter().map(|value| {}).collect::<Vec<_>>().join(\", \")",
+                        value,
+                        argument(kind, "value")
+                    ));
+                } else {
+                    template.push_str("{}");
+                    arguments.push(argument(kind, &value));
+                }
+            }
+        }
+    }
+    (template, arguments)
+}
+
+/// Expression turning `value` into TypeQL
+fn argument(kind: ValueKind, value: &str) -> String {
+    match kind {
+        ValueKind::String => format!("typeql_string(&{})", value),
+        ValueKind::Double => format!("format!(\"{{:?}}\", {})", value),
+        _ => value.to_string(),
+    }
+}
+
+fn parameter_type(type_name: &TypeName) -> String {
+    let kind = value_kind(type_name);
+    if type_name.list && kind != ValueKind::Literal {
+        format!("Vec<{}>", scalar_type(kind))
+    } else {
+        scalar_type(kind).to_string()
+    }
+}
+
+fn column_type(type_name: &TypeName) -> String {
+    let scalar = scalar_type(value_kind(type_name));
+    if type_name.list {
+        format!("Vec<{}>", scalar)
+    } else if type_name.optional {
+        format!("Option<{}>", scalar)
+    } else {
+        scalar.to_string()
+    }
+}
+
+fn scalar_type(kind: ValueKind) -> &'static str {
+    match kind {
+        ValueKind::Boolean => "bool",
+        ValueKind::Integer => "i64",
+        ValueKind::Double => "f64",
+        ValueKind::String | ValueKind::Literal | ValueKind::Instance => "String",
+    }
+}
+
+/// The `Concept` method reading a column of this type
+fn decoder(type_name: &TypeName) -> String {
+    if type_name.kind == TypeKind::Label {
+        "try_get_iid".to_string()
+    } else {
+        format!("try_get_{}", type_name.name.replace('-', "_"))
+    }
+}
+
+fn field_name(field: &Field) -> String {
+    rust_name(&field.name)
+}
+
+/// `name`, as a raw identifier if it is a keyword
+pub(super) fn rust_name(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else {
+        name.to_string()
+    }
+}
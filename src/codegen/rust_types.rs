@@ -0,0 +1,192 @@
+use std::fmt::Write;
+
+use super::rust::rust_name;
+use super::{identifier, type_identifier};
+use crate::extract_schema_metadata::{SchemaKind, SchemaMetadata, TypeDefinition};
+
+/// Render Rust types with serde derives for every type in a schema.
+///
+/// Attribute types become newtypes over their value type: integers are `i64`, doubles
+/// `f64`, booleans `bool`, and strings, decimals, dates, datetimes and durations
+/// `String`. Entity and relation types become structs with an optional field per owned
+/// attribute, inherited ones included, and relations a field per role holding the IIDs of
+/// its players. Types with subtypes also get an `Any...` enum over themselves and their
+/// subtypes, tagged with the type label. Fields and variants are renamed to the TypeQL
+/// labels when serialized.
+pub fn render_rust_types(schema: &SchemaMetadata) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// Generated by typedb-meta from a TypeQL schema. Do not edit."
+    )
+    .unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "use serde::{{Deserialize, Serialize}};").unwrap();
+
+    for attribute in schema.attributes() {
+        writeln!(out).unwrap();
+        render_attribute(&mut out, schema, attribute);
+    }
+    for type_def in schema.entities().chain(schema.relations()) {
+        writeln!(out).unwrap();
+        render_struct(&mut out, schema, type_def);
+    }
+    for type_def in schema.entities().chain(schema.relations()) {
+        let subtypes = subtypes(schema, &type_def.label);
+        if !subtypes.is_empty() {
+            writeln!(out).unwrap();
+            render_enum(&mut out, type_def, &subtypes);
+        }
+    }
+    out
+}
+
+fn render_attribute(out: &mut String, schema: &SchemaMetadata, attribute: &TypeDefinition) {
+    let value_type = schema.value_type(attribute);
+    let (rust_type, derives) = match value_type {
+        Some("boolean") => ("bool", "Debug, Clone, Copy, PartialEq, Eq, Hash"),
+        Some("integer") => ("i64", "Debug, Clone, Copy, PartialEq, Eq, Hash"),
+        Some("double") => ("f64", "Debug, Clone, Copy, PartialEq"),
+        _ => ("String", "Debug, Clone, PartialEq, Eq, Hash"),
+    };
+    match value_type {
+        Some(value_type) => writeln!(
+            out,
+            "/// The `{}` attribute, with `{}` values",
+            attribute.label, value_type
+        )
+        .unwrap(),
+        None => writeln!(out, "/// The `{}` attribute", attribute.label).unwrap(),
+    }
+    writeln!(out, "#[derive({}, Serialize, Deserialize)]", derives).unwrap();
+    writeln!(out, "#[serde(transparent)]").unwrap();
+    writeln!(
+        out,
+        "pub struct {}(pub {});",
+        type_identifier(&attribute.label),
+        rust_type
+    )
+    .unwrap();
+}
+
+fn render_struct(out: &mut String, schema: &SchemaMetadata, type_def: &TypeDefinition) {
+    let kind = match type_def.kind {
+        SchemaKind::Relation => "relation",
+        _ => "entity",
+    };
+    writeln!(out, "/// The `{}` {}", type_def.label, kind).unwrap();
+    writeln!(
+        out,
+        "#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]"
+    )
+    .unwrap();
+    writeln!(out, "pub struct {} {{", type_identifier(&type_def.label)).unwrap();
+    writeln!(
+        out,
+        "    #[serde(default, skip_serializing_if = \"Option::is_none\")]"
+    )
+    .unwrap();
+    writeln!(out, "    pub iid: Option<String>,").unwrap();
+
+    let mut owns: Vec<&str> = Vec::new();
+    let mut roles: Vec<&str> = Vec::new();
+    for ancestor in schema.lineage(type_def) {
+        owns.extend(ancestor.owns.iter().map(String::as_str));
+        roles.extend(ancestor.relates.iter().map(String::as_str));
+    }
+    let mut fields: Vec<&str> = Vec::new();
+    for label in owns {
+        if fields.contains(&label) {
+            continue;
+        }
+        fields.push(label);
+        render_rename(out, label);
+        writeln!(
+            out,
+            "    #[serde(default, skip_serializing_if = \"Option::is_none\")]"
+        )
+        .unwrap();
+        let attribute = match schema.get(label) {
+            Some(attribute) if attribute.kind == SchemaKind::Attribute => type_identifier(label),
+            // Owned but never declared as an attribute type
+            _ => "String".to_string(),
+        };
+        writeln!(
+            out,
+            "    pub {}: Option<{}>,",
+            rust_name(&identifier(label)),
+            attribute
+        )
+        .unwrap();
+    }
+    for role in roles {
+        if fields.contains(&role) {
+            continue;
+        }
+        fields.push(role);
+        writeln!(
+            out,
+            "    /// IIDs of the players of `{}:{}`",
+            type_def.label, role
+        )
+        .unwrap();
+        render_rename(out, role);
+        writeln!(
+            out,
+            "    #[serde(default, skip_serializing_if = \"Vec::is_empty\")]"
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    pub {}: Vec<String>,",
+            rust_name(&identifier(role))
+        )
+        .unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+}
+
+fn render_enum(out: &mut String, type_def: &TypeDefinition, subtypes: &[&TypeDefinition]) {
+    let name = type_identifier(&type_def.label);
+    writeln!(
+        out,
+        "/// A `{}` or an instance of one of its subtypes",
+        type_def.label
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]"
+    )
+    .unwrap();
+    writeln!(out, "#[serde(tag = \"type\")]").unwrap();
+    writeln!(out, "pub enum Any{} {{", name).unwrap();
+    for variant in std::iter::once(type_def).chain(subtypes.iter().copied()) {
+        writeln!(out, "    #[serde(rename = {:?})]", variant.label).unwrap();
+        let variant_name = type_identifier(&variant.label);
+        writeln!(out, "    {}({}),", variant_name, variant_name).unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+}
+
+/// `#[serde(rename)]` for a field whose label is not a Rust identifier
+fn render_rename(out: &mut String, label: &str) {
+    if identifier(label) != label {
+        writeln!(out, "    #[serde(rename = {:?})]", label).unwrap();
+    }
+}
+
+/// Direct and indirect subtypes of `label`, in schema order
+fn subtypes<'a>(schema: &'a SchemaMetadata, label: &str) -> Vec<&'a TypeDefinition> {
+    schema
+        .types
+        .iter()
+        .filter(|type_def| {
+            type_def.label != label
+                && schema
+                    .lineage(type_def)
+                    .iter()
+                    .any(|ancestor| ancestor.label == label)
+        })
+        .collect()
+}
@@ -0,0 +1,130 @@
+use std::fmt::Write;
+
+use super::{type_identifier, value_kind, Binding, Field, ValueKind};
+use crate::extract_function_metadata::{FunctionMetadata, TypeName};
+
+/// Render a TypeScript declaration file (`.d.ts`) with the parameters and answers of
+/// each function.
+///
+/// Each function `calculate_tax` gets a `CalculateTaxParams` interface and a
+/// `CalculateTaxRow` interface with one property per answer column. A `Functions`
+/// interface maps every function name to its `params` and `result`: an array of rows for
+/// streams, a row or `null` otherwise. Instances of schema types are IID strings;
+/// integers and doubles are numbers; decimals, dates, datetimes and durations are
+/// strings.
+pub fn render_typescript_definitions(functions: &[FunctionMetadata]) -> String {
+    let mut out = String::new();
+    writeln!(
+        out,
+        "// Generated by typedb-meta from TypeQL function signatures. Do not edit."
+    )
+    .unwrap();
+
+    let bindings: Vec<Binding> = functions.iter().map(Binding::new).collect();
+    for binding in &bindings {
+        writeln!(out).unwrap();
+        render_binding(&mut out, binding);
+    }
+
+    writeln!(out).unwrap();
+    writeln!(out, "/** Every function by name */").unwrap();
+    writeln!(out, "export interface Functions {{").unwrap();
+    for binding in &bindings {
+        let name = type_identifier(&binding.function.name);
+        let result = if binding.function.output.is_stream() {
+            format!("{}Row[]", name)
+        } else {
+            format!("{}Row | null", name)
+        };
+        writeln!(
+            out,
+            "  {}: {{ params: {}Params; result: {} }};",
+            property(&binding.function.name),
+            name,
+            result
+        )
+        .unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+fn render_binding(out: &mut String, binding: &Binding) {
+    let function = binding.function;
+    let name = type_identifier(&function.name);
+
+    writeln!(out, "/**").unwrap();
+    if let Some(doc) = &function.doc {
+        for line in doc.lines() {
+            writeln!(out, " * {}", line).unwrap();
+        }
+        writeln!(out, " *").unwrap();
+    }
+    writeln!(out, " * Parameters of `{}`", function.signature()).unwrap();
+    if let Some(reason) = function.doc_tag("deprecated") {
+        writeln!(out, " * @deprecated {}", reason).unwrap();
+    }
+    writeln!(out, " */").unwrap();
+    writeln!(out, "export interface {}Params {{", name).unwrap();
+    for parameter in &binding.parameters {
+        if value_kind(&parameter.type_name) == ValueKind::Literal {
+            writeln!(out, "  /** `{}` as TypeQL text */", parameter.type_name).unwrap();
+        }
+        render_property(out, parameter, parameter_type(&parameter.type_name));
+    }
+    writeln!(out, "}}").unwrap();
+    writeln!(out).unwrap();
+
+    writeln!(out, "/** An answer of `{}` */", function.name).unwrap();
+    writeln!(out, "export interface {}Row {{", name).unwrap();
+    for column in &binding.columns {
+        render_property(out, column, column_type(&column.type_name));
+    }
+    writeln!(out, "}}").unwrap();
+}
+
+fn render_property(out: &mut String, field: &Field, type_: String) {
+    if value_kind(&field.type_name) == ValueKind::Instance {
+        writeln!(out, "  /** IID of a `{}` instance */", field.type_name.name).unwrap();
+    }
+    writeln!(out, "  {}: {};", property(&field.name), type_).unwrap();
+}
+
+fn parameter_type(type_name: &TypeName) -> String {
+    let kind = value_kind(type_name);
+    if type_name.list && kind != ValueKind::Literal {
+        format!("{}[]", scalar_type(kind))
+    } else {
+        scalar_type(kind).to_string()
+    }
+}
+
+fn column_type(type_name: &TypeName) -> String {
+    let scalar = scalar_type(value_kind(type_name));
+    if type_name.list {
+        format!("{}[]", scalar)
+    } else if type_name.optional {
+        format!("{} | null", scalar)
+    } else {
+        scalar.to_string()
+    }
+}
+
+fn scalar_type(kind: ValueKind) -> &'static str {
+    match kind {
+        ValueKind::Boolean => "boolean",
+        ValueKind::Integer | ValueKind::Double => "number",
+        ValueKind::String | ValueKind::Literal | ValueKind::Instance => "string",
+    }
+}
+
+/// A property name, quoted unless it is a plain identifier
+fn property(name: &str) -> String {
+    let plain = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        name.to_string()
+    } else {
+        format!("{:?}", name)
+    }
+}
@@ -0,0 +1,322 @@
+//! Best-effort conversion of TypeQL 2.x inference rules into TypeQL 3 functions.
+//!
+//! TypeDB 3 has no rules: what a rule inferred is instead computed by a function that
+//! queries call. A rule concluding a relation becomes a function streaming its role
+//! players, and a rule concluding an attribute becomes one streaming the owners, and the
+//! value when it is a variable:
+//!
+//! ```text
+//! rule transitive-friendship: when {
+//!     (friend: $x, friend: $y) isa friendship;
+//!     (friend: $y, friend: $z) isa friendship;
+//! } then {
+//!     (friend: $x, friend: $z) isa friendship;
+//! };
+//! ```
+//!
+//! becomes `fun get_transitive_friendship() -> { person, person }` matching the `when`
+//! block and returning `{ $x, $z }`. Anything that does not carry over is listed in the
+//! conversion's `issues`.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+use crate::diagnostic::Diagnostic;
+use crate::dialect::{check_dialect, extract_rules, RuleMetadata};
+use crate::extract_function_metadata::extract_function_at;
+use crate::extract_schema_metadata::SchemaMetadata;
+use crate::lexer::{tokenize, Token, TokenKind};
+use crate::naming::DEFAULT_VERB_PREFIXES;
+
+/// The outcome of converting one rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleConversion {
+    /// Name of the rule
+    pub rule: String,
+    /// The TypeQL 3 function, `None` if the rule could not be converted
+    pub function: Option<String>,
+    /// Constructs that could not be converted or that behave differently as a function
+    pub issues: Vec<String>,
+}
+
+/// What a rule concludes, read from its `then` block
+enum Conclusion<'a> {
+    /// `(role: $x, ...) isa relation`, players with their role if given
+    Relation {
+        relation: &'a str,
+        players: Vec<(Option<&'a str>, &'a str)>,
+    },
+    /// `$x has attribute value`, `value` being a variable or a literal
+    Has {
+        owner: &'a str,
+        attribute: &'a str,
+        value: &'a str,
+    },
+}
+
+/// Convert every rule in TypeQL 2.x `source`, in order.
+///
+/// With a `schema`, role players whose type the `when` block does not give are typed
+/// after the only type playing their role.
+pub fn convert_rules(
+    source: &str,
+    schema: Option<&SchemaMetadata>,
+) -> Result<Vec<RuleConversion>, Diagnostic> {
+    Ok(extract_rules(source)?
+        .iter()
+        .map(|rule| convert_rule(rule, schema))
+        .collect())
+}
+
+/// Convert one rule into a function; see [`convert_rules`]
+pub fn convert_rule(rule: &RuleMetadata, schema: Option<&SchemaMetadata>) -> RuleConversion {
+    let mut conversion = RuleConversion {
+        rule: rule.name.clone(),
+        function: None,
+        issues: Vec::new(),
+    };
+    let when_tokens = code_tokens(&rule.when);
+    let then_tokens = code_tokens(&rule.then);
+    let Some(conclusion) = conclusion(&then_tokens) else {
+        conversion.issues.push(format!(
+            "unsupported conclusion `{}`; only `(role: $x, ...) isa relation` and \
+             `$x has attribute value` are converted",
+            rule.then
+        ));
+        return conversion;
+    };
+
+    // Returned variables with their types, in order
+    let mut returned: Vec<(&str, Option<String>)> = Vec::new();
+    let concluded = match &conclusion {
+        Conclusion::Relation { relation, players } => {
+            for (role, player) in players {
+                if returned.iter().any(|(var, _)| var == player) {
+                    continue;
+                }
+                let type_name = type_in(&when_tokens, player).or_else(|| {
+                    let role = (*role)?;
+                    only_player(schema?, relation, role)
+                });
+                returned.push((player, type_name));
+            }
+            relation
+        }
+        Conclusion::Has {
+            owner,
+            attribute,
+            value,
+        } => {
+            returned.push((owner, type_in(&when_tokens, owner)));
+            if value.starts_with('$') {
+                returned.push((value, Some(attribute.to_string())));
+            }
+            attribute
+        }
+    };
+
+    for construct in check_dialect(&rule.when) {
+        conversion.issues.push(construct.message);
+    }
+    for (var, _) in &returned {
+        if !when_tokens.iter().any(|token| token.text == *var) {
+            conversion.issues.push(format!(
+                "`{}` is concluded but never matched in `when`",
+                var
+            ));
+        }
+    }
+    let untyped: Vec<&str> = returned
+        .iter()
+        .filter(|(_, type_name)| type_name.is_none())
+        .map(|(var, _)| *var)
+        .collect();
+    if !untyped.is_empty() {
+        conversion.issues.push(format!(
+            "cannot tell the type of {}; add `isa` statements to `when` or give the schema",
+            untyped
+                .iter()
+                .map(|var| format!("`{}`", var))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        return conversion;
+    }
+    if when_tokens.windows(2).any(|pair| {
+        (pair[0].is_word("isa") || pair[0].is_word("has")) && pair[1].text == *concluded
+    }) {
+        conversion.issues.push(format!(
+            "the rule is recursive: `when` matches `{}`, which it infers, but the function \
+             only sees stored `{}` data; call the function where the inference was relied on",
+            concluded, concluded
+        ));
+    }
+
+    let name = function_name(&rule.name);
+    let mut function = String::new();
+    if let Some(doc) = &rule.doc {
+        for line in doc.lines() {
+            writeln!(function, "# {}", line).unwrap();
+        }
+    }
+    writeln!(
+        function,
+        "# Converted from the TypeQL 2.x rule `{}`, which inferred:",
+        rule.name
+    )
+    .unwrap();
+    for line in rule.then.lines() {
+        writeln!(function, "#     {}", line.trim()).unwrap();
+    }
+    let types: Vec<&str> = returned
+        .iter()
+        .filter_map(|(_, type_name)| type_name.as_deref())
+        .collect();
+    let vars: Vec<&str> = returned.iter().map(|(var, _)| *var).collect();
+    writeln!(function, "fun {}() -> {{ {} }}:", name, types.join(", ")).unwrap();
+    writeln!(function, "    match").unwrap();
+    for line in dedent(&rule.when).lines() {
+        writeln!(function, "        {}", line).unwrap();
+    }
+    writeln!(function, "    return {{ {} }};", vars.join(", ")).unwrap();
+
+    if let Err(diagnostic) = extract_function_at(&function, 0..function.len()) {
+        conversion.issues.push(format!(
+            "the converted function does not parse: {}",
+            diagnostic.message
+        ));
+    }
+    conversion.function = Some(function);
+    conversion
+}
+
+/// Tokens without comments
+fn code_tokens(text: &str) -> Vec<Token<'_>> {
+    tokenize(text)
+        .into_iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .collect()
+}
+
+/// The single statement of a `then` block, if it has a supported form
+fn conclusion<'a>(tokens: &[Token<'a>]) -> Option<Conclusion<'a>> {
+    let statement: Vec<&Token> = tokens.iter().filter(|token| !token.is_punct(";")).collect();
+    if tokens.iter().filter(|token| token.is_punct(";")).count() > 1 {
+        return None;
+    }
+    match statement.as_slice() {
+        [owner, has, attribute, value]
+            if owner.kind == TokenKind::Variable
+                && has.is_word("has")
+                && attribute.kind == TokenKind::Word =>
+        {
+            Some(Conclusion::Has {
+                owner: owner.text,
+                attribute: attribute.text,
+                value: value.text,
+            })
+        }
+        _ => {
+            // An optional relation variable, then `( ... ) isa relation`
+            let open = statement.iter().position(|token| token.is_punct("("))?;
+            let close = statement.iter().position(|token| token.is_punct(")"))?;
+            match &statement[..open] {
+                [] => {}
+                [var] if var.kind == TokenKind::Variable => {}
+                _ => return None,
+            }
+            let [isa, relation] = &statement[close + 1..] else {
+                return None;
+            };
+            if !isa.is_word("isa") || relation.kind != TokenKind::Word {
+                return None;
+            }
+            let mut players = Vec::new();
+            for player in statement[open + 1..close].split(|token| token.is_punct(",")) {
+                match player {
+                    [role, colon, var]
+                        if role.kind == TokenKind::Word
+                            && colon.is_punct(":")
+                            && var.kind == TokenKind::Variable =>
+                    {
+                        players.push((Some(role.text), var.text))
+                    }
+                    [var] if var.kind == TokenKind::Variable => players.push((None, var.text)),
+                    _ => return None,
+                }
+            }
+            Some(Conclusion::Relation {
+                relation: relation.text,
+                players,
+            })
+        }
+    }
+}
+
+/// The type `var` is matched as in `when`, from `$x isa person` or `has name $x`
+fn type_in(tokens: &[Token], var: &str) -> Option<String> {
+    tokens.windows(3).find_map(|window| match window {
+        [variable, isa, type_name]
+            if variable.text == var
+                && (isa.is_word("isa") || isa.is_word("isa!"))
+                && type_name.kind == TokenKind::Word =>
+        {
+            Some(type_name.text.to_string())
+        }
+        [has, attribute, variable]
+            if has.is_word("has") && attribute.kind == TokenKind::Word && variable.text == var =>
+        {
+            Some(attribute.text.to_string())
+        }
+        _ => None,
+    })
+}
+
+/// The type playing `relation:role`, when exactly one does
+fn only_player(schema: &SchemaMetadata, relation: &str, role: &str) -> Option<String> {
+    let scoped = format!("{}:{}", relation, role);
+    let players: Vec<&str> = schema
+        .types
+        .iter()
+        .filter(|type_def| type_def.plays.contains(&scoped))
+        .map(|type_def| type_def.label.as_str())
+        .collect();
+    match players.as_slice() {
+        [player] => Some(player.to_string()),
+        _ => None,
+    }
+}
+
+/// `get_` and the rule name in snake case, unless it already starts with a verb
+fn function_name(rule: &str) -> String {
+    let name = rule.replace('-', "_");
+    if DEFAULT_VERB_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+    {
+        name
+    } else {
+        format!("get_{}", name)
+    }
+}
+
+/// `text` with the indentation its lines after the first share removed; the first line
+/// is already trimmed
+fn dedent(text: &str) -> String {
+    let indent = text
+        .lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    text.lines()
+        .enumerate()
+        .map(|(position, line)| match line.get(indent..) {
+            Some(rest) if position > 0 => rest.trim_end(),
+            _ => line.trim(),
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
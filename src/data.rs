@@ -0,0 +1,369 @@
+//! Insert statements generated from JSON records.
+//!
+//! Records come in named collections, each mapped onto an entity or relation type:
+//!
+//! ```json
+//! {
+//!     "people": { "type": "person", "key": "id", "attributes": { "id": "person-id" } },
+//!     "jobs": {
+//!         "type": "employment",
+//!         "roles": { "person": { "role": "employee", "player": "people" } }
+//!     }
+//! }
+//! ```
+//!
+//! With records `{"people": [{"id": "p1", "name": "Ann"}], "jobs": [{"person": "p1"}]}`
+//! this inserts a `person` owning `person-id "p1"` and `name "Ann"`, then matches that
+//! person by its `person-id` to insert an `employment` with it as `employee`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::error::ExtractError;
+use crate::extract_schema_metadata::{SchemaKind, SchemaMetadata, TypeDefinition};
+
+/// How each collection of records maps onto the schema, by collection name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DataMapping {
+    pub collections: BTreeMap<String, CollectionMapping>,
+}
+
+/// How the records of one collection become instances of a type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionMapping {
+    /// Entity or relation type to insert
+    #[serde(rename = "type")]
+    pub type_label: String,
+    /// Field identifying a record when it plays a role; must map to an attribute
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Attribute type by field. Fields named after an attribute type the type owns need
+    /// no entry; other unmapped fields are skipped.
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+    /// Role by field, for relation types. The field holds the key of the player, or an
+    /// array of keys for several players of the role.
+    #[serde(default)]
+    pub roles: BTreeMap<String, RoleMapping>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleMapping {
+    /// Role name, e.g. `employee`
+    pub role: String,
+    /// Collection the players are keyed in
+    pub player: String,
+}
+
+/// Generate an `insert` query per record, entities first.
+///
+/// `data` is an object of collections, each an array of records. Every mapped type,
+/// attribute and role is checked against `schema`, values against the value types of
+/// their attributes, and role players against the keys of their collections. Records
+/// must give as many values and players as the `@card` of each `owns` and `relates`
+/// allows, and values of `@key` and `@unique` attributes must differ between records.
+/// Null fields are skipped, and array fields give one `has` per element.
+///
+/// Records that break any of these rules fail with [`ExtractError::InvalidData`], naming
+/// the collection and position of the record.
+pub fn generate_inserts(
+    schema: &SchemaMetadata,
+    mapping: &DataMapping,
+    data: &Value,
+) -> Result<String, ExtractError> {
+    let data = data
+        .as_object()
+        .ok_or_else(|| invalid("expected an object of collections".to_string()))?;
+    let mut collections = Vec::new();
+    for (name, collection) in &mapping.collections {
+        let type_def = schema
+            .get(&collection.type_label)
+            .filter(|type_def| type_def.kind != SchemaKind::Attribute)
+            .ok_or_else(|| {
+                invalid(format!(
+                    "collection {}: `{}` is not an entity or relation type",
+                    name, collection.type_label
+                ))
+            })?;
+        collections.push((name.as_str(), collection, type_def));
+    }
+    collections.sort_by_key(|(_, _, type_def)| type_def.kind == SchemaKind::Relation);
+
+    let mut keys: BTreeMap<&str, HashSet<String>> = BTreeMap::new();
+    for (name, collection, _) in &collections {
+        if let Some(key) = &collection.key {
+            let values = records(data, name)?
+                .iter()
+                .filter_map(|record| record.get(key))
+                .map(Value::to_string)
+                .collect();
+            keys.insert(name, values);
+        }
+    }
+
+    let mut unique_values = HashMap::new();
+    let mut out = String::new();
+    for (name, collection, type_def) in &collections {
+        if type_def.is_abstract() {
+            return Err(invalid(format!(
+                "collection {}: `{}` is abstract and cannot have instances",
+                name, type_def.label
+            )));
+        }
+        for (position, record) in records(data, name)?.iter().enumerate() {
+            let record = record
+                .as_object()
+                .ok_or_else(|| invalid(format!("{}[{}]: expected an object", name, position)))?;
+            let insert = insert(
+                schema,
+                mapping,
+                &keys,
+                &mut unique_values,
+                collection,
+                type_def,
+                record,
+            )
+            .map_err(|message| invalid(format!("{}[{}]: {}", name, position, message)))?;
+            if !out.is_empty() {
+                writeln!(out).unwrap();
+            }
+            out.push_str(&insert);
+        }
+    }
+    Ok(out)
+}
+
+fn records<'a>(
+    data: &'a Map<String, Value>,
+    collection: &str,
+) -> Result<&'a [Value], ExtractError> {
+    match data.get(collection) {
+        Some(Value::Array(records)) => Ok(records),
+        Some(_) => Err(invalid(format!(
+            "collection {}: expected an array of records",
+            collection
+        ))),
+        None => Ok(&[]),
+    }
+}
+
+/// Values of `@key` and `@unique` attributes inserted so far, by type and attribute label
+type UniqueValues = HashMap<(String, String), HashSet<String>>;
+
+fn insert(
+    schema: &SchemaMetadata,
+    mapping: &DataMapping,
+    keys: &BTreeMap<&str, HashSet<String>>,
+    unique_values: &mut UniqueValues,
+    collection: &CollectionMapping,
+    type_def: &TypeDefinition,
+    record: &Map<String, Value>,
+) -> Result<String, String> {
+    let mut matches = Vec::new();
+    let mut players = Vec::new();
+    let mut player_counts: BTreeMap<&str, u64> = BTreeMap::new();
+    for (field, role) in &collection.roles {
+        if !relates(schema, type_def, &role.role) {
+            return Err(format!("`{}` has no role `{}`", type_def.label, role.role));
+        }
+        let player = mapping
+            .collections
+            .get(&role.player)
+            .ok_or_else(|| format!("no collection {} for players", role.player))?;
+        let key = player
+            .key
+            .as_ref()
+            .ok_or_else(|| format!("collection {} has no key", role.player))?;
+        let player_type = schema
+            .get(&player.type_label)
+            .ok_or_else(|| format!("unknown type `{}`", player.type_label))?;
+        let plays = schema.lineage(player_type).iter().any(|ancestor| {
+            schema.lineage(type_def).iter().any(|relation| {
+                let scoped = format!("{}:{}", relation.label, role.role);
+                ancestor.plays.contains(&scoped)
+            })
+        });
+        if !plays {
+            return Err(format!(
+                "`{}` does not play `{}:{}`",
+                player_type.label, type_def.label, role.role
+            ));
+        }
+        let key_attribute = attribute_of(schema, player, player_type, key)
+            .ok_or_else(|| format!("key {} of {} is not an attribute", key, role.player))?;
+
+        let values = match record.get(field) {
+            None | Some(Value::Null) => continue,
+            Some(Value::Array(values)) => values.iter().collect(),
+            Some(value) => vec![value],
+        };
+        for value in values {
+            if !keys[role.player.as_str()].contains(&value.to_string()) {
+                return Err(format!(
+                    "{}: no record in {} with {} {}",
+                    field, role.player, key, value
+                ));
+            }
+            let var = format!("$p{}", players.len());
+            matches.push(format!(
+                "{} isa {}, has {} {}",
+                var,
+                player_type.label,
+                key_attribute.label,
+                literal(schema, key_attribute, value)
+                    .map_err(|message| format!("{}: {}", field, message))?
+            ));
+            players.push(format!("{}: {}", role.role, var));
+            *player_counts.entry(&role.role).or_default() += 1;
+        }
+    }
+    for ancestor in schema.lineage(type_def) {
+        for role in &ancestor.relates {
+            let cardinality = schema.relates_cardinality(type_def, role);
+            let count = player_counts.get(role.as_str()).copied().unwrap_or(0);
+            if !cardinality.allows(count) {
+                return Err(format!(
+                    "`{}` relates `{}` with {}, got {} players",
+                    type_def.label, role, cardinality, count
+                ));
+            }
+        }
+    }
+
+    let mut statement = format!("$x isa {}", type_def.label);
+    if !players.is_empty() {
+        write!(statement, ", links ({})", players.join(", ")).unwrap();
+    }
+    let mut value_counts: BTreeMap<&str, u64> = BTreeMap::new();
+    for (field, value) in record {
+        if collection.roles.contains_key(field) {
+            continue;
+        }
+        let Some(attribute) = attribute_of(schema, collection, type_def, field) else {
+            if collection.attributes.contains_key(field) {
+                return Err(format!(
+                    "{}: `{}` does not own `{}`",
+                    field, type_def.label, collection.attributes[field]
+                ));
+            }
+            continue;
+        };
+        let values = match value {
+            Value::Null => continue,
+            Value::Array(values) => values.iter().collect(),
+            value => vec![value],
+        };
+        for value in values {
+            let literal = literal(schema, attribute, value)
+                .map_err(|message| format!("{}: {}", field, message))?;
+            if schema.is_unique(type_def, &attribute.label) {
+                let seen = unique_values
+                    .entry((type_def.label.clone(), attribute.label.clone()))
+                    .or_default();
+                if !seen.insert(literal.clone()) {
+                    return Err(format!(
+                        "{}: another `{}` already has {} {}",
+                        field, type_def.label, attribute.label, literal
+                    ));
+                }
+            }
+            write!(statement, ", has {} {}", attribute.label, literal).unwrap();
+            *value_counts.entry(&attribute.label).or_default() += 1;
+        }
+    }
+    for ancestor in schema.lineage(type_def) {
+        for attribute in &ancestor.owns {
+            let cardinality = schema.owns_cardinality(type_def, attribute);
+            let count = value_counts.get(attribute.as_str()).copied().unwrap_or(0);
+            if !cardinality.allows(count) {
+                return Err(format!(
+                    "`{}` owns `{}` with {}, got {} values",
+                    type_def.label, attribute, cardinality, count
+                ));
+            }
+        }
+    }
+
+    let mut out = String::new();
+    if !matches.is_empty() {
+        writeln!(out, "match").unwrap();
+        for statement in matches {
+            writeln!(out, "    {};", statement).unwrap();
+        }
+    }
+    writeln!(out, "insert").unwrap();
+    writeln!(out, "    {};", statement).unwrap();
+    Ok(out)
+}
+
+/// The attribute type `field` maps to, if `type_def` owns it
+fn attribute_of<'a>(
+    schema: &'a SchemaMetadata,
+    collection: &CollectionMapping,
+    type_def: &TypeDefinition,
+    field: &str,
+) -> Option<&'a TypeDefinition> {
+    let label = collection
+        .attributes
+        .get(field)
+        .map_or(field, String::as_str);
+    let owned = schema
+        .lineage(type_def)
+        .iter()
+        .any(|ancestor| ancestor.owns.iter().any(|owned| owned == label));
+    schema
+        .get(label)
+        .filter(|attribute| owned && attribute.kind == SchemaKind::Attribute)
+}
+
+fn relates(schema: &SchemaMetadata, relation: &TypeDefinition, role: &str) -> bool {
+    schema
+        .lineage(relation)
+        .iter()
+        .any(|ancestor| ancestor.relates.iter().any(|related| related == role))
+}
+
+/// `value` as a TypeQL literal of the attribute's value type
+fn literal(
+    schema: &SchemaMetadata,
+    attribute: &TypeDefinition,
+    value: &Value,
+) -> Result<String, String> {
+    let value_type = schema.value_type(attribute).unwrap_or("string");
+    let literal = match (value_type, value) {
+        ("string", Value::String(text)) => quote(text),
+        ("string", Value::Number(number)) => quote(&number.to_string()),
+        ("string", Value::Bool(boolean)) => quote(&boolean.to_string()),
+        ("boolean", Value::Bool(boolean)) => boolean.to_string(),
+        ("integer", Value::Number(number)) if number.is_i64() => number.to_string(),
+        ("double", Value::Number(number)) => format!("{:?}", number.as_f64().unwrap()),
+        ("decimal", Value::Number(number)) => format!("{}dec", number),
+        ("decimal", Value::String(text)) if text.parse::<f64>().is_ok() => format!("{}dec", text),
+        ("date" | "datetime" | "datetime-tz" | "duration", Value::String(text))
+            if !text.is_empty()
+                && text
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-:.+/_ ".contains(c)) =>
+        {
+            text.clone()
+        }
+        _ => {
+            return Err(format!(
+                "{} is not a valid `{}` value for `{}`",
+                value, value_type, attribute.label
+            ))
+        }
+    };
+    Ok(literal)
+}
+
+fn invalid(message: String) -> ExtractError {
+    ExtractError::InvalidData { message }
+}
+
+pub(crate) fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Write};
+
+use crate::dialect::check_dialect;
+use crate::span::Span;
+
+/// A problem found while extracting or checking functions.
+///
+/// Checks report diagnostics rather than failing so that one pass can surface every issue
+/// at once; extraction returns one as its error type when the source cannot be parsed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// Stable identifier of the check that produced this diagnostic, e.g. `unknown-type`
+    pub code: String,
+    pub message: String,
+    /// Function the diagnostic applies to, if any
+    pub function: Option<String>,
+    /// Offending location in the source, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    /// Whether a `# typedb-meta: ignore` comment covers this finding; suppressed
+    /// diagnostics are still reported but do not fail a check
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub suppressed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Diagnostic {
+    pub fn error(code: &str, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, code, message)
+    }
+
+    pub fn warning(code: &str, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, code, message)
+    }
+
+    pub fn new(severity: Severity, code: &str, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity,
+            code: code.to_string(),
+            message: message.into(),
+            function: None,
+            span: None,
+            suppressed: false,
+        }
+    }
+
+    pub fn in_function(mut self, function: &str) -> Self {
+        self.function = Some(function.to_string());
+        self
+    }
+
+    pub fn with_span(mut self, span: Option<Span>) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Diagnostic for a typeql parse failure of the text at `offset` within `source`.
+    ///
+    /// The parser only reports its position inside the error message, so the location is
+    /// recovered from the message when it can be found.
+    pub(crate) fn parse_error(error: &typeql::Error, source: &str, offset: usize) -> Self {
+        let message = error.to_string();
+        let span = error_position(&message)
+            .and_then(|(line, column)| offset_of(&source[offset..], line, column))
+            .map(|position| {
+                let start = offset + position;
+                let end = source[start..]
+                    .find(|c: char| c.is_whitespace())
+                    .map_or(source.len(), |len| start + len.max(1));
+                Span::new(source, start, end)
+            });
+        // The first line is the summary; the rest repeats the source the renderer already shows
+        let mut summary = message
+            .lines()
+            .next()
+            .unwrap_or("syntax error")
+            .trim()
+            .to_string();
+        // The parser only knows TypeQL 3, so 2.x sources fail with errors that do not say why
+        if let Some(construct) = check_dialect(source).first() {
+            write!(
+                summary,
+                "; this looks like TypeQL 2.x: {}",
+                construct.message
+            )
+            .unwrap();
+        }
+        Diagnostic::error("parse-error", summary).with_span(span)
+    }
+
+    /// Render the diagnostic with the offending source line underlined, e.g.
+    ///
+    /// ```text
+    /// error[parse-error]: syntax error
+    ///   --> functions.tql:3:5
+    ///    |
+    ///  3 |     $x isa;
+    ///    |     ^^^^^^
+    /// ```
+    pub fn render(&self, source_name: &str, source: &str) -> String {
+        let mut out = String::new();
+        write!(out, "{}[{}]", self.severity, self.code).unwrap();
+        if let Some(function) = &self.function {
+            write!(out, " in `{}`", function).unwrap();
+        }
+        writeln!(out, ": {}", self.message).unwrap();
+
+        let span = match self.span {
+            Some(span) if span.end <= source.len() => span,
+            _ => {
+                writeln!(out, "  --> {}", source_name).unwrap();
+                return out;
+            }
+        };
+
+        let line_text = source.lines().nth(span.line - 1).unwrap_or("");
+        let gutter = " ".repeat(span.line.to_string().len());
+        let underline_width = span
+            .slice(source)
+            .lines()
+            .next()
+            .map_or(1, |text| text.chars().count().max(1));
+
+        writeln!(
+            out,
+            "{} --> {}:{}:{}",
+            gutter, source_name, span.line, span.column
+        )
+        .unwrap();
+        writeln!(out, "{} |", gutter).unwrap();
+        writeln!(out, "{} | {}", span.line, line_text).unwrap();
+        writeln!(
+            out,
+            "{} | {}{}",
+            gutter,
+            " ".repeat(span.column - 1),
+            "^".repeat(underline_width)
+        )
+        .unwrap();
+        out
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]", self.severity, self.code)?;
+        if let Some(function) = &self.function {
+            write!(f, " in {}", function)?;
+        }
+        if let Some(span) = &self.span {
+            write!(f, " at {}:{}", span.line, span.column)?;
+        }
+        write!(f, ": {}", self.message)?;
+        if self.suppressed {
+            write!(f, " (suppressed)")?;
+        }
+        Ok(())
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Find a 1-based `(line, column)` in a parser message, either pest's `--> 3:5` marker or
+/// a `line 3` mention (column 1)
+fn error_position(message: &str) -> Option<(usize, usize)> {
+    if let Some(marker) = message.find("--> ") {
+        let position = message[marker + 4..].split_whitespace().next()?;
+        let (line, column) = position.split_once(':')?;
+        return Some((line.parse().ok()?, column.parse().ok()?));
+    }
+
+    let after = &message[message.find("line ")? + 5..];
+    let digits: String = after.chars().take_while(char::is_ascii_digit).collect();
+    Some((digits.parse().ok()?, 1))
+}
+
+/// Byte offset of a 1-based line and character column within `text`
+fn offset_of(text: &str, line: usize, column: usize) -> Option<usize> {
+    let line_start = if line <= 1 {
+        0
+    } else {
+        text.match_indices('\n').nth(line - 2)?.0 + 1
+    };
+    let line_text = text[line_start..].lines().next().unwrap_or("");
+    let column_offset = line_text
+        .char_indices()
+        .nth(column.saturating_sub(1))
+        .map_or(line_text.len(), |(offset, _)| offset);
+    Some(line_start + column_offset)
+}
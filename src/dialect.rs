@@ -0,0 +1,223 @@
+//! Telling TypeQL 2.x sources from 3.x ones, so schemas and rules being migrated get a
+//! clear diagnostic instead of a parse error.
+//!
+//! The typeql parser only understands 3.x, so 2.x constructs are found on tokens: `rule`
+//! definitions, `match ... get` queries, `person sub entity` declarations, the `long`
+//! value type and the `thing` root type. Inference rules can still be extracted as text
+//! with [`extract_rules`].
+
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostic::Diagnostic;
+use crate::doc_comment::doc_comment_before;
+use crate::lexer::{tokenize, Token, TokenKind};
+use crate::span::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dialect {
+    TypeQl2,
+    TypeQl3,
+}
+
+/// A TypeQL 2.x inference rule, `rule name: when { ... } then { ... };`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleMetadata {
+    pub name: String,
+    /// The `#` comment block directly above the rule, if any
+    pub doc: Option<String>,
+    /// Patterns of the `when` block as written, without the braces
+    pub when: String,
+    /// Statement of the `then` block as written, without the braces
+    pub then: String,
+    /// The whole rule, from `rule` to its closing brace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+}
+
+/// [`Dialect::TypeQl2`] if `source` uses any construct removed in TypeQL 3, otherwise
+/// [`Dialect::TypeQl3`]
+pub fn detect_dialect(source: &str) -> Dialect {
+    if check_dialect(source).is_empty() {
+        Dialect::TypeQl3
+    } else {
+        Dialect::TypeQl2
+    }
+}
+
+/// Report every TypeQL 2.x construct in `source`, each with its 3.x replacement
+pub fn check_dialect(source: &str) -> Vec<Diagnostic> {
+    let tokens: Vec<Token> = tokenize(source)
+        .into_iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .collect();
+    let mut diagnostics = Vec::new();
+    let mut report = |message: String, start: usize, end: usize| {
+        diagnostics.push(
+            Diagnostic::error("typeql-2", message).with_span(Some(Span::new(source, start, end))),
+        );
+    };
+
+    for (position, token) in tokens.iter().enumerate() {
+        let next = tokens.get(position + 1);
+        let previous = position.checked_sub(1).map(|previous| &tokens[previous]);
+        if token.kind != TokenKind::Word {
+            continue;
+        }
+        match token.text {
+            "rule" if is_rule(&tokens, position) => {
+                let name = &tokens[position + 1];
+                report(
+                    format!(
+                        "rule `{}` is TypeQL 2.x; TypeQL 3 replaces rules with functions",
+                        name.text
+                    ),
+                    token.range.start,
+                    name.range.end,
+                );
+            }
+            "sub" => {
+                let (Some(label), Some(kind)) = (previous, next) else {
+                    continue;
+                };
+                if label.kind == TokenKind::Word
+                    && ["entity", "relation", "attribute"].contains(&kind.text)
+                {
+                    report(
+                        format!(
+                            "`{} sub {}` is TypeQL 2.x; TypeQL 3 writes `{} {}`",
+                            label.text, kind.text, kind.text, label.text
+                        ),
+                        label.range.start,
+                        kind.range.end,
+                    );
+                }
+            }
+            "long" if previous.is_some_and(|previous| previous.is_word("value")) => report(
+                "value type `long` is TypeQL 2.x; TypeQL 3 calls it `integer`".to_string(),
+                token.range.start,
+                token.range.end,
+            ),
+            "thing"
+                if previous
+                    .is_some_and(|previous| previous.is_word("isa") || previous.is_word("sub")) =>
+            {
+                report(
+                    "the root type `thing` is TypeQL 2.x; TypeQL 3 has no common supertype"
+                        .to_string(),
+                    token.range.start,
+                    token.range.end,
+                )
+            }
+            "get"
+                if previous.is_some_and(|previous| previous.is_punct(";"))
+                    && next.is_some_and(|next| {
+                        next.kind == TokenKind::Variable || next.is_punct(";")
+                    }) =>
+            {
+                report(
+                    "`get` is TypeQL 2.x; TypeQL 3 selects variables with `select`".to_string(),
+                    token.range.start,
+                    token.range.end,
+                )
+            }
+            _ => {}
+        }
+    }
+
+    diagnostics
+}
+
+/// Extract every `rule` definition in TypeQL 2.x `source`, in order
+pub fn extract_rules(source: &str) -> Result<Vec<RuleMetadata>, Diagnostic> {
+    let tokens: Vec<Token> = tokenize(source)
+        .into_iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .collect();
+    let mut rules = Vec::new();
+    let mut position = 0;
+
+    while position < tokens.len() {
+        let token = &tokens[position];
+        if !is_rule(&tokens, position) {
+            position += 1;
+            continue;
+        }
+        let start = token.range.start;
+        let name = tokens[position + 1].text;
+        let invalid = |message: &str, at: Option<&Token>| {
+            let end = at.map_or(source.len(), |token| token.range.end);
+            Diagnostic::error("invalid-rule", format!("rule `{}`: {}", name, message))
+                .with_span(Some(Span::new(source, start, end)))
+        };
+
+        position += 3;
+        if !tokens
+            .get(position)
+            .is_some_and(|when| when.is_word("when"))
+        {
+            return Err(invalid(
+                "expected `when` after the name",
+                tokens.get(position),
+            ));
+        }
+        let (when, after_when) = block(source, &tokens, position + 1)
+            .ok_or_else(|| invalid("expected a `{ ... }` block after `when`", None))?;
+        position = after_when;
+        if !tokens
+            .get(position)
+            .is_some_and(|then| then.is_word("then"))
+        {
+            return Err(invalid(
+                "expected `then` after the `when` block",
+                tokens.get(position),
+            ));
+        }
+        let (then, after_then) = block(source, &tokens, position + 1)
+            .ok_or_else(|| invalid("expected a `{ ... }` block after `then`", None))?;
+        position = after_then;
+        let end = tokens[position - 1].range.end;
+
+        rules.push(RuleMetadata {
+            name: name.to_string(),
+            doc: doc_comment_before(source, start).description,
+            when,
+            then,
+            span: Some(Span::new(source, start, end)),
+        });
+    }
+
+    Ok(rules)
+}
+
+/// Whether `tokens[position]` starts `rule name:`
+fn is_rule(tokens: &[Token], position: usize) -> bool {
+    tokens[position].is_word("rule")
+        && tokens
+            .get(position + 1)
+            .is_some_and(|name| name.kind == TokenKind::Word)
+        && tokens
+            .get(position + 2)
+            .is_some_and(|colon| colon.is_punct(":"))
+}
+
+/// The trimmed text inside the braces opening at `tokens[open]`, and the position of the
+/// token after the closing brace
+fn block(source: &str, tokens: &[Token], open: usize) -> Option<(String, usize)> {
+    if !tokens.get(open)?.is_punct("{") {
+        return None;
+    }
+    let mut depth = 0;
+    for (position, token) in tokens.iter().enumerate().skip(open) {
+        if token.is_punct("{") {
+            depth += 1;
+        } else if token.is_punct("}") {
+            depth -= 1;
+            if depth == 0 {
+                let text = &source[tokens[open].range.end..token.range.start];
+                return Some((text.trim().to_string(), position + 1));
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,404 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::extract_function_metadata::FunctionMetadata;
+use crate::extract_schema_metadata::{SchemaMetadata, TypeDefinition};
+use crate::normalize::normalize;
+use crate::refactor::type_impact;
+
+/// Semantic differences between two versions of a function.
+///
+/// Parameters are matched by position, so renaming a parameter or any body variable is
+/// not a change; neither is reformatting, editing comments or reordering the statements of
+/// a `match` stage.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FunctionDiff {
+    /// Name of the new version
+    pub name: String,
+    /// Name of the old version when the function was renamed
+    pub renamed_from: Option<String>,
+    pub added_parameters: Vec<ParameterSummary>,
+    pub removed_parameters: Vec<ParameterSummary>,
+    pub retyped_parameters: Vec<RetypedParameter>,
+    pub output: Option<OutputChange>,
+    /// Functions called by the new version but not the old one
+    pub added_calls: Vec<String>,
+    /// Functions called by the old version but not the new one
+    pub removed_calls: Vec<String>,
+    /// Whether the body differs after normalizing whitespace, comments and variable names
+    pub body_changed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParameterSummary {
+    /// Zero-based position in the parameter list
+    pub position: usize,
+    pub name: String,
+    pub type_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RetypedParameter {
+    pub position: usize,
+    /// Name in the new version
+    pub name: String,
+    pub old_type: String,
+    pub new_type: String,
+}
+
+/// Declared output types before and after, as written in the signature
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutputChange {
+    pub old: String,
+    pub new: String,
+}
+
+impl FunctionDiff {
+    /// Whether the two versions are semantically identical
+    pub fn is_empty(&self) -> bool {
+        self.renamed_from.is_none()
+            && self.added_parameters.is_empty()
+            && self.removed_parameters.is_empty()
+            && self.retyped_parameters.is_empty()
+            && self.output.is_none()
+            && self.added_calls.is_empty()
+            && self.removed_calls.is_empty()
+            && !self.body_changed
+    }
+
+    /// Whether existing callers may break: parameters or output changed, or the name did
+    pub fn is_breaking(&self) -> bool {
+        self.renamed_from.is_some()
+            || !self.added_parameters.is_empty()
+            || !self.removed_parameters.is_empty()
+            || !self.retyped_parameters.is_empty()
+            || self.output.is_some()
+    }
+}
+
+/// Compare two versions of a function
+pub fn diff_functions(old: &FunctionMetadata, new: &FunctionMetadata) -> FunctionDiff {
+    let mut diff = FunctionDiff {
+        name: new.name.clone(),
+        renamed_from: (old.name != new.name).then(|| old.name.clone()),
+        ..FunctionDiff::default()
+    };
+
+    for (position, (before, after)) in old.parameters.iter().zip(&new.parameters).enumerate() {
+        if before.type_name != after.type_name {
+            diff.retyped_parameters.push(RetypedParameter {
+                position,
+                name: after.name.clone(),
+                old_type: before.type_name.clone(),
+                new_type: after.type_name.clone(),
+            });
+        }
+    }
+    let summarize = |function: &FunctionMetadata| {
+        function
+            .parameters
+            .iter()
+            .enumerate()
+            .map(|(position, parameter)| ParameterSummary {
+                position,
+                name: parameter.name.clone(),
+                type_name: parameter.type_name.clone(),
+            })
+            .collect::<Vec<_>>()
+    };
+    let common = old.parameters.len().min(new.parameters.len());
+    diff.removed_parameters = summarize(old).split_off(common);
+    diff.added_parameters = summarize(new).split_off(common);
+
+    let (old_output, new_output) = (old.output.to_string(), new.output.to_string());
+    if old_output != new_output {
+        diff.output = Some(OutputChange {
+            old: old_output,
+            new: new_output,
+        });
+    }
+
+    diff.added_calls = missing_from(&new.referenced_functions, &old.referenced_functions);
+    diff.removed_calls = missing_from(&old.referenced_functions, &new.referenced_functions);
+    diff.body_changed = normalize(old).statements != normalize(new).statements;
+
+    diff
+}
+
+/// Names in `names` that do not appear in `other`, in order
+fn missing_from(names: &[String], other: &[String]) -> Vec<String> {
+    names
+        .iter()
+        .filter(|name| !other.contains(name))
+        .cloned()
+        .collect()
+}
+
+impl fmt::Display for FunctionDiff {
+    /// One line per change, e.g. `~ parameter 1 $year: tax_year -> integer`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(old_name) = &self.renamed_from {
+            writeln!(f, "~ renamed from {}", old_name)?;
+        }
+        for parameter in &self.removed_parameters {
+            writeln!(
+                f,
+                "- parameter {} ${}: {}",
+                parameter.position, parameter.name, parameter.type_name
+            )?;
+        }
+        for parameter in &self.added_parameters {
+            writeln!(
+                f,
+                "+ parameter {} ${}: {}",
+                parameter.position, parameter.name, parameter.type_name
+            )?;
+        }
+        for parameter in &self.retyped_parameters {
+            writeln!(
+                f,
+                "~ parameter {} ${}: {} -> {}",
+                parameter.position, parameter.name, parameter.old_type, parameter.new_type
+            )?;
+        }
+        if let Some(output) = &self.output {
+            writeln!(f, "~ output: {} -> {}", output.old, output.new)?;
+        }
+        for callee in &self.removed_calls {
+            writeln!(f, "- calls {}", callee)?;
+        }
+        for callee in &self.added_calls {
+            writeln!(f, "+ calls {}", callee)?;
+        }
+        if self.body_changed {
+            writeln!(f, "~ body changed")?;
+        }
+        Ok(())
+    }
+}
+
+/// Differences between two versions of a schema.
+///
+/// Types are matched by label. A type whose kind changed, e.g. from entity to relation,
+/// is reported as removed and added again.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SchemaDiff {
+    /// Types only in the new schema, in its order
+    pub added_types: Vec<String>,
+    /// Types only in the old schema, in its order
+    pub removed_types: Vec<String>,
+    /// Types in both schemas whose declarations differ, in the new schema's order
+    pub changed_types: Vec<TypeChange>,
+}
+
+/// Declarations of a type that differ between two schemas
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TypeChange {
+    pub label: String,
+    pub supertype: Option<DeclarationChange>,
+    pub value_type: Option<DeclarationChange>,
+    pub added_owns: Vec<String>,
+    pub removed_owns: Vec<String>,
+    /// Roles played, as scoped labels like `employment:employee`
+    pub added_plays: Vec<String>,
+    pub removed_plays: Vec<String>,
+    /// Roles declared by a relation with `relates`
+    pub added_roles: Vec<String>,
+    pub removed_roles: Vec<String>,
+}
+
+/// A declaration before and after, `None` where the type had none
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeclarationChange {
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// A function referring to types that were removed or changed incompatibly
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImpactedFunction {
+    pub function: String,
+    /// The removed or incompatibly changed types and roles it refers to
+    pub types: Vec<String>,
+}
+
+impl SchemaDiff {
+    /// Whether the two schemas declare the same types in the same way
+    pub fn is_empty(&self) -> bool {
+        self.added_types.is_empty()
+            && self.removed_types.is_empty()
+            && self.changed_types.is_empty()
+    }
+
+    /// Whether existing data or functions may break: something was removed or redeclared
+    pub fn is_breaking(&self) -> bool {
+        !self.removed_types.is_empty() || self.changed_types.iter().any(TypeChange::is_breaking)
+    }
+
+    /// Functions whose parameters, output or body refer to a removed type, a type with a
+    /// breaking change or a removed role, in the order of `functions`
+    pub fn impacted_functions(&self, functions: &[FunctionMetadata]) -> Vec<ImpactedFunction> {
+        let mut labels: Vec<String> = self.removed_types.clone();
+        for change in &self.changed_types {
+            if change.is_breaking() {
+                labels.push(change.label.clone());
+            }
+            // Functions name a role bare or scoped by its relation, as `employment:employee`
+            for role in &change.removed_roles {
+                labels.push(role.clone());
+                labels.push(format!("{}:{}", change.label, role));
+            }
+        }
+
+        let mut impacted: Vec<ImpactedFunction> = Vec::new();
+        for label in &labels {
+            for impact in type_impact(functions, label) {
+                match impacted
+                    .iter_mut()
+                    .find(|impacted| impacted.function == impact.function)
+                {
+                    Some(impacted) if !impacted.types.iter().any(|used| used == label) => {
+                        impacted.types.push(label.to_string())
+                    }
+                    Some(_) => {}
+                    None => impacted.push(ImpactedFunction {
+                        function: impact.function,
+                        types: vec![label.to_string()],
+                    }),
+                }
+            }
+        }
+        let position = |name: &str| functions.iter().position(|function| function.name == name);
+        impacted.sort_by_key(|impacted| position(&impacted.function));
+        impacted
+    }
+}
+
+impl TypeChange {
+    /// Whether anything was removed from the type or redeclared; additions are compatible
+    pub fn is_breaking(&self) -> bool {
+        self.supertype.is_some()
+            || self.value_type.is_some()
+            || !self.removed_owns.is_empty()
+            || !self.removed_plays.is_empty()
+            || !self.removed_roles.is_empty()
+    }
+
+    fn is_empty(&self) -> bool {
+        !self.is_breaking()
+            && self.added_owns.is_empty()
+            && self.added_plays.is_empty()
+            && self.added_roles.is_empty()
+    }
+}
+
+/// Compare two versions of a schema
+pub fn diff_schemas(old: &SchemaMetadata, new: &SchemaMetadata) -> SchemaDiff {
+    let same_type = |type_def: &TypeDefinition, schema: &SchemaMetadata| {
+        schema
+            .get(&type_def.label)
+            .filter(|other| other.kind == type_def.kind)
+            .is_some()
+    };
+    let mut diff = SchemaDiff {
+        added_types: new
+            .types
+            .iter()
+            .filter(|type_def| !same_type(type_def, old))
+            .map(|type_def| type_def.label.clone())
+            .collect(),
+        removed_types: old
+            .types
+            .iter()
+            .filter(|type_def| !same_type(type_def, new))
+            .map(|type_def| type_def.label.clone())
+            .collect(),
+        ..SchemaDiff::default()
+    };
+
+    for after in &new.types {
+        let Some(before) = old
+            .get(&after.label)
+            .filter(|before| before.kind == after.kind)
+        else {
+            continue;
+        };
+        let change = TypeChange {
+            label: after.label.clone(),
+            supertype: declaration_change(&before.supertype, &after.supertype),
+            value_type: declaration_change(&before.value_type, &after.value_type),
+            added_owns: missing_from(&after.owns, &before.owns),
+            removed_owns: missing_from(&before.owns, &after.owns),
+            added_plays: missing_from(&after.plays, &before.plays),
+            removed_plays: missing_from(&before.plays, &after.plays),
+            added_roles: missing_from(&after.relates, &before.relates),
+            removed_roles: missing_from(&before.relates, &after.relates),
+        };
+        if !change.is_empty() {
+            diff.changed_types.push(change);
+        }
+    }
+
+    diff
+}
+
+fn declaration_change(old: &Option<String>, new: &Option<String>) -> Option<DeclarationChange> {
+    (old != new).then(|| DeclarationChange {
+        old: old.clone(),
+        new: new.clone(),
+    })
+}
+
+impl fmt::Display for SchemaDiff {
+    /// One line per change, e.g. `~ person: + owns email`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for label in &self.removed_types {
+            writeln!(f, "- type {}", label)?;
+        }
+        for label in &self.added_types {
+            writeln!(f, "+ type {}", label)?;
+        }
+        for change in &self.changed_types {
+            write!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for TypeChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let declared = |value: &Option<String>| value.clone().unwrap_or_else(|| "none".to_string());
+        if let Some(supertype) = &self.supertype {
+            writeln!(
+                f,
+                "~ {}: sub {} -> {}",
+                self.label,
+                declared(&supertype.old),
+                declared(&supertype.new)
+            )?;
+        }
+        if let Some(value_type) = &self.value_type {
+            writeln!(
+                f,
+                "~ {}: value {} -> {}",
+                self.label,
+                declared(&value_type.old),
+                declared(&value_type.new)
+            )?;
+        }
+        let lists = [
+            ("-", "owns", &self.removed_owns),
+            ("+", "owns", &self.added_owns),
+            ("-", "plays", &self.removed_plays),
+            ("+", "plays", &self.added_plays),
+            ("-", "relates", &self.removed_roles),
+            ("+", "relates", &self.added_roles),
+        ];
+        for (sign, keyword, labels) in lists {
+            for label in labels {
+                writeln!(f, "~ {}: {} {} {}", self.label, sign, keyword, label)?;
+            }
+        }
+        Ok(())
+    }
+}
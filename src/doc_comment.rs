@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+
+use crate::extract_function_metadata::FunctionMetadata;
+
+/// A `@name text` tag from a doc comment, e.g. `@deprecated use calculate_tax_v2`.
+///
+/// Lines after a tag belong to it until the next tag, so `@example` can span several lines.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct DocTag {
+    pub name: String,
+    pub text: String,
+}
+
+/// A doc comment split into its description, tags and `typedb-meta:` directives
+#[derive(Debug, Default)]
+pub(crate) struct DocComment {
+    pub description: Option<String>,
+    pub tags: Vec<DocTag>,
+    /// Lint rules named by `# typedb-meta: ignore <rule>, ...` lines
+    pub ignored_rules: Vec<String>,
+}
+
+/// Prefix of comment lines addressed to this tool rather than to readers
+pub(crate) const DIRECTIVE_PREFIX: &str = "typedb-meta:";
+
+/// Names of the doc tags listing a function's categories, e.g. `# @tag: tax, income`
+const CATEGORY_TAGS: &[&str] = &["tag", "tags"];
+
+impl FunctionMetadata {
+    /// Text of the first tag called `name`
+    pub fn doc_tag(&self, name: &str) -> Option<&str> {
+        self.doc_tags
+            .iter()
+            .find(|tag| tag.name == name)
+            .map(|tag| tag.text.as_str())
+    }
+
+    /// Whether the doc comment has a `@deprecated` tag
+    pub fn is_deprecated(&self) -> bool {
+        self.doc_tag("deprecated").is_some()
+    }
+
+    /// Whether a `# typedb-meta: ignore` comment above the function names `rule`
+    pub fn ignores_rule(&self, rule: &str) -> bool {
+        self.lint_ignores.iter().any(|ignored| ignored == rule)
+    }
+
+    /// Whether `tag` is one of the function's categories, ignoring case
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own.eq_ignore_ascii_case(tag))
+    }
+
+    /// Every `@example` in the doc comment
+    pub fn examples(&self) -> impl Iterator<Item = &str> {
+        self.doc_tags
+            .iter()
+            .filter(|tag| tag.name == "example")
+            .map(|tag| tag.text.as_str())
+    }
+}
+
+/// The comma-separated categories of every `@tag` in `doc_tags`, in order and without
+/// repeats, which differ by more than case
+pub(crate) fn categories(doc_tags: &[DocTag]) -> Vec<String> {
+    let mut categories: Vec<String> = Vec::new();
+    for tag in doc_tags {
+        if !CATEGORY_TAGS.contains(&tag.name.as_str()) {
+            continue;
+        }
+        for category in tag.text.split(',').map(str::trim) {
+            if !category.is_empty()
+                && !categories
+                    .iter()
+                    .any(|known| known.eq_ignore_ascii_case(category))
+            {
+                categories.push(category.to_string());
+            }
+        }
+    }
+    categories
+}
+
+/// The block of `#` comment lines directly above the line where `offset` is, if nothing
+/// else precedes `offset` on that line. A blank line ends the block.
+pub(crate) fn doc_comment_before(source: &str, offset: usize) -> DocComment {
+    let line_start = source[..offset]
+        .rfind('\n')
+        .map_or(0, |newline| newline + 1);
+    if !source[line_start..offset].trim().is_empty() {
+        return DocComment::default();
+    }
+
+    let mut lines: Vec<&str> = source[..line_start]
+        .lines()
+        .rev()
+        .map(str::trim)
+        .take_while(|line| line.starts_with('#'))
+        .map(|line| {
+            let text = line.trim_start_matches('#');
+            text.strip_prefix(' ').unwrap_or(text).trim_end()
+        })
+        .collect();
+    lines.reverse();
+    parse_doc_comment(&lines)
+}
+
+fn parse_doc_comment(lines: &[&str]) -> DocComment {
+    let mut description = Vec::new();
+    let mut tags: Vec<(String, Vec<&str>)> = Vec::new();
+    let mut ignored_rules = Vec::new();
+
+    for line in lines {
+        if let Some(directive) = line.trim_start().strip_prefix(DIRECTIVE_PREFIX) {
+            if let Some(rules) = directive.trim().strip_prefix("ignore") {
+                ignored_rules.extend(
+                    rules
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .filter(|rule| !rule.is_empty())
+                        .map(str::to_string),
+                );
+            }
+        } else if let Some(tag) = line.strip_prefix('@') {
+            let name_end = tag
+                .find(|c: char| c == ':' || c.is_whitespace())
+                .unwrap_or(tag.len());
+            let (name, text) = tag.split_at(name_end);
+            // `@tag: tax` and `@tag:tax` are written like `@tag tax`
+            let text = text.strip_prefix(':').unwrap_or(text);
+            tags.push((name.to_string(), vec![text.trim()]));
+        } else if let Some((_, text)) = tags.last_mut() {
+            text.push(line);
+        } else {
+            description.push(*line);
+        }
+    }
+
+    let description = description.join("\n").trim().to_string();
+    DocComment {
+        description: (!description.is_empty()).then_some(description),
+        tags: tags
+            .into_iter()
+            .map(|(name, text)| DocTag {
+                name,
+                text: dedent(&text).trim_matches('\n').to_string(),
+            })
+            .collect(),
+        ignored_rules,
+    }
+}
+
+/// Join lines, removing the indentation they all share so example code keeps its shape
+fn dedent(lines: &[&str]) -> String {
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or("").trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_names_end_at_a_colon() {
+        let source = "# Total income\n# @tag:tax, income\n# @tags: audit\n# @since 2024\nfun f";
+        let doc_comment = doc_comment_before(source, source.len() - "fun f".len());
+
+        let names: Vec<&str> = doc_comment
+            .tags
+            .iter()
+            .map(|tag| tag.name.as_str())
+            .collect();
+        assert_eq!(names, ["tag", "tags", "since"]);
+        assert_eq!(categories(&doc_comment.tags), ["tax", "income", "audit"]);
+    }
+}
@@ -0,0 +1,204 @@
+use std::fmt::Write;
+
+use crate::body::ConstraintKind;
+use crate::extract_function_metadata::FunctionMetadata;
+use crate::namespace::Namespaces;
+
+/// Render Markdown reference documentation for a function library.
+///
+/// Each function gets its own section with the signature, a parameter table, the return
+/// type, the functions it calls and is called by, and its body as a fenced code block.
+pub fn render_markdown(functions: &[FunctionMetadata]) -> String {
+    render_markdown_with_namespaces(functions, &Namespaces::default())
+}
+
+/// Render Markdown reference documentation like [`render_markdown`], with the index and
+/// sections grouped by namespace.
+///
+/// Namespaces come in name order, followed by the functions without one; each section
+/// names its function's namespace.
+pub fn render_markdown_with_namespaces(
+    functions: &[FunctionMetadata],
+    namespaces: &Namespaces,
+) -> String {
+    let mut ordered: Vec<&FunctionMetadata> = functions.iter().collect();
+    // Stable, so functions keep their order within a namespace
+    ordered.sort_by_key(|function| match namespaces.namespace_of(&function.name) {
+        Some(namespace) => (0, namespace),
+        None => (1, ""),
+    });
+    let mut out = String::new();
+
+    writeln!(out, "# Functions").unwrap();
+    writeln!(out).unwrap();
+    let mut current = None;
+    for function in &ordered {
+        let namespace = namespaces.namespace_of(&function.name);
+        let indent = if namespaces.is_empty() { "" } else { "  " };
+        if !namespaces.is_empty() && current != Some(namespace) {
+            writeln!(out, "- **{}**", namespace.unwrap_or("Other")).unwrap();
+            current = Some(namespace);
+        }
+        writeln!(
+            out,
+            "{}- [`{}`](#{})",
+            indent,
+            function.name,
+            anchor(&function.name)
+        )
+        .unwrap();
+    }
+
+    for function in ordered {
+        writeln!(out).unwrap();
+        render_function(&mut out, function, functions, namespaces);
+    }
+
+    out
+}
+
+fn render_function(
+    out: &mut String,
+    function: &FunctionMetadata,
+    all: &[FunctionMetadata],
+    namespaces: &Namespaces,
+) {
+    writeln!(out, "## {}", function.name).unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "```typeql").unwrap();
+    writeln!(out, "{}", function.signature()).unwrap();
+    writeln!(out, "```").unwrap();
+    writeln!(out).unwrap();
+    if let Some(reason) = function.doc_tag("deprecated") {
+        writeln!(out, "> **Deprecated.** {}", reason).unwrap();
+        writeln!(out).unwrap();
+    }
+    if let Some(doc) = &function.doc {
+        writeln!(out, "{}", doc).unwrap();
+        writeln!(out).unwrap();
+    }
+    if let Some(namespace) = namespaces.namespace_of(&function.name) {
+        writeln!(out, "**Namespace:** `{}`", namespace).unwrap();
+        writeln!(out).unwrap();
+    }
+    if !function.tags.is_empty() {
+        let tags: Vec<String> = function
+            .tags
+            .iter()
+            .map(|tag| format!("`{}`", tag))
+            .collect();
+        writeln!(out, "**Tags:** {}", tags.join(", ")).unwrap();
+        writeln!(out).unwrap();
+    }
+
+    writeln!(out, "### Parameters").unwrap();
+    writeln!(out).unwrap();
+    if function.parameters.is_empty() {
+        writeln!(out, "_None_").unwrap();
+    } else {
+        writeln!(out, "| Name | Type |").unwrap();
+        writeln!(out, "| --- | --- |").unwrap();
+        for parameter in &function.parameters {
+            writeln!(out, "| `${}` | `{}` |", parameter.name, parameter.type_name).unwrap();
+        }
+    }
+    writeln!(out).unwrap();
+
+    writeln!(out, "### Returns").unwrap();
+    writeln!(out).unwrap();
+    writeln!(
+        out,
+        "`{}` via `return {};`",
+        function.output, function.return_expression
+    )
+    .unwrap();
+    writeln!(out).unwrap();
+
+    let callers: Vec<&str> = all
+        .iter()
+        .filter(|other| other.referenced_functions.contains(&function.name))
+        .map(|other| other.name.as_str())
+        .collect();
+    writeln!(out, "### Calls").unwrap();
+    writeln!(out).unwrap();
+    write_function_links(
+        out,
+        function.referenced_functions.iter().map(String::as_str),
+        all,
+    );
+    writeln!(out).unwrap();
+    writeln!(out, "### Called by").unwrap();
+    writeln!(out).unwrap();
+    write_function_links(out, callers.into_iter(), all);
+    writeln!(out).unwrap();
+
+    let computations: Vec<String> = function
+        .constraints
+        .iter()
+        .filter_map(|constraint| match &constraint.kind {
+            ConstraintKind::Let { vars, tree, .. } => {
+                let vars: Vec<String> = vars.iter().map(|var| format!("${}", var)).collect();
+                Some(format!("- `{} = {}`", vars.join(", "), tree))
+            }
+            _ => None,
+        })
+        .collect();
+    if !computations.is_empty() {
+        writeln!(out, "### Computations").unwrap();
+        writeln!(out).unwrap();
+        for computation in computations {
+            writeln!(out, "{}", computation).unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+
+    let examples: Vec<&str> = function.examples().collect();
+    if !examples.is_empty() {
+        writeln!(out, "### Examples").unwrap();
+        writeln!(out).unwrap();
+        for example in examples {
+            writeln!(out, "```typeql").unwrap();
+            writeln!(out, "{}", example).unwrap();
+            writeln!(out, "```").unwrap();
+            writeln!(out).unwrap();
+        }
+    }
+
+    writeln!(out, "### Definition").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "```typeql").unwrap();
+    writeln!(out, "{}", function.code_block.trim_end()).unwrap();
+    writeln!(out, "```").unwrap();
+}
+
+fn write_function_links<'a>(
+    out: &mut String,
+    names: impl Iterator<Item = &'a str>,
+    all: &[FunctionMetadata],
+) {
+    let links: Vec<String> = names
+        .map(|name| {
+            // Only link functions documented on this page
+            if all.iter().any(|function| function.name == name) {
+                format!("[`{}`](#{})", name, anchor(name))
+            } else {
+                format!("`{}`", name)
+            }
+        })
+        .collect();
+
+    if links.is_empty() {
+        writeln!(out, "_None_").unwrap();
+    } else {
+        writeln!(out, "{}", links.join(", ")).unwrap();
+    }
+}
+
+/// GitHub-style heading anchor
+fn anchor(heading: &str) -> String {
+    heading
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+        .collect()
+}
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::extract_function_metadata::FunctionMetadata;
+use crate::normalize::{masked_statements, normalize, NormalizedFunction};
+
+/// Similarity from which [`find_duplicates`] reports a pair unless told otherwise
+pub const DEFAULT_SIMILARITY_THRESHOLD: f64 = 0.8;
+
+/// Functions that are copies of each other, found by [`find_duplicates`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DuplicateReport {
+    /// Groups of functions that normalize equal, with their names sorted
+    pub exact: Vec<Vec<String>>,
+    /// Near-duplicates sharing most of their body, most similar first
+    pub similar: Vec<SimilarPair>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarPair {
+    pub first: String,
+    pub second: String,
+    /// Share of body statements the two have in common, from 0 to 1
+    pub similarity: f64,
+}
+
+/// Find structural duplicates and near-duplicates among `functions`.
+///
+/// Exact duplicates are functions whose [`normalize`]d forms are equal. Similarity is
+/// the multiset Jaccard index of the body statements with variables other than
+/// parameters anonymised; pairs at or above `threshold` are reported.
+pub fn find_duplicates(functions: &[FunctionMetadata], threshold: f64) -> DuplicateReport {
+    let normalized: Vec<NormalizedFunction> = functions.iter().map(normalize).collect();
+
+    let mut groups: HashMap<&NormalizedFunction, Vec<String>> = HashMap::new();
+    for (function, form) in functions.iter().zip(&normalized) {
+        groups.entry(form).or_default().push(function.name.clone());
+    }
+    let mut exact: Vec<Vec<String>> = groups
+        .into_values()
+        .filter(|names| names.len() > 1)
+        .map(|mut names| {
+            names.sort();
+            names
+        })
+        .collect();
+    exact.sort();
+
+    let statements: Vec<Vec<String>> = functions.iter().map(masked_statements).collect();
+    let mut similar = Vec::new();
+    for i in 0..functions.len() {
+        for j in i + 1..functions.len() {
+            if normalized[i] == normalized[j] {
+                continue;
+            }
+            let similarity = jaccard(&statements[i], &statements[j]);
+            if similarity >= threshold {
+                similar.push(SimilarPair {
+                    first: functions[i].name.clone(),
+                    second: functions[j].name.clone(),
+                    similarity,
+                });
+            }
+        }
+    }
+    similar.sort_by(|a, b| {
+        b.similarity
+            .total_cmp(&a.similarity)
+            .then_with(|| (&a.first, &a.second).cmp(&(&b.first, &b.second)))
+    });
+
+    DuplicateReport { exact, similar }
+}
+
+/// Multiset Jaccard index: shared statements over all statements
+fn jaccard(a: &[String], b: &[String]) -> f64 {
+    let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+    for statement in a {
+        counts.entry(statement).or_default().0 += 1;
+    }
+    for statement in b {
+        counts.entry(statement).or_default().1 += 1;
+    }
+    let (shared, total) = counts.values().fold((0, 0), |(shared, total), &(x, y)| {
+        (shared + x.min(y), total + x.max(y))
+    });
+    if total == 0 {
+        1.0
+    } else {
+        shared as f64 / total as f64
+    }
+}
@@ -0,0 +1,270 @@
+//! Errors returned by the library: extracting functions, reading saved metadata and
+//! the operations built on them
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::diagnostic::Diagnostic;
+use crate::snapshot::UPDATE_SNAPSHOTS_VAR;
+
+/// Why functions could not be extracted, read from metadata or operated on
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ExtractError {
+    /// Source that is not valid TypeQL
+    ParseError(Box<SourceDiagnostic>),
+    /// Valid TypeQL that extraction does not handle, such as a schema query where a data
+    /// query is expected
+    UnsupportedConstruct(Box<SourceDiagnostic>),
+    /// A `fun` keyword without the name and parameter list that must follow it
+    EmptySignature(Box<SourceDiagnostic>),
+    /// A file or directory that could not be read
+    Io { path: PathBuf, source: io::Error },
+    /// A path pattern that is not a valid glob
+    InvalidPattern { pattern: String, message: String },
+    /// A path pattern that matches no files
+    NoMatches { pattern: String },
+    /// A metadata document that is not valid JSON or misses required fields, or built
+    /// metadata that is incomplete
+    InvalidMetadata { message: String },
+    /// A metadata document written by a newer version of this crate
+    UnsupportedVersion { version: u32, supported: u32 },
+    /// Functions calling each other in a cycle where they must be ordered, the cycle
+    /// listed from its entry function back to itself
+    CyclicCalls { cycle: Vec<String> },
+    /// A function defined more than once where names must be unique
+    DuplicateFunction { name: String },
+    /// A function name that is not defined
+    UnknownFunction { name: String },
+    /// A refactoring that cannot be applied, such as renaming to a name already in use
+    InvalidRefactoring { message: String },
+    /// A golden file that does not exist when comparing snapshots
+    SnapshotMissing { golden: PathBuf },
+    /// A golden file that differs from the current snapshot, with a line diff from it
+    SnapshotMismatch { golden: PathBuf, diff: String },
+    /// Records or arguments that do not fit the schema or function they are given for
+    InvalidData { message: String },
+    /// A malformed message or tool call from an LSP or MCP client
+    Protocol { message: String },
+    /// Input or output of a session that could not be read or written
+    Stream { source: io::Error },
+    /// An embedder that failed, or returned embeddings that do not fit its texts
+    Embedding {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// A failed SQLite operation, with the database file when it was being opened
+    #[cfg(feature = "sqlite")]
+    Sqlite {
+        path: Option<PathBuf>,
+        source: rusqlite::Error,
+    },
+    /// A failed request to a TypeDB server, with what was being done when known
+    #[cfg(feature = "client")]
+    Driver {
+        context: Option<String>,
+        source: typedb_driver::Error,
+    },
+    /// Metadata that could not be built into an Arrow record batch
+    #[cfg(feature = "parquet")]
+    Arrow { source: arrow_schema::ArrowError },
+    /// A failure encoding or writing a Parquet file
+    #[cfg(feature = "parquet")]
+    Parquet {
+        source: parquet::errors::ParquetError,
+    },
+}
+
+/// A [`Diagnostic`] with the name of the source it was found in, and the diagnostic
+/// rendered against that source
+#[derive(Debug, Clone)]
+pub struct SourceDiagnostic {
+    pub source_name: String,
+    pub diagnostic: Diagnostic,
+    /// [`Diagnostic::render`] output, showing the offending line
+    pub rendered: String,
+}
+
+impl ExtractError {
+    /// The error for a diagnostic that extraction from `source` returned
+    pub fn from_diagnostic(diagnostic: Diagnostic, source_name: &str, source: &str) -> Self {
+        let located = Box::new(SourceDiagnostic {
+            source_name: source_name.to_string(),
+            rendered: diagnostic.render(source_name, source),
+            diagnostic,
+        });
+        match located.diagnostic.code.as_str() {
+            "unsupported-query" => ExtractError::UnsupportedConstruct(located),
+            "empty-signature" => ExtractError::EmptySignature(located),
+            _ => ExtractError::ParseError(located),
+        }
+    }
+
+    /// The error for a diagnostic found in text passed in directly, named `<input>`
+    pub(crate) fn from_input(diagnostic: Diagnostic, source: &str) -> Self {
+        Self::from_diagnostic(diagnostic, "<input>", source)
+    }
+
+    pub(crate) fn io(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        ExtractError::Io {
+            path: path.into(),
+            source,
+        }
+    }
+
+    pub(crate) fn stream(source: io::Error) -> Self {
+        ExtractError::Stream { source }
+    }
+
+    #[cfg(any(feature = "lsp", feature = "mcp"))]
+    pub(crate) fn protocol(message: impl Into<String>) -> Self {
+        ExtractError::Protocol {
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn embedding(source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        ExtractError::Embedding {
+            source: source.into(),
+        }
+    }
+
+    #[cfg(feature = "client")]
+    pub(crate) fn driver(context: impl Into<String>, source: typedb_driver::Error) -> Self {
+        ExtractError::Driver {
+            context: Some(context.into()),
+            source,
+        }
+    }
+
+    /// The diagnostic behind a parse error, unsupported construct or empty signature
+    pub fn diagnostic(&self) -> Option<&Diagnostic> {
+        match self {
+            ExtractError::ParseError(located)
+            | ExtractError::UnsupportedConstruct(located)
+            | ExtractError::EmptySignature(located) => Some(&located.diagnostic),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtractError::ParseError(located)
+            | ExtractError::UnsupportedConstruct(located)
+            | ExtractError::EmptySignature(located) => {
+                write!(f, "{}", located.rendered.trim_end())
+            }
+            ExtractError::Io { path, source } => {
+                write!(f, "failed to read {}: {}", path.display(), source)
+            }
+            ExtractError::InvalidPattern { pattern, message } => {
+                write!(f, "invalid path pattern `{}`: {}", pattern, message)
+            }
+            ExtractError::NoMatches { pattern } => write!(f, "no files match `{}`", pattern),
+            ExtractError::InvalidMetadata { message } => write!(f, "{}", message),
+            ExtractError::UnsupportedVersion { version, supported } => write!(
+                f,
+                "metadata version {} is newer than the supported version {}",
+                version, supported
+            ),
+            ExtractError::CyclicCalls { cycle } => {
+                write!(f, "cyclic function calls: {}", cycle.join(" -> "))
+            }
+            ExtractError::DuplicateFunction { name } => {
+                write!(f, "function `{}` is defined more than once", name)
+            }
+            ExtractError::UnknownFunction { name } => write!(f, "no function named `{}`", name),
+            ExtractError::InvalidRefactoring { message } => write!(f, "{}", message),
+            ExtractError::SnapshotMissing { golden } => write!(
+                f,
+                "no snapshot at {}; run with {}=1 to create it",
+                golden.display(),
+                UPDATE_SNAPSHOTS_VAR
+            ),
+            ExtractError::SnapshotMismatch { golden, diff } => write!(
+                f,
+                "snapshot {} does not match; run with {}=1 to update it\n{}",
+                golden.display(),
+                UPDATE_SNAPSHOTS_VAR,
+                diff
+            ),
+            ExtractError::InvalidData { message } | ExtractError::Protocol { message } => {
+                write!(f, "{}", message)
+            }
+            ExtractError::Stream { source } => write!(f, "stream error: {}", source),
+            ExtractError::Embedding { source } => write!(f, "embedding failed: {}", source),
+            #[cfg(feature = "sqlite")]
+            ExtractError::Sqlite {
+                path: Some(path),
+                source,
+            } => write!(f, "failed to open store {}: {}", path.display(), source),
+            #[cfg(feature = "sqlite")]
+            ExtractError::Sqlite { path: None, source } => write!(f, "store error: {}", source),
+            #[cfg(feature = "client")]
+            ExtractError::Driver {
+                context: Some(context),
+                source,
+            } => write!(f, "{}: {}", context, source),
+            #[cfg(feature = "client")]
+            ExtractError::Driver {
+                context: None,
+                source,
+            } => write!(f, "TypeDB error: {}", source),
+            #[cfg(feature = "parquet")]
+            ExtractError::Arrow { source } => write!(f, "Arrow error: {}", source),
+            #[cfg(feature = "parquet")]
+            ExtractError::Parquet { source } => write!(f, "Parquet error: {}", source),
+        }
+    }
+}
+
+impl std::error::Error for ExtractError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExtractError::Io { source, .. } | ExtractError::Stream { source } => Some(source),
+            ExtractError::Embedding { source } => Some(source.as_ref()),
+            #[cfg(feature = "sqlite")]
+            ExtractError::Sqlite { source, .. } => Some(source),
+            #[cfg(feature = "client")]
+            ExtractError::Driver { source, .. } => Some(source),
+            #[cfg(feature = "parquet")]
+            ExtractError::Arrow { source } => Some(source),
+            #[cfg(feature = "parquet")]
+            ExtractError::Parquet { source } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for ExtractError {
+    fn from(source: rusqlite::Error) -> Self {
+        ExtractError::Sqlite { path: None, source }
+    }
+}
+
+#[cfg(feature = "client")]
+impl From<typedb_driver::Error> for ExtractError {
+    fn from(source: typedb_driver::Error) -> Self {
+        ExtractError::Driver {
+            context: None,
+            source,
+        }
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<arrow_schema::ArrowError> for ExtractError {
+    fn from(source: arrow_schema::ArrowError) -> Self {
+        ExtractError::Arrow { source }
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<parquet::errors::ParquetError> for ExtractError {
+    fn from(source: parquet::errors::ParquetError) -> Self {
+        ExtractError::Parquet { source }
+    }
+}
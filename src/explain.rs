@@ -0,0 +1,381 @@
+//! Plain English descriptions of what a function does, for readers new to TypeQL.
+//!
+//! The description is built from templates over the extracted body, not from the doc
+//! comment: what is matched, which matches are kept or excluded, what is computed and
+//! what is returned. For example:
+//!
+//! ```text
+//! fun calculate_total_income($taxpayer: taxpayer) -> double:
+//!     match
+//!         $income (earner: $taxpayer) isa income_source, has amount $amt;
+//!     return sum($amt);
+//! ```
+//!
+//! is explained as "Matches income_source relations for the taxpayer and returns the sum
+//! of amount."
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::body::{BodyPattern, Constraint, ConstraintKind};
+use crate::extract_function_metadata::{FunctionMetadata, ReturnExpr, Selector};
+
+/// Describe `function` in a few English sentences
+pub fn explain(function: &FunctionMetadata) -> String {
+    let nouns = Nouns::of(function);
+    let patterns: Vec<BodyPattern> = if function.patterns.is_empty() {
+        function
+            .constraints
+            .iter()
+            .map(|constraint| BodyPattern::Statement {
+                constraints: vec![constraint.clone()],
+            })
+            .collect()
+    } else {
+        function.patterns.clone()
+    };
+
+    let mut description = Description::default();
+    for pattern in &patterns {
+        description.add(pattern, &nouns);
+    }
+
+    let returns = returns(&function.return_expression, &nouns);
+    let mut out = match description.things.len() {
+        0 => capitalize(&returns),
+        1 => format!("Matches {} and {}", description.things[0], returns),
+        _ => format!("Matches {}, and {}", list(&description.things), returns),
+    };
+    out.push('.');
+    for sentence in &description.sentences {
+        write!(out, " {}.", sentence).unwrap();
+    }
+    if !description.filters.is_empty() {
+        write!(
+            out,
+            " Keeps only matches where {}.",
+            list(&description.filters)
+        )
+        .unwrap();
+    }
+    if !function.referenced_functions.is_empty() {
+        let mut callees: Vec<String> = Vec::new();
+        for callee in &function.referenced_functions {
+            let callee = format!("`{}`", callee);
+            if !callees.contains(&callee) {
+                callees.push(callee);
+            }
+        }
+        write!(out, " Calls {}.", list(&callees)).unwrap();
+    }
+    out
+}
+
+/// What the variables of a function stand for, e.g. `$amt` in `has amount $amt` is
+/// "amount"
+struct Nouns {
+    nouns: HashMap<String, String>,
+    parameters: HashSet<String>,
+}
+
+impl Nouns {
+    fn of(function: &FunctionMetadata) -> Self {
+        let mut nouns = HashMap::new();
+        // Earlier sources win: parameters, then types, attributes and roles
+        for parameter in &function.parameters {
+            let noun = if parameter.name.len() > 2
+                && parameter
+                    .name
+                    .chars()
+                    .all(|c| c.is_alphabetic() || c == '_' || c == '-')
+            {
+                format!("the {}", parameter.name.replace(['_', '-'], " "))
+            } else {
+                format!("`${}`", parameter.name)
+            };
+            nouns.insert(parameter.name.clone(), noun);
+        }
+        for constraint in &function.constraints {
+            if let ConstraintKind::Isa { var, type_name, .. } = &constraint.kind {
+                nouns
+                    .entry(var.clone())
+                    .or_insert_with(|| type_name.clone());
+            }
+        }
+        for constraint in &function.constraints {
+            if let ConstraintKind::Has {
+                attribute: Some(attribute),
+                value,
+                ..
+            } = &constraint.kind
+            {
+                if let Some(var) = value.strip_prefix('$') {
+                    nouns
+                        .entry(var.to_string())
+                        .or_insert_with(|| attribute.clone());
+                }
+            }
+        }
+        for constraint in &function.constraints {
+            if let ConstraintKind::Links { role_players, .. } = &constraint.kind {
+                for role_player in role_players {
+                    if let Some(role) = &role_player.role {
+                        nouns
+                            .entry(role_player.player.clone())
+                            .or_insert_with(|| role.clone());
+                    }
+                }
+            }
+        }
+        nouns.remove("_");
+        let parameters = function
+            .parameters
+            .iter()
+            .map(|parameter| parameter.name.clone())
+            .collect();
+        Nouns { nouns, parameters }
+    }
+
+    fn var(&self, var: &str) -> String {
+        self.nouns
+            .get(var)
+            .cloned()
+            .unwrap_or_else(|| format!("`${}`", var))
+    }
+
+    /// An operand as written, named if it is a single variable
+    fn operand(&self, text: &str) -> String {
+        match text.strip_prefix('$') {
+            Some(var)
+                if var
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '-') =>
+            {
+                self.var(var)
+            }
+            _ => format!("`{}`", text),
+        }
+    }
+
+    fn is_parameter(&self, var: &str) -> bool {
+        self.parameters.contains(var)
+    }
+}
+
+/// Phrases collected from the body
+#[derive(Default)]
+struct Description {
+    /// What is matched, e.g. "income_source relations for the taxpayer"
+    things: Vec<String>,
+    /// Conditions on the matches, e.g. "income is at least bracket_min"
+    filters: Vec<String>,
+    /// Whole sentences for negations, alternatives, optional parts and computed values
+    sentences: Vec<String>,
+}
+
+impl Description {
+    fn add(&mut self, pattern: &BodyPattern, nouns: &Nouns) {
+        match pattern {
+            BodyPattern::Statement { constraints } => {
+                self.things.extend(thing(constraints, nouns));
+                for constraint in constraints {
+                    match &constraint.kind {
+                        ConstraintKind::Comparison {
+                            lhs,
+                            comparator,
+                            rhs,
+                        } => self.filters.push(format!(
+                            "{} is {} {}",
+                            nouns.operand(lhs),
+                            comparator_words(comparator),
+                            nouns.operand(rhs)
+                        )),
+                        ConstraintKind::Let {
+                            vars, expression, ..
+                        } => self.sentences.push(format!(
+                            "Computes {} as `{}`",
+                            list(&vars.iter().map(|var| nouns.var(var)).collect::<Vec<_>>()),
+                            expression
+                        )),
+                        ConstraintKind::LetIn {
+                            vars, expression, ..
+                        } => self.sentences.push(format!(
+                            "Takes {} from each element of `{}`",
+                            list(&vars.iter().map(|var| nouns.var(var)).collect::<Vec<_>>()),
+                            expression
+                        )),
+                        _ => {}
+                    }
+                }
+            }
+            BodyPattern::Conjunction { patterns } => {
+                for pattern in patterns {
+                    self.add(pattern, nouns);
+                }
+            }
+            BodyPattern::Negation { patterns } => {
+                let condition = condition(patterns, nouns);
+                self.sentences
+                    .push(format!("Excludes matches {}", condition));
+            }
+            BodyPattern::Optional { patterns } => {
+                let condition = condition(patterns, nouns);
+                self.sentences
+                    .push(format!("Optionally extends matches {}", condition));
+            }
+            BodyPattern::Disjunction { branches } => {
+                let branches: Vec<String> = branches
+                    .iter()
+                    .map(|branch| condition(branch, nouns))
+                    .collect();
+                self.sentences
+                    .push(format!("Keeps matches either {}", branches.join(" or ")));
+            }
+        }
+    }
+}
+
+/// A nested block as a condition on matches, e.g. "with employment relations where
+/// salary is greater than 100"
+fn condition(patterns: &[BodyPattern], nouns: &Nouns) -> String {
+    let mut description = Description::default();
+    for pattern in patterns {
+        description.add(pattern, nouns);
+    }
+    match (
+        description.things.is_empty(),
+        description.filters.is_empty(),
+    ) {
+        (false, false) => format!(
+            "with {} where {}",
+            list(&description.things),
+            list(&description.filters)
+        ),
+        (false, true) => format!("with {}", list(&description.things)),
+        (true, false) => format!("where {}", list(&description.filters)),
+        (true, true) => "with a nested pattern".to_string(),
+    }
+}
+
+/// The instance a statement matches, e.g. "income_source relations for the taxpayer"
+fn thing(constraints: &[Constraint], nouns: &Nouns) -> Option<String> {
+    let type_name = constraints
+        .iter()
+        .find_map(|constraint| match &constraint.kind {
+            ConstraintKind::Isa { type_name, .. } => Some(type_name.as_str()),
+            _ => None,
+        });
+    let links = constraints
+        .iter()
+        .find_map(|constraint| match &constraint.kind {
+            ConstraintKind::Links { role_players, .. } => Some(role_players),
+            _ => None,
+        });
+
+    let mut phrase = match (type_name, links) {
+        (Some(type_name), Some(_)) => format!("{} relations", type_name),
+        (None, Some(_)) => "relations".to_string(),
+        (Some(type_name), None) => format!("instances of {}", type_name),
+        (None, None) => return None,
+    };
+    if let Some(role_players) = links {
+        let parameters: Vec<String> = role_players
+            .iter()
+            .filter(|role_player| nouns.is_parameter(&role_player.player))
+            .map(|role_player| nouns.var(&role_player.player))
+            .collect();
+        if !parameters.is_empty() {
+            write!(phrase, " for {}", list(&parameters)).unwrap();
+        }
+    }
+    for constraint in constraints {
+        if let ConstraintKind::Has {
+            attribute: Some(attribute),
+            value,
+            ..
+        } = &constraint.kind
+        {
+            if !value.starts_with('$') {
+                write!(phrase, " whose {} is {}", attribute, value).unwrap();
+            }
+        }
+    }
+    Some(phrase)
+}
+
+fn returns(return_expression: &ReturnExpr, nouns: &Nouns) -> String {
+    let vars = |vars: &[String]| list(&vars.iter().map(|var| nouns.var(var)).collect::<Vec<_>>());
+    match return_expression {
+        ReturnExpr::Single {
+            selector,
+            vars: names,
+        } => {
+            let which = match selector {
+                Selector::First => "first",
+                Selector::Last => "last",
+            };
+            format!("returns {} of the {} match", vars(names), which)
+        }
+        ReturnExpr::Stream { vars: names } if names.len() == 1 => {
+            format!("returns every {} found", vars(names))
+        }
+        ReturnExpr::Stream { vars: names } => {
+            format!("returns every combination of {} found", vars(names))
+        }
+        ReturnExpr::Reduce { reducers } => {
+            let reductions: Vec<String> = reducers
+                .iter()
+                .map(|reduction| {
+                    let Some(var) = &reduction.var else {
+                        return "the number of matches".to_string();
+                    };
+                    let var = nouns.var(var);
+                    match reduction.op.as_str() {
+                        "sum" => format!("the sum of {}", var),
+                        "count" => format!("the number of {}", var),
+                        "max" => format!("the largest {}", var),
+                        "min" => format!("the smallest {}", var),
+                        "mean" => format!("the mean of {}", var),
+                        "median" => format!("the median of {}", var),
+                        "std" => format!("the standard deviation of {}", var),
+                        "list" => format!("the list of every {}", var),
+                        op => format!("the {} of {}", op, var),
+                    }
+                })
+                .collect();
+            format!("returns {}", list(&reductions))
+        }
+        ReturnExpr::Check => "returns whether there is any match".to_string(),
+    }
+}
+
+fn comparator_words(comparator: &str) -> &str {
+    match comparator {
+        ">=" => "at least",
+        "<=" => "at most",
+        ">" => "greater than",
+        "<" => "less than",
+        "==" => "equal to",
+        "!=" => "different from",
+        "like" => "like",
+        "contains" => "containing",
+        other => other,
+    }
+}
+
+/// `a`, `a and b`, `a, b and c`
+fn list(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [item] => item.clone(),
+        [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
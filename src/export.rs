@@ -0,0 +1,54 @@
+use std::fmt::Write;
+
+use crate::extract_function_metadata::FunctionMetadata;
+
+const CSV_HEADER: &[&str] = &[
+    "name",
+    "parameter_count",
+    "parameter_types",
+    "output",
+    "referenced_function_count",
+];
+
+/// Render one CSV row per function, with a header row, for spreadsheet analysis.
+///
+/// Parameter types are joined with `; ` in declaration order and the output is written
+/// as in the signature, e.g. `{ person, double }`. Fields are quoted only when needed.
+pub fn render_csv(functions: &[FunctionMetadata]) -> String {
+    let mut out = String::new();
+    write_csv_row(&mut out, CSV_HEADER.iter().map(|column| column.to_string()));
+
+    for function in functions {
+        let parameter_types: Vec<&str> = function
+            .parameters
+            .iter()
+            .map(|parameter| parameter.type_name.as_str())
+            .collect();
+        write_csv_row(
+            &mut out,
+            [
+                function.name.clone(),
+                function.parameters.len().to_string(),
+                parameter_types.join("; "),
+                function.output.to_string(),
+                function.referenced_functions.len().to_string(),
+            ],
+        );
+    }
+
+    out
+}
+
+fn write_csv_row(out: &mut String, fields: impl IntoIterator<Item = String>) {
+    let fields: Vec<String> = fields.into_iter().map(|field| csv_field(&field)).collect();
+    writeln!(out, "{}", fields.join(",")).unwrap();
+}
+
+/// Quote a field containing a separator, quote or line break, doubling inner quotes
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
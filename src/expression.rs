@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use typeql::expression::{Expression, FunctionName};
+
+use crate::body::ConstraintKind;
+use crate::extract_function_metadata::{variable_name, FunctionMetadata};
+
+/// An arithmetic expression as a tree, e.g. the right-hand side of a `let`.
+///
+/// Parentheses are not kept as nodes; the tree shape records the grouping and
+/// [`Display`](fmt::Display) adds back only the parentheses that are needed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Expr {
+    /// `$x`, stored without `$`
+    Variable { name: String },
+    /// A value literal as written, e.g. `0.25` or `"single"`
+    Literal { value: String },
+    /// `left op right` for `+`, `-`, `*`, `/`, `%` and `^`
+    Binary {
+        op: String,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    /// A built-in (`round`, `max`, ...) or user-defined function call
+    Call {
+        function: String,
+        builtin: bool,
+        args: Vec<Expr>,
+    },
+    /// `[a, b, c]`
+    List { items: Vec<Expr> },
+    /// `$list[index]`
+    Index { list: String, index: Box<Expr> },
+    /// `$list[from..to]`
+    Slice {
+        list: String,
+        from: Box<Expr>,
+        to: Box<Expr>,
+    },
+}
+
+impl Expr {
+    /// Named variables read by the expression, without `$`, in order of first appearance
+    pub fn variables(&self) -> Vec<&str> {
+        let mut variables = Vec::new();
+        self.visit(&mut |expr| {
+            let name = match expr {
+                Expr::Variable { name } => name,
+                Expr::Index { list, .. } | Expr::Slice { list, .. } => list,
+                _ => return,
+            };
+            if name != "_" && !variables.contains(&name.as_str()) {
+                variables.push(name.as_str());
+            }
+        });
+        variables
+    }
+
+    /// User-defined functions called by the expression, in order of first appearance
+    pub fn functions(&self) -> Vec<&str> {
+        let mut functions = Vec::new();
+        self.visit(&mut |expr| {
+            if let Expr::Call {
+                function,
+                builtin: false,
+                ..
+            } = expr
+            {
+                if !functions.contains(&function.as_str()) {
+                    functions.push(function.as_str());
+                }
+            }
+        });
+        functions
+    }
+
+    /// Visit this node and every node below it, parents first
+    pub fn visit<'a>(&'a self, visit: &mut impl FnMut(&'a Expr)) {
+        visit(self);
+        match self {
+            Expr::Binary { left, right, .. } => {
+                left.visit(visit);
+                right.visit(visit);
+            }
+            Expr::Call { args: items, .. } | Expr::List { items } => {
+                for item in items {
+                    item.visit(visit);
+                }
+            }
+            Expr::Index { index, .. } => index.visit(visit),
+            Expr::Slice { from, to, .. } => {
+                from.visit(visit);
+                to.visit(visit);
+            }
+            Expr::Variable { .. } | Expr::Literal { .. } => {}
+        }
+    }
+
+    /// Binding strength of a binary operator; anything else binds tightest
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Binary { op, .. } => match op.as_str() {
+                "+" | "-" => 1,
+                "*" | "/" | "%" => 2,
+                _ => 3,
+            },
+            _ => 4,
+        }
+    }
+}
+
+impl From<&Expression> for Expr {
+    fn from(expression: &Expression) -> Self {
+        let var_name = |var| variable_name(var).unwrap_or("_").to_string();
+        match expression {
+            Expression::Variable(var) => Expr::Variable {
+                name: var_name(var),
+            },
+            Expression::Value(literal) => Expr::Literal {
+                value: literal.to_string(),
+            },
+            Expression::Operation(operation) => Expr::Binary {
+                op: operation.op.to_string(),
+                left: Box::new(Expr::from(&operation.left)),
+                right: Box::new(Expr::from(&operation.right)),
+            },
+            Expression::Paren(paren) => Expr::from(&paren.inner),
+            Expression::Function(call) => Expr::Call {
+                function: match &call.name {
+                    FunctionName::Builtin(builtin) => builtin.to_string(),
+                    FunctionName::Identifier(ident) => ident.as_str().to_string(),
+                },
+                builtin: matches!(call.name, FunctionName::Builtin(_)),
+                args: call.args.iter().map(Expr::from).collect(),
+            },
+            Expression::List(list) => Expr::List {
+                items: list.items.iter().map(Expr::from).collect(),
+            },
+            Expression::ListIndex(list_index) => Expr::Index {
+                list: var_name(&list_index.variable),
+                index: Box::new(Expr::from(&list_index.index)),
+            },
+            Expression::ListIndexRange(range) => Expr::Slice {
+                list: var_name(&range.var),
+                from: Box::new(Expr::from(&range.from)),
+                to: Box::new(Expr::from(&range.to)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Variable { name } => write!(f, "${}", name),
+            Expr::Literal { value } => write!(f, "{}", value),
+            Expr::Binary { op, left, right } => {
+                let precedence = self.precedence();
+                // `^` groups to the right, everything else to the left
+                let right_associative = op == "^";
+                let left_needs_parens = left.precedence() < precedence
+                    || (right_associative && left.precedence() == precedence);
+                let right_needs_parens = right.precedence() < precedence
+                    || (!right_associative && right.precedence() == precedence);
+                write_operand(f, left, left_needs_parens)?;
+                write!(f, " {} ", op)?;
+                write_operand(f, right, right_needs_parens)
+            }
+            Expr::Call { function, args, .. } => {
+                let args: Vec<String> = args.iter().map(Expr::to_string).collect();
+                write!(f, "{}({})", function, args.join(", "))
+            }
+            Expr::List { items } => {
+                let items: Vec<String> = items.iter().map(Expr::to_string).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+            Expr::Index { list, index } => write!(f, "${}[{}]", list, index),
+            Expr::Slice { list, from, to } => write!(f, "${}[{}..{}]", list, from, to),
+        }
+    }
+}
+
+fn write_operand(f: &mut fmt::Formatter<'_>, operand: &Expr, parens: bool) -> fmt::Result {
+    if parens {
+        write!(f, "({})", operand)
+    } else {
+        write!(f, "{}", operand)
+    }
+}
+
+impl FunctionMetadata {
+    /// Variables that feed the value of `var` through `let` bindings, directly or
+    /// transitively, in the order they are reached. Parameters and matched variables
+    /// end the chain.
+    pub fn inputs_of(&self, var: &str) -> Vec<&str> {
+        let mut inputs: Vec<&str> = Vec::new();
+        let mut pending = vec![var];
+
+        while let Some(current) = pending.pop() {
+            for constraint in &self.constraints {
+                let (vars, tree) = match &constraint.kind {
+                    ConstraintKind::Let { vars, tree, .. }
+                    | ConstraintKind::LetIn { vars, tree, .. } => (vars, tree),
+                    _ => continue,
+                };
+                if !vars.iter().any(|bound| bound == current) {
+                    continue;
+                }
+                for input in tree.variables() {
+                    if input != var && !inputs.contains(&input) {
+                        inputs.push(input);
+                        pending.push(input);
+                    }
+                }
+            }
+        }
+
+        inputs
+    }
+}
@@ -1,393 +1,880 @@
-use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::ops::Range;
+use std::time::Instant;
+use typeql::common::Spanned;
+use typeql::expression::{Expression, FunctionName};
 use typeql::parse_definition_function;
+use typeql::pattern::Pattern;
+use typeql::query::stage::reduce::Reducer;
+use typeql::query::stage::Stage;
+use typeql::schema::definable::function::{
+    Argument, Output, ReturnReduction, ReturnStatement, Signature, SingleSelector,
+};
+use typeql::statement::thing::{self, HasValue, Head, RolePlayer};
+use typeql::statement::Statement;
+use typeql::type_::{NamedType, NamedTypeAny, TypeRef, TypeRefAny};
+use typeql::Variable;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::body::{extract_constraints, extract_patterns, BodyPattern, Constraint};
+use crate::diagnostic::Diagnostic;
+use crate::doc_comment::{categories, doc_comment_before, DocTag};
+use crate::error::ExtractError;
+use crate::expression::Expr;
+use crate::lexer::{body_start, function_ranges, tokenize, tokens, Token, TokenKind};
+use crate::query::{stage_kind, StageKind};
+use crate::span::Span;
+use crate::stats::ExtractionStats;
+
+/// What a `fun` definition declares and what its body refers to.
+///
+/// Equality and hashing compare every field, including `source_file` and the spans, so
+/// the same definition read from two places is not equal; compare
+/// [`without_location`](FunctionMetadata::without_location) copies to ignore where
+/// definitions came from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct FunctionMetadata {
     pub name: String,
+    /// Path of the file the function was read from, when it was loaded from one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_file: Option<String>,
+    /// The `#` comment block directly above the definition, without its tags
+    #[serde(default)]
+    pub doc: Option<String>,
+    /// `@name text` tags from the doc comment, such as `@deprecated` and `@example`
+    #[serde(default)]
+    pub doc_tags: Vec<DocTag>,
+    /// Lint rules suppressed by `# typedb-meta: ignore <rule>` comments above the definition
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lint_ignores: Vec<String>,
+    /// Categories from `# @tag: tax, income` lines in the doc comment
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
     pub parameters: Vec<Parameter>,
-    pub output: String,
-    pub return_expression: Option<String>,
+    pub output: OutputType,
+    pub return_expression: ReturnExpr,
     pub code_block: String,
     pub referenced_functions: Vec<String>,
+    /// Every call to a user-defined function in the body with its arguments, in source order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub call_sites: Vec<CallSite>,
+    /// Schema labels used in the body (`isa`, `has`, relation types and role names), in order of appearance
+    pub referenced_types: Vec<String>,
+    /// Every constraint in the body, flattened in source order
+    pub constraints: Vec<Constraint>,
+    /// The body's patterns with `not`, `or` and `try` blocks nested
+    #[serde(default)]
+    pub patterns: Vec<BodyPattern>,
+    /// The body's stages in pipeline order, e.g. `match` then `reduce`
+    #[serde(default)]
+    pub stages: Vec<StageKind>,
+    /// Where the function's parts are in the source it was extracted from
+    #[serde(default)]
+    pub spans: FunctionSpans,
+    /// Results of [`Extractor`]s by extractor name
+    ///
+    /// [`Extractor`]: crate::extractor::Extractor
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extensions: BTreeMap<String, serde_json::Value>,
+}
+
+/// Source locations of a function's parts; absent when the parser reports no position
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct FunctionSpans {
+    /// The whole `fun ...;` definition
+    pub definition: Option<Span>,
+    pub name: Option<Span>,
+    pub output: Option<Span>,
+    pub code_block: Option<Span>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Parameter {
     pub name: String,
     pub type_name: String,
+    pub kind: TypeKind,
+    /// Location of `$name: type`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
 }
 
-/// Extract metadata from a TypeQL function definition
-pub fn extract_function_metadata(function_text: &str) -> Result<FunctionMetadata> {
-    // Parse the function using TypeQL parser
-    let func_ast = parse_definition_function(function_text)?;
-    
-    // Get debug string for detailed extraction
-    let debug_str = format!("{:#?}", func_ast);
-    
-    // Extract function name
-    let name = extract_function_name(&debug_str);
-    
-    // Extract parameters
-    let parameters = extract_parameters(&debug_str);
-    
-    // Extract output/return type
-    let output = extract_output(&debug_str);
-    
-    // Extract code block (the match block and return statement)
-    let code_block = extract_code_block(function_text);
-    
-    // Extract referenced functions from the AST debug string
-    let referenced_functions = extract_referenced_functions(&debug_str);
-    
-    // Extract return expression for native types
-    let return_expression = extract_return_expression(&debug_str);
-    
-    Ok(FunctionMetadata {
-        name,
-        parameters,
-        output,
-        return_expression,
-        code_block,
-        referenced_functions,
-    })
-}
-
-fn extract_function_name(debug_str: &str) -> String {
-    // Find the function name in the signature
-    if let Some(sig_start) = debug_str.find("signature: Signature") {
-        let sig_section = &debug_str[sig_start..];
-        if let Some(ident_start) = sig_section.find("ident: \"") {
-            let name_start = ident_start + 8;
-            if let Some(name_end) = sig_section[name_start..].find("\"") {
-                return sig_section[name_start..name_start + name_end].to_string();
+/// A call to a user-defined function in a body, e.g. `get_bracket($income, 2024)`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct CallSite {
+    /// Name of the called function
+    pub function: String,
+    /// The argument expressions, in order
+    pub arguments: Vec<Expr>,
+    /// Location of the call, from its name to the closing parenthesis
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    /// How many `not { ... }` blocks the call is nested in
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub negation_depth: usize,
+}
+
+impl FunctionMetadata {
+    /// JSON Schema describing the serialized form of [`FunctionMetadata`]
+    #[cfg(feature = "json-schema")]
+    pub fn json_schema() -> schemars::Schema {
+        schemars::schema_for!(FunctionMetadata)
+    }
+
+    /// Each value in the return statement paired with its declared output type, in order.
+    ///
+    /// Values are written as in the return statement, e.g. `$min` or `sum($amt)`, so
+    /// `return first $min, $max` gives `[("$min", bracket_min), ("$max", bracket_max)]`.
+    /// `return check` yields nothing. The type is `None` when the output declares fewer
+    /// types than values are returned.
+    pub fn returned_values(&self) -> Vec<(String, Option<&TypeName>)> {
+        let values: Vec<String> = match &self.return_expression {
+            ReturnExpr::Single { vars, .. } | ReturnExpr::Stream { vars } => {
+                vars.iter().map(|var| format!("${}", var)).collect()
             }
+            ReturnExpr::Reduce { reducers } => reducers.iter().map(Reduction::to_string).collect(),
+            ReturnExpr::Check => Vec::new(),
+        };
+        let types = self.output.types();
+        values
+            .into_iter()
+            .enumerate()
+            .map(|(position, value)| (value, types.get(position)))
+            .collect()
+    }
+}
+
+impl FunctionMetadata {
+    /// A copy without `source_file` or spans, for comparing definitions wherever they
+    /// were read from
+    pub fn without_location(&self) -> FunctionMetadata {
+        let mut metadata = self.clone();
+        metadata.source_file = None;
+        metadata.spans = FunctionSpans::default();
+        for parameter in &mut metadata.parameters {
+            parameter.span = None;
+        }
+        for call_site in &mut metadata.call_sites {
+            call_site.span = None;
         }
+        metadata
     }
-    "unknown".to_string()
-}
-
-fn extract_parameters(debug_str: &str) -> Vec<Parameter> {
-    let mut parameters = Vec::new();
-    
-    // Find the args section first
-    if let Some(args_start) = debug_str.find("args: [") {
-        let args_section = &debug_str[args_start..];
-        
-        // Find all Argument blocks within the args section
-        let mut remaining = args_section;
-        while let Some(arg_start) = remaining.find("Argument {") {
-            let arg_section = &remaining[arg_start..];
-            
-            // Find the end of this argument block (look for the next Argument or the end of args)
-            let next_arg = remaining[arg_start + 10..].find("Argument {").map(|p| p + arg_start + 10);
-            let output_pos = remaining.find("],\n        output:");
-            
-            let arg_end = next_arg.unwrap_or(output_pos.unwrap_or(arg_section.len()));
-            let current_arg = &remaining[arg_start..arg_end];
-            
-            // Extract variable name
-            let var_name = if let Some(var_start) = current_arg.find("ident: \"") {
-                let name_start = var_start + 8;
-                if let Some(name_end) = current_arg[name_start..].find("\"") {
-                    current_arg[name_start..name_start + name_end].to_string()
-                } else {
-                    remaining = &remaining[arg_start + 10..];
-                    continue;
-                }
-            } else {
-                remaining = &remaining[arg_start + 10..];
-                continue;
-            };
-            
-            // Extract type - look for the type_ field in this argument
-            let type_name = if let Some(type_start) = current_arg.find("type_: Simple(") {
-                let type_section = &current_arg[type_start..];
-                
-                // Check for BuiltinValueType first (for primitive types like double)
-                if let Some(builtin_start) = type_section.find("BuiltinValueType(") {
-                    let builtin_section = &type_section[builtin_start..];
-                    if let Some(token_start) = builtin_section.find("token: ") {
-                        let token_start = token_start + 7;
-                        if let Some(token_end) = builtin_section[token_start..].find(",").or(builtin_section[token_start..].find("\n")) {
-                            builtin_section[token_start..token_start + token_end]
-                                .trim()
-                                .trim_end_matches(',')
-                                .to_lowercase()
-                        } else {
-                            "unknown".to_string()
-                        }
-                    } else {
-                        "unknown".to_string()
-                    }
-                }
-                // Then check for Label type (for custom types)
-                else if let Some(label_start) = type_section.find("Label(") {
-                    let label_section = &type_section[label_start..];
-                    if let Some(ident_start) = label_section.find("ident: \"") {
-                        let name_start = ident_start + 8;
-                        if let Some(name_end) = label_section[name_start..].find("\"") {
-                            label_section[name_start..name_start + name_end].to_string()
-                        } else {
-                            "unknown".to_string()
-                        }
-                    } else {
-                        "unknown".to_string()
-                    }
-                } else {
-                    "unknown".to_string()
-                }
-            } else {
-                "unknown".to_string()
-            };
-            
-            parameters.push(Parameter {
-                name: var_name,
-                type_name,
-            });
-            
-            // Move to next argument
-            remaining = &remaining[arg_start + 10..];
-            
-            // Stop if we've reached the end of args
-            if output_pos.is_some() && arg_start > output_pos.unwrap() {
-                break;
+
+    /// The signature on one line, e.g. `fun get_total($taxpayer: person) -> double`
+    pub fn signature(&self) -> String {
+        let parameters: Vec<String> = self.parameters.iter().map(Parameter::to_string).collect();
+        format!(
+            "fun {}({}) -> {}",
+            self.name,
+            parameters.join(", "),
+            self.output
+        )
+    }
+}
+
+/// A multi-line summary for terminals: the name, parameters, output, each returned value
+/// with its type, referenced functions and the code block
+impl fmt::Display for FunctionMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Function Name: {}", self.name)?;
+        writeln!(f)?;
+
+        writeln!(f, "Parameters:")?;
+        if self.parameters.is_empty() {
+            writeln!(f, "  (none)")?;
+        }
+        for parameter in &self.parameters {
+            writeln!(f, "  {}", parameter)?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "Output: {}", self.output)?;
+        writeln!(f, "Return Expression: {}", self.return_expression)?;
+        for (value, type_name) in self.returned_values() {
+            match type_name {
+                Some(type_name) => writeln!(f, "  {}: {}", value, type_name)?,
+                None => writeln!(f, "  {}: (undeclared)", value)?,
             }
         }
+        writeln!(f)?;
+
+        writeln!(f, "Referenced Functions:")?;
+        if self.referenced_functions.is_empty() {
+            writeln!(f, "  (none)")?;
+        }
+        for function in &self.referenced_functions {
+            writeln!(f, "  - {}", function)?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "Code Block:")?;
+        for line in self.code_block.lines() {
+            writeln!(f, "  {}", line)?;
+        }
+        Ok(())
     }
-    
-    parameters
-}
-
-fn extract_output(debug_str: &str) -> String {
-    // Check if it's a stream output
-    if debug_str.contains("output: Stream(") {
-        // Extract stream content
-        if let Some(stream_start) = debug_str.find("output: Stream(") {
-            let stream_section = &debug_str[stream_start..];
-            let mut output_types = Vec::new();
-            
-            // Look for types in the stream
-            if stream_section.contains("ident: \"") {
-                let mut section = stream_section;
-                while let Some(ident_start) = section.find("ident: \"") {
-                    let name_start = ident_start + 8;
-                    if let Some(name_end) = section[name_start..].find("\"") {
-                        let type_name = section[name_start..name_start + name_end].to_string();
-                        output_types.push(type_name);
-                        section = &section[name_start + name_end..];
-                        // Stop at the end of output section
-                        if section.find("},").is_some() && section.find("},").unwrap() < 50 {
-                            break;
-                        }
-                    } else {
-                        break;
-                    }
-                }
-            }
-            
-            if !output_types.is_empty() {
-                format!("{{ {} }}", output_types.join(", "))
-            } else {
-                "{ stream }".to_string()
-            }
-        } else {
-            "stream".to_string()
+}
+
+impl Parameter {
+    /// The schema label of the parameter type, `None` for built-in value types
+    pub fn label(&self) -> Option<&str> {
+        match self.kind {
+            TypeKind::Label => Some(self.type_name.trim_end_matches(['[', ']', '?'])),
+            TypeKind::Builtin => None,
         }
     }
-    // Single output
-    else if let Some(output_start) = debug_str.find("output: Single(") {
-        let output_section = &debug_str[output_start..];
-        let mut output_types = Vec::new();
-        
-        // Look for the first type only (avoid collecting noise)
-        if let Some(builtin_start) = output_section.find("BuiltinValueType(") {
-            let builtin_section = &output_section[builtin_start..];
-            if let Some(token_start) = builtin_section.find("token: ") {
-                let token_start = token_start + 7;
-                if let Some(token_end) = builtin_section[token_start..].find(",").or(builtin_section[token_start..].find("\n")) {
-                    let type_name = builtin_section[token_start..token_start + token_end]
-                        .trim()
-                        .to_lowercase();
-                    return type_name;
-                }
-            }
+}
+
+/// `$name: type`, as written in a signature
+impl fmt::Display for Parameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "${}: {}", self.name, self.type_name)
+    }
+}
+
+/// Declared return type of a function: `-> { a, b }` streams, `-> a, b` returns a single row
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(tag = "kind", content = "types", rename_all = "snake_case")]
+pub enum OutputType {
+    Single(Vec<TypeName>),
+    Stream(Vec<TypeName>),
+}
+
+impl OutputType {
+    pub fn is_stream(&self) -> bool {
+        matches!(self, OutputType::Stream(_))
+    }
+
+    /// Element types in declaration order
+    pub fn types(&self) -> &[TypeName] {
+        match self {
+            OutputType::Single(types) | OutputType::Stream(types) => types,
         }
-        
-        // Check for Label types (for tuple returns)
-        let mut section = output_section;
-        let mut depth = 0;
-        while let Some(label_pos) = section.find("Label(") {
-            if depth > 4 { break; }  // Limit to first few return types
-            depth += 1;
-            
-            let label_section = &section[label_pos..];
-            if let Some(ident_start) = label_section.find("ident: \"") {
-                let name_start = ident_start + 8;
-                if let Some(name_end) = label_section[name_start..].find("\"") {
-                    let type_name = label_section[name_start..name_start + name_end].to_string();
-                    output_types.push(type_name);
-                }
+    }
+}
+
+impl fmt::Display for OutputType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let types: Vec<String> = self.types().iter().map(TypeName::to_string).collect();
+        match self {
+            OutputType::Single(_) => write!(f, "{}", types.join(", ")),
+            OutputType::Stream(_) => write!(f, "{{ {} }}", types.join(", ")),
+        }
+    }
+}
+
+/// The function's `return` statement; variable names are stored without the `$` prefix
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReturnExpr {
+    /// `return first $a, $b;`
+    Single {
+        selector: Selector,
+        vars: Vec<String>,
+    },
+    /// `return sum($amt), count($x);`, one entry per reducer in order.
+    ///
+    /// Function returns cannot group; grouped reductions belong in a `reduce ... groupby`
+    /// stage of the body.
+    Reduce { reducers: Vec<Reduction> },
+    /// `return { $a, $b };`
+    Stream { vars: Vec<String> },
+    /// `return check;`
+    Check,
+}
+
+/// One reducer of a reduce return, e.g. `sum($amt)`; `var` is absent for a bare `count`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct Reduction {
+    pub op: String,
+    pub var: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Selector {
+    First,
+    Last,
+}
+
+impl fmt::Display for ReturnExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReturnExpr::Single { selector, vars } => {
+                write!(f, "{} {}", selector, format_var_list(vars))
             }
-            section = &section[label_pos + 6..];
-            
-            // Stop at the end of the Single output section
-            if section.find("],").is_some() && section.find("],").unwrap() < 100 {
-                break;
+            ReturnExpr::Reduce { reducers } => {
+                let reducers: Vec<String> = reducers.iter().map(Reduction::to_string).collect();
+                write!(f, "{}", reducers.join(", "))
             }
+            ReturnExpr::Stream { vars } => write!(f, "{{ {} }}", format_var_list(vars)),
+            ReturnExpr::Check => write!(f, "check"),
+        }
+    }
+}
+
+impl fmt::Display for Reduction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.var {
+            Some(var) => write!(f, "{}(${})", self.op, var),
+            None => write!(f, "{}", self.op),
+        }
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Selector::First => write!(f, "first"),
+            Selector::Last => write!(f, "last"),
+        }
+    }
+}
+
+fn format_var_list(vars: &[String]) -> String {
+    vars.iter()
+        .map(|var| format!("${}", var))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A type as written in a function signature, e.g. `double`, `taxpayer` or `string[]`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct TypeName {
+    pub name: String,
+    pub kind: TypeKind,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub list: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TypeKind {
+    /// Built-in value type such as `double` or `string`
+    Builtin,
+    /// Schema type label, including scoped role labels like `employment:employee`
+    Label,
+}
+
+impl fmt::Display for TypeName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if self.list {
+            write!(f, "[]")?;
         }
-        
-        if !output_types.is_empty() {
-            output_types[..output_types.len().min(4)].join(", ")  // Limit to first 4 types
-        } else {
-            "unknown".to_string()
+        if self.optional {
+            write!(f, "?")?;
         }
-    } else {
-        "unknown".to_string()
+        Ok(())
+    }
+}
+
+/// Built-in value types; any other type name is a schema label
+pub(crate) const VALUE_TYPES: &[&str] = &[
+    "boolean",
+    "integer",
+    "double",
+    "decimal",
+    "date",
+    "datetime",
+    "datetime-tz",
+    "duration",
+    "string",
+];
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+/// Extract metadata from a TypeQL function definition.
+///
+/// Parse failures are returned as [`ExtractError::ParseError`] locating the error in
+/// `function_text`, or [`ExtractError::EmptySignature`] when no name and parameter list
+/// follow `fun`.
+pub fn extract_function_metadata(function_text: &str) -> Result<FunctionMetadata, ExtractError> {
+    extract_function_at(function_text, 0..function_text.len())
+        .map_err(|diagnostic| ExtractError::from_input(diagnostic, function_text))
+}
+
+/// Extract the function defined at `range` of `source`, with spans relative to `source`
+pub(crate) fn extract_function_at(
+    source: &str,
+    range: Range<usize>,
+) -> Result<FunctionMetadata, Diagnostic> {
+    extract_function_timed(source, range).map(|(metadata, _)| metadata)
+}
+
+/// [`extract_function_at`], also measuring how long parsing and extraction took
+pub(crate) fn extract_function_timed(
+    source: &str,
+    range: Range<usize>,
+) -> Result<(FunctionMetadata, ExtractionStats), Diagnostic> {
+    let function_text = &source[range.clone()];
+    let started = Instant::now();
+    if let Some(keyword) = empty_signature(function_text) {
+        return Err(Diagnostic::error(
+            "empty-signature",
+            "`fun` is not followed by a signature such as `fun get_total($x: person) -> double:`",
+        )
+        .with_span(Some(Span::new(
+            source,
+            range.start + keyword.start,
+            range.start + keyword.end,
+        ))));
+    }
+    // Parse the function using TypeQL parser
+    let func_ast = parse_definition_function(function_text)
+        .map_err(|error| Diagnostic::parse_error(&error, source, range.start))?;
+    let parsed = Instant::now();
+    let signature = &func_ast.signature;
+    let block = &func_ast.block;
+
+    // Parser offsets are relative to `function_text`
+    let to_span = |span: Option<typeql::common::Span>| {
+        span.map(|span| {
+            Span::new(
+                source,
+                range.start + span.begin_offset,
+                range.start + span.end_offset,
+            )
+        })
+    };
+    let output_span = match &signature.output {
+        Output::Stream(stream) => stream.span,
+        Output::Single(single) => single.span,
+    };
+
+    let doc_comment = doc_comment_before(source, range.start);
+
+    let metadata = FunctionMetadata {
+        name: signature.ident.as_str().to_string(),
+        source_file: None,
+        doc: doc_comment.description,
+        tags: categories(&doc_comment.tags),
+        doc_tags: doc_comment.tags,
+        lint_ignores: doc_comment.ignored_rules,
+        parameters: extract_parameters(signature, &to_span),
+        output: extract_output(&signature.output),
+        return_expression: extract_return_expression(&block.return_stmt),
+        code_block: extract_code_block(function_text),
+        referenced_functions: extract_referenced_functions(&block.stages),
+        call_sites: extract_call_sites(&block.stages, &to_span),
+        referenced_types: extract_referenced_types(&block.stages),
+        constraints: extract_constraints(&block.stages),
+        patterns: extract_patterns(&block.stages),
+        stages: block.stages.iter().map(stage_kind).collect(),
+        spans: FunctionSpans {
+            definition: Some(Span::new(source, range.start, range.end)),
+            name: to_span(signature.ident.span()),
+            output: to_span(output_span),
+            code_block: to_span(block.span),
+        },
+        extensions: BTreeMap::new(),
+    };
+    let stats = ExtractionStats {
+        input_bytes: function_text.len(),
+        function_count: 1,
+        parse_time: parsed - started,
+        extraction_time: parsed.elapsed(),
+    };
+    Ok((metadata, stats))
+}
+
+/// Extract metadata for every `fun` definition in a TypeQL source file.
+///
+/// The source may contain `define` blocks, type definitions and comments; only function
+/// definitions are parsed. Functions are returned in source order, and spans are relative
+/// to `source`. Errors are reported as by [`extract_function_metadata`].
+pub fn extract_functions_from_source(source: &str) -> Result<Vec<FunctionMetadata>, ExtractError> {
+    extract_functions_in(source).map_err(|diagnostic| ExtractError::from_input(diagnostic, source))
+}
+
+/// [`extract_functions_from_source`], leaving the caller to name the source in errors
+pub(crate) fn extract_functions_in(source: &str) -> Result<Vec<FunctionMetadata>, Diagnostic> {
+    function_ranges(source)
+        .into_iter()
+        .map(|range| extract_function_at(source, range))
+        .collect()
+}
+
+/// The range of the `fun` keyword starting `function_text` when no name and parameter list
+/// follow it
+fn empty_signature(function_text: &str) -> Option<Range<usize>> {
+    let mut tokens = tokens(function_text).filter(|token| token.kind != TokenKind::Comment);
+    let keyword = tokens.next().filter(|keyword| keyword.is_word("fun"))?;
+    let named = tokens
+        .next()
+        .is_some_and(|name| name.kind == TokenKind::Word)
+        && tokens.next().is_some_and(|open| open.is_punct("("));
+    (!named).then_some(keyword.range)
+}
+
+fn extract_parameters(
+    signature: &Signature,
+    to_span: &impl Fn(Option<typeql::common::Span>) -> Option<Span>,
+) -> Vec<Parameter> {
+    signature
+        .args
+        .iter()
+        .map(
+            |Argument {
+                 span, var, type_, ..
+             }| {
+                let type_name = type_name(type_);
+                Parameter {
+                    name: variable_name(var).unwrap_or("_").to_string(),
+                    type_name: type_name.to_string(),
+                    kind: type_name.kind,
+                    span: to_span(*span),
+                }
+            },
+        )
+        .collect()
+}
+
+fn extract_output(output: &Output) -> OutputType {
+    match output {
+        Output::Stream(stream) => OutputType::Stream(stream.types.iter().map(type_name).collect()),
+        Output::Single(single) => OutputType::Single(single.types.iter().map(type_name).collect()),
     }
 }
 
 fn extract_code_block(function_text: &str) -> String {
-    // Find the match block and return statement
-    // Look for the actual start of the match block or first statement after the colon
-    if let Some(match_pos) = function_text.find("match") {
-        let code_part = &function_text[match_pos..];
-        
-        // Clean up indentation
-        let lines: Vec<&str> = code_part.lines().collect();
-        let cleaned_lines: Vec<String> = lines.iter().map(|line| {
-            line.trim_start().to_string()
-        }).collect();
-        
-        cleaned_lines.join("\n")
-    } else if let Some(colon_pos) = function_text.rfind("->") {
-        // Find content after the return type declaration
-        if let Some(actual_colon) = function_text[colon_pos..].find(':') {
-            let start = colon_pos + actual_colon + 1;
-            let code_part = function_text[start..].trim();
-            
-            // Clean up indentation
-            let lines: Vec<&str> = code_part.lines().collect();
-            let cleaned_lines: Vec<String> = lines.iter().map(|line| {
-                line.trim_start().to_string()
-            }).collect();
-            
-            cleaned_lines.join("\n")
-        } else {
-            function_text.to_string()
-        }
-    } else {
-        function_text.to_string()
+    // The code block starts at the first token after the signature's colon, so a
+    // `match` in a name, variable or comment before it does not count
+    let tokens: Vec<Token> = tokenize(function_text)
+        .into_iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .collect();
+    let body = match body_start(&tokens).and_then(|start| tokens.get(start)) {
+        Some(first) => &function_text[first.range.start..],
+        None => return function_text.to_string(),
+    };
+
+    // Clean up indentation
+    body.lines()
+        .map(|line| line.trim_start())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn extract_return_expression(return_stmt: &ReturnStatement) -> ReturnExpr {
+    match return_stmt {
+        ReturnStatement::Single(single) => ReturnExpr::Single {
+            selector: match single.selector {
+                SingleSelector::First => Selector::First,
+                SingleSelector::Last => Selector::Last,
+            },
+            vars: variable_names(&single.vars),
+        },
+        ReturnStatement::Stream(stream) => ReturnExpr::Stream {
+            vars: variable_names(&stream.vars),
+        },
+        ReturnStatement::Reduce(ReturnReduction::Value(reducers, _)) if reducers.is_empty() => {
+            ReturnExpr::Check
+        }
+        ReturnStatement::Reduce(ReturnReduction::Value(reducers, _)) => ReturnExpr::Reduce {
+            reducers: reducers.iter().map(extract_reduction).collect(),
+        },
+        ReturnStatement::Reduce(ReturnReduction::Check(_)) => ReturnExpr::Check,
+    }
+}
+
+fn extract_reduction(reducer: &Reducer) -> Reduction {
+    match reducer {
+        Reducer::Count(count) => Reduction {
+            op: "count".to_string(),
+            var: count
+                .variable
+                .as_ref()
+                .and_then(variable_name)
+                .map(str::to_string),
+        },
+        Reducer::Stat(stat) => Reduction {
+            op: stat.reduce_operator.to_string(),
+            var: variable_name(&stat.variable).map(str::to_string),
+        },
     }
 }
 
-fn extract_return_expression(debug_str: &str) -> Option<String> {
-    // Look for return_stmt in the AST
-    if let Some(return_pos) = debug_str.find("return_stmt: ") {
-        let return_section = &debug_str[return_pos..];
-        
-        // Check for Single return with selector (first, last, etc.)
-        if return_section.starts_with("return_stmt: Single(") {
-            // Extract selector (First, Last, etc.)
-            let selector = if return_section.contains("selector: First") {
-                "first "
-            } else if return_section.contains("selector: Last") {
-                "last "
-            } else if return_section.contains("selector: Any") {
-                ""
-            } else {
-                ""
-            };
-            
-            // Find the variable in vars array
-            if let Some(vars_pos) = return_section.find("vars: [") {
-                let vars_section = &return_section[vars_pos..];
-                if let Some(ident_pos) = vars_section.find("ident: \"") {
-                    let ident_start = ident_pos + 8;
-                    if let Some(ident_end) = vars_section[ident_start..].find("\"") {
-                        let var_name = &vars_section[ident_start..ident_start + ident_end];
-                        return Some(format!("{}${}", selector, var_name));
+pub(crate) fn extract_referenced_functions(stages: &[Stage]) -> Vec<String> {
+    let mut referenced_functions = Vec::new();
+    let mut seen = HashSet::new();
+
+    for_each_expression(stages, &mut |expression| {
+        if let Expression::Function(call) = expression {
+            if let FunctionName::Identifier(ident) = &call.name {
+                // Only add if we haven't seen this function before
+                if seen.insert(ident.as_str().to_string()) {
+                    referenced_functions.push(ident.as_str().to_string());
+                }
+            }
+        }
+    });
+
+    referenced_functions
+}
+
+fn extract_call_sites(
+    stages: &[Stage],
+    to_span: &impl Fn(Option<typeql::common::Span>) -> Option<Span>,
+) -> Vec<CallSite> {
+    let mut call_sites = Vec::new();
+    for_each_statement_with_negation(stages, &mut |statement, negation_depth| {
+        for expression in statement_expressions(statement) {
+            visit_expression(expression, &mut |expression| {
+                if let Expression::Function(call) = expression {
+                    if let FunctionName::Identifier(ident) = &call.name {
+                        call_sites.push(CallSite {
+                            function: ident.as_str().to_string(),
+                            arguments: call.args.iter().map(Expr::from).collect(),
+                            span: to_span(call.span()),
+                            negation_depth,
+                        });
                     }
                 }
+            });
+        }
+    });
+    call_sites
+}
+
+pub(crate) fn extract_referenced_types(stages: &[Stage]) -> Vec<String> {
+    let mut referenced_types = Vec::new();
+    let mut seen = HashSet::new();
+    let mut add = |label: Option<String>| {
+        if let Some(label) = label {
+            if seen.insert(label.clone()) {
+                referenced_types.push(label);
             }
         }
-        // Check for Reduce return (aggregate functions)
-        else if return_section.starts_with("return_stmt: Reduce(") {
-            // Look for reduce_operator
-            if let Some(op_pos) = return_section.find("reduce_operator: ") {
-                let op_start = op_pos + 17;
-                let op_section = &return_section[op_start..];
-                if let Some(op_end) = op_section.find(",").or(op_section.find("\n")) {
-                    let operator = op_section[..op_end].trim().to_lowercase();
-                    
-                    // Find the variable being aggregated
-                    if let Some(var_pos) = return_section.find("variable: Named") {
-                        let var_section = &return_section[var_pos..];
-                        if let Some(ident_pos) = var_section.find("ident: \"") {
-                            let ident_start = ident_pos + 8;
-                            if let Some(ident_end) = var_section[ident_start..].find("\"") {
-                                let var_name = &var_section[ident_start..ident_start + ident_end];
-                                return Some(format!("{}(${})", operator, var_name));
+    };
+
+    for_each_statement(stages, &mut |statement| match statement {
+        Statement::Thing(thing) => {
+            if let Head::Relation(relation_type, relation) = &thing.head {
+                add(relation_type.as_ref().and_then(type_ref_label));
+                for role_player in &relation.role_players {
+                    if let RolePlayer::Typed(role, _) = role_player {
+                        add(type_ref_any_label(role));
+                    }
+                }
+            }
+            for constraint in &thing.constraints {
+                match constraint {
+                    thing::Constraint::Isa(isa) => add(type_ref_any_label(&isa.type_)),
+                    thing::Constraint::Has(has) => {
+                        add(has.type_.as_ref().and_then(type_ref_any_label))
+                    }
+                    thing::Constraint::Links(links) => {
+                        for role_player in &links.relation.role_players {
+                            if let RolePlayer::Typed(role, _) = role_player {
+                                add(type_ref_any_label(role));
                             }
                         }
                     }
+                    thing::Constraint::Iid(_) => {}
                 }
             }
         }
-        // Check for Stream return (set of values)
-        else if return_section.starts_with("return_stmt: Stream(") {
-            // Find the variables in the stream
-            if let Some(vars_pos) = return_section.find("vars: [") {
-                let vars_section = &return_section[vars_pos..];
-                if let Some(ident_pos) = vars_section.find("ident: \"") {
-                    let ident_start = ident_pos + 8;
-                    if let Some(ident_end) = vars_section[ident_start..].find("\"") {
-                        let var_name = &vars_section[ident_start..ident_start + ident_end];
-                        return Some(format!("{{ ${} }}", var_name));
-                    }
+        Statement::AttributeValue(attribute) => {
+            add(attribute.type_.as_ref().and_then(type_ref_any_label))
+        }
+        Statement::AttributeComparison(attribute) => add(type_ref_any_label(&attribute.isa.type_)),
+        Statement::Type(type_statement) => add(type_ref_any_label(&type_statement.type_)),
+        Statement::Is(_)
+        | Statement::InIterable(_)
+        | Statement::Comparison(_)
+        | Statement::Assignment(_) => {}
+    });
+
+    referenced_types
+}
+
+/// Label named by a type reference, `None` for type variables and built-in value types
+pub(crate) fn type_ref_label(type_ref: &TypeRef) -> Option<String> {
+    match type_ref {
+        TypeRef::Named(NamedType::BuiltinValueType(_)) | TypeRef::Variable(_) => None,
+        TypeRef::Named(named) => Some(named_type_name(named)),
+    }
+}
+
+pub(crate) fn type_ref_any_label(type_ref: &TypeRefAny) -> Option<String> {
+    match type_ref {
+        TypeRefAny::Type(inner) => type_ref_label(inner),
+        TypeRefAny::List(list) => type_ref_label(&list.inner),
+        TypeRefAny::Optional(optional) => type_ref_label(&optional.inner),
+    }
+}
+
+/// Name of a named variable without the `$` prefix, `None` for anonymous `$_`
+pub(crate) fn variable_name(var: &Variable) -> Option<&str> {
+    match var {
+        Variable::Named { ident, .. } => Some(ident.as_str()),
+        Variable::Anonymous { .. } => None,
+    }
+}
+
+fn variable_names(vars: &[Variable]) -> Vec<String> {
+    vars.iter()
+        .map(|var| variable_name(var).unwrap_or("_").to_string())
+        .collect()
+}
+
+pub(crate) fn named_type_name(named_type: &NamedType) -> String {
+    match named_type {
+        NamedType::Label(label) => label.ident.as_str().to_string(),
+        NamedType::Role(scoped_label) => scoped_label.to_string(),
+        NamedType::BuiltinValueType(builtin) => builtin.token.to_string().to_lowercase(),
+    }
+}
+
+fn type_name(named_type: &NamedTypeAny) -> TypeName {
+    let (inner, list, optional) = match named_type {
+        NamedTypeAny::Simple(inner) => (inner, false, false),
+        NamedTypeAny::List(list) => (&list.inner, true, false),
+        NamedTypeAny::Optional(optional) => (&optional.inner, false, true),
+    };
+    let kind = match inner {
+        NamedType::BuiltinValueType(_) => TypeKind::Builtin,
+        NamedType::Label(_) | NamedType::Role(_) => TypeKind::Label,
+    };
+    TypeName {
+        name: named_type_name(inner),
+        kind,
+        list,
+        optional,
+    }
+}
+
+/// Visit every statement in a function body or query, descending into nested patterns
+pub(crate) fn for_each_statement(stages: &[Stage], visit: &mut impl FnMut(&Statement)) {
+    for_each_statement_with_negation(stages, &mut |statement, _| visit(statement));
+}
+
+/// [`for_each_statement`], also passing how many `not { ... }` blocks enclose each statement
+fn for_each_statement_with_negation(stages: &[Stage], visit: &mut impl FnMut(&Statement, usize)) {
+    for stage in stages {
+        let statements = match stage {
+            Stage::Match(match_) => {
+                for pattern in &match_.patterns {
+                    visit_pattern_statements(pattern, 0, visit);
                 }
+                continue;
             }
+            Stage::Insert(insert) => &insert.statements,
+            Stage::Put(put) => &put.statements,
+            Stage::Update(update) => &update.statements,
+            Stage::Delete(_) | Stage::Fetch(_) | Stage::Operator(_) => continue,
+        };
+        for statement in statements {
+            visit(statement, 0);
         }
     }
-    
-    None
 }
 
-fn extract_referenced_functions(debug_str: &str) -> Vec<String> {
-    let mut referenced_functions = Vec::new();
-    let mut seen = HashSet::new();
-    
-    // Look for function calls in the AST debug output
-    // Function calls appear as: FunctionCall { ... name: Identifier( ... ident: "function_name"
-    
-    let mut remaining = debug_str;
-    
-    while let Some(call_start) = remaining.find("FunctionCall {") {
-        let call_section = &remaining[call_start..];
-        
-        // Look for the name: Identifier pattern within this FunctionCall
-        if let Some(name_start) = call_section.find("name: Identifier(") {
-            let name_section = &call_section[name_start..];
-            
-            // Find the ident field within the Identifier
-            if let Some(ident_start) = name_section.find("ident: \"") {
-                let ident_start = ident_start + 8;
-                if let Some(quote_end) = name_section[ident_start..].find("\"") {
-                    let func_name = name_section[ident_start..ident_start + quote_end].to_string();
-                    
-                    // Only add if we haven't seen this function before
-                    if seen.insert(func_name.clone()) {
-                        referenced_functions.push(func_name);
-                    }
-                }
+fn visit_pattern_statements(
+    pattern: &Pattern,
+    negation_depth: usize,
+    visit: &mut impl FnMut(&Statement, usize),
+) {
+    match pattern {
+        Pattern::Statement(statement) => visit(statement, negation_depth),
+        Pattern::Conjunction(conjunction) => {
+            for inner in &conjunction.patterns {
+                visit_pattern_statements(inner, negation_depth, visit);
+            }
+        }
+        Pattern::Negation(negation) => {
+            for inner in &negation.patterns {
+                visit_pattern_statements(inner, negation_depth + 1, visit);
+            }
+        }
+        Pattern::Optional(optional) => {
+            for inner in &optional.patterns {
+                visit_pattern_statements(inner, negation_depth, visit);
+            }
+        }
+        Pattern::Disjunction(disjunction) => {
+            for inner in disjunction.branches.iter().flatten() {
+                visit_pattern_statements(inner, negation_depth, visit);
             }
         }
-        
-        // Move past this FunctionCall
-        remaining = &remaining[call_start + 14..];
     }
-    
-    referenced_functions
-}
\ No newline at end of file
+}
+
+/// Visit every expression (including nested sub-expressions) in a function body or query
+fn for_each_expression(stages: &[Stage], visit: &mut impl FnMut(&Expression)) {
+    for_each_statement(stages, &mut |statement| {
+        for expression in statement_expressions(statement) {
+            visit_expression(expression, visit);
+        }
+    });
+}
+
+/// Top-level expressions appearing directly in a statement
+fn statement_expressions(statement: &Statement) -> Vec<&Expression> {
+    match statement {
+        Statement::Assignment(assignment) => vec![&assignment.rhs],
+        Statement::InIterable(in_iterable) => vec![&in_iterable.rhs],
+        Statement::Comparison(comparison) => vec![&comparison.lhs, &comparison.comparison.rhs],
+        Statement::AttributeValue(attribute) => vec![&attribute.value],
+        Statement::AttributeComparison(attribute) => vec![&attribute.comparison.rhs],
+        Statement::Thing(thing) => thing
+            .constraints
+            .iter()
+            .filter_map(|constraint| match constraint {
+                thing::Constraint::Has(has) => match &has.value {
+                    HasValue::Expression(expression) => Some(expression),
+                    HasValue::Comparison(comparison) => Some(&comparison.rhs),
+                    HasValue::Variable(_) => None,
+                },
+                _ => None,
+            })
+            .collect(),
+        Statement::Is(_) | Statement::Type(_) => Vec::new(),
+    }
+}
+
+pub(crate) fn visit_expression(expression: &Expression, visit: &mut impl FnMut(&Expression)) {
+    visit(expression);
+    match expression {
+        Expression::Function(call) => {
+            for arg in &call.args {
+                visit_expression(arg, visit);
+            }
+        }
+        Expression::Operation(operation) => {
+            visit_expression(&operation.left, visit);
+            visit_expression(&operation.right, visit);
+        }
+        Expression::Paren(paren) => visit_expression(&paren.inner, visit),
+        Expression::List(list) => {
+            for item in &list.items {
+                visit_expression(item, visit);
+            }
+        }
+        Expression::ListIndex(list_index) => visit_expression(&list_index.index, visit),
+        Expression::ListIndexRange(range) => {
+            visit_expression(&range.from, visit);
+            visit_expression(&range.to, visit);
+        }
+        Expression::Variable(_) | Expression::Value(_) => {}
+    }
+}
@@ -0,0 +1,464 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use typeql::query::{Query, SchemaQuery};
+use typeql::schema::definable::type_::{CapabilityBase, Type};
+use typeql::schema::definable::Definable;
+use typeql::token::Kind;
+use typeql::type_::{TypeRef, TypeRefAny};
+
+use crate::diagnostic::Diagnostic;
+use crate::extract_function_metadata::named_type_name;
+use crate::lexer::define_ranges;
+use crate::span::Span;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchemaMetadata {
+    pub types: Vec<TypeDefinition>,
+}
+
+/// A schema type with everything declared for it across all `define` statements
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypeDefinition {
+    pub label: String,
+    pub kind: SchemaKind,
+    pub supertype: Option<String>,
+    /// Attribute types owned by this type
+    pub owns: Vec<String>,
+    /// Roles played, as scoped labels like `employment:employee`
+    pub plays: Vec<String>,
+    /// Role names declared by a relation type
+    pub relates: Vec<String>,
+    /// Value type of an attribute type, e.g. `string`
+    pub value_type: Option<String>,
+    /// Annotations on the type and its value type as written, e.g. `@abstract` or
+    /// `@regex("^[A-Z]+$")`
+    #[serde(default)]
+    pub annotations: Vec<String>,
+    /// Annotations on `owns`, by owned attribute type, e.g. `email: ["@key"]`
+    #[serde(default)]
+    pub owns_annotations: BTreeMap<String, Vec<String>>,
+    /// Annotations on `plays`, by scoped role label
+    #[serde(default)]
+    pub plays_annotations: BTreeMap<String, Vec<String>>,
+    /// Annotations on `relates`, by role name
+    #[serde(default)]
+    pub relates_annotations: BTreeMap<String, Vec<String>>,
+}
+
+/// How many instances may take part in an `owns`, `plays` or `relates`, from `@card`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cardinality {
+    pub min: u64,
+    /// `None` when unbounded, as in `@card(1..)`
+    pub max: Option<u64>,
+}
+
+impl Cardinality {
+    /// Default of `owns` and `relates` without `@card`
+    pub const OPTIONAL: Cardinality = Cardinality {
+        min: 0,
+        max: Some(1),
+    };
+    /// Default of `plays` without `@card`
+    pub const ANY: Cardinality = Cardinality { min: 0, max: None };
+    /// Implied by `@key`
+    pub const EXACTLY_ONE: Cardinality = Cardinality {
+        min: 1,
+        max: Some(1),
+    };
+
+    /// Read `@card(2)`, `@card(0..1)` or `@card(1..)`; `None` for other annotations
+    pub fn parse(annotation: &str) -> Option<Cardinality> {
+        let range = annotation
+            .trim()
+            .strip_prefix("@card(")?
+            .strip_suffix(')')?
+            .trim();
+        let parse = |bound: &str| bound.trim().parse::<u64>().ok();
+        match range.split_once("..") {
+            Some((min, max)) if max.trim().is_empty() => Some(Cardinality {
+                min: parse(min)?,
+                max: None,
+            }),
+            Some((min, max)) => Some(Cardinality {
+                min: parse(min)?,
+                max: Some(parse(max)?),
+            }),
+            None => {
+                let exact = parse(range)?;
+                Some(Cardinality {
+                    min: exact,
+                    max: Some(exact),
+                })
+            }
+        }
+    }
+
+    pub fn allows(&self, count: u64) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+/// `@card(min..max)`
+impl fmt::Display for Cardinality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "@card({})", max),
+            Some(max) => write!(f, "@card({}..{})", self.min, max),
+            None => write!(f, "@card({}..)", self.min),
+        }
+    }
+}
+
+impl TypeDefinition {
+    /// Whether the type is declared `@abstract` and so cannot have instances
+    pub fn is_abstract(&self) -> bool {
+        has_annotation(&self.annotations, "abstract")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaKind {
+    Entity,
+    Relation,
+    Attribute,
+}
+
+impl SchemaMetadata {
+    pub fn get(&self, label: &str) -> Option<&TypeDefinition> {
+        self.types.iter().find(|type_def| type_def.label == label)
+    }
+
+    /// Whether `label` is a defined type or a role declared by any relation
+    pub fn contains(&self, label: &str) -> bool {
+        self.get(label).is_some() || self.roles().any(|(_, role)| role == label)
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = &TypeDefinition> {
+        self.of_kind(SchemaKind::Entity)
+    }
+
+    pub fn relations(&self) -> impl Iterator<Item = &TypeDefinition> {
+        self.of_kind(SchemaKind::Relation)
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item = &TypeDefinition> {
+        self.of_kind(SchemaKind::Attribute)
+    }
+
+    /// All `(relation, role)` pairs declared with `relates`
+    pub fn roles(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.relations().flat_map(|relation| {
+            relation
+                .relates
+                .iter()
+                .map(move |role| (relation.label.as_str(), role.as_str()))
+        })
+    }
+
+    /// Supertypes of `label`, nearest first; empty if it is not defined
+    pub fn ancestors(&self, label: &str) -> Vec<&TypeDefinition> {
+        match self.get(label) {
+            Some(type_def) => self.lineage(type_def).split_off(1),
+            None => Vec::new(),
+        }
+    }
+
+    /// Direct and indirect subtypes of `label`, in schema order
+    pub fn descendants(&self, label: &str) -> Vec<&TypeDefinition> {
+        self.types
+            .iter()
+            .filter(|type_def| {
+                type_def.label != label
+                    && self
+                        .lineage(type_def)
+                        .iter()
+                        .any(|ancestor| ancestor.label == label)
+            })
+            .collect()
+    }
+
+    /// Whether `label` is `supertype` itself or one of its subtypes
+    pub fn is_subtype(&self, label: &str, supertype: &str) -> bool {
+        label == supertype
+            || self
+                .ancestors(label)
+                .iter()
+                .any(|ancestor| ancestor.label == supertype)
+    }
+
+    /// `type_def` followed by its supertypes, nearest first; stops at cycles and at
+    /// supertypes missing from the schema
+    pub(crate) fn lineage<'a>(&'a self, type_def: &'a TypeDefinition) -> Vec<&'a TypeDefinition> {
+        let mut lineage = vec![type_def];
+        let mut current = type_def;
+        while let Some(supertype) = current
+            .supertype
+            .as_deref()
+            .and_then(|label| self.get(label))
+        {
+            if lineage.iter().any(|seen| seen.label == supertype.label) {
+                break;
+            }
+            lineage.push(supertype);
+            current = supertype;
+        }
+        lineage
+    }
+
+    /// How many values of `attribute` an instance of `type_def` owns, from the `owns`
+    /// declared on it or on the nearest supertype that owns `attribute`
+    pub fn owns_cardinality(&self, type_def: &TypeDefinition, attribute: &str) -> Cardinality {
+        match self.owns_annotations(type_def, attribute) {
+            Some(annotations) if has_annotation(annotations, "key") => Cardinality::EXACTLY_ONE,
+            Some(annotations) => cardinality(annotations).unwrap_or(Cardinality::OPTIONAL),
+            None => Cardinality::OPTIONAL,
+        }
+    }
+
+    /// Whether `attribute` is owned by `type_def` as a `@key`
+    pub fn is_key(&self, type_def: &TypeDefinition, attribute: &str) -> bool {
+        self.owns_annotations(type_def, attribute)
+            .is_some_and(|annotations| has_annotation(annotations, "key"))
+    }
+
+    /// Whether no two instances of `type_def` may own the same value of `attribute`, as
+    /// with `@key` or `@unique`
+    pub fn is_unique(&self, type_def: &TypeDefinition, attribute: &str) -> bool {
+        self.owns_annotations(type_def, attribute)
+            .is_some_and(|annotations| {
+                has_annotation(annotations, "key") || has_annotation(annotations, "unique")
+            })
+    }
+
+    /// How many players of `role` an instance of `relation` links, from the `relates`
+    /// declared on it or on the nearest supertype declaring `role`
+    pub fn relates_cardinality(&self, relation: &TypeDefinition, role: &str) -> Cardinality {
+        self.lineage(relation)
+            .into_iter()
+            .find(|ancestor| ancestor.relates.iter().any(|related| related == role))
+            .and_then(|ancestor| ancestor.relates_annotations.get(role))
+            .and_then(|annotations| cardinality(annotations))
+            .unwrap_or(Cardinality::OPTIONAL)
+    }
+
+    /// How many instances of a relation an instance of `type_def` may play the scoped
+    /// role `role` in
+    pub fn plays_cardinality(&self, type_def: &TypeDefinition, role: &str) -> Cardinality {
+        self.lineage(type_def)
+            .into_iter()
+            .find(|ancestor| ancestor.plays.iter().any(|played| played == role))
+            .and_then(|ancestor| ancestor.plays_annotations.get(role))
+            .and_then(|annotations| cardinality(annotations))
+            .unwrap_or(Cardinality::ANY)
+    }
+
+    /// Annotations of the nearest `owns attribute` in the lineage of `type_def`
+    fn owns_annotations<'a>(
+        &'a self,
+        type_def: &'a TypeDefinition,
+        attribute: &str,
+    ) -> Option<&'a [String]> {
+        let owner = self
+            .lineage(type_def)
+            .into_iter()
+            .find(|ancestor| ancestor.owns.iter().any(|owned| owned == attribute))?;
+        Some(
+            owner
+                .owns_annotations
+                .get(attribute)
+                .map_or(&[], Vec::as_slice),
+        )
+    }
+
+    /// Value type of an attribute type, declared on it or inherited from a supertype
+    pub(crate) fn value_type<'a>(&'a self, attribute: &'a TypeDefinition) -> Option<&'a str> {
+        self.lineage(attribute)
+            .into_iter()
+            .find_map(|type_def| type_def.value_type.as_deref())
+    }
+
+    fn of_kind(&self, kind: SchemaKind) -> impl Iterator<Item = &TypeDefinition> {
+        self.types
+            .iter()
+            .filter(move |type_def| type_def.kind == kind)
+    }
+
+    fn entry(&mut self, label: &str, kind: SchemaKind) -> &mut TypeDefinition {
+        let position = match self
+            .types
+            .iter()
+            .position(|type_def| type_def.label == label)
+        {
+            Some(position) => position,
+            None => {
+                self.types.push(TypeDefinition {
+                    label: label.to_string(),
+                    kind,
+                    supertype: None,
+                    owns: Vec::new(),
+                    plays: Vec::new(),
+                    relates: Vec::new(),
+                    value_type: None,
+                    annotations: Vec::new(),
+                    owns_annotations: BTreeMap::new(),
+                    plays_annotations: BTreeMap::new(),
+                    relates_annotations: BTreeMap::new(),
+                });
+                self.types.len() - 1
+            }
+        };
+        &mut self.types[position]
+    }
+}
+
+/// Extract type metadata from TypeQL schema source containing one or more `define` blocks.
+///
+/// Functions inside the blocks are skipped; use `extract_functions_from_source` for those.
+pub fn extract_schema_metadata(schema_text: &str) -> Result<SchemaMetadata, Diagnostic> {
+    let mut schema = SchemaMetadata::default();
+    let mut pending = Vec::new();
+
+    for range in define_ranges(schema_text) {
+        let query = typeql::parse_query(&schema_text[range.clone()])
+            .map_err(|error| Diagnostic::parse_error(&error, schema_text, range.start))?;
+        let define = match query {
+            Query::Schema(SchemaQuery::Define(define)) => define,
+            _ => {
+                return Err(
+                    Diagnostic::error("unsupported-query", "expected a define query")
+                        .with_span(Some(Span::new(schema_text, range.start, range.end))),
+                )
+            }
+        };
+
+        for definable in define.definables {
+            if let Definable::Type(type_) = definable {
+                match type_.kind.and_then(schema_kind) {
+                    Some(kind) => add_type(&mut schema, &type_, kind),
+                    // `define person owns name;` extends a type that may be declared later
+                    None => pending.push(type_),
+                }
+            }
+        }
+    }
+
+    for type_ in &pending {
+        let kind = schema
+            .get(type_.label.ident.as_str())
+            .map_or_else(|| infer_kind(type_), |existing| existing.kind);
+        add_type(&mut schema, type_, kind);
+    }
+
+    Ok(schema)
+}
+
+fn schema_kind(kind: Kind) -> Option<SchemaKind> {
+    match kind {
+        Kind::Entity => Some(SchemaKind::Entity),
+        Kind::Relation => Some(SchemaKind::Relation),
+        Kind::Attribute => Some(SchemaKind::Attribute),
+        Kind::Role => None,
+    }
+}
+
+/// Guess the kind of a type that is never declared with a kind keyword
+fn infer_kind(type_: &Type) -> SchemaKind {
+    let has = |predicate: fn(&CapabilityBase) -> bool| {
+        type_
+            .capabilities
+            .iter()
+            .any(|capability| predicate(&capability.base))
+    };
+    if has(|base| matches!(base, CapabilityBase::Relates(_))) {
+        SchemaKind::Relation
+    } else if has(|base| matches!(base, CapabilityBase::ValueType(_))) {
+        SchemaKind::Attribute
+    } else {
+        SchemaKind::Entity
+    }
+}
+
+fn add_type(schema: &mut SchemaMetadata, type_: &Type, kind: SchemaKind) {
+    let entry = schema.entry(type_.label.ident.as_str(), kind);
+    for annotation in &type_.annotations {
+        push_unique(&mut entry.annotations, annotation.to_string());
+    }
+
+    for capability in &type_.capabilities {
+        let annotations = capability.annotations.iter().map(ToString::to_string);
+        match &capability.base {
+            CapabilityBase::Sub(sub) => {
+                entry.supertype = Some(sub.supertype_label.ident.as_str().to_string())
+            }
+            CapabilityBase::Owns(owns) => {
+                let owned = type_ref_any_name(&owns.owned);
+                add_annotations(&mut entry.owns_annotations, &owned, annotations);
+                push_unique(&mut entry.owns, owned);
+            }
+            CapabilityBase::Plays(plays) => {
+                let role = plays.role.to_string();
+                add_annotations(&mut entry.plays_annotations, &role, annotations);
+                push_unique(&mut entry.plays, role);
+            }
+            CapabilityBase::Relates(relates) => {
+                let role = type_ref_any_name(&relates.related);
+                add_annotations(&mut entry.relates_annotations, &role, annotations);
+                push_unique(&mut entry.relates, role);
+            }
+            CapabilityBase::ValueType(value_type) => {
+                entry.value_type = Some(named_type_name(&value_type.value_type));
+                for annotation in annotations {
+                    push_unique(&mut entry.annotations, annotation);
+                }
+            }
+            CapabilityBase::Alias(_) => {}
+        }
+    }
+}
+
+fn add_annotations(
+    by_label: &mut BTreeMap<String, Vec<String>>,
+    label: &str,
+    annotations: impl Iterator<Item = String>,
+) {
+    for annotation in annotations {
+        push_unique(by_label.entry(label.to_string()).or_default(), annotation);
+    }
+}
+
+/// Whether `annotations` include `@name`, with or without arguments
+fn has_annotation(annotations: &[String], name: &str) -> bool {
+    annotations.iter().any(|annotation| {
+        annotation
+            .strip_prefix('@')
+            .and_then(|rest| rest.strip_prefix(name))
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('('))
+    })
+}
+
+fn cardinality(annotations: &[String]) -> Option<Cardinality> {
+    annotations
+        .iter()
+        .find_map(|annotation| Cardinality::parse(annotation))
+}
+
+fn push_unique(values: &mut Vec<String>, value: String) {
+    if !values.contains(&value) {
+        values.push(value);
+    }
+}
+
+fn type_ref_any_name(type_ref: &TypeRefAny) -> String {
+    let inner = match type_ref {
+        TypeRefAny::Type(inner) => inner,
+        TypeRefAny::List(list) => &list.inner,
+        TypeRefAny::Optional(optional) => &optional.inner,
+    };
+    match inner {
+        TypeRef::Named(named) => named_type_name(named),
+        TypeRef::Variable(var) => var.to_string(),
+    }
+}
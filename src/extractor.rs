@@ -0,0 +1,176 @@
+//! Extra per-function fields computed by pluggable extractors and stored in
+//! [`FunctionMetadata::extensions`].
+//!
+//! ```
+//! use typedb_examples::{Extractor, ExtractorPipeline, FunctionMetadata};
+//!
+//! /// Rough cost: one point per constraint, ten per call
+//! struct CostHint;
+//!
+//! impl Extractor for CostHint {
+//!     type Output = usize;
+//!
+//!     fn name(&self) -> &str {
+//!         "cost"
+//!     }
+//!
+//!     fn extract(&self, function: &FunctionMetadata) -> Option<usize> {
+//!         Some(function.constraints.len() + 10 * function.referenced_functions.len())
+//!     }
+//! }
+//!
+//! let pipeline = ExtractorPipeline::new().with_extractor(CostHint);
+//! let mut functions = vec![FunctionMetadata::builder("get_income")
+//!     .single_output(["double"])
+//!     .return_first(["income"])
+//!     .referenced_function("get_salary")
+//!     .build()
+//!     .unwrap()];
+//! pipeline.run(&mut functions).unwrap();
+//! assert_eq!(functions[0].extension::<usize>("cost"), Some(10));
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::extract_function_metadata::FunctionMetadata;
+use crate::variables::VariableInfo;
+
+/// Computes one extra field for a function
+pub trait Extractor {
+    type Output: Serialize;
+
+    /// Key the output is stored under in [`FunctionMetadata::extensions`]
+    fn name(&self) -> &str;
+
+    /// The field for `function`, or `None` to store nothing
+    fn extract(&self, function: &FunctionMetadata) -> Option<Self::Output>;
+}
+
+/// An [`Extractor`] with its output already serialized, so extractors with different
+/// outputs can be kept together
+trait DynExtractor {
+    fn name(&self) -> &str;
+
+    fn extract_value(&self, function: &FunctionMetadata) -> serde_json::Result<Option<Value>>;
+}
+
+impl<E: Extractor> DynExtractor for E {
+    fn name(&self) -> &str {
+        Extractor::name(self)
+    }
+
+    fn extract_value(&self, function: &FunctionMetadata) -> serde_json::Result<Option<Value>> {
+        self.extract(function).map(serde_json::to_value).transpose()
+    }
+}
+
+/// Runs a set of [`Extractor`]s over extracted functions and attaches their outputs
+pub struct ExtractorPipeline {
+    extractors: Vec<Box<dyn DynExtractor>>,
+}
+
+impl Default for ExtractorPipeline {
+    fn default() -> Self {
+        ExtractorPipeline::new()
+    }
+}
+
+impl ExtractorPipeline {
+    /// A pipeline with the built-in extractors: [`Variables`] and [`DocTagExtractor`]s for
+    /// the `owner` and `since` tags
+    pub fn new() -> Self {
+        ExtractorPipeline::empty()
+            .with_extractor(Variables)
+            .with_extractor(DocTagExtractor::new("owner"))
+            .with_extractor(DocTagExtractor::new("since"))
+    }
+
+    /// A pipeline that runs no extractors until some are added
+    pub fn empty() -> Self {
+        ExtractorPipeline {
+            extractors: Vec::new(),
+        }
+    }
+
+    /// Add an extractor, run after those already added; one with the name of an earlier
+    /// extractor replaces its output
+    pub fn with_extractor(mut self, extractor: impl Extractor + 'static) -> Self {
+        self.extractors.push(Box::new(extractor));
+        self
+    }
+
+    /// Names of the extractors, in the order they run
+    pub fn extractor_names(&self) -> impl Iterator<Item = &str> {
+        self.extractors.iter().map(|extractor| extractor.name())
+    }
+
+    /// Run every extractor over every function, storing outputs in `extensions`.
+    ///
+    /// Fails only if an output cannot be represented as JSON, e.g. a map with non-string
+    /// keys.
+    pub fn run(&self, functions: &mut [FunctionMetadata]) -> serde_json::Result<()> {
+        for function in functions {
+            for extractor in &self.extractors {
+                if let Some(value) = extractor.extract_value(function)? {
+                    function
+                        .extensions
+                        .insert(extractor.name().to_string(), value);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FunctionMetadata {
+    /// The output of the extractor called `name`, or `None` when there is none or it is
+    /// not a `T`
+    pub fn extension<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        let value = self.extensions.get(name)?;
+        T::deserialize(value).ok()
+    }
+}
+
+/// `variables`: every variable with its roles and usage count, as
+/// [`FunctionMetadata::variables`] gives them
+pub struct Variables;
+
+impl Extractor for Variables {
+    type Output = Vec<VariableInfo>;
+
+    fn name(&self) -> &str {
+        "variables"
+    }
+
+    fn extract(&self, function: &FunctionMetadata) -> Option<Vec<VariableInfo>> {
+        Some(function.variables())
+    }
+}
+
+/// The text of a doc tag, stored under the tag name, e.g. `@owner billing-team` as
+/// `"owner": "billing-team"`
+pub struct DocTagExtractor {
+    pub tag: String,
+}
+
+impl DocTagExtractor {
+    pub fn new(tag: &str) -> Self {
+        DocTagExtractor {
+            tag: tag.to_string(),
+        }
+    }
+}
+
+impl Extractor for DocTagExtractor {
+    type Output = String;
+
+    fn name(&self) -> &str {
+        &self.tag
+    }
+
+    fn extract(&self, function: &FunctionMetadata) -> Option<String> {
+        function.doc_tag(&self.tag).map(str::to_string)
+    }
+}
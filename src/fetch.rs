@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use typeql::query::stage::fetch::{
+    Fetch, FetchAttribute, FetchObject, FetchObjectBody, FetchSingleVal, FetchSome, FetchStream,
+};
+use typeql::query::stage::Stage;
+
+use crate::expression::Expr;
+use crate::extract_function_metadata::{type_ref_any_label, variable_name};
+
+/// The shape of a `fetch` projection, for generating response types.
+///
+/// `list` marks values fetched in `[ ... ]`, which produce an array per result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FetchValue {
+    /// `{ "key": ..., ... }`
+    Object { entries: Vec<FetchEntry> },
+    /// `{ $x.* }`: every attribute of `var`, keyed by attribute type
+    AllAttributes { var: String },
+    /// `$x.name` or `[$x.name]`
+    Attribute {
+        owner: String,
+        attribute: String,
+        list: bool,
+    },
+    /// A variable or expression such as `$x` or `$a + $b`
+    Expression { expression: String, tree: Expr },
+    /// `[get_friends($x)]`, or `get_age($x)` for a single value
+    FunctionCall { function: String, list: bool },
+    /// A nested `match ... fetch` pipeline, or an anonymous function block such as
+    /// `( match ...; return count; )`
+    Subquery {
+        /// The nested fetch, if the subquery ends in one
+        fetch: Option<Box<FetchValue>>,
+        list: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchEntry {
+    /// Key as written, without quotes
+    pub key: String,
+    pub value: FetchValue,
+}
+
+impl FetchValue {
+    /// Keys of this value and nested objects as dotted paths, e.g. `friends.name`
+    pub fn key_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        self.collect_key_paths("", &mut paths);
+        paths
+    }
+
+    fn collect_key_paths(&self, prefix: &str, paths: &mut Vec<String>) {
+        match self {
+            FetchValue::Object { entries } => {
+                for entry in entries {
+                    let path = if prefix.is_empty() {
+                        entry.key.clone()
+                    } else {
+                        format!("{}.{}", prefix, entry.key)
+                    };
+                    paths.push(path.clone());
+                    entry.value.collect_key_paths(&path, paths);
+                }
+            }
+            FetchValue::Subquery {
+                fetch: Some(fetch), ..
+            } => fetch.collect_key_paths(prefix, paths),
+            _ => {}
+        }
+    }
+}
+
+/// The structure of the last `fetch` stage in `stages`
+pub(crate) fn extract_fetch(stages: &[Stage]) -> Option<FetchValue> {
+    stages.iter().rev().find_map(|stage| match stage {
+        Stage::Fetch(Fetch { object, .. }) => Some(fetch_object(object)),
+        _ => None,
+    })
+}
+
+fn fetch_object(object: &FetchObject) -> FetchValue {
+    match &object.body {
+        FetchObjectBody::Entries(entries) => FetchValue::Object {
+            entries: entries
+                .iter()
+                .map(|entry| FetchEntry {
+                    key: entry.key.to_string().trim_matches(['"', '\'']).to_string(),
+                    value: fetch_some(&entry.value),
+                })
+                .collect(),
+        },
+        FetchObjectBody::AttributesAll(var) => FetchValue::AllAttributes {
+            var: variable_name(var).unwrap_or("_").to_string(),
+        },
+    }
+}
+
+fn fetch_some(value: &FetchSome) -> FetchValue {
+    match value {
+        FetchSome::Object(object) => fetch_object(object),
+        FetchSome::List(list) => match &list.stream {
+            FetchStream::Attribute(attribute) => fetch_attribute(attribute, true),
+            FetchStream::Function(call) => FetchValue::FunctionCall {
+                function: call.name.to_string(),
+                list: true,
+            },
+            FetchStream::SubQueryFetch(stages) => FetchValue::Subquery {
+                fetch: extract_fetch(stages).map(Box::new),
+                list: true,
+            },
+            FetchStream::SubQueryFunctionBlock(_) => FetchValue::Subquery {
+                fetch: None,
+                list: true,
+            },
+        },
+        FetchSome::Single(single) => match single {
+            FetchSingleVal::Attribute(attribute) => fetch_attribute(attribute, false),
+            FetchSingleVal::Expression(typeql::expression::Expression::Function(call)) => {
+                FetchValue::FunctionCall {
+                    function: call.name.to_string(),
+                    list: false,
+                }
+            }
+            FetchSingleVal::Expression(expression) => FetchValue::Expression {
+                expression: expression.to_string(),
+                tree: Expr::from(expression),
+            },
+            FetchSingleVal::FunctionBlock(_) => FetchValue::Subquery {
+                fetch: None,
+                list: false,
+            },
+        },
+    }
+}
+
+fn fetch_attribute(attribute: &FetchAttribute, list: bool) -> FetchValue {
+    FetchValue::Attribute {
+        owner: variable_name(&attribute.owner).unwrap_or("_").to_string(),
+        attribute: type_ref_any_label(&attribute.attribute).unwrap_or_default(),
+        list,
+    }
+}
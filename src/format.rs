@@ -0,0 +1,276 @@
+use crate::error::ExtractError;
+use crate::extract_function_metadata::extract_function_at;
+use crate::generate::STAGE_KEYWORDS;
+use crate::lexer::{function_ranges, tokenize, Token, TokenKind};
+
+const INDENT: &str = "    ";
+
+/// Stages whose statements are printed one per line beneath the keyword
+const BLOCK_STAGES: &[&str] = &["match", "insert", "put", "update", "delete"];
+
+/// Keywords that start a new constraint after a comma, e.g. `$x isa person, has name $n`
+const CONSTRAINT_KEYWORDS: &[&str] = &[
+    "isa", "isa!", "has", "links", "iid", "sub", "sub!", "owns", "plays", "relates", "value",
+    "label",
+];
+
+/// Keywords written with a space before an opening parenthesis, unlike function calls
+const SPACED_KEYWORDS: &[&str] = &[
+    "links", "isa", "isa!", "has", "not", "or", "try", "in", "is", "let", "match", "return",
+    "first", "last", "groupby",
+];
+
+/// Parse a function definition and print it in canonical form.
+///
+/// The signature goes on one line, stage keywords are indented once and their statements
+/// twice, statements with several constraints put each constraint on its own line, and
+/// punctuation is spaced consistently. Comments are kept on their own lines.
+///
+/// Invalid TypeQL is rejected with the parse error rather than reformatted.
+pub fn format_function(function_text: &str) -> Result<String, ExtractError> {
+    extract_function_at(function_text, 0..function_text.len())
+        .map_err(|diagnostic| ExtractError::from_input(diagnostic, function_text))?;
+    Ok(Printer::default().print(function_text))
+}
+
+/// Format every function definition in `source`, leaving all other text untouched
+pub fn format_functions_in_source(source: &str) -> Result<String, ExtractError> {
+    let mut formatted = String::with_capacity(source.len());
+    let mut last_end = 0;
+
+    for range in function_ranges(source) {
+        extract_function_at(source, range.clone())
+            .map_err(|diagnostic| ExtractError::from_input(diagnostic, source))?;
+        formatted.push_str(&source[last_end..range.start]);
+        formatted.push_str(&Printer::default().print(&source[range.clone()]));
+        last_end = range.end;
+    }
+
+    formatted.push_str(&source[last_end..]);
+    Ok(formatted)
+}
+
+/// A token after joining operators and labels that the lexer splits, e.g. `->` or `isa!`
+struct Piece {
+    text: String,
+    kind: TokenKind,
+}
+
+#[derive(Default)]
+struct Printer {
+    lines: Vec<String>,
+    line: String,
+    indent: usize,
+}
+
+impl Printer {
+    fn print(mut self, function_text: &str) -> String {
+        let pieces = join_tokens(tokenize(function_text));
+        let body_start = self.print_signature(&pieces);
+        self.print_body(&pieces[body_start..]);
+        self.flush();
+        self.lines.join("\n")
+    }
+
+    /// Print `fun name(...) -> output:` and return the index of the first body piece
+    fn print_signature(&mut self, pieces: &[Piece]) -> usize {
+        let mut depth = 0;
+        let mut seen_arrow = false;
+        let mut previous = None;
+
+        for (index, piece) in pieces.iter().enumerate() {
+            if piece.kind == TokenKind::Comment {
+                self.comment(piece, 0);
+                continue;
+            }
+            self.push(piece, previous);
+            previous = Some(piece);
+            match piece.text.as_str() {
+                "(" => depth += 1,
+                ")" => depth -= 1,
+                "->" if depth == 0 => seen_arrow = true,
+                ":" if depth == 0 && seen_arrow => {
+                    self.flush();
+                    return index + 1;
+                }
+                _ => {}
+            }
+        }
+
+        pieces.len()
+    }
+
+    fn print_body(&mut self, pieces: &[Piece]) {
+        // Indentation of statements in the current stage, increased inside `{ }` blocks
+        let mut statement_indent = 2;
+        let mut depth = 0;
+        let mut in_statement = false;
+        let mut previous: Option<&Piece> = None;
+        // Whether each open brace starts a nested pattern block rather than inline braces
+        let mut braces = Vec::new();
+
+        for (index, piece) in pieces.iter().enumerate() {
+            let text = piece.text.as_str();
+            let next = pieces.get(index + 1).map(|next| next.text.as_str());
+
+            if piece.kind == TokenKind::Comment {
+                self.comment(piece, statement_indent + usize::from(in_statement));
+                continue;
+            }
+
+            if !in_statement && depth == 0 && STAGE_KEYWORDS.contains(&text) {
+                self.flush();
+                self.indent = 1;
+                self.push(piece, None);
+                statement_indent = 2;
+                if BLOCK_STAGES.contains(&text) {
+                    self.flush();
+                } else {
+                    in_statement = true;
+                }
+                previous = Some(piece);
+                continue;
+            }
+
+            if !in_statement && self.line.is_empty() {
+                self.indent = statement_indent;
+                previous = None;
+            }
+
+            match text {
+                "(" | "[" => depth += 1,
+                ")" | "]" => depth -= 1,
+                _ => {}
+            }
+
+            match text {
+                "{" if depth == 0
+                    && previous.is_none_or(|previous| opens_block(&previous.text)) =>
+                {
+                    self.push(piece, previous);
+                    self.flush();
+                    statement_indent += 1;
+                    in_statement = false;
+                    braces.push(true);
+                }
+                "{" => {
+                    self.push(piece, previous);
+                    braces.push(false);
+                }
+                "}" if braces.pop() == Some(true) => {
+                    self.flush();
+                    statement_indent -= 1;
+                    self.indent = statement_indent;
+                    self.push(piece, None);
+                    // `} or {` and the closing `};` stay on the brace's line
+                    in_statement = matches!(next, Some("or") | Some(";"));
+                    if !in_statement {
+                        self.flush();
+                    }
+                }
+                ";" => {
+                    self.push(piece, previous);
+                    self.flush();
+                    in_statement = false;
+                }
+                "," if depth == 0
+                    && next.is_some_and(|next| CONSTRAINT_KEYWORDS.contains(&next)) =>
+                {
+                    self.push(piece, previous);
+                    self.flush();
+                    self.indent = statement_indent + 1;
+                    previous = None;
+                    continue;
+                }
+                _ => {
+                    self.push(piece, previous);
+                    in_statement = true;
+                }
+            }
+            previous = Some(piece);
+        }
+    }
+
+    fn push(&mut self, piece: &Piece, previous: Option<&Piece>) {
+        if !self.line.is_empty() && previous.is_none_or(|previous| needs_space(previous, piece)) {
+            self.line.push(' ');
+        }
+        self.line.push_str(&piece.text);
+    }
+
+    fn comment(&mut self, piece: &Piece, indent: usize) {
+        self.flush();
+        self.indent = indent;
+        self.line.push_str(piece.text.trim_end());
+        self.flush();
+    }
+
+    fn flush(&mut self) {
+        if !self.line.is_empty() {
+            let line = std::mem::take(&mut self.line);
+            self.lines
+                .push(format!("{}{}", INDENT.repeat(self.indent), line));
+        }
+    }
+}
+
+/// Whether a `{` after `previous` opens a nested block of patterns, as in `not { ... }`
+fn opens_block(previous: &str) -> bool {
+    matches!(previous, "not" | "try" | "or")
+}
+
+fn needs_space(previous: &Piece, piece: &Piece) -> bool {
+    let (prev, next) = (previous.text.as_str(), piece.text.as_str());
+    if matches!(next, "," | ";" | ":" | ")" | "]") || matches!(prev, "(" | "[") {
+        return false;
+    }
+    // Function calls hug their arguments; keywords and variables do not
+    if next == "(" && previous.kind == TokenKind::Word {
+        return SPACED_KEYWORDS.contains(&prev);
+    }
+    true
+}
+
+/// Join tokens the lexer splits but that are one unit in TypeQL: operators (`->`, `>=`,
+/// `<=`, `==`, `!=`), exact kinds (`isa!`, `sub!`), and colon-separated words written
+/// without spaces, such as scoped labels (`employment:employee`) and date-times.
+fn join_tokens(tokens: Vec<Token<'_>>) -> Vec<Piece> {
+    let mut pieces: Vec<Piece> = Vec::with_capacity(tokens.len());
+    let glued =
+        |index: usize| index > 0 && tokens[index - 1].range.end == tokens[index].range.start;
+
+    let mut index = 0;
+    while index < tokens.len() {
+        let token = &tokens[index];
+        if let Some(last) = pieces.last_mut().filter(|_| glued(index)) {
+            let joined = format!("{}{}", last.text, token.text);
+            if matches!(
+                joined.as_str(),
+                "->" | ">=" | "<=" | "==" | "!=" | "isa!" | "sub!"
+            ) {
+                last.text = joined;
+                last.kind = TokenKind::Punct;
+                index += 1;
+                continue;
+            }
+            let next = tokens.get(index + 1);
+            if last.kind == TokenKind::Word
+                && token.is_punct(":")
+                && next.is_some_and(|next| next.kind == TokenKind::Word)
+                && glued(index + 1)
+            {
+                last.text = format!("{}:{}", last.text, tokens[index + 1].text);
+                index += 2;
+                continue;
+            }
+        }
+
+        pieces.push(Piece {
+            text: token.text.to_string(),
+            kind: token.kind,
+        });
+        index += 1;
+    }
+
+    pieces
+}
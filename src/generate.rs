@@ -0,0 +1,235 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::call_graph::CallGraph;
+use crate::doc_comment::DIRECTIVE_PREFIX;
+use crate::error::ExtractError;
+use crate::extract_function_metadata::{FunctionMetadata, Parameter};
+
+/// Keywords that begin a stage or the return statement, written at the outer indentation
+/// level of a function body
+pub(crate) const STAGE_KEYWORDS: &[&str] = &[
+    "match", "insert", "put", "update", "delete", "fetch", "select", "sort", "offset", "limit",
+    "reduce", "require", "distinct", "return",
+];
+
+impl FunctionMetadata {
+    /// Regenerate a `fun` definition from the metadata.
+    ///
+    /// The signature is built from `name`, `parameters` and `output`, and the return
+    /// statement from `return_expression`, so edits to those fields are reflected. The
+    /// rest of the body is taken from `code_block`. The doc comment, its tags and any lint
+    /// suppressions are written above the definition.
+    pub fn to_typeql(&self) -> String {
+        let mut out = String::new();
+        if let Some(doc) = &self.doc {
+            for line in doc.lines() {
+                writeln!(out, "# {}", line).unwrap();
+            }
+        }
+        for tag in &self.doc_tags {
+            let mut lines = tag.text.lines();
+            let first = format!("# @{} {}", tag.name, lines.next().unwrap_or(""));
+            writeln!(out, "{}", first.trim_end()).unwrap();
+            for line in lines {
+                writeln!(out, "# {}", line).unwrap();
+            }
+        }
+        if !self.lint_ignores.is_empty() {
+            writeln!(
+                out,
+                "# {} ignore {}",
+                DIRECTIVE_PREFIX,
+                self.lint_ignores.join(", ")
+            )
+            .unwrap();
+        }
+        let parameters: Vec<String> = self.parameters.iter().map(Parameter::to_string).collect();
+        writeln!(
+            out,
+            "fun {}({}) -> {}:",
+            self.name,
+            parameters.join(", "),
+            self.output
+        )
+        .unwrap();
+
+        for line in body_without_return(&self.code_block).lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let first_word = line
+                .split(|c: char| !c.is_alphanumeric())
+                .next()
+                .unwrap_or("");
+            let indent = if STAGE_KEYWORDS.contains(&first_word) {
+                4
+            } else {
+                8
+            };
+            writeln!(out, "{}{}", " ".repeat(indent), line).unwrap();
+        }
+
+        write!(out, "    return {};", self.return_expression).unwrap();
+        out
+    }
+}
+
+/// Emit one `define` document with every function after the functions it calls, ready to
+/// load in a single schema transaction.
+///
+/// Fails with [`ExtractError::DuplicateFunction`] if a function is defined twice, or
+/// [`ExtractError::CyclicCalls`] if functions call each other in a cycle.
+pub fn bundle(functions: &[FunctionMetadata]) -> Result<String, ExtractError> {
+    for (position, function) in functions.iter().enumerate() {
+        if functions[..position]
+            .iter()
+            .any(|earlier| earlier.name == function.name)
+        {
+            return Err(ExtractError::DuplicateFunction {
+                name: function.name.clone(),
+            });
+        }
+    }
+
+    let graph = CallGraph::from_functions(functions);
+    let order = graph.topological_order()?;
+
+    let mut out = String::from("define\n");
+    for name in order {
+        let function = functions
+            .iter()
+            .find(|function| function.name == name)
+            .expect("graph nodes come from the input functions");
+        writeln!(out).unwrap();
+        writeln!(out, "{}", function.to_typeql()).unwrap();
+    }
+    Ok(out)
+}
+
+/// Keep only the functions needed by `entries`: the entry functions themselves and
+/// everything they call, directly or transitively. Input order is preserved.
+///
+/// Fails with [`ExtractError::UnknownFunction`] if an entry function is not in `functions`.
+pub fn tree_shake(
+    functions: Vec<FunctionMetadata>,
+    entries: &[&str],
+) -> Result<Vec<FunctionMetadata>, ExtractError> {
+    let graph = CallGraph::from_functions(&functions);
+    if let Some(missing) = entries.iter().find(|entry| !graph.contains(entry)) {
+        return Err(ExtractError::UnknownFunction {
+            name: missing.to_string(),
+        });
+    }
+    let needed: HashSet<String> = graph
+        .reachable_from(entries)
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+
+    Ok(functions
+        .into_iter()
+        .filter(|function| needed.contains(&function.name))
+        .collect())
+}
+
+/// The code block up to (not including) its final `return` statement
+fn body_without_return(code_block: &str) -> &str {
+    let mut end = code_block.len();
+    let mut offset = 0;
+    for line in code_block.split_inclusive('\n') {
+        if line.trim_start().starts_with("return") {
+            end = offset;
+        }
+        offset += line.len();
+    }
+    &code_block[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::extract_function_metadata::extract_function_metadata;
+    use crate::format::format_function;
+
+    const FUNCTIONS: &[&str] = &[
+        // Doc comment with tags, single reducing output
+        r#"# Total income of a taxpayer
+# @tag tax, income
+# @since 2024
+fun calculate_total_income($taxpayer: taxpayer) -> double:
+    match
+        $income (earner: $taxpayer) isa income_source, has amount $amt;
+    return sum($amt);"#,
+        // Stream output
+        r#"fun mutual_friends($p1: person, $p2: person) -> { person }:
+    match
+        $f1 isa friendship, links (friend: $p1, friend: $pm);
+        $f2 isa friendship, links (friend: $p2, friend: $pm);
+    return { $pm };"#,
+        // Optional output
+        r#"fun get_nickname($p: person) -> nickname?:
+    match
+        try { $p has nickname $n; };
+    return first $n;"#,
+        // Several stages, a deprecation tag and a tuple of outputs
+        r#"# Bracket an income falls in
+# @deprecated use get_bracket instead
+fun get_tax_bracket($income: double, $year: integer) -> double, double:
+    match
+        $b isa tax_bracket, has year $year, has minimum $min, has rate $rate;
+        $income >= $min;
+    sort $min desc;
+    return first $min, $rate;"#,
+    ];
+
+    /// Definitions with one constraint per statement, which `to_typeql` writes in the
+    /// formatter's layout
+    const FORMATTED: &[&str] = &[
+        r#"# Highest bracket an income reaches
+# @tag tax
+fun get_bracket($income: double) -> bracket_min, rate:
+    match
+        $bracket isa tax_bracket;
+        $bracket has bracket_min $min;
+        $bracket has rate $rate;
+        $income >= $min;
+    sort $min desc;
+    limit 1;
+    return first $min, $rate;"#,
+        r#"fun count_dependents($taxpayer: taxpayer) -> integer:
+    match
+        $d isa dependency;
+        $d links (parent: $taxpayer);
+    reduce $count = count($d);
+    return first $count;"#,
+    ];
+
+    #[test]
+    fn to_typeql_round_trips() {
+        for function_text in FUNCTIONS {
+            let original = extract_function_metadata(function_text).unwrap();
+            let generated = original.to_typeql();
+            let reparsed = extract_function_metadata(&generated)
+                .unwrap_or_else(|error| panic!("{}\n{}", error, generated));
+            assert_eq!(
+                original.without_location(),
+                reparsed.without_location(),
+                "round trip changed `{}`:\n{}",
+                original.name,
+                generated
+            );
+        }
+    }
+
+    #[test]
+    fn format_keeps_generated_layout() {
+        for function_text in FORMATTED {
+            let generated = extract_function_metadata(function_text)
+                .unwrap()
+                .to_typeql();
+            let formatted = format_function(&generated).unwrap();
+            assert_eq!(formatted, generated, "formatting changed:\n{}", formatted);
+        }
+    }
+}
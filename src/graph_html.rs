@@ -0,0 +1,410 @@
+//! A standalone HTML page for exploring a call graph in the browser.
+//!
+//! The page needs no network access: the graph and a small viewer script are embedded.
+//! Functions are laid out left to right, callers before the functions they call. Scroll to
+//! zoom, drag to pan, and click a function to highlight its callers and callees and show
+//! its signature, doc comment, explanation and body in a side panel. Calls in a cycle are
+//! drawn in red, and functions called but not defined are dashed. Functions in the same
+//! namespace share a labelled band.
+
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::call_graph::CallGraph;
+use crate::explain::explain;
+use crate::extract_function_metadata::FunctionMetadata;
+use crate::namespace::Namespaces;
+
+#[derive(Serialize)]
+struct Graph<'a> {
+    /// Namespaces in the order their bands are drawn
+    namespaces: Vec<&'a str>,
+    nodes: Vec<Node<'a>>,
+    /// Caller and callee indices into `nodes`
+    edges: Vec<Edge>,
+}
+
+#[derive(Serialize)]
+struct Node<'a> {
+    name: &'a str,
+    /// `false` for functions that are called but not among those rendered
+    defined: bool,
+    cyclic: bool,
+    namespace: Option<&'a str>,
+    signature: Option<String>,
+    doc: Option<&'a str>,
+    source_file: Option<&'a str>,
+    explanation: Option<String>,
+    code_block: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Edge {
+    from: usize,
+    to: usize,
+    cyclic: bool,
+}
+
+/// An HTML page with an interactive, zoomable call graph of `functions`
+pub fn render_call_graph_html(functions: &[FunctionMetadata]) -> String {
+    render_call_graph_html_with_namespaces(functions, &Namespaces::default())
+}
+
+/// An HTML page like [`render_call_graph_html`], with the functions of each namespace
+/// grouped in a band
+pub fn render_call_graph_html_with_namespaces(
+    functions: &[FunctionMetadata],
+    namespaces: &Namespaces,
+) -> String {
+    let graph = CallGraph::from_functions(functions);
+    let components = graph.recursive_components();
+    let cyclic: HashSet<&str> = components.iter().flatten().copied().collect();
+    let in_cycle = |caller: &str, callee: &str| {
+        components
+            .iter()
+            .any(|component| component.contains(&caller) && component.contains(&callee))
+    };
+
+    let mut nodes: Vec<Node<'_>> = functions
+        .iter()
+        .map(|function| Node {
+            name: &function.name,
+            defined: true,
+            cyclic: cyclic.contains(function.name.as_str()),
+            namespace: namespaces.namespace_of(&function.name),
+            signature: Some(function.signature()),
+            doc: function.doc.as_deref(),
+            source_file: function.source_file.as_deref(),
+            explanation: Some(explain(function)),
+            code_block: Some(&function.code_block),
+        })
+        .collect();
+    let mut edges = Vec::new();
+    let mut seen = HashSet::new();
+    for (from, function) in functions.iter().enumerate() {
+        for callee in &function.referenced_functions {
+            if !seen.insert((function.name.as_str(), callee.as_str())) {
+                continue;
+            }
+            let to = match nodes.iter().position(|node| node.name == callee) {
+                Some(to) => to,
+                None => {
+                    nodes.push(Node {
+                        name: callee,
+                        defined: false,
+                        cyclic: false,
+                        namespace: None,
+                        signature: None,
+                        doc: None,
+                        source_file: None,
+                        explanation: None,
+                        code_block: None,
+                    });
+                    nodes.len() - 1
+                }
+            };
+            edges.push(Edge {
+                from,
+                to,
+                cyclic: in_cycle(&function.name, callee),
+            });
+        }
+    }
+
+    let graph = Graph {
+        namespaces: namespaces.names(),
+        nodes,
+        edges,
+    };
+    let data = serde_json::to_string(&graph)
+        .expect("graph serializes")
+        // Keep `</script>` in a doc comment or body from ending the data block
+        .replace("</", "<\\/");
+    PAGE.replace("__GRAPH_DATA__", &data)
+}
+
+const PAGE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Call graph</title>
+<style>
+  html, body { margin: 0; height: 100%; font-family: Helvetica, Arial, sans-serif; }
+  body { display: flex; }
+  #canvas { flex: 1; position: relative; overflow: hidden; background: #fafafa; }
+  #canvas svg { width: 100%; height: 100%; cursor: grab; }
+  #canvas svg.dragging { cursor: grabbing; }
+  #toolbar { position: absolute; top: 8px; left: 8px; display: flex; gap: 4px; }
+  #toolbar input { width: 220px; padding: 4px; }
+  #panel { width: 380px; overflow: auto; border-left: 1px solid #ddd; padding: 12px;
+           box-sizing: border-box; font-size: 14px; }
+  #panel pre { background: #f3f3f3; padding: 8px; overflow: auto; font-size: 12px; }
+  #panel h2 { font-size: 16px; margin-top: 0; word-break: break-all; }
+  #panel a { color: #0b57d0; cursor: pointer; }
+  .node rect { fill: #fff; stroke: #555; rx: 4; }
+  .node text { font-size: 12px; dominant-baseline: middle; pointer-events: none; }
+  .node { cursor: pointer; }
+  .node.undefined rect { stroke-dasharray: 4 3; fill: #f3f3f3; }
+  .node.cyclic rect { stroke: #c62828; }
+  .node.selected rect { stroke-width: 3; fill: #e8f0fe; }
+  .node.match rect { fill: #fff3c4; }
+  .cluster { fill: #eef3fb; stroke: #b6c8e6; rx: 8; }
+  .cluster-label { font-size: 12px; font-weight: bold; fill: #4a6491; }
+  .edge { fill: none; stroke: #999; marker-end: url(#arrow); }
+  .edge.cyclic { stroke: #c62828; marker-end: url(#arrow-cyclic); }
+  .edge.active { stroke: #0b57d0; stroke-width: 2; marker-end: url(#arrow-active); }
+  .faded { opacity: 0.2; }
+</style>
+</head>
+<body>
+<div id="canvas">
+  <div id="toolbar">
+    <input id="search" type="search" placeholder="Find a function (Enter to go)">
+    <button id="fit">Fit</button>
+  </div>
+  <svg id="svg">
+    <defs>
+      <marker id="arrow" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="8"
+              markerHeight="8" orient="auto"><path d="M0,0L10,5L0,10z" fill="#999"/></marker>
+      <marker id="arrow-cyclic" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="8"
+              markerHeight="8" orient="auto"><path d="M0,0L10,5L0,10z" fill="#c62828"/></marker>
+      <marker id="arrow-active" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="8"
+              markerHeight="8" orient="auto"><path d="M0,0L10,5L0,10z" fill="#0b57d0"/></marker>
+    </defs>
+    <g id="viewport"></g>
+  </svg>
+</div>
+<div id="panel"><p>Click a function to see its details.</p></div>
+<script type="application/json" id="graph-data">__GRAPH_DATA__</script>
+<script>
+(function () {
+  const graph = JSON.parse(document.getElementById("graph-data").textContent);
+  const nodes = graph.nodes, edges = graph.edges;
+  const callees = nodes.map(() => []), callers = nodes.map(() => []);
+  for (const edge of edges) {
+    callees[edge.from].push(edge);
+    callers[edge.to].push(edge);
+  }
+
+  // Layers by longest call chain from the callers, ignoring calls that close a cycle
+  const layer = nodes.map(() => 0), state = nodes.map(() => 0), order = [];
+  const back = new Set();
+  function visit(node) {
+    state[node] = 1;
+    for (const edge of callees[node]) {
+      if (state[edge.to] === 1) back.add(edge);
+      else if (state[edge.to] === 0) visit(edge.to);
+    }
+    state[node] = 2;
+    order.push(node);
+  }
+  nodes.forEach((_, node) => { if (state[node] === 0) visit(node); });
+  order.reverse();
+  for (const node of order) {
+    for (const edge of callees[node]) {
+      if (!back.has(edge)) layer[edge.to] = Math.max(layer[edge.to], layer[node] + 1);
+    }
+  }
+
+  // Each namespace gets a band across the columns, tall enough for its fullest column;
+  // functions without one come last
+  const namespaces = graph.namespaces;
+  const band = nodes.map(node => node.namespace === null ? namespaces.length
+                                                         : namespaces.indexOf(node.namespace));
+  const columns = [];
+  nodes.forEach((_, node) => (columns[layer[node]] = columns[layer[node]] || []).push(node));
+  const bandRows = namespaces.map(() => 0).concat([0]);
+  for (const column of columns) {
+    const rows = bandRows.map(() => 0);
+    column.forEach(node => rows[band[node]]++);
+    rows.forEach((count, index) => (bandRows[index] = Math.max(bandRows[index], count)));
+  }
+  const bandTop = [];
+  let top = 0;
+  bandRows.forEach((rows, index) => {
+    bandTop[index] = top;
+    if (rows) top += rows * 44 + 40;
+  });
+
+  // Within a layer and band, place functions near the functions calling them
+  const x = [], y = [], width = nodes.map(node => 20 + 7 * node.name.length);
+  const columnX = [];
+  let left = 0;
+  columns.forEach((column, index) => {
+    columnX[index] = left;
+    left += Math.max(...column.map(node => width[node])) + 120;
+  });
+  columns.forEach((column, index) => {
+    const barycenter = node => {
+      const placed = callers[node].filter(edge => y[edge.from] !== undefined);
+      return placed.length ? placed.reduce((sum, edge) => sum + y[edge.from], 0) / placed.length
+                           : Infinity;
+    };
+    column.sort((a, b) => band[a] - band[b] || barycenter(a) - barycenter(b));
+    const rows = bandRows.map(() => 0);
+    column.forEach(node => {
+      x[node] = columnX[index];
+      y[node] = bandTop[band[node]] + rows[band[node]]++ * 44;
+    });
+  });
+
+  const svgNs = "http://www.w3.org/2000/svg";
+  const svg = document.getElementById("svg"), viewport = document.getElementById("viewport");
+  const element = (name, attributes, parent) => {
+    const created = document.createElementNS(svgNs, name);
+    for (const key in attributes) created.setAttribute(key, attributes[key]);
+    parent.appendChild(created);
+    return created;
+  };
+
+  namespaces.forEach((namespace, index) => {
+    const members = nodes.map((_, node) => node).filter(node => band[node] === index);
+    if (!members.length) return;
+    const left = Math.min(...members.map(node => x[node])) - 12;
+    const right = Math.max(...members.map(node => x[node] + width[node])) + 12;
+    element("rect", { class: "cluster", x: left, y: bandTop[index] - 26, width: right - left,
+                      height: bandRows[index] * 44 + 20 }, viewport);
+    element("text", { class: "cluster-label", x: left + 8, y: bandTop[index] - 12 },
+            viewport).textContent = namespace;
+  });
+
+  const edgeElements = edges.map(edge => {
+    const x1 = x[edge.from] + width[edge.from], y1 = y[edge.from] + 14;
+    const x2 = x[edge.to], y2 = y[edge.to] + 14;
+    const bend = Math.max(40, Math.abs(x2 - x1) / 2);
+    const path = edge.from === edge.to
+      ? `M${x1},${y1 - 6} C${x1 + 40},${y1 - 40} ${x2 - 40},${y2 - 40} ${x2},${y2 - 6}`
+      : `M${x1},${y1} C${x1 + bend},${y1} ${x2 - bend},${y2} ${x2},${y2}`;
+    return element("path", { d: path, class: "edge" + (edge.cyclic ? " cyclic" : "") },
+                   viewport);
+  });
+  const nodeElements = nodes.map((node, index) => {
+    const group = element("g", {
+      class: "node" + (node.defined ? "" : " undefined") + (node.cyclic ? " cyclic" : ""),
+      transform: `translate(${x[index]},${y[index]})`,
+    }, viewport);
+    element("rect", { width: width[index], height: 28 }, group);
+    element("text", { x: 10, y: 14 }, group).textContent = node.name;
+    group.addEventListener("click", event => { event.stopPropagation(); select(index); });
+    return group;
+  });
+
+  // Pan and zoom
+  let scale = 1, panX = 20, panY = 20;
+  const apply = () => viewport.setAttribute("transform",
+                                           `translate(${panX},${panY}) scale(${scale})`);
+  function fit() {
+    const box = viewport.getBBox(), area = svg.getBoundingClientRect();
+    scale = Math.min(2, area.width / (box.width + 40), area.height / (box.height + 40));
+    panX = 20 - box.x * scale;
+    panY = 20 - box.y * scale;
+    apply();
+  }
+  function centre(node) {
+    const area = svg.getBoundingClientRect();
+    scale = Math.max(scale, 1);
+    panX = area.width / 2 - (x[node] + width[node] / 2) * scale;
+    panY = area.height / 2 - (y[node] + 14) * scale;
+    apply();
+  }
+  svg.addEventListener("wheel", event => {
+    event.preventDefault();
+    const area = svg.getBoundingClientRect();
+    const factor = Math.exp(-event.deltaY * 0.0015);
+    const pointX = event.clientX - area.left, pointY = event.clientY - area.top;
+    panX = pointX - (pointX - panX) * factor;
+    panY = pointY - (pointY - panY) * factor;
+    scale *= factor;
+    apply();
+  }, { passive: false });
+  let drag = null;
+  svg.addEventListener("mousedown", event => {
+    drag = { x: event.clientX - panX, y: event.clientY - panY, moved: false };
+    svg.classList.add("dragging");
+  });
+  window.addEventListener("mousemove", event => {
+    if (!drag) return;
+    drag.moved = true;
+    panX = event.clientX - drag.x;
+    panY = event.clientY - drag.y;
+    apply();
+  });
+  window.addEventListener("mouseup", () => { svg.classList.remove("dragging"); });
+  svg.addEventListener("click", () => {
+    if (drag && !drag.moved) select(null);
+    drag = null;
+  });
+  document.getElementById("fit").addEventListener("click", fit);
+
+  // Selection and details
+  const panel = document.getElementById("panel");
+  function add(parent, name, text) {
+    const created = document.createElement(name);
+    if (text !== undefined) created.textContent = text;
+    parent.appendChild(created);
+    return created;
+  }
+  function links(title, related) {
+    if (!related.length) return;
+    add(panel, "h3", title);
+    const list = add(panel, "ul");
+    for (const node of related) {
+      const link = add(add(list, "li"), "a", nodes[node].name);
+      link.addEventListener("click", () => { select(node); centre(node); });
+    }
+  }
+  function select(selected) {
+    nodeElements.forEach((group, index) => {
+      const related = selected === null || index === selected
+        || callees[selected].some(edge => edge.to === index)
+        || callers[selected].some(edge => edge.from === index);
+      group.classList.toggle("faded", !related);
+      group.classList.toggle("selected", index === selected);
+    });
+    edgeElements.forEach((path, index) => {
+      const edge = edges[index];
+      const active = selected !== null && (edge.from === selected || edge.to === selected);
+      path.classList.toggle("active", active);
+      path.classList.toggle("faded", selected !== null && !active);
+    });
+    panel.textContent = "";
+    if (selected === null) {
+      add(panel, "p", "Click a function to see its details.");
+      return;
+    }
+    const node = nodes[selected];
+    add(panel, "h2", node.name);
+    if (!node.defined) add(panel, "p", "Called but not defined in these files.");
+    if (node.signature) add(panel, "pre", node.signature);
+    if (node.source_file) add(panel, "p", node.source_file);
+    if (node.namespace) add(panel, "p", "Namespace: " + node.namespace);
+    if (node.cyclic) add(panel, "p", "Part of a call cycle.");
+    if (node.doc) add(panel, "p", node.doc);
+    if (node.explanation) add(panel, "p", node.explanation);
+    links("Calls", [...new Set(callees[selected].map(edge => edge.to))]);
+    links("Called by", [...new Set(callers[selected].map(edge => edge.from))]);
+    if (node.code_block) {
+      add(panel, "h3", "Body");
+      add(panel, "pre", node.code_block);
+    }
+  }
+
+  const search = document.getElementById("search");
+  search.addEventListener("input", () => {
+    const text = search.value.trim().toLowerCase();
+    nodeElements.forEach((group, index) => group.classList.toggle("match",
+      text !== "" && nodes[index].name.toLowerCase().includes(text)));
+  });
+  search.addEventListener("keydown", event => {
+    if (event.key !== "Enter") return;
+    const text = search.value.trim().toLowerCase();
+    const found = nodes.findIndex(node => node.name.toLowerCase().includes(text));
+    if (text !== "" && found >= 0) { select(found); centre(found); }
+  });
+
+  fit();
+})();
+</script>
+</body>
+</html>
+"##;
@@ -0,0 +1,280 @@
+//! Running functions on a TypeDB server and checking their answers (requires the `client`
+//! feature)
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use typedb_driver::concept::{Concept, Value as ConceptValue};
+use typedb_driver::{TransactionType, TypeDBDriver};
+
+use crate::codegen::{value_kind, Binding, QueryPart, ValueKind};
+use crate::data::quote;
+use crate::error::ExtractError;
+use crate::extract_function_metadata::FunctionMetadata;
+
+/// A call of a function with sample arguments and the answers it should give
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionTest {
+    /// Name to report the test by; defaults to the function name
+    #[serde(default)]
+    pub name: Option<String>,
+    pub function: String,
+    /// Argument by parameter name without `$`. Instances of schema types are IIDs, and
+    /// decimals, dates, datetimes and durations TypeQL literals, both as strings.
+    #[serde(default)]
+    pub arguments: BTreeMap<String, Value>,
+    /// Expected answers, each a value by returned variable without `$`. Instances are
+    /// compared by IID.
+    pub expected: Vec<BTreeMap<String, Value>>,
+    /// Whether the answers must come in the expected order
+    #[serde(default)]
+    pub ordered: bool,
+}
+
+impl FunctionTest {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.function)
+    }
+}
+
+/// Whether a [`FunctionTest`] passed, and the answers the server gave
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestOutcome {
+    pub name: String,
+    pub passed: bool,
+    pub actual: Vec<BTreeMap<String, Value>>,
+    /// Why the test failed without answers, e.g. a query error
+    pub error: Option<String>,
+}
+
+impl fmt::Display for TestOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.passed {
+            return write!(f, "PASS {}", self.name);
+        }
+        match &self.error {
+            Some(error) => write!(f, "FAIL {}: {}", self.name, error),
+            None => {
+                let actual = serde_json::to_string(&self.actual).map_err(|_| fmt::Error)?;
+                write!(f, "FAIL {}: got {}", self.name, actual)
+            }
+        }
+    }
+}
+
+/// The read query calling `function` with `arguments`, by parameter name without `$`.
+///
+/// Missing arguments and values that do not fit their parameter fail with
+/// [`ExtractError::InvalidData`].
+pub fn call_query(
+    function: &FunctionMetadata,
+    arguments: &BTreeMap<String, Value>,
+) -> Result<String, ExtractError> {
+    let binding = Binding::new(function);
+    let mut query = String::new();
+    for part in binding.query() {
+        match part {
+            QueryPart::Text(text) => query.push_str(&text),
+            QueryPart::Argument(position) => {
+                let parameter = &binding.parameters[position];
+                let Some(value) = arguments.get(&parameter.var) else {
+                    return Err(ExtractError::InvalidData {
+                        message: format!("missing argument for ${}", parameter.var),
+                    });
+                };
+                let kind = value_kind(&parameter.type_name);
+                let literal = match value {
+                    Value::Array(values) if kind != ValueKind::Literal => values
+                        .iter()
+                        .map(|value| argument(kind, value))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map(|values| format!("[{}]", values.join(", "))),
+                    value => argument(kind, value),
+                }
+                .map_err(|message| ExtractError::InvalidData {
+                    message: format!("argument ${}: {}", parameter.var, message),
+                })?;
+                query.push_str(&literal);
+            }
+        }
+    }
+    Ok(query)
+}
+
+/// `value` as TypeQL for a parameter of this kind
+fn argument(kind: ValueKind, value: &Value) -> Result<String, String> {
+    let literal = match (kind, value) {
+        (ValueKind::Boolean, Value::Bool(boolean)) => boolean.to_string(),
+        (ValueKind::Integer, Value::Number(number)) if number.is_i64() => number.to_string(),
+        (ValueKind::Double, Value::Number(number)) => format!("{:?}", number.as_f64().unwrap()),
+        (ValueKind::String, Value::String(text)) => quote(text),
+        (ValueKind::Literal, Value::String(text)) => text.clone(),
+        (ValueKind::Literal, Value::Number(number)) => number.to_string(),
+        (ValueKind::Instance, Value::String(iid))
+            if iid.len() > 2
+                && iid.starts_with("0x")
+                && iid[2..].chars().all(|c| c.is_ascii_hexdigit()) =>
+        {
+            iid.clone()
+        }
+        (ValueKind::Instance, _) => {
+            return Err(format!(
+                "expected an IID such as \"0x1e00...\", got {}",
+                value
+            ))
+        }
+        _ => return Err(format!("unexpected value {}", value)),
+    };
+    Ok(literal)
+}
+
+/// Call the function deployed to `database` as `test` describes, in a read transaction,
+/// and compare its answers with those expected.
+///
+/// The function runs as deployed, so [`sync_functions`] first to test local changes.
+/// Query errors fail the test; only connection errors are returned as `Err`.
+///
+/// [`sync_functions`]: crate::client::sync_functions
+pub async fn run_function_test(
+    driver: &TypeDBDriver,
+    database: &str,
+    function: &FunctionMetadata,
+    test: &FunctionTest,
+) -> Result<TestOutcome, ExtractError> {
+    let failed = |error: String| TestOutcome {
+        name: test.name().to_string(),
+        passed: false,
+        actual: Vec::new(),
+        error: Some(error),
+    };
+    let query = match call_query(function, &test.arguments) {
+        Ok(query) => query,
+        Err(error) => return Ok(failed(error.to_string())),
+    };
+
+    let transaction = driver.transaction(database, TransactionType::Read).await?;
+    let answer = match transaction.query(&query).await {
+        Ok(answer) => answer,
+        Err(error) => return Ok(failed(error.to_string())),
+    };
+    let columns: Vec<String> = Binding::new(function)
+        .columns
+        .into_iter()
+        .map(|column| column.var)
+        .collect();
+    let mut rows = answer.into_rows();
+    let mut actual = Vec::new();
+    while let Some(row) = rows.next().await {
+        let row = match row {
+            Ok(row) => row,
+            Err(error) => return Ok(failed(error.to_string())),
+        };
+        let mut values = BTreeMap::new();
+        for column in &columns {
+            let value = match row.get(column) {
+                Ok(concept) => concept.map_or(Value::Null, to_json),
+                Err(error) => return Ok(failed(error.to_string())),
+            };
+            values.insert(column.clone(), value);
+        }
+        actual.push(values);
+    }
+
+    Ok(TestOutcome {
+        name: test.name().to_string(),
+        passed: answers_match(&test.expected, &actual, test.ordered),
+        actual,
+        error: None,
+    })
+}
+
+/// Run every test against the function it names in `functions`
+pub async fn run_function_tests(
+    driver: &TypeDBDriver,
+    database: &str,
+    functions: &[FunctionMetadata],
+    tests: &[FunctionTest],
+) -> Result<Vec<TestOutcome>, ExtractError> {
+    let mut outcomes = Vec::new();
+    for test in tests {
+        let Some(function) = functions
+            .iter()
+            .find(|function| function.name == test.function)
+        else {
+            outcomes.push(TestOutcome {
+                name: test.name().to_string(),
+                passed: false,
+                actual: Vec::new(),
+                error: Some(format!("no function `{}`", test.function)),
+            });
+            continue;
+        };
+        outcomes.push(run_function_test(driver, database, function, test).await?);
+    }
+    Ok(outcomes)
+}
+
+/// A value as JSON; instances become their IIDs, and values without a JSON counterpart
+/// their TypeQL text
+fn to_json(concept: &Concept) -> Value {
+    if let Some(value) = concept.try_get_value() {
+        return match value {
+            ConceptValue::Boolean(boolean) => Value::Bool(*boolean),
+            ConceptValue::Integer(integer) => Value::from(*integer),
+            ConceptValue::Double(double) => {
+                Number::from_f64(*double).map_or(Value::Null, Value::Number)
+            }
+            ConceptValue::String(text) => Value::String(text.clone()),
+            other => Value::String(other.to_string()),
+        };
+    }
+    match concept.try_get_iid() {
+        Some(iid) => Value::String(iid.to_string()),
+        None => Value::Null,
+    }
+}
+
+fn answers_match(
+    expected: &[BTreeMap<String, Value>],
+    actual: &[BTreeMap<String, Value>],
+    ordered: bool,
+) -> bool {
+    if expected.len() != actual.len() {
+        return false;
+    }
+    if ordered {
+        return expected
+            .iter()
+            .zip(actual)
+            .all(|(expected, actual)| row_matches(expected, actual));
+    }
+    let mut unmatched: Vec<&BTreeMap<String, Value>> = actual.iter().collect();
+    expected.iter().all(|expected| {
+        match unmatched
+            .iter()
+            .position(|actual| row_matches(expected, actual))
+        {
+            Some(position) => {
+                unmatched.swap_remove(position);
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// Whether every expected column has the expected value; doubles match to within a
+/// relative 1e-9
+fn row_matches(expected: &BTreeMap<String, Value>, actual: &BTreeMap<String, Value>) -> bool {
+    expected.iter().all(|(column, expected)| {
+        let actual = actual.get(column).unwrap_or(&Value::Null);
+        match (expected.as_f64(), actual.as_f64()) {
+            (Some(expected), Some(actual)) => {
+                (expected - actual).abs() <= 1e-9 * expected.abs().max(actual.abs()).max(1.0)
+            }
+            _ => expected == actual,
+        }
+    })
+}
@@ -0,0 +1,91 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::ExtractError;
+use crate::extract_function_metadata::{extract_functions_in, FunctionMetadata};
+
+/// Extract every function from the files named by `patterns`.
+///
+/// Files are found as by [`expand_paths`] and read in path order. Every function records
+/// its `source_file` alongside spans holding byte offsets into that file.
+pub fn extract_from_paths(patterns: &[&str]) -> Result<Vec<FunctionMetadata>, ExtractError> {
+    let mut functions = Vec::new();
+    for file in expand_paths(patterns)? {
+        functions.extend(extract_from_file(&file)?);
+    }
+    Ok(functions)
+}
+
+/// The TypeQL files named by `patterns`, sorted and without duplicates.
+///
+/// Each pattern is a file, a directory or a glob such as `schemas/**/*.tql`. Directories,
+/// including those matched by a glob, are searched recursively for `.tql` and `.tqls`
+/// files. A pattern that matches nothing is an error.
+pub fn expand_paths(patterns: &[&str]) -> Result<Vec<PathBuf>, ExtractError> {
+    let mut files = BTreeSet::new();
+    for pattern in patterns {
+        let matches: Vec<PathBuf> = glob::glob(pattern)
+            .map_err(|error| ExtractError::InvalidPattern {
+                pattern: pattern.to_string(),
+                message: error.to_string(),
+            })?
+            .map(|entry| {
+                entry.map_err(|error| ExtractError::io(error.path().to_path_buf(), error.into()))
+            })
+            .collect::<Result<_, _>>()?;
+        if matches.is_empty() {
+            return Err(ExtractError::NoMatches {
+                pattern: pattern.to_string(),
+            });
+        }
+        for path in matches {
+            files.extend(typeql_files(&path)?);
+        }
+    }
+    Ok(files.into_iter().collect())
+}
+
+/// Extract every function in the file at `path`, recording it as their `source_file`.
+///
+/// A parse error is reported with the file name and the offending line, in
+/// [`ExtractError::ParseError`].
+pub fn extract_from_file(path: &Path) -> Result<Vec<FunctionMetadata>, ExtractError> {
+    let source = fs::read_to_string(path).map_err(|error| ExtractError::io(path, error))?;
+    let source_file = path.display().to_string();
+    let mut functions = extract_functions_in(&source)
+        .map_err(|diagnostic| ExtractError::from_diagnostic(diagnostic, &source_file, &source))?;
+    for function in &mut functions {
+        function.source_file = Some(source_file.clone());
+    }
+    Ok(functions)
+}
+
+/// `path` itself if it is a file, otherwise every `.tql` and `.tqls` file below it, sorted
+pub fn typeql_files(path: &Path) -> Result<Vec<PathBuf>, ExtractError> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = Vec::new();
+    let mut directories = vec![path.to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        let entries =
+            fs::read_dir(&directory).map_err(|error| ExtractError::io(&directory, error))?;
+        for entry in entries {
+            let entry_path = entry
+                .map_err(|error| ExtractError::io(&directory, error))?
+                .path();
+            if entry_path.is_dir() {
+                directories.push(entry_path);
+            } else if entry_path
+                .extension()
+                .is_some_and(|extension| extension == "tql" || extension == "tqls")
+            {
+                files.push(entry_path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
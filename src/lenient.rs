@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+use crate::diagnostic::Diagnostic;
+use crate::extract_function_metadata::{extract_function_at, FunctionMetadata};
+use crate::lexer::{function_ranges, tokenize, TokenKind};
+use crate::span::Span;
+
+/// Outcome of lenient extraction: full metadata when the function parses, otherwise
+/// whatever could be recovered from the raw text
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Extraction {
+    Complete(Box<FunctionMetadata>),
+    Partial(PartialFunctionMetadata),
+}
+
+/// Pieces of a function definition recovered without a successful parse
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PartialFunctionMetadata {
+    pub name: Option<String>,
+    /// Text between the signature's parentheses, e.g. `$x: person, $y: double`
+    pub raw_parameters: Option<String>,
+    /// Text between `->` and the signature's closing `:`
+    pub raw_output: Option<String>,
+    /// Everything after the signature
+    pub raw_body: Option<String>,
+    pub span: Span,
+    /// Why the full parse failed, plus anything that could not be recovered
+    pub errors: Vec<Diagnostic>,
+}
+
+impl Extraction {
+    pub fn metadata(&self) -> Option<&FunctionMetadata> {
+        match self {
+            Extraction::Complete(metadata) => Some(metadata),
+            Extraction::Partial(_) => None,
+        }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Extraction::Complete(metadata) => Some(&metadata.name),
+            Extraction::Partial(partial) => partial.name.as_deref(),
+        }
+    }
+
+    pub fn errors(&self) -> &[Diagnostic] {
+        match self {
+            Extraction::Complete(_) => &[],
+            Extraction::Partial(partial) => &partial.errors,
+        }
+    }
+}
+
+/// Like [`extract_function_metadata`](crate::extract_function_metadata), but never fails:
+/// when the function does not parse, the name, parameters, output and body are recovered
+/// from the raw text alongside the parse error.
+pub fn extract_function_metadata_lenient(function_text: &str) -> Extraction {
+    extract_lenient_at(function_text, 0..function_text.len())
+}
+
+/// Lenient extraction of every function in a source file; one broken definition does not
+/// prevent the others from being extracted.
+pub fn extract_functions_from_source_lenient(source: &str) -> Vec<Extraction> {
+    function_ranges(source)
+        .into_iter()
+        .map(|range| extract_lenient_at(source, range))
+        .collect()
+}
+
+fn extract_lenient_at(source: &str, range: Range<usize>) -> Extraction {
+    match extract_function_at(source, range.clone()) {
+        Ok(metadata) => Extraction::Complete(Box::new(metadata)),
+        Err(diagnostic) => Extraction::Partial(recover(source, range, diagnostic)),
+    }
+}
+
+fn recover(source: &str, range: Range<usize>, parse_error: Diagnostic) -> PartialFunctionMetadata {
+    let text = &source[range.clone()];
+    let tokens: Vec<_> = tokenize(text)
+        .into_iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .collect();
+    let mut partial = PartialFunctionMetadata {
+        name: None,
+        raw_parameters: None,
+        raw_output: None,
+        raw_body: None,
+        span: Span::new(source, range.start, range.end),
+        errors: vec![parse_error],
+    };
+
+    let fun = tokens.iter().position(|token| token.is_word("fun"));
+    let name = fun
+        .and_then(|fun| tokens.get(fun + 1))
+        .filter(|token| token.kind == TokenKind::Word);
+    partial.name = name.map(|token| token.text.to_string());
+    let name = match name {
+        Some(_) => fun.unwrap_or(0) + 1,
+        None => {
+            partial.errors.push(recovery_error(
+                source,
+                &range,
+                "could not find the function name",
+            ));
+            return partial;
+        }
+    };
+
+    // Parameters run from the opening parenthesis to its matching close
+    let open = tokens[name..]
+        .iter()
+        .position(|token| token.is_punct("("))
+        .map(|pos| name + pos);
+    let close = open.and_then(|open| {
+        let mut depth = 0;
+        tokens[open..]
+            .iter()
+            .position(|token| {
+                if token.is_punct("(") {
+                    depth += 1;
+                } else if token.is_punct(")") {
+                    depth -= 1;
+                }
+                depth == 0
+            })
+            .map(|pos| open + pos)
+    });
+    let (open, close) = match (open, close) {
+        (Some(open), Some(close)) => (open, close),
+        _ => {
+            partial.errors.push(recovery_error(
+                source,
+                &range,
+                "could not find the parameter list",
+            ));
+            return partial;
+        }
+    };
+    partial.raw_parameters = Some(
+        text[tokens[open].range.end..tokens[close].range.start]
+            .trim()
+            .to_string(),
+    );
+
+    // The signature ends at the first `:` after the parameters; output types may not be scoped
+    let colon = tokens[close..]
+        .iter()
+        .position(|token| token.is_punct(":"))
+        .map(|pos| close + pos);
+    match colon {
+        Some(colon) => {
+            let output = text[tokens[close].range.end..tokens[colon].range.start].trim();
+            partial.raw_output = Some(output.trim_start_matches("->").trim().to_string());
+            partial.raw_body = Some(text[tokens[colon].range.end..].trim().to_string());
+        }
+        None => partial.errors.push(recovery_error(
+            source,
+            &range,
+            "could not find the end of the signature",
+        )),
+    }
+
+    partial
+}
+
+fn recovery_error(source: &str, range: &Range<usize>, message: &str) -> Diagnostic {
+    Diagnostic::warning("partial-extraction", message).with_span(Some(Span::new(
+        source,
+        range.start,
+        range.end,
+    )))
+}
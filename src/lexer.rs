@@ -0,0 +1,176 @@
+//! Minimal TypeQL tokenizer used for source-level operations that the typeql
+//! parser does not cover, such as splitting files into definitions.
+
+use std::iter::Peekable;
+use std::ops::Range;
+use std::str::CharIndices;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    /// Keywords, labels and numbers, e.g. `match`, `tax_year`, `0.25`
+    Word,
+    /// `$name` (the range includes the `$`)
+    Variable,
+    /// Single or double quoted string literal, quotes included
+    String,
+    /// `#` comment up to (not including) the end of line
+    Comment,
+    /// Any other single character, e.g. `;`, `(`, `,`
+    Punct,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub range: Range<usize>,
+}
+
+impl Token<'_> {
+    pub fn is_word(&self, word: &str) -> bool {
+        self.kind == TokenKind::Word && self.text == word
+    }
+
+    pub fn is_punct(&self, punct: &str) -> bool {
+        self.kind == TokenKind::Punct && self.text == punct
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-' || c == '.'
+}
+
+/// Split TypeQL source into tokens, skipping whitespace
+pub(crate) fn tokenize(source: &str) -> Vec<Token<'_>> {
+    tokens(source).collect()
+}
+
+/// Lazily split TypeQL source into tokens, for callers that only need a prefix
+pub(crate) fn tokens(source: &str) -> Tokens<'_> {
+    Tokens {
+        source,
+        chars: source.char_indices().peekable(),
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Tokens<'a> {
+    source: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let chars = &mut self.chars;
+        let (start, c) = loop {
+            let (start, c) = chars.next()?;
+            if !c.is_whitespace() {
+                break (start, c);
+            }
+        };
+
+        let kind = if c == '#' {
+            while chars.next_if(|&(_, next)| next != '\n').is_some() {}
+            TokenKind::Comment
+        } else if c == '"' || c == '\'' {
+            let mut escaped = false;
+            for (_, next) in chars.by_ref() {
+                if escaped {
+                    escaped = false;
+                } else if next == '\\' {
+                    escaped = true;
+                } else if next == c {
+                    break;
+                }
+            }
+            TokenKind::String
+        } else if c == '$' {
+            while chars.next_if(|&(_, next)| is_word_char(next)).is_some() {}
+            TokenKind::Variable
+        } else if is_word_char(c) {
+            while chars.next_if(|&(_, next)| is_word_char(next)).is_some() {}
+            TokenKind::Word
+        } else {
+            TokenKind::Punct
+        };
+
+        let end = chars.peek().map_or(self.source.len(), |&(pos, _)| pos);
+        Some(Token {
+            kind,
+            text: &self.source[start..end],
+            range: start..end,
+        })
+    }
+}
+
+/// Byte ranges of every `fun ... return ...;` definition in `source`
+pub(crate) fn function_ranges(source: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+    let mut in_return = false;
+
+    for token in tokenize(source) {
+        match start {
+            None if token.is_word("fun") => start = Some(token.range.start),
+            Some(begin) if token.is_word("fun") => {
+                // A new definition started before the previous one returned
+                ranges.push(begin..token.range.start);
+                start = Some(token.range.start);
+                in_return = false;
+            }
+            Some(_) if token.is_word("return") => in_return = true,
+            Some(begin) if in_return && token.is_punct(";") => {
+                ranges.push(begin..token.range.end);
+                start = None;
+                in_return = false;
+            }
+            _ => {}
+        }
+    }
+
+    // Leave an unterminated trailing definition to the parser to report
+    if let Some(begin) = start {
+        ranges.push(begin..source.len());
+    }
+
+    ranges
+}
+
+/// Position of the first body token of a `fun` definition in `tokens`, comments left out:
+/// after the `:` that follows the parameter list
+pub(crate) fn body_start(tokens: &[Token]) -> Option<usize> {
+    let close = tokens.iter().position(|token| token.is_punct(")"))?;
+    let colon = tokens[close..]
+        .iter()
+        .position(|token| token.is_punct(":"))?;
+    Some(close + colon + 1)
+}
+
+/// Byte ranges of every `define` block, each running up to the next schema query keyword
+pub(crate) fn define_ranges(source: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+
+    for token in tokenize(source) {
+        let is_schema_keyword = ["define", "redefine", "undefine"]
+            .iter()
+            .any(|keyword| token.is_word(keyword));
+        if !is_schema_keyword {
+            continue;
+        }
+        if let Some(begin) = start.take() {
+            ranges.push(begin..token.range.start);
+        }
+        if token.is_word("define") {
+            start = Some(token.range.start);
+        }
+    }
+
+    if let Some(begin) = start {
+        ranges.push(begin..source.len());
+    }
+
+    ranges
+}
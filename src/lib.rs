@@ -1,7 +1,255 @@
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary_function;
+#[cfg(feature = "parquet")]
+pub mod arrow_export;
+pub mod baseline;
+pub mod body;
+pub mod builder;
+pub mod call_graph;
+pub mod catalog;
+pub mod codegen;
+pub mod convert_rules;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod data;
+pub mod diagnostic;
+pub mod dialect;
+pub mod diff;
+pub mod doc_comment;
+pub mod duplicates;
+pub mod docs;
+pub mod error;
+pub mod expression;
+pub mod explain;
+pub mod export;
 pub mod extract_function_metadata;
+pub mod extract_schema_metadata;
+pub mod extractor;
+pub mod fetch;
+pub mod format;
+pub mod generate;
+pub mod graph_html;
+#[cfg(feature = "client")]
+pub mod harness;
+pub mod ingest;
+pub mod lenient;
+mod lexer;
+pub mod lint;
+pub mod literals;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+#[cfg(feature = "mcp")]
+pub mod mcp;
+pub mod metadata_json;
+pub mod naming;
+pub mod namespace;
+#[cfg(feature = "napi")]
+pub mod node;
+pub mod normalize;
+#[cfg(feature = "pyo3")]
+mod python;
+pub mod refactor;
+pub mod query;
+pub mod recursion;
+pub mod registry;
+pub mod repl;
+pub mod sarif;
+pub mod schema_diagram;
+pub mod search;
+pub mod semantic;
+pub mod signature;
+pub mod snapshot;
+pub mod span;
+pub mod stats;
+#[cfg(feature = "sqlite")]
+pub mod store;
+pub mod sync;
+pub mod synthetic;
+pub mod validate;
+pub mod variables;
+pub mod visitor;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+pub mod watch;
 
 pub use extract_function_metadata::{
     extract_function_metadata,
+    extract_functions_from_source,
+    CallSite,
     FunctionMetadata,
+    FunctionSpans,
+    OutputType,
     Parameter,
-};
\ No newline at end of file
+    Reduction,
+    ReturnExpr,
+    Selector,
+    TypeKind,
+    TypeName,
+};
+#[cfg(feature = "arbitrary")]
+pub use arbitrary_function::ArbitraryFunction;
+#[cfg(feature = "parquet")]
+pub use arrow_export::{function_schema, to_record_batch, write_parquet};
+pub use baseline::{Baseline, BaselineEntry};
+pub use body::{BodyPattern, Constraint, ConstraintKind, RolePlayer};
+pub use builder::FunctionMetadataBuilder;
+pub use call_graph::CallGraph;
+pub use catalog::{catalog_inserts, CATALOG_SCHEMA};
+pub use codegen::{
+    render_graphql_schema,
+    render_python_client,
+    render_rust_client,
+    render_rust_types,
+    render_typescript_definitions,
+};
+#[cfg(feature = "client")]
+pub use client::{
+    check_drift,
+    connect,
+    fetch_functions,
+    fetch_schema,
+    store_catalog,
+    sync_functions,
+    ServerConfig,
+};
+pub use data::{generate_inserts, CollectionMapping, DataMapping, RoleMapping};
+pub use convert_rules::{convert_rule, convert_rules, RuleConversion};
+pub use diagnostic::{Diagnostic, Severity};
+pub use dialect::{check_dialect, detect_dialect, extract_rules, Dialect, RuleMetadata};
+pub use diff::{
+    diff_functions,
+    diff_schemas,
+    DeclarationChange,
+    FunctionDiff,
+    ImpactedFunction,
+    OutputChange,
+    ParameterSummary,
+    RetypedParameter,
+    SchemaDiff,
+    TypeChange,
+};
+pub use doc_comment::DocTag;
+pub use duplicates::{
+    find_duplicates,
+    DuplicateReport,
+    SimilarPair,
+    DEFAULT_SIMILARITY_THRESHOLD,
+};
+pub use docs::{render_markdown, render_markdown_with_namespaces};
+pub use error::{ExtractError, SourceDiagnostic};
+pub use expression::Expr;
+pub use explain::explain;
+pub use export::render_csv;
+pub use fetch::{FetchEntry, FetchValue};
+pub use format::{format_function, format_functions_in_source};
+pub use generate::{bundle, tree_shake};
+pub use graph_html::{render_call_graph_html, render_call_graph_html_with_namespaces};
+#[cfg(feature = "client")]
+pub use harness::{call_query, run_function_test, run_function_tests, FunctionTest, TestOutcome};
+pub use ingest::{expand_paths, extract_from_file, extract_from_paths, typeql_files};
+pub use lenient::{
+    extract_function_metadata_lenient,
+    extract_functions_from_source_lenient,
+    Extraction,
+    PartialFunctionMetadata,
+};
+pub use lint::{
+    check_deprecated_calls,
+    check_missing_returns,
+    check_unbound_variables,
+    check_unused_parameters,
+    ArgumentTypes,
+    CallArity,
+    DeprecatedCalls,
+    IncompatibleTypes,
+    LintConfig,
+    Linter,
+    MissingReturn,
+    Rule,
+    UnboundVariables,
+    UndefinedCalls,
+    UnknownTypes,
+    UnusedParameters,
+};
+pub use literals::{extract_literals, Literal, LiteralKind};
+pub use metadata_json::{
+    from_metadata_json,
+    to_metadata_json,
+    write_json_line,
+    MetadataDocument,
+    METADATA_VERSION,
+};
+pub use naming::{
+    check_function_name_case,
+    check_function_name_verbs,
+    check_parameter_names,
+    FunctionNameCase,
+    FunctionNameVerb,
+    ParameterNameType,
+    DEFAULT_VERB_PREFIXES,
+};
+pub use namespace::{NamespaceConfig, Namespaces};
+pub use normalize::{normalize, NormalizedFunction};
+pub use query::{extract_query_metadata, QueryMetadata, StageKind};
+pub use recursion::{recursion_report, RecursiveGroup};
+pub use refactor::{
+    inline_function,
+    literal_to_parameter,
+    rename_function,
+    rename_type,
+    type_impact,
+    CallSiteUpdate,
+    ParameterizedFunction,
+    TypeImpact,
+};
+pub use registry::{FunctionRegistry, SourceFile};
+pub use sarif::render_sarif;
+pub use search::SearchHit;
+pub use semantic::{embedding_text, CachedEmbedder, Embedder};
+pub use signature::{extract_signature, FunctionSignature};
+pub use snapshot::{
+    check_snapshot,
+    line_diff,
+    snapshot_text,
+    SnapshotMode,
+    SnapshotOutcome,
+    UPDATE_SNAPSHOTS_VAR,
+};
+pub use span::Span;
+pub use stats::{
+    extract_function_metadata_with_stats,
+    extract_functions_from_source_with_stats,
+    ExtractionStats,
+};
+#[cfg(feature = "sqlite")]
+pub use store::{FunctionStore, RefreshSummary};
+pub use synthetic::{generate_synthetic_data, SyntheticConfig, ValueRange};
+pub use sync::{
+    detect_drift,
+    plan_migration,
+    plan_sync,
+    DriftReport,
+    SyncAction,
+    SyncMode,
+    SyncPlan,
+    SyncStatement,
+};
+pub use extract_schema_metadata::{
+    extract_schema_metadata,
+    Cardinality,
+    SchemaKind,
+    SchemaMetadata,
+    TypeDefinition,
+};
+pub use extractor::{DocTagExtractor, Extractor, ExtractorPipeline, Variables};
+pub use validate::{
+    check_argument_types,
+    check_call_arity,
+    check_function_references,
+    check_type_compatibility,
+    validate_functions,
+    validate_functions_against_labels,
+};
+pub use variables::{VariableInfo, VariableRole};
+pub use visitor::{walk, walk_all, FunctionVisitor};
+pub use watch::{watch, FileChange, WatchEvent, Watcher};
@@ -0,0 +1,436 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::body::ConstraintKind;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::extract_function_metadata::FunctionMetadata;
+use crate::extract_schema_metadata::SchemaMetadata;
+use crate::lexer::{tokenize, TokenKind};
+use crate::naming::{FunctionNameCase, FunctionNameVerb, ParameterNameType};
+use crate::validate::{
+    check_argument_types, check_call_arity, check_function_references, check_type_compatibility,
+    known_labels, validate_functions_against_labels,
+};
+use crate::visitor::{walk_all, FunctionVisitor};
+
+/// A static check over a function library, run by a [`Linter`]
+pub trait Rule {
+    /// Stable identifier, used as the code of the rule's diagnostics and in [`LintConfig`]
+    fn code(&self) -> &'static str;
+
+    /// Check every function, reporting problems at the rule's default severity
+    fn check(&self, functions: &[FunctionMetadata]) -> Vec<Diagnostic>;
+}
+
+/// Which rules a [`Linter`] runs and how severe their findings are
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintConfig {
+    /// Codes of rules to skip
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    /// Severity to report instead of a rule's default, by rule code
+    #[serde(default)]
+    pub severities: BTreeMap<String, Severity>,
+    /// Prefixes accepted by the `function-name-verb` rule
+    #[serde(default = "default_verb_prefixes")]
+    pub verb_prefixes: Vec<String>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        LintConfig {
+            disabled: Vec::new(),
+            severities: BTreeMap::new(),
+            verb_prefixes: default_verb_prefixes(),
+        }
+    }
+}
+
+fn default_verb_prefixes() -> Vec<String> {
+    FunctionNameVerb::default().prefixes
+}
+
+impl LintConfig {
+    pub fn disable(mut self, code: &str) -> Self {
+        self.disabled.push(code.to_string());
+        self
+    }
+
+    pub fn with_severity(mut self, code: &str, severity: Severity) -> Self {
+        self.severities.insert(code.to_string(), severity);
+        self
+    }
+
+    pub fn is_enabled(&self, code: &str) -> bool {
+        !self.disabled.iter().any(|disabled| disabled == code)
+    }
+}
+
+/// Runs a set of [`Rule`]s over a function library according to a [`LintConfig`]
+pub struct Linter {
+    rules: Vec<Box<dyn Rule>>,
+    config: LintConfig,
+}
+
+impl Linter {
+    /// A linter with the default rules: undefined calls, calls with the wrong number or
+    /// type of arguments, unbound variables, missing return values, unused parameters,
+    /// calls to deprecated functions and the naming conventions.
+    ///
+    /// `allowed` lists functions defined elsewhere that may be called.
+    pub fn new(config: LintConfig, allowed: &[&str]) -> Self {
+        Linter {
+            rules: vec![
+                Box::new(UndefinedCalls {
+                    allowed: allowed.iter().map(|name| name.to_string()).collect(),
+                }),
+                Box::new(CallArity),
+                Box::new(ArgumentTypes),
+                Box::new(UnboundVariables),
+                Box::new(MissingReturn),
+                Box::new(UnusedParameters),
+                Box::new(DeprecatedCalls),
+                Box::new(FunctionNameCase),
+                Box::new(FunctionNameVerb {
+                    prefixes: config.verb_prefixes.clone(),
+                }),
+                Box::new(ParameterNameType),
+            ],
+            config,
+        }
+    }
+
+    /// A linter that runs no rules until some are added
+    pub fn empty(config: LintConfig) -> Self {
+        Linter {
+            rules: Vec::new(),
+            config,
+        }
+    }
+
+    pub fn with_rule(mut self, rule: impl Rule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// Codes of the rules this linter knows, enabled or not
+    pub fn rule_codes(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.rules.iter().map(|rule| rule.code())
+    }
+
+    /// Run every enabled rule, in the order they were added.
+    ///
+    /// Findings in a function with a `# typedb-meta: ignore <rule>` comment for their rule
+    /// are kept but marked suppressed.
+    pub fn run(&self, functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for rule in &self.rules {
+            let code = rule.code();
+            if !self.config.is_enabled(code) {
+                continue;
+            }
+            let severity = self.config.severities.get(code).copied();
+            diagnostics.extend(rule.check(functions).into_iter().map(|mut diagnostic| {
+                if let Some(severity) = severity {
+                    diagnostic.severity = severity;
+                }
+                diagnostic.suppressed = is_suppressed(functions, &diagnostic);
+                diagnostic
+            }));
+        }
+        diagnostics
+    }
+}
+
+/// Whether the function the diagnostic is about ignores its rule
+fn is_suppressed(functions: &[FunctionMetadata], diagnostic: &Diagnostic) -> bool {
+    diagnostic.function.as_ref().is_some_and(|name| {
+        functions
+            .iter()
+            .any(|function| &function.name == name && function.ignores_rule(&diagnostic.code))
+    })
+}
+
+/// `undefined-function`: calls to functions that are not defined, built in or allowed
+pub struct UndefinedCalls {
+    pub allowed: Vec<String>,
+}
+
+impl Rule for UndefinedCalls {
+    fn code(&self) -> &'static str {
+        "undefined-function"
+    }
+
+    fn check(&self, functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+        let allowed: Vec<&str> = self.allowed.iter().map(String::as_str).collect();
+        check_function_references(functions, &allowed)
+    }
+}
+
+/// `call-arity`: see [`check_call_arity`]
+pub struct CallArity;
+
+impl Rule for CallArity {
+    fn code(&self) -> &'static str {
+        "call-arity"
+    }
+
+    fn check(&self, functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+        check_call_arity(functions)
+    }
+}
+
+/// `argument-type`: see [`check_argument_types`]
+pub struct ArgumentTypes;
+
+impl Rule for ArgumentTypes {
+    fn code(&self) -> &'static str {
+        "argument-type"
+    }
+
+    fn check(&self, functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+        check_argument_types(functions)
+    }
+}
+
+/// `unknown-type`: types the schema does not define; not a default rule, as it needs
+/// the schema
+pub struct UnknownTypes {
+    pub known_labels: Vec<String>,
+}
+
+impl UnknownTypes {
+    pub fn from_schema(schema: &SchemaMetadata) -> Self {
+        UnknownTypes {
+            known_labels: known_labels(schema),
+        }
+    }
+}
+
+impl Rule for UnknownTypes {
+    fn code(&self) -> &'static str {
+        "unknown-type"
+    }
+
+    fn check(&self, functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+        let known: Vec<&str> = self.known_labels.iter().map(String::as_str).collect();
+        validate_functions_against_labels(functions, &known)
+    }
+}
+
+/// `incompatible-type`: see [`check_type_compatibility`]; not a default rule, as it needs
+/// the schema
+pub struct IncompatibleTypes {
+    pub schema: SchemaMetadata,
+}
+
+impl IncompatibleTypes {
+    pub fn from_schema(schema: &SchemaMetadata) -> Self {
+        IncompatibleTypes {
+            schema: schema.clone(),
+        }
+    }
+}
+
+impl Rule for IncompatibleTypes {
+    fn code(&self) -> &'static str {
+        "incompatible-type"
+    }
+
+    fn check(&self, functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+        check_type_compatibility(functions, &self.schema)
+    }
+}
+
+/// `unbound-variable`: see [`check_unbound_variables`]
+pub struct UnboundVariables;
+
+impl Rule for UnboundVariables {
+    fn code(&self) -> &'static str {
+        "unbound-variable"
+    }
+
+    fn check(&self, functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+        check_unbound_variables(functions)
+    }
+}
+
+/// `missing-return`: see [`check_missing_returns`]
+pub struct MissingReturn;
+
+impl Rule for MissingReturn {
+    fn code(&self) -> &'static str {
+        "missing-return"
+    }
+
+    fn check(&self, functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+        check_missing_returns(functions)
+    }
+}
+
+/// `unused-parameter`: see [`check_unused_parameters`]
+pub struct UnusedParameters;
+
+impl Rule for UnusedParameters {
+    fn code(&self) -> &'static str {
+        "unused-parameter"
+    }
+
+    fn check(&self, functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+        check_unused_parameters(functions)
+    }
+}
+
+/// `deprecated-call`: see [`check_deprecated_calls`]
+pub struct DeprecatedCalls;
+
+impl Rule for DeprecatedCalls {
+    fn code(&self) -> &'static str {
+        "deprecated-call"
+    }
+
+    fn check(&self, functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+        check_deprecated_calls(functions)
+    }
+}
+
+/// Report parameters that are never mentioned in the body or the return statement
+pub fn check_unused_parameters(functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for function in functions {
+        let variables = function.variables();
+        for parameter in &function.parameters {
+            let used = variables
+                .iter()
+                .any(|info| info.name == parameter.name && info.usage_count > 0);
+            if !used && parameter.name != "_" {
+                diagnostics.push(
+                    Diagnostic::warning(
+                        "unused-parameter",
+                        format!("parameter `${}` is never used", parameter.name),
+                    )
+                    .in_function(&function.name)
+                    .with_span(parameter.span),
+                );
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Report functions that call a deprecated function without being deprecated themselves
+pub fn check_deprecated_calls(functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+    let mut visitor = DeprecatedCallVisitor {
+        functions,
+        diagnostics: Vec::new(),
+    };
+    walk_all(&mut visitor, functions);
+    visitor.diagnostics
+}
+
+struct DeprecatedCallVisitor<'a> {
+    functions: &'a [FunctionMetadata],
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> FunctionVisitor<'a> for DeprecatedCallVisitor<'a> {
+    fn visit_call(&mut self, function: &'a FunctionMetadata, callee: &'a str) {
+        if function.is_deprecated() {
+            return;
+        }
+        let deprecated = self
+            .functions
+            .iter()
+            .find(|other| other.name == callee && other.is_deprecated());
+        if let Some(deprecated) = deprecated {
+            let reason = deprecated.doc_tag("deprecated").unwrap_or_default();
+            let message = if reason.is_empty() {
+                format!("calls deprecated function `{}`", callee)
+            } else {
+                format!("calls deprecated function `{}`: {}", callee, reason)
+            };
+            self.diagnostics.push(
+                Diagnostic::warning("deprecated-call", message)
+                    .in_function(&function.name)
+                    .with_span(function.spans.name),
+            );
+        }
+    }
+}
+
+/// Report variables that are read, e.g. returned or compared, but never given a value.
+///
+/// Variables in statements kept as written (`ConstraintKind::Other`) and those assigned
+/// with `=` in any stage, such as `reduce $total = sum($x)`, are assumed bound.
+pub fn check_unbound_variables(functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for function in functions {
+        let mut assumed_bound: Vec<&str> = function
+            .constraints
+            .iter()
+            .filter(|constraint| matches!(constraint.kind, ConstraintKind::Other { .. }))
+            .flat_map(|constraint| constraint.variables.iter().map(String::as_str))
+            .collect();
+        assumed_bound.extend(assigned_variables(&function.code_block));
+        for variable in function.variables() {
+            if !variable.is_bound() && !assumed_bound.contains(&variable.name.as_str()) {
+                diagnostics.push(
+                    Diagnostic::error(
+                        "unbound-variable",
+                        format!("variable `${}` is never bound", variable.name),
+                    )
+                    .in_function(&function.name)
+                    .with_span(function.spans.code_block),
+                );
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Report declared output types that the return statement gives no value for
+pub fn check_missing_returns(functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for function in functions {
+        let returned = function.returned_values().len();
+        // `return check;` answers a single boolean
+        if returned == 0 {
+            continue;
+        }
+        for (position, output_type) in function.output.types().iter().enumerate().skip(returned) {
+            diagnostics.push(
+                Diagnostic::error(
+                    "missing-return",
+                    format!(
+                        "output {} `{}` has no value in the return statement",
+                        position + 1,
+                        output_type
+                    ),
+                )
+                .in_function(&function.name)
+                .with_span(function.spans.output),
+            );
+        }
+    }
+
+    diagnostics
+}
+
+/// Variables written directly before a single `=`, without `$`
+fn assigned_variables(code: &str) -> Vec<&str> {
+    let tokens = tokenize(code);
+    tokens
+        .windows(3)
+        .filter(|window| {
+            window[0].kind == TokenKind::Variable
+                && window[1].is_punct("=")
+                && !window[2].is_punct("=")
+        })
+        .map(|window| window[0].text.trim_start_matches('$'))
+        .collect()
+}
@@ -0,0 +1,260 @@
+//! An inventory of the literal values hardcoded in function bodies, to audit magic
+//! numbers such as tax rates and thresholds.
+//!
+//! Literals are found on tokens of the source, so each comes with its position in the
+//! file and the statement it constrains:
+//!
+//! ```text
+//! fun get_high_earners() -> { person }:
+//!     match
+//!         $p isa person, has income $i;
+//!         $i > 100000;
+//!     return { $p };
+//! ```
+//!
+//! reports `100000`, an integer, in `$i > 100000`.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::lexer::{body_start, function_ranges, tokenize, Token, TokenKind};
+use crate::span::Span;
+
+/// The value type a literal is written as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiteralKind {
+    Boolean,
+    Integer,
+    Double,
+    /// `0.25dec`
+    Decimal,
+    String,
+    Date,
+    Datetime,
+    /// `P1Y2M`, `PT12H`
+    Duration,
+}
+
+/// A literal value in a function body
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Literal {
+    /// Name of the function whose body contains the literal
+    pub function: String,
+    /// The literal as written, quotes included for strings
+    pub value: String,
+    pub kind: LiteralKind,
+    pub span: Span,
+    /// The statement containing the literal, on one line and without its `;`, e.g.
+    /// `$i > 100000`
+    pub constraint: String,
+}
+
+/// Keywords opening a stage of patterns, left out of a literal's constraint
+const PATTERN_STAGES: &[&str] = &["match", "insert", "put", "update", "delete"];
+
+/// Every literal in the bodies of the functions defined in `source`, in source order.
+///
+/// Signatures are skipped, as are `define` blocks and anything else outside a `fun`.
+pub fn extract_literals(source: &str) -> Vec<Literal> {
+    let mut literals = Vec::new();
+    for range in function_ranges(source) {
+        let offset = range.start;
+        let text = &source[range];
+        let tokens: Vec<Token> = tokenize(text)
+            .into_iter()
+            .filter(|token| token.kind != TokenKind::Comment)
+            .collect();
+        let Some(name) = tokens.get(1).filter(|name| name.kind == TokenKind::Word) else {
+            continue;
+        };
+        let Some(body_start) = body_start(&tokens) else {
+            continue;
+        };
+
+        let mut position = body_start;
+        while position < tokens.len() {
+            let Some((kind, end)) = literal_at(&tokens, position) else {
+                position += 1;
+                continue;
+            };
+            let (start_byte, end_byte) = (tokens[position].range.start, tokens[end - 1].range.end);
+            literals.push(Literal {
+                function: name.text.to_string(),
+                value: text[start_byte..end_byte].to_string(),
+                kind,
+                span: Span::new(source, offset + start_byte, offset + end_byte),
+                constraint: constraint(text, &tokens, body_start, position, end),
+            });
+            position = end;
+        }
+    }
+    literals
+}
+
+impl fmt::Display for LiteralKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LiteralKind::Boolean => "boolean",
+            LiteralKind::Integer => "integer",
+            LiteralKind::Double => "double",
+            LiteralKind::Decimal => "decimal",
+            LiteralKind::String => "string",
+            LiteralKind::Date => "date",
+            LiteralKind::Datetime => "datetime",
+            LiteralKind::Duration => "duration",
+        };
+        f.write_str(name)
+    }
+}
+
+impl Literal {
+    /// The value type of a variable holding the literal, e.g. `datetime-tz` for a
+    /// datetime with a zone offset
+    pub fn value_type(&self) -> &'static str {
+        value_type(self.kind, &self.value)
+    }
+}
+
+/// The value type of `value` if it is a single literal, e.g. `integer` for `2024`
+pub(crate) fn literal_value_type(value: &str) -> Option<&'static str> {
+    let tokens: Vec<Token> = tokenize(value)
+        .into_iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .collect();
+    match literal_at(&tokens, 0)? {
+        (kind, end) if end == tokens.len() => Some(value_type(kind, value)),
+        _ => None,
+    }
+}
+
+fn value_type(kind: LiteralKind, value: &str) -> &'static str {
+    match kind {
+        LiteralKind::Boolean => "boolean",
+        LiteralKind::Integer => "integer",
+        LiteralKind::Double => "double",
+        LiteralKind::Decimal => "decimal",
+        LiteralKind::String => "string",
+        LiteralKind::Date => "date",
+        LiteralKind::Datetime => {
+            let time = &value[value.find('T').map_or(0, |t| t + 1)..];
+            if time.ends_with('Z') || time.contains(['+', '-']) {
+                "datetime-tz"
+            } else {
+                "datetime"
+            }
+        }
+        LiteralKind::Duration => "duration",
+    }
+}
+
+/// `100000 (integer) in $i > 100000`
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}) in {}", self.value, self.kind, self.constraint)
+    }
+}
+
+/// The kind of the literal starting at `tokens[position]` and the position after it; a
+/// datetime spans several tokens as the lexer splits it at `:`
+fn literal_at(tokens: &[Token], position: usize) -> Option<(LiteralKind, usize)> {
+    let token = tokens.get(position)?;
+    match token.kind {
+        TokenKind::String => return Some((LiteralKind::String, position + 1)),
+        TokenKind::Word => {}
+        _ => return None,
+    }
+    let text = token.text;
+    if text == "true" || text == "false" {
+        return Some((LiteralKind::Boolean, position + 1));
+    }
+    if is_date(text) {
+        if !text[10..].starts_with('T') {
+            return Some((LiteralKind::Date, position + 1));
+        }
+        // `2024-01-01T09:30:00+01:00`
+        let mut end = position + 1;
+        while let Some(next) = tokens.get(end) {
+            let glued = tokens[end - 1].range.end == next.range.start;
+            if !glued || !(next.kind == TokenKind::Word || next.is_punct(":") || next.is_punct("+"))
+            {
+                break;
+            }
+            end += 1;
+        }
+        return Some((LiteralKind::Datetime, end));
+    }
+    if is_duration(text) {
+        return Some((LiteralKind::Duration, position + 1));
+    }
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let kind = if digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        LiteralKind::Integer
+    } else if digits
+        .strip_suffix("dec")
+        .is_some_and(|decimal| decimal.parse::<f64>().is_ok())
+    {
+        LiteralKind::Decimal
+    } else if digits.parse::<f64>().is_ok() {
+        LiteralKind::Double
+    } else {
+        // Such as an iid, `0x1f00...`
+        return None;
+    };
+    Some((kind, position + 1))
+}
+
+/// Whether `text` starts with a `yyyy-mm-dd` date and continues only with a time
+fn is_date(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() >= 10
+        && bytes[..10]
+            .iter()
+            .enumerate()
+            .all(|(index, byte)| match index {
+                4 | 7 => *byte == b'-',
+                _ => byte.is_ascii_digit(),
+            })
+        && (bytes.len() == 10 || bytes[10] == b'T')
+}
+
+/// Whether `text` is an ISO 8601 duration such as `P1Y2M` or `PT1.5H`
+fn is_duration(text: &str) -> bool {
+    let Some(rest) = text.strip_prefix('P') else {
+        return false;
+    };
+    rest.chars().any(|c| c.is_ascii_digit())
+        && rest
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_digit() || c == 'T')
+        && rest
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || "YMWDTHS".contains(c))
+}
+
+/// The statement around `tokens[first..end]`, bounded by `;`, braces and the start of the
+/// body, with a leading `match` or other pattern stage keyword left out
+fn constraint(text: &str, tokens: &[Token], body_start: usize, first: usize, end: usize) -> String {
+    let is_boundary =
+        |token: &Token| token.is_punct(";") || token.is_punct("{") || token.is_punct("}");
+    let mut start = tokens[body_start..first]
+        .iter()
+        .rposition(is_boundary)
+        .map_or(body_start, |boundary| body_start + boundary + 1);
+    if PATTERN_STAGES
+        .iter()
+        .any(|stage| tokens[start].is_word(stage))
+    {
+        start += 1;
+    }
+    let stop = tokens[end..]
+        .iter()
+        .position(is_boundary)
+        .map_or(tokens.len(), |boundary| end + boundary);
+    let statement = &text[tokens[start].range.start..tokens[stop - 1].range.end];
+    statement.split_whitespace().collect::<Vec<_>>().join(" ")
+}
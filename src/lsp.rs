@@ -0,0 +1,424 @@
+//! A Language Server Protocol server for TypeQL function libraries (requires the `lsp`
+//! feature).
+//!
+//! Every `.tql` and `.tqls` file under the workspace root is extracted when the client
+//! connects, and open documents replace their file's text as they are edited. The server
+//! offers:
+//!
+//! - hover on a function name, showing its signature and doc comment
+//! - go to definition of a function, in whichever file defines it
+//! - diagnostics for parse errors and from the [`Linter`], updated on every edit
+//!
+//! Messages are JSON-RPC over stdio with `Content-Length` headers, and documents are
+//! synchronized in full on each change.
+
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::error::ExtractError;
+use crate::extract_function_metadata::FunctionMetadata;
+use crate::ingest::typeql_files;
+use crate::lenient::{extract_functions_from_source_lenient, Extraction};
+use crate::lint::{LintConfig, Linter};
+use crate::registry::FunctionRegistry;
+use crate::span::Span;
+
+/// JSON-RPC error code for requests the server does not handle
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Serve one client over `input` and `output` until it sends `exit`, linting with
+/// `config`.
+///
+/// Fails with [`ExtractError::Protocol`] on a message that is not JSON-RPC with a
+/// `Content-Length` header, and [`ExtractError::Stream`] if `input` or `output` fails.
+pub fn serve(
+    mut input: impl BufRead,
+    mut output: impl Write,
+    config: LintConfig,
+) -> Result<(), ExtractError> {
+    let mut server = Server {
+        config,
+        files: BTreeMap::new(),
+        registry: FunctionRegistry::new(),
+        parse_errors: BTreeMap::new(),
+    };
+    while let Some(message) = read_message(&mut input)? {
+        let method = message["method"].as_str().unwrap_or_default();
+        if method == "exit" {
+            break;
+        }
+        let params = &message["params"];
+        let mut outgoing = Vec::new();
+        match message.get("id") {
+            Some(id) => {
+                let response = match server.request(method, params) {
+                    Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    None => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {
+                            "code": METHOD_NOT_FOUND,
+                            "message": format!("unsupported method `{}`", method),
+                        },
+                    }),
+                };
+                outgoing.push(response);
+            }
+            None => outgoing.extend(server.notification(method, params)),
+        }
+        for message in outgoing {
+            write_message(&mut output, &message).map_err(ExtractError::stream)?;
+        }
+    }
+    Ok(())
+}
+
+struct Server {
+    config: LintConfig,
+    /// Text of every known file, from disk or from the editor while it is open
+    files: BTreeMap<PathBuf, String>,
+    /// Functions that parse, with `source_file` set to their path
+    registry: FunctionRegistry,
+    parse_errors: BTreeMap<PathBuf, Vec<Diagnostic>>,
+}
+
+impl Server {
+    /// The result of a request, or `None` if the method is not supported
+    fn request(&mut self, method: &str, params: &Value) -> Option<Value> {
+        let result = match method {
+            "initialize" => {
+                if let Some(root) = workspace_root(params) {
+                    self.load_workspace(&root);
+                }
+                json!({
+                    "capabilities": {
+                        "textDocumentSync": { "openClose": true, "change": 1, "save": true },
+                        "hoverProvider": true,
+                        "definitionProvider": true,
+                    },
+                    "serverInfo": {
+                        "name": "typedb-meta",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                })
+            }
+            "shutdown" => Value::Null,
+            "textDocument/hover" => self.hover(params).unwrap_or(Value::Null),
+            "textDocument/definition" => self.definition(params).unwrap_or(Value::Null),
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    /// Notifications to send in reply, e.g. fresh diagnostics after an edit
+    fn notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+        let document = &params["textDocument"];
+        let path = document["uri"].as_str().and_then(uri_to_path);
+        match (method, path) {
+            ("initialized", _) => {}
+            ("textDocument/didOpen", Some(path)) => {
+                let text = document["text"].as_str().unwrap_or_default();
+                self.files.insert(path, text.to_string());
+            }
+            ("textDocument/didChange", Some(path)) => {
+                // Full synchronization: the last change holds the whole document
+                let changes = params["contentChanges"].as_array();
+                let Some(text) = changes
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                else {
+                    return Vec::new();
+                };
+                self.files.insert(path, text.to_string());
+            }
+            ("textDocument/didClose", Some(path)) => match fs::read_to_string(&path) {
+                Ok(text) => {
+                    self.files.insert(path, text);
+                }
+                Err(_) => {
+                    self.files.remove(&path);
+                    return vec![publish(&path, Vec::new())];
+                }
+            },
+            _ => return Vec::new(),
+        }
+        self.analyse();
+        self.diagnostics()
+    }
+
+    fn load_workspace(&mut self, root: &Path) {
+        for path in typeql_files(root).unwrap_or_default() {
+            if let Ok(text) = fs::read_to_string(&path) {
+                self.files.insert(path, text);
+            }
+        }
+        self.analyse();
+    }
+
+    /// Re-extract every file; a function defined in several files is taken from the first
+    fn analyse(&mut self) {
+        self.registry = FunctionRegistry::new();
+        self.parse_errors.clear();
+        for (path, text) in &self.files {
+            for extraction in extract_functions_from_source_lenient(text) {
+                match extraction {
+                    Extraction::Complete(mut function) => {
+                        if !self.registry.contains(&function.name) {
+                            function.source_file = Some(path.display().to_string());
+                            self.registry.insert(*function);
+                        }
+                    }
+                    Extraction::Partial(partial) => self
+                        .parse_errors
+                        .entry(path.clone())
+                        .or_default()
+                        .extend(partial.errors),
+                }
+            }
+        }
+    }
+
+    /// A `publishDiagnostics` notification for every known file
+    fn diagnostics(&self) -> Vec<Value> {
+        let linter = Linter::new(self.config.clone(), &[]);
+        let mut by_file: BTreeMap<&Path, Vec<Value>> = self
+            .files
+            .keys()
+            .map(|path| (path.as_path(), Vec::new()))
+            .collect();
+
+        for (path, errors) in &self.parse_errors {
+            let text = &self.files[path];
+            by_file
+                .entry(path)
+                .or_default()
+                .extend(errors.iter().map(|error| diagnostic(text, error, None)));
+        }
+        for finding in linter.run(self.registry.functions()) {
+            let function = finding
+                .function
+                .as_deref()
+                .and_then(|name| self.registry.get(name));
+            let Some((path, text)) = function.and_then(|function| self.file_of(function)) else {
+                continue;
+            };
+            if finding.suppressed {
+                continue;
+            }
+            let fallback = function.and_then(|function| function.spans.name);
+            by_file
+                .entry(path)
+                .or_default()
+                .push(diagnostic(text, &finding, fallback));
+        }
+
+        by_file
+            .into_iter()
+            .map(|(path, diagnostics)| publish(path, diagnostics))
+            .collect()
+    }
+
+    fn file_of(&self, function: &FunctionMetadata) -> Option<(&Path, &str)> {
+        let path = Path::new(function.source_file.as_deref()?);
+        let (path, text) = self.files.get_key_value(path)?;
+        Some((path.as_path(), text.as_str()))
+    }
+
+    /// The function named by the word under the cursor
+    fn function_at(&self, params: &Value) -> Option<&FunctionMetadata> {
+        let path = uri_to_path(params["textDocument"]["uri"].as_str()?)?;
+        let text = self.files.get(&path)?;
+        let position = &params["position"];
+        let offset = offset(
+            text,
+            position["line"].as_u64()? as usize,
+            position["character"].as_u64()? as usize,
+        );
+        self.registry.get(word_at(text, offset)?)
+    }
+
+    fn hover(&self, params: &Value) -> Option<Value> {
+        let function = self.function_at(params)?;
+        let mut markdown = format!("```typeql\n{}\n```", function.signature());
+        if let Some(doc) = &function.doc {
+            write!(markdown, "\n\n{}", doc).unwrap();
+        }
+        if let Some(reason) = function.doc_tag("deprecated") {
+            write!(markdown, "\n\n**Deprecated.** {}", reason).unwrap();
+        }
+        Some(json!({ "contents": { "kind": "markdown", "value": markdown } }))
+    }
+
+    fn definition(&self, params: &Value) -> Option<Value> {
+        let function = self.function_at(params)?;
+        let (path, text) = self.file_of(function)?;
+        let span = function.spans.name.or(function.spans.definition)?;
+        Some(json!({ "uri": path_to_uri(path), "range": range(text, &span) }))
+    }
+}
+
+/// The workspace root from `initialize` parameters, preferring `rootUri`
+fn workspace_root(params: &Value) -> Option<PathBuf> {
+    if let Some(uri) = params["rootUri"].as_str() {
+        return uri_to_path(uri);
+    }
+    if let Some(uri) = params["workspaceFolders"][0]["uri"].as_str() {
+        return uri_to_path(uri);
+    }
+    params["rootPath"].as_str().map(PathBuf::from)
+}
+
+fn publish(path: &Path, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": path_to_uri(path), "diagnostics": diagnostics },
+    })
+}
+
+/// `finding` as an LSP diagnostic in `text`, at `fallback` when it has no span of its own
+/// and at the start of the file when neither is known
+fn diagnostic(text: &str, finding: &Diagnostic, fallback: Option<Span>) -> Value {
+    let span = finding
+        .span
+        .or(fallback)
+        .unwrap_or_else(|| Span::new(text, 0, 0));
+    let severity = match finding.severity {
+        Severity::Error => 1,
+        Severity::Warning => 2,
+        Severity::Info => 3,
+    };
+    json!({
+        "range": range(text, &span),
+        "severity": severity,
+        "code": finding.code,
+        "source": "typedb-meta",
+        "message": finding.message,
+    })
+}
+
+/// `span` as an LSP range: 0-based lines and UTF-16 columns
+fn range(text: &str, span: &Span) -> Value {
+    json!({ "start": position(text, span.start), "end": position(text, span.end) })
+}
+
+fn position(text: &str, offset: usize) -> Value {
+    let offset = offset.min(text.len());
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    json!({
+        "line": before.matches('\n').count(),
+        "character": before[line_start..].encode_utf16().count(),
+    })
+}
+
+/// Byte offset of an LSP position, clamped to the end of its line
+fn offset(text: &str, line: usize, character: usize) -> usize {
+    let line_start = if line == 0 {
+        0
+    } else {
+        text.match_indices('\n')
+            .nth(line - 1)
+            .map_or(text.len(), |(newline, _)| newline + 1)
+    };
+    let mut units = 0;
+    for (position, c) in text[line_start..].char_indices() {
+        if c == '\n' || units >= character {
+            return line_start + position;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+/// The name that `offset` falls in or directly after
+fn word_at(text: &str, offset: usize) -> Option<&str> {
+    let is_name = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+    let start = text[..offset]
+        .rfind(|c: char| !is_name(c))
+        .map_or(0, |position| position + 1);
+    let end = text[offset..]
+        .find(|c: char| !is_name(c))
+        .map_or(text.len(), |position| offset + position);
+    (start < end).then(|| &text[start..end])
+}
+
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut position = 0;
+    while position < bytes.len() {
+        let escaped = (bytes[position] == b'%')
+            .then(|| path.get(position + 1..position + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                position += 3;
+            }
+            None => {
+                decoded.push(bytes[position]);
+                position += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
+fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.display().to_string().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            write!(uri, "%{:02X}", byte).unwrap();
+        }
+    }
+    uri
+}
+
+/// The next message, or `None` at the end of the input
+fn read_message(input: &mut impl BufRead) -> Result<Option<Value>, ExtractError> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header).map_err(ExtractError::stream)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            let value = value.trim();
+            let parsed = value.parse::<usize>().map_err(|_| {
+                ExtractError::protocol(format!("invalid Content-Length `{}`", value))
+            })?;
+            length = Some(parsed);
+        }
+    }
+    let Some(length) = length else {
+        return Err(ExtractError::protocol(
+            "message without a Content-Length header",
+        ));
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body).map_err(ExtractError::stream)?;
+    let message = serde_json::from_slice(&body)
+        .map_err(|error| ExtractError::protocol(format!("message is not valid JSON: {}", error)))?;
+    Ok(Some(message))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()?;
+    Ok(())
+}
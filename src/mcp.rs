@@ -0,0 +1,268 @@
+//! A Model Context Protocol server exposing a function library to coding assistants
+//! (requires the `mcp` feature).
+//!
+//! The library is extracted from the configured paths on every tool call, so answers
+//! follow edits to the files. Tools:
+//!
+//! - `list_functions`: the signature of every function
+//! - `get_function`: the full metadata of one function
+//! - `search_functions`: functions by description, parameter type and output type
+//! - `call_graph`: what a function calls, or what calls it
+//! - `extract_function`: metadata for a `fun` definition passed as text
+//!
+//! Messages are JSON-RPC, one per line on stdio.
+
+use serde_json::{json, Value};
+use std::fmt::Write as _;
+use std::io::{BufRead, Write};
+
+use crate::call_graph::CallGraph;
+use crate::error::ExtractError;
+use crate::extract_function_metadata::{extract_function_metadata, FunctionMetadata};
+use crate::ingest::extract_from_paths;
+use crate::registry::FunctionRegistry;
+
+/// Protocol revision answered when the client does not ask for one
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// JSON-RPC error code for requests the server does not handle
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Serve one client over `input` and `output` until the input ends, answering from the
+/// functions in the files matching `patterns`.
+///
+/// Fails with [`ExtractError::Protocol`] on a line that is not JSON, and
+/// [`ExtractError::Stream`] if `input` or `output` fails. Failed tool calls are answered
+/// rather than returned.
+pub fn serve(
+    input: impl BufRead,
+    mut output: impl Write,
+    patterns: &[&str],
+) -> Result<(), ExtractError> {
+    for line in input.lines() {
+        let line = line.map_err(ExtractError::stream)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message: Value = serde_json::from_str(&line).map_err(|error| {
+            ExtractError::protocol(format!("message is not valid JSON: {}", error))
+        })?;
+        // Notifications need no answer
+        let Some(id) = message.get("id") else {
+            continue;
+        };
+        let method = message["method"].as_str().unwrap_or_default();
+        let response = match request(method, &message["params"], patterns) {
+            Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            None => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {
+                    "code": METHOD_NOT_FOUND,
+                    "message": format!("unsupported method `{}`", method),
+                },
+            }),
+        };
+        writeln!(output, "{}", response)
+            .and_then(|()| output.flush())
+            .map_err(ExtractError::stream)?;
+    }
+    Ok(())
+}
+
+/// The result of a request, or `None` if the method is not supported
+fn request(method: &str, params: &Value, patterns: &[&str]) -> Option<Value> {
+    let result = match method {
+        "initialize" => json!({
+            "protocolVersion": params["protocolVersion"].as_str().unwrap_or(PROTOCOL_VERSION),
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "typedb-meta", "version": env!("CARGO_PKG_VERSION") },
+        }),
+        "ping" => json!({}),
+        "tools/list" => json!({ "tools": tools() }),
+        "tools/call" => {
+            let name = params["name"].as_str().unwrap_or_default();
+            // Tool failures are results the assistant can read, not protocol errors
+            let (text, is_error) = match call_tool(name, &params["arguments"], patterns) {
+                Ok(text) => (text, false),
+                Err(error) => (error.to_string(), true),
+            };
+            json!({ "content": [{ "type": "text", "text": text }], "isError": is_error })
+        }
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn tools() -> Value {
+    let name = json!({ "type": "string", "description": "Function name" });
+    json!([
+        {
+            "name": "list_functions",
+            "description": "Signatures of every TypeQL function in the library",
+            "inputSchema": { "type": "object", "properties": {} },
+        },
+        {
+            "name": "get_function",
+            "description": "Full metadata of a TypeQL function as JSON: parameters, output, \
+                return statement, body, referenced functions and types, and doc comment",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "name": name },
+                "required": ["name"],
+            },
+        },
+        {
+            "name": "search_functions",
+            "description": "TypeQL functions matching every given filter, best matches \
+                first when searching by text",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "text": {
+                        "type": "string",
+                        "description": "Words describing the function, matched against its \
+                            name, doc comment, signature and body; tolerates typos",
+                    },
+                    "parameter_type": {
+                        "type": "string",
+                        "description": "Type of at least one parameter, e.g. `person`",
+                    },
+                    "returns": {
+                        "type": "string",
+                        "description": "Type among the outputs, e.g. `double`",
+                    },
+                },
+            },
+        },
+        {
+            "name": "call_graph",
+            "description": "Functions that a function calls, directly or not, or the \
+                functions that call it",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": name,
+                    "direction": {
+                        "type": "string",
+                        "enum": ["callees", "callers", "all_callers", "reachable"],
+                        "description": "`callees` and `callers` are direct; `all_callers` \
+                            and `reachable` follow calls transitively",
+                    },
+                },
+                "required": ["name", "direction"],
+            },
+        },
+        {
+            "name": "extract_function",
+            "description": "Metadata of a TypeQL `fun` definition, or the parse error",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "source": { "type": "string", "description": "The `fun` definition" },
+                },
+                "required": ["source"],
+            },
+        },
+    ])
+}
+
+fn call_tool(name: &str, arguments: &Value, patterns: &[&str]) -> Result<String, ExtractError> {
+    let argument = |key: &str| arguments[key].as_str();
+    let required = |key: &str| {
+        argument(key).ok_or_else(|| ExtractError::protocol(format!("missing `{}`", key)))
+    };
+    let unknown = |name: &str| ExtractError::UnknownFunction {
+        name: name.to_string(),
+    };
+
+    if name == "extract_function" {
+        let source = required("source")?;
+        return to_json(&extract_function_metadata(source)?);
+    }
+
+    let registry: FunctionRegistry = extract_from_paths(patterns)?.into_iter().collect();
+    let text = match name {
+        "list_functions" => signatures(registry.iter()),
+        "get_function" => {
+            let name = required("name")?;
+            to_json(registry.get(name).ok_or_else(|| unknown(name))?)?
+        }
+        "search_functions" => {
+            // Ranked best first when searching by text
+            let candidates: Vec<&FunctionMetadata> = match argument("text") {
+                Some(text) => registry
+                    .search(text)
+                    .into_iter()
+                    .map(|hit| hit.function)
+                    .collect(),
+                None => registry.iter().collect(),
+            };
+            let matches = candidates.into_iter().filter(|function| {
+                argument("parameter_type").is_none_or(|type_name| {
+                    registry
+                        .with_parameter_type(type_name)
+                        .any(|other| other.name == function.name)
+                }) && argument("returns").is_none_or(|type_name| {
+                    registry
+                        .returning(type_name)
+                        .any(|other| other.name == function.name)
+                })
+            });
+            signatures(matches)
+        }
+        "call_graph" => {
+            let name = required("name")?;
+            if !registry.contains(name) {
+                return Err(unknown(name));
+            }
+            let graph = CallGraph::from_functions(registry.functions());
+            let names: Vec<&str> = match required("direction")? {
+                "callees" => graph.callees(name).iter().map(String::as_str).collect(),
+                "callers" => graph.callers_of(name),
+                "all_callers" => graph.all_callers_of(name),
+                "reachable" => graph
+                    .reachable_from(&[name])
+                    .into_iter()
+                    .filter(|reached| *reached != name)
+                    .collect(),
+                direction => {
+                    return Err(ExtractError::protocol(format!(
+                        "unknown direction `{}`",
+                        direction
+                    )))
+                }
+            };
+            if names.is_empty() {
+                "(none)".to_string()
+            } else {
+                names.join("\n")
+            }
+        }
+        _ => return Err(ExtractError::protocol(format!("unknown tool `{}`", name))),
+    };
+    Ok(text)
+}
+
+/// Metadata as indented JSON
+fn to_json(function: &FunctionMetadata) -> Result<String, ExtractError> {
+    serde_json::to_string_pretty(function).map_err(|error| ExtractError::InvalidMetadata {
+        message: format!("cannot serialize `{}`: {}", function.name, error),
+    })
+}
+
+/// One line per function: its signature, then the first line of its doc comment
+fn signatures<'a>(functions: impl Iterator<Item = &'a FunctionMetadata>) -> String {
+    let mut out = String::new();
+    for function in functions {
+        write!(out, "{}", function.signature()).unwrap();
+        if let Some(summary) = function.doc.as_deref().and_then(|doc| doc.lines().next()) {
+            write!(out, "  # {}", summary).unwrap();
+        }
+        out.push('\n');
+    }
+    if out.is_empty() {
+        out.push_str("(none)\n");
+    }
+    out
+}
@@ -0,0 +1,164 @@
+//! The versioned JSON document that metadata is saved as, e.g. `function_metadata.json`
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{self, Write};
+
+use crate::doc_comment::categories;
+use crate::error::ExtractError;
+use crate::extract_function_metadata::{extract_function_at, FunctionMetadata, OutputType};
+
+/// Version written by [`to_metadata_json`].
+///
+/// Version 1 is the bare array of functions written before documents were versioned.
+pub const METADATA_VERSION: u32 = 2;
+
+/// A saved set of functions and the format version they were written in
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct MetadataDocument {
+    pub metadata_version: u32,
+    pub functions: Vec<FunctionMetadata>,
+}
+
+impl MetadataDocument {
+    /// JSON Schema describing the document written by [`to_metadata_json`]
+    #[cfg(feature = "json-schema")]
+    pub fn json_schema() -> schemars::Schema {
+        schemars::schema_for!(MetadataDocument)
+    }
+}
+
+#[derive(Serialize)]
+struct DocumentRef<'a> {
+    metadata_version: u32,
+    functions: Vec<&'a FunctionMetadata>,
+}
+
+/// Serialize `functions` as a pretty-printed [`MetadataDocument`].
+///
+/// Functions are sorted by name and every other list keeps source order, so extracting
+/// the same sources always gives the same text and diffs only show real changes.
+pub fn to_metadata_json(functions: &[FunctionMetadata]) -> serde_json::Result<String> {
+    let mut sorted: Vec<&FunctionMetadata> = functions.iter().collect();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+    serde_json::to_string_pretty(&DocumentRef {
+        metadata_version: METADATA_VERSION,
+        functions: sorted,
+    })
+}
+
+/// Write `function` as a single line of JSON, for JSON Lines output.
+///
+/// Lines carry no version of their own; each is one function in the
+/// [`METADATA_VERSION`] format. Writing functions one at a time as they are extracted
+/// keeps memory flat for large corpora.
+pub fn write_json_line(mut writer: impl Write, function: &FunctionMetadata) -> io::Result<()> {
+    serde_json::to_writer(&mut writer, function)?;
+    writeln!(writer)
+}
+
+/// Read functions from a metadata document of any version up to [`METADATA_VERSION`].
+///
+/// Unversioned documents are rebuilt from each function's name, parameters, output and
+/// code block, so they pick up fields added since; their doc comments are kept, but
+/// spans are not, as they referred to the original source.
+pub fn from_metadata_json(json: &str) -> Result<Vec<FunctionMetadata>, ExtractError> {
+    let invalid = |error: anyhow::Error| ExtractError::InvalidMetadata {
+        message: format!("{:#}", error),
+    };
+    let value: Value = serde_json::from_str(json)
+        .context("metadata is not valid JSON")
+        .map_err(invalid)?;
+    match value {
+        Value::Array(functions) => functions
+            .iter()
+            .enumerate()
+            .map(|(position, function)| {
+                upgrade_unversioned(function)
+                    .with_context(|| {
+                        format!(
+                            "cannot upgrade function {} of version 1 metadata",
+                            position + 1
+                        )
+                    })
+                    .map_err(invalid)
+            })
+            .collect(),
+        Value::Object(_) => {
+            let version = value
+                .get("metadata_version")
+                .and_then(Value::as_u64)
+                // Versions too large for a `u32` are as unsupported as `u32::MAX`
+                .map(|version| u32::try_from(version).unwrap_or(u32::MAX))
+                .context("metadata document has no `metadata_version`")
+                .map_err(invalid)?;
+            if version > METADATA_VERSION {
+                return Err(ExtractError::UnsupportedVersion {
+                    version,
+                    supported: METADATA_VERSION,
+                });
+            }
+            let document: MetadataDocument = serde_json::from_value(value)
+                .with_context(|| format!("invalid version {} metadata", version))
+                .map_err(invalid)?;
+            Ok(document.functions)
+        }
+        _ => Err(ExtractError::InvalidMetadata {
+            message: "metadata must be a document object or an array of functions".to_string(),
+        }),
+    }
+}
+
+/// Re-extract a function saved before documents were versioned
+fn upgrade_unversioned(function: &Value) -> Result<FunctionMetadata> {
+    let text = |field: &str| {
+        function
+            .get(field)
+            .and_then(Value::as_str)
+            .with_context(|| format!("missing `{}`", field))
+    };
+
+    let parameters = function
+        .get("parameters")
+        .and_then(Value::as_array)
+        .context("missing `parameters`")?
+        .iter()
+        .map(|parameter| {
+            let name = parameter.get("name").and_then(Value::as_str);
+            let type_name = parameter.get("type_name").and_then(Value::as_str);
+            match (name, type_name) {
+                (Some(name), Some(type_name)) => Ok(format!("${}: {}", name, type_name)),
+                _ => bail!("parameter without `name` and `type_name`"),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    // The earliest format stored the output as written; later ones as an `OutputType`
+    let output = match function.get("output") {
+        Some(Value::String(output)) => output.clone(),
+        Some(output) => OutputType::deserialize(output)
+            .context("invalid `output`")?
+            .to_string(),
+        None => bail!("missing `output`"),
+    };
+
+    let source = format!(
+        "fun {}({}) -> {}:\n{}",
+        text("name")?,
+        parameters.join(", "),
+        output,
+        text("code_block")?
+    );
+    let mut metadata = extract_function_at(&source, 0..source.len())
+        .map_err(|diagnostic| anyhow!(diagnostic.render("<metadata>", &source)))?
+        .without_location();
+    if let Some(doc) = function.get("doc") {
+        metadata.doc = Deserialize::deserialize(doc).context("invalid `doc`")?;
+    }
+    if let Some(doc_tags) = function.get("doc_tags") {
+        metadata.doc_tags = Deserialize::deserialize(doc_tags).context("invalid `doc_tags`")?;
+        metadata.tags = categories(&metadata.doc_tags);
+    }
+    Ok(metadata)
+}
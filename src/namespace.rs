@@ -0,0 +1,142 @@
+//! Grouping functions into namespaces, for libraries spanning several domains.
+//!
+//! TypeQL has no namespaces, so they are read from conventions: a name prefix such as
+//! `tax_` puts `tax_bracket` and `get_tax_owed` in the `tax` namespace, and a library laid
+//! out as `functions/tax/*.tql` can be grouped by directory instead.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::extract_function_metadata::FunctionMetadata;
+use crate::naming::DEFAULT_VERB_PREFIXES;
+
+/// How functions are assigned to namespaces
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceConfig {
+    /// Name prefixes such as `tax_`, each naming a namespace without its trailing `_`.
+    ///
+    /// A prefix matches at the start of a name or after a verb prefix such as `get_`; the
+    /// longest matching prefix wins.
+    #[serde(default)]
+    pub prefixes: Vec<String>,
+    /// Put functions no prefix matches in the namespace of the directory their file is in,
+    /// or of the file itself when it has no directory
+    #[serde(default)]
+    pub by_directory: bool,
+}
+
+/// The namespace of each function that has one
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Namespaces {
+    by_function: BTreeMap<String, String>,
+}
+
+impl NamespaceConfig {
+    /// A configuration with the prefixes shared by at least `min_functions` function
+    /// names, e.g. `tax_` for `get_tax_bracket` and `calculate_tax_owed`.
+    ///
+    /// The prefix is the first word of a name after any verb prefix, and only counts
+    /// when more words follow it.
+    pub fn infer(functions: &[FunctionMetadata], min_functions: usize) -> Self {
+        let mut counts: BTreeMap<String, BTreeSet<&str>> = BTreeMap::new();
+        for function in functions {
+            let name = without_verb(&function.name);
+            if let Some((word, rest)) = name.split_once('_') {
+                if !word.is_empty() && !rest.is_empty() {
+                    counts
+                        .entry(format!("{}_", word))
+                        .or_default()
+                        .insert(&function.name);
+                }
+            }
+        }
+        NamespaceConfig {
+            prefixes: counts
+                .into_iter()
+                .filter(|(_, names)| names.len() >= min_functions.max(1))
+                .map(|(prefix, _)| prefix)
+                .collect(),
+            by_directory: false,
+        }
+    }
+
+    /// The namespace of `function`, if a prefix matches its name or it is grouped by
+    /// location
+    pub fn namespace_of(&self, function: &FunctionMetadata) -> Option<String> {
+        let candidates = [function.name.as_str(), without_verb(&function.name)];
+        let prefix = self
+            .prefixes
+            .iter()
+            .filter(|prefix| !prefix.trim_end_matches('_').is_empty())
+            .filter(|prefix| {
+                candidates
+                    .iter()
+                    .any(|name| name.starts_with(prefix.as_str()))
+            })
+            .max_by_key(|prefix| prefix.len());
+        if let Some(prefix) = prefix {
+            return Some(prefix.trim_end_matches('_').to_string());
+        }
+        if !self.by_directory {
+            return None;
+        }
+        let path = Path::new(function.source_file.as_deref()?);
+        let directory = path
+            .parent()
+            .and_then(Path::file_name)
+            .filter(|directory| !directory.is_empty());
+        directory
+            .or_else(|| path.file_stem())
+            .map(|name| name.to_string_lossy().into_owned())
+    }
+
+    /// Assign every function in `functions` that has a namespace
+    pub fn assign(&self, functions: &[FunctionMetadata]) -> Namespaces {
+        Namespaces {
+            by_function: functions
+                .iter()
+                .filter_map(|function| {
+                    self.namespace_of(function)
+                        .map(|namespace| (function.name.clone(), namespace))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Namespaces {
+    /// The namespace `function` was assigned to
+    pub fn namespace_of(&self, function: &str) -> Option<&str> {
+        self.by_function.get(function).map(String::as_str)
+    }
+
+    /// Names of the namespaces, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let names: BTreeSet<&str> = self.by_function.values().map(String::as_str).collect();
+        names.into_iter().collect()
+    }
+
+    /// The functions in each namespace, both sorted by name
+    pub fn groups(&self) -> BTreeMap<&str, Vec<&str>> {
+        let mut groups: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (function, namespace) in &self.by_function {
+            groups.entry(namespace).or_default().push(function);
+        }
+        groups
+    }
+
+    /// Whether no function has a namespace
+    pub fn is_empty(&self) -> bool {
+        self.by_function.is_empty()
+    }
+}
+
+/// `name` without a leading verb prefix such as `get_`
+fn without_verb(name: &str) -> &str {
+    DEFAULT_VERB_PREFIXES
+        .iter()
+        .find_map(|verb| name.strip_prefix(verb))
+        .unwrap_or(name)
+}
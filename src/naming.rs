@@ -0,0 +1,213 @@
+//! Naming convention rules for the [`Linter`](crate::lint::Linter)
+
+use crate::diagnostic::Diagnostic;
+use crate::extract_function_metadata::{FunctionMetadata, TypeKind, VALUE_TYPES};
+use crate::lint::Rule;
+
+/// Prefixes a function name should start with unless configured otherwise
+pub const DEFAULT_VERB_PREFIXES: &[&str] = &[
+    "get_",
+    "calculate_",
+    "compute_",
+    "find_",
+    "list_",
+    "count_",
+    "is_",
+    "has_",
+    "check_",
+];
+
+/// `function-name-case`: see [`check_function_name_case`]
+pub struct FunctionNameCase;
+
+impl Rule for FunctionNameCase {
+    fn code(&self) -> &'static str {
+        "function-name-case"
+    }
+
+    fn check(&self, functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+        check_function_name_case(functions)
+    }
+}
+
+/// `function-name-verb`: see [`check_function_name_verbs`]
+pub struct FunctionNameVerb {
+    pub prefixes: Vec<String>,
+}
+
+impl Default for FunctionNameVerb {
+    fn default() -> Self {
+        FunctionNameVerb {
+            prefixes: DEFAULT_VERB_PREFIXES
+                .iter()
+                .map(|prefix| prefix.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl Rule for FunctionNameVerb {
+    fn code(&self) -> &'static str {
+        "function-name-verb"
+    }
+
+    fn check(&self, functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+        let prefixes: Vec<&str> = self.prefixes.iter().map(String::as_str).collect();
+        check_function_name_verbs(functions, &prefixes)
+    }
+}
+
+/// `parameter-name-type`: see [`check_parameter_names`]
+pub struct ParameterNameType;
+
+impl Rule for ParameterNameType {
+    fn code(&self) -> &'static str {
+        "parameter-name-type"
+    }
+
+    fn check(&self, functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+        check_parameter_names(functions)
+    }
+}
+
+/// Report function names that are not snake_case, e.g. `calculateTax` or `calculate-tax`
+pub fn check_function_name_case(functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+    functions
+        .iter()
+        .filter(|function| !is_snake_case(&function.name))
+        .map(|function| {
+            Diagnostic::warning(
+                "function-name-case",
+                format!(
+                    "function name `{}` should be snake_case, e.g. `{}`",
+                    function.name,
+                    to_snake_case(&function.name)
+                ),
+            )
+            .in_function(&function.name)
+            .with_span(function.spans.name)
+        })
+        .collect()
+}
+
+/// Report function names that do not start with one of `prefixes`, such as `get_`
+pub fn check_function_name_verbs(
+    functions: &[FunctionMetadata],
+    prefixes: &[&str],
+) -> Vec<Diagnostic> {
+    functions
+        .iter()
+        .filter(|function| {
+            !prefixes
+                .iter()
+                .any(|prefix| function.name.starts_with(prefix))
+        })
+        .map(|function| {
+            Diagnostic::warning(
+                "function-name-verb",
+                format!(
+                    "function name `{}` should start with a verb such as {}",
+                    function.name,
+                    prefixes
+                        .iter()
+                        .take(3)
+                        .map(|prefix| format!("`{}`", prefix))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            )
+            .in_function(&function.name)
+            .with_span(function.spans.name)
+        })
+        .collect()
+}
+
+/// Report parameters named after one type but declared as another, e.g. `$company: person`
+/// when another signature uses `company`, or `$string: integer`.
+///
+/// A parameter is only reported when its name, with `-` read as `_`, is exactly a value
+/// type or a label from some function's signature, and not the type it is declared with.
+/// Labels only used in bodies are ignored, since role players are commonly named after
+/// their role, as in `(employee: $employee)`.
+pub fn check_parameter_names(functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+    let mut labels: Vec<String> = Vec::new();
+    for function in functions {
+        let parameter_labels = function
+            .parameters
+            .iter()
+            .filter_map(|parameter| parameter.label());
+        let output_labels = function
+            .output
+            .types()
+            .iter()
+            .filter(|output_type| output_type.kind == TypeKind::Label)
+            .map(|output_type| output_type.name.as_str());
+        for label in parameter_labels.chain(output_labels) {
+            let label = normalize(label);
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+    }
+
+    let mut diagnostics = Vec::new();
+    for function in functions {
+        for parameter in &function.parameters {
+            let name = normalize(&parameter.name);
+            let declared = normalize(
+                parameter
+                    .type_name
+                    .trim_end_matches(['[', ']', '?'])
+                    .rsplit(':')
+                    .next()
+                    .unwrap_or(""),
+            );
+            let named_after_type =
+                labels.contains(&name) || VALUE_TYPES.iter().any(|value| normalize(value) == name);
+            if named_after_type && name != declared {
+                diagnostics.push(
+                    Diagnostic::warning(
+                        "parameter-name-type",
+                        format!(
+                            "parameter `${}` is named after type `{}` but declared as `{}`",
+                            parameter.name, parameter.name, parameter.type_name
+                        ),
+                    )
+                    .in_function(&function.name)
+                    .with_span(parameter.span),
+                );
+            }
+        }
+    }
+    diagnostics
+}
+
+fn normalize(name: &str) -> String {
+    name.replace('-', "_")
+}
+
+fn is_snake_case(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !name.contains("__")
+        && !name.ends_with('_')
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for c in name.chars() {
+        if c.is_uppercase() {
+            if !snake.is_empty() && !snake.ends_with('_') {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else if c == '-' {
+            snake.push('_');
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
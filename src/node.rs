@@ -0,0 +1,63 @@
+//! Node.js bindings (requires the `napi` feature).
+//!
+//! Build the addon with `napi build --release --features napi`, then from TypeScript:
+//!
+//! ```ts
+//! import { extractFromPaths, renderTypescriptDefinitions } from "./index";
+//!
+//! const functions = extractFromPaths(["schema/**/*.tql"]);
+//! const definitions = renderTypescriptDefinitions(["schema/**/*.tql"]);
+//! ```
+//!
+//! Metadata is returned as plain objects in the shape the CLI writes as JSON, and errors
+//! are thrown with the message the CLI would print.
+
+use napi::{Error, Result};
+use napi_derive::napi;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::codegen::render_typescript_definitions;
+use crate::extract_function_metadata::{extract_function_metadata, extract_functions_from_source};
+use crate::ingest::extract_from_paths;
+
+fn to_js(value: &impl Serialize) -> Result<Value> {
+    serde_json::to_value(value).map_err(|error| Error::from_reason(error.to_string()))
+}
+
+fn patterns(patterns: &[String]) -> Vec<&str> {
+    patterns.iter().map(String::as_str).collect()
+}
+
+/// Metadata of one `fun` definition
+#[napi(js_name = "extractFunctionMetadata")]
+pub fn extract_function_metadata_js(function_text: String) -> Result<Value> {
+    let metadata = extract_function_metadata(&function_text)
+        .map_err(|error| Error::from_reason(error.to_string()))?;
+    to_js(&metadata)
+}
+
+/// Metadata of every function in a TypeQL source
+#[napi(js_name = "extractFunctions")]
+pub fn extract_functions_js(source: String) -> Result<Value> {
+    let functions = extract_functions_from_source(&source)
+        .map_err(|error| Error::from_reason(error.to_string()))?;
+    to_js(&functions)
+}
+
+/// Metadata of every function in the files matching the glob patterns
+#[napi(js_name = "extractFromPaths")]
+pub fn extract_from_paths_js(paths: Vec<String>) -> Result<Value> {
+    let functions = extract_from_paths(&patterns(&paths))
+        .map_err(|error| Error::from_reason(error.to_string()))?;
+    to_js(&functions)
+}
+
+/// TypeScript definitions for calling the functions in the files matching the glob
+/// patterns, as `typedb-meta codegen --language typescript` writes them
+#[napi(js_name = "renderTypescriptDefinitions")]
+pub fn render_typescript_definitions_js(paths: Vec<String>) -> Result<String> {
+    let functions = extract_from_paths(&patterns(&paths))
+        .map_err(|error| Error::from_reason(error.to_string()))?;
+    Ok(render_typescript_definitions(&functions))
+}
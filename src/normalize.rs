@@ -0,0 +1,223 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::Peekable;
+
+use crate::extract_function_metadata::FunctionMetadata;
+use crate::generate::STAGE_KEYWORDS;
+use crate::lexer::{tokenize, TokenKind};
+
+/// A function reduced to what determines its behaviour, for deduplicating and comparing
+/// functions regardless of naming and layout.
+///
+/// Two functions normalize equal when they have the same parameter types and output and
+/// their bodies differ only in variable names, whitespace, comments and the order of the
+/// statements in `match` stages and `{ ... }` blocks. The function and parameter names
+/// are not part of the normalized form.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct NormalizedFunction {
+    pub parameter_types: Vec<String>,
+    pub output: String,
+    /// Body statements without their `;`, one token apart, with stage keywords such as
+    /// `match` as statements of their own. Parameters are written `$@0`, `$@1`, ... by
+    /// position and other variables `$v0`, `$v1`, ... by first appearance.
+    pub statements: Vec<String>,
+}
+
+impl fmt::Display for NormalizedFunction {
+    /// The normalized form on one line, e.g. `(person) -> { person }: match; ...`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "({}) -> {}: {}",
+            self.parameter_types.join(", "),
+            self.output,
+            self.statements.join("; ")
+        )
+    }
+}
+
+/// Normalize `function` for comparison.
+///
+/// Statements are sorted by their text with variables other than parameters anonymised,
+/// and variables are then renamed in order of first appearance. Statements that differ
+/// only in variable names keep their source order, so some reorderings of such
+/// statements still normalize differently.
+pub fn normalize(function: &FunctionMetadata) -> NormalizedFunction {
+    let mut names = HashMap::new();
+    NormalizedFunction {
+        parameter_types: function
+            .parameters
+            .iter()
+            .map(|parameter| parameter.type_name.clone())
+            .collect(),
+        output: function.output.to_string(),
+        statements: canonical_body(function)
+            .iter()
+            .map(|statement| render(statement, &mut |var| rename(&mut names, var)))
+            .collect(),
+    }
+}
+
+impl FunctionMetadata {
+    /// A hex BLAKE3 hash of the function's name and [`normalize`]d form, for telling
+    /// whether a function actually changed between builds.
+    ///
+    /// Reformatting, editing comments, renaming variables or reordering `match` statements
+    /// keeps the fingerprint; renaming the function or changing its behaviour does not.
+    pub fn fingerprint(&self) -> String {
+        let content = format!("{}\n{}", self.name, normalize(self));
+        blake3::hash(content.as_bytes()).to_hex().to_string()
+    }
+}
+
+/// Part of a statement: a token, or a `{ ... }` block with the statements inside it
+enum Piece {
+    Token(String),
+    Block(Vec<Vec<Piece>>),
+}
+
+/// Every statement of the body, nested ones included, with local variables written `$_`
+pub(crate) fn masked_statements(function: &FunctionMetadata) -> Vec<String> {
+    fn collect(statement: &[Piece], out: &mut Vec<String>) {
+        out.push(render(statement, &mut mask));
+        for piece in statement {
+            if let Piece::Block(statements) = piece {
+                for nested in statements {
+                    collect(nested, out);
+                }
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    for statement in &canonical_body(function) {
+        collect(statement, &mut out);
+    }
+    out
+}
+
+/// Body statements with parameters renamed by position and commutative statements sorted
+fn canonical_body(function: &FunctionMetadata) -> Vec<Vec<Piece>> {
+    let parameters: HashMap<&str, String> = function
+        .parameters
+        .iter()
+        .enumerate()
+        .map(|(position, parameter)| (parameter.name.as_str(), format!("$@{}", position)))
+        .collect();
+    let mut tokens = tokenize(&function.code_block)
+        .into_iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .map(|token| match token.kind {
+            TokenKind::Variable => parameters
+                .get(&token.text[1..])
+                .cloned()
+                .unwrap_or_else(|| token.text.to_string()),
+            _ => token.text.to_string(),
+        })
+        .peekable();
+
+    let mut body = Vec::new();
+    let mut stage: Vec<Vec<Piece>> = Vec::new();
+    let mut sorted = false;
+    while tokens.peek().is_some() {
+        let starts_stage = tokens
+            .peek()
+            .is_some_and(|token| STAGE_KEYWORDS.contains(&token.as_str()));
+        if starts_stage {
+            finish_stage(&mut body, &mut stage, sorted);
+            let keyword = tokens.next().unwrap_or_default();
+            sorted = keyword == "match";
+            body.push(vec![Piece::Token(keyword)]);
+        }
+        let statement = parse_statement(&mut tokens);
+        if !statement.is_empty() {
+            stage.push(statement);
+        }
+        // An unbalanced `}` would otherwise never be consumed
+        tokens.next_if(|token| token == "}");
+    }
+    finish_stage(&mut body, &mut stage, sorted);
+    body
+}
+
+fn finish_stage(body: &mut Vec<Vec<Piece>>, stage: &mut Vec<Vec<Piece>>, sorted: bool) {
+    if sorted {
+        sort_statements(stage);
+    }
+    body.append(stage);
+}
+
+/// Tokens up to the next `;` or the `}` closing the enclosing block, which is not consumed
+fn parse_statement(tokens: &mut Peekable<impl Iterator<Item = String>>) -> Vec<Piece> {
+    let mut statement = Vec::new();
+    while let Some(token) = tokens.next_if(|token| token != "}") {
+        match token.as_str() {
+            ";" => break,
+            "{" => {
+                let mut block = Vec::new();
+                while tokens.peek().is_some_and(|token| token != "}") {
+                    let nested = parse_statement(tokens);
+                    if !nested.is_empty() {
+                        block.push(nested);
+                    }
+                }
+                tokens.next();
+                sort_statements(&mut block);
+                statement.push(Piece::Block(block));
+            }
+            _ => statement.push(Piece::Token(token)),
+        }
+    }
+    statement
+}
+
+/// Sort by the statements' text with local variables anonymised, so the order does not
+/// depend on variable names
+fn sort_statements(statements: &mut [Vec<Piece>]) {
+    statements.sort_by_cached_key(|statement| render(statement, &mut mask));
+}
+
+fn render(statement: &[Piece], variable: &mut impl FnMut(&str) -> String) -> String {
+    statement
+        .iter()
+        .map(|piece| match piece {
+            Piece::Token(token) if token.starts_with('$') => variable(token),
+            Piece::Token(token) => token.clone(),
+            Piece::Block(statements) => {
+                let statements: Vec<String> = statements
+                    .iter()
+                    .map(|statement| render(statement, variable))
+                    .collect();
+                format!("{{ {}; }}", statements.join("; "))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parameters (`$@0`, `$@1`, ...) and `$_` as they are, other variables as `$_`
+fn mask(var: &str) -> String {
+    if is_parameter(var) {
+        var.to_string()
+    } else {
+        "$_".to_string()
+    }
+}
+
+/// Parameters as they are, other variables as `$v0`, `$v1`, ... by first appearance
+fn rename(names: &mut HashMap<String, String>, var: &str) -> String {
+    if is_parameter(var) || var == "$_" {
+        return var.to_string();
+    }
+    let next = names.len();
+    names
+        .entry(var.to_string())
+        .or_insert_with(|| format!("$v{}", next))
+        .clone()
+}
+
+/// Parameters are renamed to `$@` and their position, which cannot clash with a variable
+fn is_parameter(var: &str) -> bool {
+    var.starts_with("$@")
+}
@@ -0,0 +1,219 @@
+//! Python bindings (requires the `pyo3` feature).
+//!
+//! Build the extension module with `maturin develop --features pyo3`, then:
+//!
+//! ```python
+//! import typedb_examples
+//!
+//! registry = typedb_examples.FunctionRegistry()
+//! registry.load_paths(["schema/**/*.tql"])
+//! graph = registry.call_graph()
+//! print(graph.all_callers_of("get_income"))
+//! ```
+//!
+//! Metadata is handed to Python as plain dicts and lists, in the shape the CLI writes as
+//! JSON.
+
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+use serde::Serialize;
+
+use crate::call_graph::CallGraph;
+use crate::error::ExtractError;
+use crate::extract_function_metadata::{
+    extract_function_metadata as extract_one, extract_functions_from_source, FunctionMetadata,
+};
+use crate::ingest::extract_from_paths as extract_paths;
+use crate::registry::FunctionRegistry;
+
+/// `value` as Python objects, by way of its JSON form
+fn to_python(py: Python<'_>, value: &impl Serialize) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value).map_err(|error| value_error(&error))?;
+    Ok(py
+        .import_bound("json")?
+        .call_method1("loads", (json,))?
+        .unbind())
+}
+
+fn value_error(error: &impl ToString) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+fn extract_error(error: ExtractError) -> PyErr {
+    match error {
+        ExtractError::Io { .. } => PyOSError::new_err(error.to_string()),
+        error => value_error(&error),
+    }
+}
+
+/// Metadata of one `fun` definition; raises `ValueError` if it does not parse
+#[pyfunction]
+fn extract_function_metadata(py: Python<'_>, function_text: &str) -> PyResult<PyObject> {
+    let metadata = extract_one(function_text).map_err(extract_error)?;
+    to_python(py, &metadata)
+}
+
+/// Metadata of every function in a TypeQL source
+#[pyfunction]
+fn extract_functions(py: Python<'_>, source: &str) -> PyResult<PyObject> {
+    let functions = extract_functions_from_source(source).map_err(extract_error)?;
+    to_python(py, &functions)
+}
+
+/// Metadata of every function in the files matching the glob patterns
+#[pyfunction]
+fn extract_from_paths(py: Python<'_>, patterns: Vec<String>) -> PyResult<PyObject> {
+    let patterns: Vec<&str> = patterns.iter().map(String::as_str).collect();
+    let functions = extract_paths(&patterns).map_err(extract_error)?;
+    to_python(py, &functions)
+}
+
+/// [`FunctionRegistry`] for Python: functions by name with signature queries
+#[pyclass(name = "FunctionRegistry")]
+struct PyFunctionRegistry {
+    registry: FunctionRegistry,
+}
+
+#[pymethods]
+impl PyFunctionRegistry {
+    #[new]
+    fn new() -> Self {
+        PyFunctionRegistry {
+            registry: FunctionRegistry::new(),
+        }
+    }
+
+    /// Add the functions in `text`, remembering `path` as their source
+    fn add_source(&mut self, path: String, text: String) -> PyResult<()> {
+        self.registry.add_source(path, text).map_err(extract_error)
+    }
+
+    /// Add the functions in the files matching the glob patterns
+    fn load_paths(&mut self, patterns: Vec<String>) -> PyResult<()> {
+        let patterns: Vec<&str> = patterns.iter().map(String::as_str).collect();
+        let functions = extract_paths(&patterns).map_err(extract_error)?;
+        self.registry.extend(functions);
+        Ok(())
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.registry
+            .iter()
+            .map(|function| function.name.clone())
+            .collect()
+    }
+
+    /// The function called `name` as a dict, or `None`
+    fn get(&self, py: Python<'_>, name: &str) -> PyResult<Option<PyObject>> {
+        self.registry
+            .get(name)
+            .map(|function| to_python(py, function))
+            .transpose()
+    }
+
+    fn with_parameter_type(&self, py: Python<'_>, type_name: &str) -> PyResult<PyObject> {
+        let functions: Vec<&FunctionMetadata> =
+            self.registry.with_parameter_type(type_name).collect();
+        to_python(py, &functions)
+    }
+
+    fn returning(&self, py: Python<'_>, type_name: &str) -> PyResult<PyObject> {
+        let functions: Vec<&FunctionMetadata> = self.registry.returning(type_name).collect();
+        to_python(py, &functions)
+    }
+
+    fn calling(&self, py: Python<'_>, name: &str) -> PyResult<PyObject> {
+        let functions: Vec<&FunctionMetadata> = self.registry.calling(name).collect();
+        to_python(py, &functions)
+    }
+
+    fn call_graph(&self) -> PyCallGraph {
+        PyCallGraph {
+            graph: CallGraph::from_functions(self.registry.functions()),
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        self.registry.len()
+    }
+
+    fn __contains__(&self, name: &str) -> bool {
+        self.registry.contains(name)
+    }
+}
+
+/// [`CallGraph`] for Python, built with `FunctionRegistry.call_graph()`
+#[pyclass(name = "CallGraph")]
+struct PyCallGraph {
+    graph: CallGraph,
+}
+
+#[pymethods]
+impl PyCallGraph {
+    fn functions(&self) -> Vec<String> {
+        self.graph.functions().map(str::to_string).collect()
+    }
+
+    fn callees(&self, name: &str) -> Vec<String> {
+        self.graph.callees(name).to_vec()
+    }
+
+    fn callers_of(&self, name: &str) -> Vec<String> {
+        owned(self.graph.callers_of(name))
+    }
+
+    fn all_callers_of(&self, name: &str) -> Vec<String> {
+        owned(self.graph.all_callers_of(name))
+    }
+
+    fn reachable_from(&self, entries: Vec<String>) -> Vec<String> {
+        let entries: Vec<&str> = entries.iter().map(String::as_str).collect();
+        owned(self.graph.reachable_from(&entries))
+    }
+
+    /// Raises `ValueError` naming a cycle if there is one
+    fn topological_order(&self) -> PyResult<Vec<String>> {
+        self.graph
+            .topological_order()
+            .map(owned)
+            .map_err(|error| value_error(&error))
+    }
+
+    fn cycles(&self) -> Vec<Vec<String>> {
+        self.graph.cycles().into_iter().map(owned).collect()
+    }
+
+    fn recursive_components(&self) -> Vec<Vec<String>> {
+        self.graph
+            .recursive_components()
+            .into_iter()
+            .map(owned)
+            .collect()
+    }
+
+    fn is_recursive(&self, name: &str) -> bool {
+        self.graph.is_recursive(name)
+    }
+
+    fn to_dot(&self) -> String {
+        self.graph.to_dot()
+    }
+
+    fn to_mermaid(&self) -> String {
+        self.graph.to_mermaid()
+    }
+}
+
+fn owned(names: Vec<&str>) -> Vec<String> {
+    names.into_iter().map(str::to_string).collect()
+}
+
+#[pymodule]
+fn typedb_examples(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(extract_function_metadata, module)?)?;
+    module.add_function(wrap_pyfunction!(extract_functions, module)?)?;
+    module.add_function(wrap_pyfunction!(extract_from_paths, module)?)?;
+    module.add_class::<PyFunctionRegistry>()?;
+    module.add_class::<PyCallGraph>()?;
+    Ok(())
+}
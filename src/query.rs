@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use typeql::query::stage::reduce::Reducer;
+use typeql::query::stage::{Operator, Stage};
+use typeql::query::{Pipeline, Query};
+use typeql::Variable;
+
+use crate::body::{extract_constraints, extract_patterns, BodyPattern, Constraint};
+use crate::diagnostic::Diagnostic;
+use crate::extract_function_metadata::{
+    extract_referenced_functions, extract_referenced_types, variable_name,
+};
+use crate::fetch::{extract_fetch, FetchValue};
+use crate::span::Span;
+
+/// Metadata of a standalone TypeQL data query, i.e. a pipeline of stages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryMetadata {
+    /// Stages in pipeline order
+    pub stages: Vec<StageKind>,
+    /// Named variables without `$`, in order of first appearance
+    pub variables: Vec<String>,
+    /// Functions defined in `with` preambles
+    pub preamble_functions: Vec<String>,
+    pub referenced_functions: Vec<String>,
+    pub referenced_types: Vec<String>,
+    /// Constraints of the `match` and write stages, flattened as for functions
+    pub constraints: Vec<Constraint>,
+    pub patterns: Vec<BodyPattern>,
+    /// Structure of the final `fetch` stage, if any
+    pub fetch: Option<FetchValue>,
+    pub span: Option<Span>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum StageKind {
+    Match,
+    Insert,
+    Put,
+    Update,
+    Delete,
+    Fetch,
+    Select,
+    Sort,
+    Offset,
+    Limit,
+    Reduce,
+    Require,
+    Distinct,
+}
+
+impl fmt::Display for StageKind {
+    /// The stage keyword, e.g. `match`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keyword = match self {
+            StageKind::Match => "match",
+            StageKind::Insert => "insert",
+            StageKind::Put => "put",
+            StageKind::Update => "update",
+            StageKind::Delete => "delete",
+            StageKind::Fetch => "fetch",
+            StageKind::Select => "select",
+            StageKind::Sort => "sort",
+            StageKind::Offset => "offset",
+            StageKind::Limit => "limit",
+            StageKind::Reduce => "reduce",
+            StageKind::Require => "require",
+            StageKind::Distinct => "distinct",
+        };
+        f.write_str(keyword)
+    }
+}
+
+impl QueryMetadata {
+    /// Whether any stage writes data
+    pub fn is_write(&self) -> bool {
+        self.stages.iter().any(|stage| {
+            matches!(
+                stage,
+                StageKind::Insert | StageKind::Put | StageKind::Update | StageKind::Delete
+            )
+        })
+    }
+}
+
+/// Extract metadata from a TypeQL data query such as `match ... fetch { ... };`.
+///
+/// Schema queries (`define`, `redefine`, `undefine`) are rejected with an
+/// `unsupported-query` diagnostic.
+pub fn extract_query_metadata(query_text: &str) -> Result<QueryMetadata, Diagnostic> {
+    let query = typeql::parse_query(query_text)
+        .map_err(|error| Diagnostic::parse_error(&error, query_text, 0))?;
+    let pipeline = match query {
+        Query::Pipeline(pipeline) => pipeline,
+        Query::Schema(_) => {
+            return Err(Diagnostic::error(
+                "unsupported-query",
+                "expected a data query, not a schema query",
+            )
+            .with_span(Some(Span::new(query_text, 0, query_text.len()))))
+        }
+    };
+
+    let constraints = extract_constraints(&pipeline.stages);
+    Ok(QueryMetadata {
+        stages: pipeline.stages.iter().map(stage_kind).collect(),
+        variables: query_variables(&pipeline, &constraints),
+        preamble_functions: pipeline
+            .preambles
+            .iter()
+            .map(|preamble| preamble.function.signature.ident.as_str().to_string())
+            .collect(),
+        referenced_functions: extract_referenced_functions(&pipeline.stages),
+        referenced_types: extract_referenced_types(&pipeline.stages),
+        patterns: extract_patterns(&pipeline.stages),
+        constraints,
+        fetch: extract_fetch(&pipeline.stages),
+        span: pipeline
+            .span
+            .map(|span| Span::new(query_text, span.begin_offset, span.end_offset)),
+    })
+}
+
+pub(crate) fn stage_kind(stage: &Stage) -> StageKind {
+    match stage {
+        Stage::Match(_) => StageKind::Match,
+        Stage::Insert(_) => StageKind::Insert,
+        Stage::Put(_) => StageKind::Put,
+        Stage::Update(_) => StageKind::Update,
+        Stage::Delete(_) => StageKind::Delete,
+        Stage::Fetch(_) => StageKind::Fetch,
+        Stage::Operator(Operator::Select(_)) => StageKind::Select,
+        Stage::Operator(Operator::Sort(_)) => StageKind::Sort,
+        Stage::Operator(Operator::Offset(_)) => StageKind::Offset,
+        Stage::Operator(Operator::Limit(_)) => StageKind::Limit,
+        Stage::Operator(Operator::Reduce(_)) => StageKind::Reduce,
+        Stage::Operator(Operator::Require(_)) => StageKind::Require,
+        Stage::Operator(Operator::Distinct(_)) => StageKind::Distinct,
+    }
+}
+
+/// Variables of the constraints followed by those only named by operators, such as the
+/// targets of `reduce`
+fn query_variables(pipeline: &Pipeline, constraints: &[Constraint]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut add = |name: &str| {
+        if name != "_" && !names.iter().any(|known| known == name) {
+            names.push(name.to_string());
+        }
+    };
+    for constraint in constraints {
+        constraint.variables.iter().for_each(|var| add(var));
+    }
+
+    let mut operator_variables: Vec<&Variable> = Vec::new();
+    for stage in &pipeline.stages {
+        match stage {
+            Stage::Operator(Operator::Select(select)) => {
+                operator_variables.extend(&select.variables)
+            }
+            Stage::Operator(Operator::Require(require)) => {
+                operator_variables.extend(&require.variables)
+            }
+            Stage::Operator(Operator::Sort(sort)) => operator_variables.extend(
+                sort.ordered_variables
+                    .iter()
+                    .map(|ordered| &ordered.variable),
+            ),
+            Stage::Operator(Operator::Reduce(reduce)) => {
+                for assignment in &reduce.reduce_assignments {
+                    operator_variables.push(&assignment.assign_to);
+                    match &assignment.reducer {
+                        Reducer::Count(count) => operator_variables.extend(&count.variable),
+                        Reducer::Stat(stat) => operator_variables.push(&stat.variable),
+                    }
+                }
+                operator_variables.extend(reduce.groupby.iter().flatten());
+            }
+            _ => {}
+        }
+    }
+    operator_variables
+        .into_iter()
+        .filter_map(variable_name)
+        .for_each(add);
+    names
+}
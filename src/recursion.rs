@@ -0,0 +1,112 @@
+//! A review of recursive functions.
+//!
+//! TypeDB evaluates recursive functions until no new answers appear, which needs the
+//! recursion to be stratified: a function may not depend on itself through a negation or
+//! an aggregate, as neither can be decided before the recursion completes. The report
+//! lists every group of recursive functions with the calls and reductions to check.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::call_graph::CallGraph;
+use crate::extract_function_metadata::{FunctionMetadata, ReturnExpr};
+use crate::query::StageKind;
+
+/// Functions that call each other, directly or through the others in the group
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecursiveGroup {
+    /// The functions, in input order; a single function calls itself
+    pub functions: Vec<String>,
+    /// Calls between functions of the group, as `(caller, callee)`
+    pub calls: Vec<(String, String)>,
+    /// Calls within the group made from a `not { ... }` block
+    pub negated_calls: Vec<(String, String)>,
+    /// Functions of the group that aggregate with `reduce` or a reducing return
+    pub reducing: Vec<String>,
+}
+
+impl RecursiveGroup {
+    /// Whether several functions take part, rather than one calling itself
+    pub fn is_mutual(&self) -> bool {
+        self.functions.len() > 1
+    }
+
+    /// Whether the recursion avoids negation and aggregates
+    pub fn is_stratified(&self) -> bool {
+        self.negated_calls.is_empty() && self.reducing.is_empty()
+    }
+}
+
+/// Every group of recursive functions in `functions`, called functions first
+pub fn recursion_report(functions: &[FunctionMetadata]) -> Vec<RecursiveGroup> {
+    let graph = CallGraph::from_functions(functions);
+    graph
+        .recursive_components()
+        .into_iter()
+        .map(|component| {
+            let members: Vec<&FunctionMetadata> = component
+                .iter()
+                .filter_map(|name| functions.iter().find(|function| function.name == *name))
+                .collect();
+            let mut group = RecursiveGroup {
+                functions: component.iter().map(|name| name.to_string()).collect(),
+                calls: Vec::new(),
+                negated_calls: Vec::new(),
+                reducing: Vec::new(),
+            };
+            for function in members {
+                for callee in graph.callees(&function.name) {
+                    if component.contains(&callee.as_str()) {
+                        group.calls.push((function.name.clone(), callee.clone()));
+                    }
+                }
+                for call_site in &function.call_sites {
+                    let call = (function.name.clone(), call_site.function.clone());
+                    if call_site.negation_depth > 0
+                        && component.contains(&call_site.function.as_str())
+                        && !group.negated_calls.contains(&call)
+                    {
+                        group.negated_calls.push(call);
+                    }
+                }
+                if is_reducing(function) {
+                    group.reducing.push(function.name.clone());
+                }
+            }
+            group
+        })
+        .collect()
+}
+
+/// `mutually recursive: a, b` followed by one indented line per call or reduction that
+/// makes the recursion unstratified
+impl fmt::Display for RecursiveGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_mutual() {
+            write!(f, "mutually recursive: {}", self.functions.join(", "))?;
+        } else {
+            write!(f, "recursive: {}", self.functions[0])?;
+        }
+        for (caller, callee) in &self.negated_calls {
+            write!(
+                f,
+                "\n  `{}` calls `{}` inside `not`, recursion through negation",
+                caller, callee
+            )?;
+        }
+        for function in &self.reducing {
+            write!(
+                f,
+                "\n  `{}` aggregates its matches, recursion through an aggregate",
+                function
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether the function returns an aggregate or has a `reduce` stage
+fn is_reducing(function: &FunctionMetadata) -> bool {
+    matches!(function.return_expression, ReturnExpr::Reduce { .. })
+        || function.stages.contains(&StageKind::Reduce)
+}
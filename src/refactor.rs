@@ -0,0 +1,792 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::ops::Range;
+
+use crate::error::ExtractError;
+use crate::extract_function_metadata::{
+    extract_function_at, extract_function_metadata, FunctionMetadata, ReturnExpr, TypeKind,
+    VALUE_TYPES,
+};
+use crate::generate::STAGE_KEYWORDS;
+use crate::lexer::{function_ranges, tokenize, Token, TokenKind};
+use crate::literals::Literal;
+use crate::registry::{FunctionRegistry, SourceFile};
+
+/// Where a function refers to a schema type
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TypeImpact {
+    pub function: String,
+    /// Parameters declared with the type, including lists and optionals of it
+    pub parameters: Vec<String>,
+    /// Whether the type appears in the declared output
+    pub in_output: bool,
+    /// Whether the body matches on or otherwise mentions the type
+    pub in_body: bool,
+}
+
+/// A function with one of its literals turned into a parameter
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParameterizedFunction {
+    /// The rewritten definition
+    pub function: String,
+    /// Calls in other functions to update so they keep passing the literal
+    pub call_sites: Vec<CallSiteUpdate>,
+}
+
+/// A call to rewrite after a function's signature changed
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CallSiteUpdate {
+    /// The function making the call
+    pub caller: String,
+    /// The call as written, e.g. `get_tax($p)`
+    pub call: String,
+    /// The call with the new argument, e.g. `get_tax($p, 0.25)`
+    pub updated: String,
+}
+
+/// Rename a function, rewriting its definition and every call site in the registry.
+///
+/// Only whole identifiers in definition (`fun name(`) or call (`name(`) position are
+/// touched, so labels, strings, comments and names containing `old_name` are left alone.
+/// Registry metadata is updated to match, keeping each function's source file, spans
+/// and extensions. Returns the edited text of every loaded file that changed, in load
+/// order.
+///
+/// Fails with [`ExtractError::DuplicateFunction`] if a loaded file still defines
+/// `new_name`, such as a function removed from the registry.
+pub fn rename_function(
+    registry: &mut FunctionRegistry,
+    old_name: &str,
+    new_name: &str,
+) -> Result<Vec<SourceFile>, ExtractError> {
+    if !registry.contains(old_name) {
+        return Err(ExtractError::UnknownFunction {
+            name: old_name.to_string(),
+        });
+    }
+    if registry.contains(new_name) {
+        return Err(invalid(format!(
+            "a function named `{}` already exists",
+            new_name
+        )));
+    }
+    if !is_identifier(new_name) {
+        return Err(invalid(format!(
+            "`{}` is not a valid function name",
+            new_name
+        )));
+    }
+
+    // A definition removed from the registry but still in its file would clash
+    if registry
+        .sources()
+        .iter()
+        .any(|source| defines(&source.text, new_name))
+    {
+        return Err(ExtractError::DuplicateFunction {
+            name: new_name.to_string(),
+        });
+    }
+
+    for (function, origin) in registry.functions_mut() {
+        // Functions loaded from files are re-extracted from the rewritten text below,
+        // matched by name
+        if origin.is_some() {
+            if function.name == old_name {
+                function.name = new_name.to_string();
+            }
+            continue;
+        }
+        let calls_old_name = function
+            .referenced_functions
+            .iter()
+            .any(|callee| callee == old_name);
+        if function.name != old_name && !calls_old_name {
+            continue;
+        }
+        let renamed = rename_in_text(&function.to_typeql(), old_name, new_name);
+        reextract(function, &renamed)?;
+    }
+    registry.reindex();
+
+    let mut changed = Vec::new();
+    for file in 0..registry.sources().len() {
+        let text = &registry.sources()[file].text;
+        let renamed = rename_in_text(text, old_name, new_name);
+        if renamed != *text {
+            registry.replace_source(file, renamed)?;
+            changed.push(registry.sources()[file].clone());
+        }
+    }
+
+    Ok(changed)
+}
+
+/// List every function whose parameters, output or body reference the schema type `label`
+pub fn type_impact(functions: &[FunctionMetadata], label: &str) -> Vec<TypeImpact> {
+    functions
+        .iter()
+        .filter_map(|function| {
+            let impact =
+                TypeImpact {
+                    function: function.name.clone(),
+                    parameters: function
+                        .parameters
+                        .iter()
+                        .filter(|parameter| parameter.label() == Some(label))
+                        .map(|parameter| parameter.name.clone())
+                        .collect(),
+                    in_output: function.output.types().iter().any(|type_name| {
+                        type_name.kind == TypeKind::Label && type_name.name == label
+                    }),
+                    in_body: function.referenced_types.iter().any(|used| used == label),
+                };
+            let affected = !impact.parameters.is_empty() || impact.in_output || impact.in_body;
+            affected.then_some(impact)
+        })
+        .collect()
+}
+
+/// Rename a schema type in every function signature and body in the registry, and in
+/// the `define` statements of loaded files.
+///
+/// Function names, variables, strings, comments and role names are left alone, and each
+/// function keeps its source file, spans and extensions. Returns the edited text of every
+/// loaded file that changed, in load order.
+pub fn rename_type(
+    registry: &mut FunctionRegistry,
+    old_label: &str,
+    new_label: &str,
+) -> Result<Vec<SourceFile>, ExtractError> {
+    if VALUE_TYPES.contains(&old_label) {
+        return Err(invalid(format!("`{}` is a built-in value type", old_label)));
+    }
+    if !is_identifier(new_label) || VALUE_TYPES.contains(&new_label) {
+        return Err(invalid(format!(
+            "`{}` is not a valid type label",
+            new_label
+        )));
+    }
+
+    for (function, origin) in registry.functions_mut() {
+        // Functions loaded from files are re-extracted from the rewritten text below
+        if origin.is_some() {
+            continue;
+        }
+        let renamed = rename_label_in_text(&function.to_typeql(), old_label, new_label);
+        reextract(function, &renamed)?;
+    }
+
+    let mut changed = Vec::new();
+    for file in 0..registry.sources().len() {
+        let text = &registry.sources()[file].text;
+        let renamed = rename_label_in_text(text, old_label, new_label);
+        if renamed != *text {
+            registry.replace_source(file, renamed)?;
+            changed.push(registry.sources()[file].clone());
+        }
+    }
+
+    Ok(changed)
+}
+
+/// Rewrite `caller` with every `let ... in callee(...)` replaced by the body of `callee`,
+/// returning the new definition.
+///
+/// The callee's parameters become the arguments of each call, with literal arguments
+/// bound by `let`, and its returned variables become those the call assigned. Its other
+/// variables are renamed where the caller already uses their names, so inlining never
+/// captures a caller variable. Only functions returning a stream from a single `match`
+/// stage can be inlined, as other returns pick or aggregate matches.
+pub fn inline_function(
+    caller: &FunctionMetadata,
+    callee: &FunctionMetadata,
+) -> Result<String, ExtractError> {
+    if caller.name == callee.name {
+        return Err(invalid(format!(
+            "`{}` cannot be inlined into itself",
+            caller.name
+        )));
+    }
+    let ReturnExpr::Stream { vars: returned } = &callee.return_expression else {
+        return Err(invalid(format!(
+            "`{}` does not return a stream; only functions returning `{{ ... }}` can be inlined",
+            callee.name
+        )));
+    };
+    let patterns = match_patterns(callee)?;
+
+    let text = caller.to_typeql();
+    let tokens: Vec<Token> = tokenize(&text)
+        .into_iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .collect();
+    let mut used: HashSet<&str> = tokens
+        .iter()
+        .filter(|token| token.kind == TokenKind::Variable)
+        .map(|token| &token.text[1..])
+        .collect();
+    let mut fresh_names: Vec<String> = Vec::new();
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+
+    for (position, token) in tokens.iter().enumerate() {
+        if !token.is_word(&callee.name)
+            || !tokens
+                .get(position + 1)
+                .is_some_and(|next| next.is_punct("("))
+        {
+            continue;
+        }
+        let previous = position.checked_sub(1).map(|previous| &tokens[previous]);
+        if previous.is_some_and(|previous| {
+            previous.is_word("fun") || previous.is_word("isa") || previous.is_word("sub")
+        }) {
+            continue;
+        }
+        if !previous.is_some_and(|previous| previous.is_word("in")) {
+            return Err(invalid(format!(
+                "`{}` calls `{}` outside `let ... in`, which cannot be inlined",
+                caller.name, callee.name
+            )));
+        }
+
+        // `let $a, $b in` before the call
+        let start = tokens[..position]
+            .iter()
+            .rposition(|token| token.is_word("let"))
+            .ok_or_else(|| {
+                invalid(format!(
+                    "expected `let` before the call to `{}`",
+                    callee.name
+                ))
+            })?;
+        let assigned: Vec<&str> = tokens[start + 1..position - 1]
+            .iter()
+            .filter(|token| !token.is_punct(","))
+            .map(|token| &token.text[1..])
+            .collect();
+        // Arguments up to the closing parenthesis, then the `;`
+        let close = tokens[position..]
+            .iter()
+            .position(|token| token.is_punct(")"))
+            .map(|offset| position + offset)
+            .ok_or_else(|| invalid(format!("unterminated call to `{}`", callee.name)))?;
+        let arguments: Vec<&[Token]> = tokens[position + 2..close]
+            .split(|token| token.is_punct(","))
+            .filter(|argument| !argument.is_empty())
+            .collect();
+        if !tokens
+            .get(close + 1)
+            .is_some_and(|token| token.is_punct(";"))
+        {
+            return Err(invalid(format!(
+                "expected `;` after the call to `{}`",
+                callee.name
+            )));
+        }
+        if assigned.len() != returned.len() {
+            return Err(invalid(format!(
+                "the call assigns {} variables but `{}` returns {}",
+                assigned.len(),
+                callee.name,
+                returned.len()
+            )));
+        }
+        if arguments.len() != callee.parameters.len() {
+            return Err(invalid(format!(
+                "the call passes {} arguments but `{}` takes {}",
+                arguments.len(),
+                callee.name,
+                callee.parameters.len()
+            )));
+        }
+
+        let mut fresh = |name: &str, used: &mut HashSet<&str>| {
+            let mut candidate = name.to_string();
+            let mut suffix = 1;
+            while used.contains(candidate.as_str()) || fresh_names.contains(&candidate) {
+                suffix += 1;
+                candidate = format!("{}_{}", name, suffix);
+            }
+            fresh_names.push(candidate.clone());
+            candidate
+        };
+        let mut renames: HashMap<&str, String> = HashMap::new();
+        let mut statements = Vec::new();
+        for (parameter, argument) in callee.parameters.iter().zip(&arguments) {
+            match argument {
+                [variable] if variable.kind == TokenKind::Variable => {
+                    renames.insert(&parameter.name, variable.text[1..].to_string());
+                }
+                [literal] if matches!(literal.kind, TokenKind::Word | TokenKind::String) => {
+                    let name = fresh(&parameter.name, &mut used);
+                    statements.push(format!("let ${} = {};", name, literal.text));
+                    renames.insert(&parameter.name, name);
+                }
+                _ => {
+                    return Err(invalid(format!(
+                        "argument for `${}` of `{}` is not a variable or literal",
+                        parameter.name, callee.name
+                    )))
+                }
+            }
+        }
+        for (var, target) in returned.iter().zip(&assigned) {
+            match renames.get(var.as_str()) {
+                // A parameter returned as is
+                Some(bound) => statements.push(format!("${} is ${};", target, bound)),
+                None => {
+                    renames.insert(var, target.to_string());
+                }
+            }
+        }
+
+        let mut body = String::new();
+        let mut last_end = 0;
+        for token in tokenize(&patterns) {
+            if token.kind != TokenKind::Variable || token.text == "$_" {
+                continue;
+            }
+            let var = &token.text[1..];
+            let renamed = match renames.get(var) {
+                Some(renamed) => renamed.clone(),
+                None => {
+                    let renamed = fresh(var, &mut used);
+                    renames.insert(var, renamed.clone());
+                    renamed
+                }
+            };
+            body.push_str(&patterns[last_end..token.range.start]);
+            write!(body, "${}", renamed).unwrap();
+            last_end = token.range.end;
+        }
+        body.push_str(&patterns[last_end..]);
+        statements.extend(
+            body.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string),
+        );
+
+        // Statements go on lines of their own when the call had one, else stay on its line
+        let line_start = text[..tokens[start].range.start]
+            .rfind('\n')
+            .map_or(0, |newline| newline + 1);
+        let before = &text[line_start..tokens[start].range.start];
+        let separator = if before.trim().is_empty() {
+            format!("\n{}", before)
+        } else {
+            " ".to_string()
+        };
+        edits.push((
+            tokens[start].range.start..tokens[close + 1].range.end,
+            statements.join(&separator),
+        ));
+    }
+
+    if edits.is_empty() {
+        return Err(invalid(format!(
+            "`{}` does not call `{}`",
+            caller.name, callee.name
+        )));
+    }
+    let mut inlined = text.clone();
+    for (range, replacement) in edits.into_iter().rev() {
+        inlined.replace_range(range, &replacement);
+    }
+    extract_function_at(&inlined, 0..inlined.len()).map_err(|diagnostic| {
+        invalid(format!(
+            "the inlined function does not parse: {}",
+            diagnostic.message
+        ))
+    })?;
+    Ok(inlined)
+}
+
+/// Turn `literal`, found in `source` by [`extract_literals`], into a new last parameter
+/// `$parameter` of the function containing it.
+///
+/// Only the selected occurrence is replaced; recursive calls pass the new parameter on.
+/// Calls from the other functions in `registry` are listed with the literal appended to
+/// their arguments, so their results do not change.
+///
+/// [`extract_literals`]: crate::literals::extract_literals
+pub fn literal_to_parameter(
+    source: &str,
+    literal: &Literal,
+    parameter: &str,
+    registry: &FunctionRegistry,
+) -> Result<ParameterizedFunction, ExtractError> {
+    let parameter = parameter.trim_start_matches('$');
+    if !is_identifier(parameter) {
+        return Err(invalid(format!(
+            "`{}` is not a valid variable name",
+            parameter
+        )));
+    }
+    let range = function_ranges(source)
+        .into_iter()
+        .find(|range| range.start <= literal.span.start && literal.span.end <= range.end)
+        .ok_or_else(|| invalid(format!("no function contains line {}", literal.span.line)))?;
+    if source.get(literal.span.start..literal.span.end) != Some(literal.value.as_str()) {
+        return Err(invalid(format!(
+            "`{}` is not at line {}, column {}",
+            literal.value, literal.span.line, literal.span.column
+        )));
+    }
+
+    let text = &source[range.clone()];
+    let tokens: Vec<Token> = tokenize(text)
+        .into_iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .collect();
+    let variable = format!("${}", parameter);
+    if tokens.iter().any(|token| token.text == variable) {
+        return Err(invalid(format!(
+            "`{}` already uses `{}`",
+            literal.function, variable
+        )));
+    }
+    let open = tokens
+        .iter()
+        .position(|token| token.is_punct("("))
+        .ok_or_else(|| invalid(format!("`{}` has no parameter list", literal.function)))?;
+    let close = tokens[open..]
+        .iter()
+        .position(|token| token.is_punct(")"))
+        .map(|offset| open + offset)
+        .ok_or_else(|| invalid(format!("`{}` has no parameter list", literal.function)))?;
+
+    let declaration = format!("{}: {}", variable, literal.value_type());
+    let mut edits: Vec<(Range<usize>, String)> = vec![(
+        tokens[close].range.start..tokens[close].range.start,
+        if close == open + 1 {
+            declaration
+        } else {
+            format!(", {}", declaration)
+        },
+    )];
+    let literal_start = literal.span.start - range.start;
+    edits.push((
+        literal_start..literal.span.end - range.start,
+        variable.clone(),
+    ));
+    for (_, call_close) in calls(&tokens[close..], &literal.function) {
+        edits.push((
+            call_close.range.start..call_close.range.start,
+            argument_after(&tokens[close..], call_close, &variable),
+        ));
+    }
+    edits.sort_by_key(|(range, _)| range.start);
+    let mut function = text.to_string();
+    for (range, replacement) in edits.into_iter().rev() {
+        function.replace_range(range, &replacement);
+    }
+    extract_function_at(&function, 0..function.len()).map_err(|diagnostic| {
+        invalid(format!(
+            "the rewritten function does not parse: {}",
+            diagnostic.message
+        ))
+    })?;
+
+    let mut call_sites = Vec::new();
+    for caller in registry.calling(&literal.function) {
+        if caller.name == literal.function {
+            continue;
+        }
+        let code = &caller.code_block;
+        let tokens: Vec<Token> = tokenize(code)
+            .into_iter()
+            .filter(|token| token.kind != TokenKind::Comment)
+            .collect();
+        for (name, call_close) in calls(&tokens, &literal.function) {
+            let call = &code[name.range.start..call_close.range.end];
+            let updated = format!(
+                "{}{})",
+                &code[name.range.start..call_close.range.start],
+                argument_after(&tokens, call_close, &literal.value)
+            );
+            call_sites.push(CallSiteUpdate {
+                caller: caller.name.clone(),
+                call: call.split_whitespace().collect::<Vec<_>>().join(" "),
+                updated: updated.split_whitespace().collect::<Vec<_>>().join(" "),
+            });
+        }
+    }
+
+    Ok(ParameterizedFunction {
+        function,
+        call_sites,
+    })
+}
+
+/// The name and closing parenthesis of every call to `name` in `tokens`
+fn calls<'t, 'a>(tokens: &'t [Token<'a>], name: &str) -> Vec<(&'t Token<'a>, &'t Token<'a>)> {
+    let mut calls = Vec::new();
+    for (position, token) in tokens.iter().enumerate() {
+        if !token.is_word(name)
+            || !tokens
+                .get(position + 1)
+                .is_some_and(|next| next.is_punct("("))
+        {
+            continue;
+        }
+        let previous = position.checked_sub(1).map(|previous| &tokens[previous]);
+        if previous.is_some_and(|previous| {
+            previous.is_word("fun") || previous.is_word("isa") || previous.is_word("sub")
+        }) {
+            continue;
+        }
+        let mut depth = 0;
+        for token in &tokens[position + 1..] {
+            if token.is_punct("(") {
+                depth += 1;
+            } else if token.is_punct(")") {
+                depth -= 1;
+                if depth == 0 {
+                    calls.push((&tokens[position], token));
+                    break;
+                }
+            }
+        }
+    }
+    calls
+}
+
+/// `argument`, preceded by `, ` unless the call ending at `close` has no arguments yet
+fn argument_after(tokens: &[Token], close: &Token, argument: &str) -> String {
+    let empty = tokens
+        .iter()
+        .position(|token| token.range == close.range)
+        .is_some_and(|position| position > 0 && tokens[position - 1].is_punct("("));
+    if empty {
+        argument.to_string()
+    } else {
+        format!(", {}", argument)
+    }
+}
+
+/// The patterns of a function body that is a single `match` stage
+fn match_patterns(function: &FunctionMetadata) -> Result<String, ExtractError> {
+    let code = &function.code_block;
+    let tokens: Vec<Token> = tokenize(code)
+        .into_iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .collect();
+    if !tokens.first().is_some_and(|token| token.is_word("match")) {
+        return Err(invalid(format!(
+            "`{}` does not start with a `match` stage",
+            function.name
+        )));
+    }
+    let mut depth = 0;
+    for (position, token) in tokens.iter().enumerate().skip(1) {
+        if token.is_punct("{") {
+            depth += 1;
+        } else if token.is_punct("}") {
+            depth -= 1;
+        }
+        let starts_statement = tokens[position - 1].is_punct(";");
+        if depth != 0 || !starts_statement || token.kind != TokenKind::Word {
+            continue;
+        }
+        if token.text == "return" {
+            return Ok(code[tokens[0].range.end..token.range.start].to_string());
+        }
+        if STAGE_KEYWORDS.contains(&token.text) {
+            return Err(invalid(format!(
+                "`{}` has a `{}` stage; only a single `match` can be inlined",
+                function.name, token.text
+            )));
+        }
+    }
+    Err(invalid(format!(
+        "`{}` has no return statement",
+        function.name
+    )))
+}
+
+/// Replace `function` with the metadata of its rewritten definition `text`, keeping
+/// where it was read from and what extractors added.
+///
+/// Renames change neither the parameters nor the number of calls, so their spans carry
+/// over in order.
+fn reextract(function: &mut FunctionMetadata, text: &str) -> Result<(), ExtractError> {
+    let mut rewritten = extract_function_metadata(text)?;
+    rewritten.source_file = function.source_file.take();
+    rewritten.extensions = std::mem::take(&mut function.extensions);
+    rewritten.spans = std::mem::take(&mut function.spans);
+    for (parameter, previous) in rewritten.parameters.iter_mut().zip(&function.parameters) {
+        parameter.span = previous.span;
+    }
+    for (call_site, previous) in rewritten.call_sites.iter_mut().zip(&function.call_sites) {
+        call_site.span = previous.span;
+    }
+    *function = rewritten;
+    Ok(())
+}
+
+/// Whether `text` has a `fun name` definition
+fn defines(text: &str, name: &str) -> bool {
+    tokenize(text)
+        .windows(2)
+        .any(|pair| pair[0].is_word("fun") && pair[1].is_word(name))
+}
+
+/// Replace `old_name` wherever it is defined or called in `text`
+fn rename_in_text(text: &str, old_name: &str, new_name: &str) -> String {
+    let tokens = tokenize(text);
+    let mut renamed = String::with_capacity(text.len());
+    let mut last_end = 0;
+
+    for (position, token) in tokens.iter().enumerate() {
+        if !token.is_word(old_name) {
+            continue;
+        }
+        let previous = position.checked_sub(1).map(|previous| &tokens[previous]);
+        let next = tokens.get(position + 1);
+        let is_definition = previous.is_some_and(|previous| previous.is_word("fun"));
+        // `$r isa employment (employee: $x)` puts a label, not a call, before `(`
+        let is_call = next.is_some_and(|next| next.is_punct("("))
+            && !previous.is_some_and(|previous| {
+                previous.is_word("isa") || previous.is_word("sub") || previous.is_punct("!")
+            });
+        if is_definition || is_call {
+            renamed.push_str(&text[last_end..token.range.start]);
+            renamed.push_str(new_name);
+            last_end = token.range.end;
+        }
+    }
+
+    renamed.push_str(&text[last_end..]);
+    renamed
+}
+
+/// Replace `old_label` wherever it is used as a type label in `text`
+fn rename_label_in_text(text: &str, old_label: &str, new_label: &str) -> String {
+    let tokens = tokenize(text);
+    let mut renamed = String::with_capacity(text.len());
+    let mut last_end = 0;
+    let glued = |first: &Token, second: &Token| first.range.end == second.range.start;
+    // Parentheses open around the current token, as role-player tuples are
+    let mut depth = 0usize;
+
+    for (position, token) in tokens.iter().enumerate() {
+        if token.is_punct("(") {
+            depth += 1;
+        } else if token.is_punct(")") {
+            depth = depth.saturating_sub(1);
+        }
+        if !token.is_word(old_label) {
+            continue;
+        }
+        let previous = position.checked_sub(1).map(|previous| &tokens[previous]);
+        let next = tokens.get(position + 1);
+        let after_next = tokens.get(position + 2);
+
+        let is_definition = previous.is_some_and(|previous| previous.is_word("fun"));
+        let is_call = next.is_some_and(|next| next.is_punct("("))
+            && !previous.is_some_and(|previous| {
+                previous.is_word("isa") || previous.is_word("sub") || previous.is_punct("!")
+            });
+        // `employment:employee` scopes a role by its relation; the relation is a type
+        let is_scope = match (next, after_next) {
+            (Some(colon), Some(role)) => {
+                colon.is_punct(":") && glued(token, colon) && glued(colon, role)
+            }
+            _ => false,
+        };
+        let is_scoped_role = position >= 2 && {
+            let (scope, colon) = (&tokens[position - 2], &tokens[position - 1]);
+            scope.kind == TokenKind::Word
+                && colon.is_punct(":")
+                && glued(scope, colon)
+                && glued(colon, token)
+        };
+        // `(employee: $x)`, `relates employee` and `relates employee as worker` name
+        // roles; a type followed by `:` outside parentheses ends a function's output list
+        let is_role = !is_scope
+            && ((depth > 0 && next.is_some_and(|next| next.is_punct(":")))
+                || previous.is_some_and(|previous| {
+                    previous.is_word("relates")
+                        || previous.is_word("plays")
+                        || previous.is_word("as")
+                }));
+
+        if !(is_definition || is_call || is_scoped_role || is_role) {
+            renamed.push_str(&text[last_end..token.range.start]);
+            renamed.push_str(new_label);
+            last_end = token.range.end;
+        }
+    }
+
+    renamed.push_str(&text[last_end..]);
+    renamed
+}
+
+/// The error for a refactoring that cannot be applied
+fn invalid(message: impl Into<String>) -> ExtractError {
+    ExtractError::InvalidRefactoring {
+        message: message.into(),
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_alphabetic() || first == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATES: &str = r#"define
+
+fun get_rate($income: double) -> double:
+    match
+        let $rate = $income * 0.1;
+    return first $rate;
+
+fun get_tax($income: double) -> double:
+    match
+        let $tax = get_rate($income);
+    return first $tax;
+"#;
+
+    const OVERRIDES: &str = r#"define
+
+# Flat rate replacing the one in rates.tql
+fun get_rate($income: double) -> double:
+    match
+        let $rate = $income * 0.2;
+    return first $rate;
+"#;
+
+    #[test]
+    fn rename_function_across_files_defining_the_same_name() {
+        let mut registry = FunctionRegistry::new();
+        registry.add_source("rates.tql", RATES).unwrap();
+        registry.add_source("overrides.tql", OVERRIDES).unwrap();
+
+        let changed = rename_function(&mut registry, "get_rate", "get_flat_rate").unwrap();
+
+        let paths: Vec<&str> = changed.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(paths, ["rates.tql", "overrides.tql"]);
+        assert!(changed
+            .iter()
+            .all(|file| file.text.contains("fun get_flat_rate(")));
+        assert!(!registry.contains("get_rate"));
+        // The later file's definition is still the one loaded
+        let renamed = registry.get("get_flat_rate").unwrap();
+        assert_eq!(renamed.source_file.as_deref(), Some("overrides.tql"));
+        assert!(renamed.code_block.contains("0.2"));
+        assert_eq!(
+            registry.get("get_tax").unwrap().referenced_functions,
+            ["get_flat_rate"]
+        );
+    }
+}
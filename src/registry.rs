@@ -0,0 +1,306 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::call_graph::CallGraph;
+use crate::error::ExtractError;
+use crate::extract_function_metadata::{extract_functions_in, FunctionMetadata};
+use crate::namespace::{NamespaceConfig, Namespaces};
+
+/// A set of functions indexed by name, with queries over their signatures.
+///
+/// Functions loaded with [`FunctionRegistry::add_source`] remember the file they came
+/// from, so source-level refactorings can hand back the edited text.
+#[derive(Debug, Default)]
+pub struct FunctionRegistry {
+    functions: Vec<FunctionMetadata>,
+    /// Index into `sources` of the file each function was loaded from
+    origins: Vec<Option<usize>>,
+    index: HashMap<String, usize>,
+    sources: Vec<SourceFile>,
+}
+
+/// A TypeQL file whose functions were loaded into a registry
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub path: String,
+    pub text: String,
+}
+
+impl FunctionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a function, returning the previous definition with the same name if any
+    pub fn insert(&mut self, function: FunctionMetadata) -> Option<FunctionMetadata> {
+        self.insert_from(function, None)
+    }
+
+    /// Extract every function in `text` and add them, remembering `path` as their source
+    pub fn add_source(
+        &mut self,
+        path: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Result<(), ExtractError> {
+        let (path, text) = (path.into(), text.into());
+        let functions = extract_functions_in(&text)
+            .map_err(|diagnostic| ExtractError::from_diagnostic(diagnostic, &path, &text))?;
+        self.add_extracted(path, text, functions);
+        Ok(())
+    }
+
+    /// Add functions already extracted from `text`, remembering `path` as their source
+    pub(crate) fn add_extracted(
+        &mut self,
+        path: String,
+        text: String,
+        functions: Vec<FunctionMetadata>,
+    ) {
+        let file = self.sources.len();
+        for mut function in functions {
+            function.source_file = Some(path.clone());
+            self.insert_from(function, Some(file));
+        }
+        self.sources.push(SourceFile { path, text });
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<FunctionMetadata> {
+        let position = self.index.remove(name)?;
+        let removed = self.functions.remove(position);
+        self.origins.remove(position);
+        for slot in self.index.values_mut() {
+            if *slot > position {
+                *slot -= 1;
+            }
+        }
+        Some(removed)
+    }
+
+    /// Files loaded with [`FunctionRegistry::add_source`], in load order
+    pub fn sources(&self) -> &[SourceFile] {
+        &self.sources
+    }
+
+    /// The file `name` was loaded from, if it came from one
+    pub fn source_of(&self, name: &str) -> Option<&SourceFile> {
+        let position = *self.index.get(name)?;
+        self.origins[position].map(|file| &self.sources[file])
+    }
+
+    fn insert_from(
+        &mut self,
+        function: FunctionMetadata,
+        origin: Option<usize>,
+    ) -> Option<FunctionMetadata> {
+        match self.index.get(&function.name) {
+            Some(&position) => {
+                self.origins[position] = origin;
+                Some(std::mem::replace(&mut self.functions[position], function))
+            }
+            None => {
+                self.index
+                    .insert(function.name.clone(), self.functions.len());
+                self.functions.push(function);
+                self.origins.push(origin);
+                None
+            }
+        }
+    }
+
+    /// Replace the text of a loaded file and re-extract its functions in place.
+    ///
+    /// Functions are matched to their new definitions by name, so a refactoring renaming
+    /// a function renames its entry first. A name the file defines more than once takes
+    /// the last definition, as when the file was loaded, and definitions that were
+    /// removed or are loaded from a later file are skipped. Extensions are kept.
+    pub(crate) fn replace_source(&mut self, file: usize, text: String) -> Result<(), ExtractError> {
+        let path = &self.sources[file].path;
+        // Later definitions overwrite earlier ones with the same name
+        let mut extracted: HashMap<String, FunctionMetadata> = extract_functions_in(&text)
+            .map_err(|diagnostic| ExtractError::from_diagnostic(diagnostic, path, &text))?
+            .into_iter()
+            .map(|function| (function.name.clone(), function))
+            .collect();
+        let mut replacements = Vec::new();
+        for position in 0..self.functions.len() {
+            if self.origins[position] != Some(file) {
+                continue;
+            }
+            let name = &self.functions[position].name;
+            let function = extracted
+                .remove(name)
+                .ok_or_else(|| ExtractError::UnknownFunction { name: name.clone() })?;
+            replacements.push((position, function));
+        }
+
+        for (position, mut function) in replacements {
+            function.source_file = Some(path.clone());
+            function.extensions = std::mem::take(&mut self.functions[position].extensions);
+            self.functions[position] = function;
+        }
+        self.sources[file].text = text;
+        Ok(())
+    }
+
+    /// Mutable access for refactorings; callers must keep names unique and reindex
+    pub(crate) fn functions_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (&mut FunctionMetadata, Option<usize>)> {
+        self.functions.iter_mut().zip(self.origins.iter().copied())
+    }
+
+    pub(crate) fn reindex(&mut self) {
+        self.index = self
+            .functions
+            .iter()
+            .enumerate()
+            .map(|(position, function)| (function.name.clone(), position))
+            .collect();
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FunctionMetadata> {
+        self.index
+            .get(name)
+            .map(|&position| &self.functions[position])
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.index.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.functions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// All functions in insertion order
+    pub fn functions(&self) -> &[FunctionMetadata] {
+        &self.functions
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, FunctionMetadata> {
+        self.functions.iter()
+    }
+
+    /// Functions with at least one parameter of type `type_name`, ignoring list and
+    /// optional markers, e.g. `taxpayer` matches `$t: taxpayer` and `$ts: taxpayer[]`
+    pub fn with_parameter_type<'a>(
+        &'a self,
+        type_name: &'a str,
+    ) -> impl Iterator<Item = &'a FunctionMetadata> + 'a {
+        self.functions.iter().filter(move |function| {
+            function
+                .parameters
+                .iter()
+                .any(|parameter| base_type(&parameter.type_name) == type_name)
+        })
+    }
+
+    /// Functions whose output includes `type_name`, streamed or single
+    pub fn returning<'a>(
+        &'a self,
+        type_name: &'a str,
+    ) -> impl Iterator<Item = &'a FunctionMetadata> + 'a {
+        self.functions.iter().filter(move |function| {
+            function
+                .output
+                .types()
+                .iter()
+                .any(|output| output.name == type_name)
+        })
+    }
+
+    /// Functions returning a stream that includes `type_name`, e.g. `-> { person }`
+    pub fn returning_stream_of<'a>(
+        &'a self,
+        type_name: &'a str,
+    ) -> impl Iterator<Item = &'a FunctionMetadata> + 'a {
+        self.returning(type_name)
+            .filter(|function| function.output.is_stream())
+    }
+
+    /// Functions with `tag` among their categories, ignoring case
+    pub fn tagged<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a FunctionMetadata> + 'a {
+        self.functions
+            .iter()
+            .filter(move |function| function.has_tag(tag))
+    }
+
+    /// Every category used by a function, sorted
+    pub fn tags(&self) -> Vec<&str> {
+        let tags: BTreeSet<&str> = self
+            .functions
+            .iter()
+            .flat_map(|function| &function.tags)
+            .map(String::as_str)
+            .collect();
+        tags.into_iter().collect()
+    }
+
+    /// The namespace of each function, as assigned by `config`
+    pub fn namespaces(&self, config: &NamespaceConfig) -> Namespaces {
+        config.assign(&self.functions)
+    }
+
+    /// Functions no other function in the registry calls, leaving out the entry points
+    /// `entries` that queries call, in load order
+    pub fn orphans(&self, entries: &[&str]) -> Vec<&FunctionMetadata> {
+        let graph = CallGraph::from_functions(&self.functions);
+        graph
+            .orphans(entries)
+            .into_iter()
+            .filter_map(|name| self.get(name))
+            .collect()
+    }
+
+    /// Functions that call `name` directly
+    pub fn calling<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a FunctionMetadata> + 'a {
+        self.functions.iter().filter(move |function| {
+            function
+                .referenced_functions
+                .iter()
+                .any(|callee| callee == name)
+        })
+    }
+}
+
+impl FromIterator<FunctionMetadata> for FunctionRegistry {
+    fn from_iter<I: IntoIterator<Item = FunctionMetadata>>(functions: I) -> Self {
+        let mut registry = FunctionRegistry::new();
+        registry.extend(functions);
+        registry
+    }
+}
+
+impl Extend<FunctionMetadata> for FunctionRegistry {
+    fn extend<I: IntoIterator<Item = FunctionMetadata>>(&mut self, functions: I) {
+        for function in functions {
+            self.insert(function);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a FunctionRegistry {
+    type Item = &'a FunctionMetadata;
+    type IntoIter = std::slice::Iter<'a, FunctionMetadata>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.functions.iter()
+    }
+}
+
+impl IntoIterator for FunctionRegistry {
+    type Item = FunctionMetadata;
+    type IntoIter = std::vec::IntoIter<FunctionMetadata>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.functions.into_iter()
+    }
+}
+
+/// `taxpayer[]` and `taxpayer?` both have base type `taxpayer`
+fn base_type(type_name: &str) -> &str {
+    type_name.trim_end_matches(['[', ']', '?'])
+}
@@ -0,0 +1,239 @@
+//! An interactive session for exploring functions.
+//!
+//! Paste a `fun` definition to see its metadata, lint findings and an explanation; it is
+//! then added to the session's registry, which commands query:
+//!
+//! ```text
+//! > calls get_tax_bracket
+//! > show mutual_friends
+//! ```
+//!
+//! A definition may span several lines and ends at the `;` after its `return`, or at an
+//! empty line.
+
+use std::io::{self, BufRead, Write};
+
+use crate::error::ExtractError;
+use crate::explain::explain;
+use crate::extract_function_metadata::{extract_function_metadata, FunctionMetadata};
+use crate::ingest::extract_from_paths;
+use crate::lexer::{tokenize, Token, TokenKind};
+use crate::lint::{LintConfig, Linter};
+use crate::registry::FunctionRegistry;
+
+const HELP: &str = "\
+Paste a `fun` definition to extract it and add it to the session, or enter a command:
+  list                  functions in the session
+  show <function>       metadata of a function
+  json <function>       metadata as JSON
+  explain <function>    what a function does, in English
+  calls <function>      functions it calls
+  callers <function>    functions calling it
+  search <words>        functions matching words from their name, docs or body
+  lint                  lint findings for every function
+  load <path>...        add the functions in files, directories or globs
+  help                  this message
+  quit                  end the session
+";
+
+/// Read commands and definitions from `input` until it ends or `quit` is entered, writing
+/// prompts and answers to `output`. `registry` holds functions loaded beforehand.
+///
+/// Only failures to read `input` or write `output` end the session with an error.
+pub fn run(
+    input: impl BufRead,
+    output: impl Write,
+    registry: FunctionRegistry,
+    config: LintConfig,
+) -> Result<(), ExtractError> {
+    session(input, output, registry, config).map_err(ExtractError::stream)
+}
+
+fn session(
+    input: impl BufRead,
+    mut output: impl Write,
+    mut registry: FunctionRegistry,
+    config: LintConfig,
+) -> io::Result<()> {
+    let linter = Linter::new(config, &[]);
+    let mut lines = input.lines();
+    write!(output, "> ")?;
+    output.flush()?;
+
+    while let Some(line) = lines.next() {
+        let line = line?;
+        let trimmed = line.trim();
+        if trimmed.starts_with("fun ") || trimmed.starts_with('#') {
+            let mut text = line.clone();
+            while !is_complete(&text) {
+                write!(output, ". ")?;
+                output.flush()?;
+                match lines.next() {
+                    Some(line) if !line.as_ref().is_ok_and(|line| line.trim().is_empty()) => {
+                        text.push('\n');
+                        text.push_str(&line?);
+                    }
+                    _ => break,
+                }
+            }
+            define(&mut output, &mut registry, &linter, &text)?;
+        } else if !trimmed.is_empty() {
+            let (command, argument) = trimmed.split_once(' ').unwrap_or((trimmed, ""));
+            if matches!(command, "quit" | "exit") {
+                return Ok(());
+            }
+            command_output(
+                &mut output,
+                &mut registry,
+                &linter,
+                command,
+                argument.trim(),
+            )?;
+        }
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+    writeln!(output)?;
+    Ok(())
+}
+
+/// Whether a definition has reached the `;` ending its return statement
+fn is_complete(text: &str) -> bool {
+    let tokens: Vec<Token> = tokenize(text)
+        .into_iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .collect();
+    tokens.iter().any(|token| token.is_word("return"))
+        && tokens.last().is_some_and(|token| token.is_punct(";"))
+}
+
+fn define(
+    output: &mut impl Write,
+    registry: &mut FunctionRegistry,
+    linter: &Linter,
+    text: &str,
+) -> io::Result<()> {
+    let function = match extract_function_metadata(text) {
+        Ok(function) => function,
+        Err(error) => {
+            writeln!(output, "{}", error)?;
+            return Ok(());
+        }
+    };
+    let name = function.name.clone();
+    writeln!(output, "{}", function)?;
+    let replaced = registry.insert(function).is_some();
+
+    for finding in linter.run(registry.functions()) {
+        if finding.function.as_deref() == Some(name.as_str()) && !finding.suppressed {
+            writeln!(output, "{}", finding)?;
+        }
+    }
+    writeln!(output, "\n{}", explain(registry.get(&name).unwrap()))?;
+    writeln!(
+        output,
+        "{} `{}` ({} functions in the session)",
+        if replaced { "replaced" } else { "added" },
+        name,
+        registry.len()
+    )?;
+    Ok(())
+}
+
+fn command_output(
+    output: &mut impl Write,
+    registry: &mut FunctionRegistry,
+    linter: &Linter,
+    command: &str,
+    argument: &str,
+) -> io::Result<()> {
+    let needs_function = matches!(command, "show" | "json" | "explain" | "calls" | "callers");
+    if (needs_function || matches!(command, "search" | "load")) && argument.is_empty() {
+        writeln!(output, "`{}` needs an argument", command)?;
+        return Ok(());
+    }
+    if needs_function && command != "callers" && !registry.contains(argument) {
+        writeln!(output, "no function `{}`", argument)?;
+        return Ok(());
+    }
+
+    match command {
+        "help" => write!(output, "{}", HELP)?,
+        "list" => {
+            for function in registry.iter() {
+                writeln!(output, "{}", function.signature())?;
+            }
+            writeln!(output, "({} functions)", registry.len())?;
+        }
+        "show" => writeln!(output, "{}", registry.get(argument).unwrap())?,
+        "json" => writeln!(
+            output,
+            "{}",
+            serde_json::to_string_pretty(registry.get(argument).unwrap())?
+        )?,
+        "explain" => writeln!(output, "{}", explain(registry.get(argument).unwrap()))?,
+        "calls" => {
+            let callees = &registry.get(argument).unwrap().referenced_functions;
+            if callees.is_empty() {
+                writeln!(output, "`{}` calls no functions", argument)?;
+            }
+            for callee in callees {
+                let defined = if registry.contains(callee) {
+                    ""
+                } else {
+                    " (not in the session)"
+                };
+                writeln!(output, "{}{}", callee, defined)?;
+            }
+        }
+        "callers" => {
+            let callers: Vec<&FunctionMetadata> = registry.calling(argument).collect();
+            if callers.is_empty() {
+                writeln!(output, "no function calls `{}`", argument)?;
+            }
+            for caller in callers {
+                writeln!(output, "{}", caller.signature())?;
+            }
+        }
+        "search" => {
+            let hits = registry.search(argument);
+            if hits.is_empty() {
+                writeln!(output, "no matches")?;
+            }
+            for hit in hits.iter().take(10) {
+                writeln!(output, "{:6.2}  {}", hit.score, hit.function.signature())?;
+            }
+        }
+        "lint" => {
+            let findings = linter.run(registry.functions());
+            if findings.is_empty() {
+                writeln!(output, "no findings")?;
+            }
+            for finding in findings {
+                writeln!(output, "{}", finding)?;
+            }
+        }
+        "load" => {
+            let patterns: Vec<&str> = argument.split_whitespace().collect();
+            match extract_from_paths(&patterns) {
+                Ok(functions) => {
+                    let count = functions.len();
+                    registry.extend(functions);
+                    writeln!(
+                        output,
+                        "loaded {} functions ({} in the session)",
+                        count,
+                        registry.len()
+                    )?;
+                }
+                Err(error) => writeln!(output, "{}", error)?,
+            }
+        }
+        _ => writeln!(
+            output,
+            "unknown command `{}`; enter `help` for a list",
+            command
+        )?,
+    }
+    Ok(())
+}
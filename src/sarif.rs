@@ -0,0 +1,86 @@
+use serde_json::{json, Value};
+
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::extract_function_metadata::FunctionMetadata;
+
+/// Render diagnostics as a SARIF 2.1.0 log, for code review annotations in GitHub and
+/// GitLab.
+///
+/// Results are located in the `source_file` of the function they name, looked up in
+/// `functions`; diagnostics without a function or span are reported without a location.
+pub fn render_sarif(diagnostics: &[Diagnostic], functions: &[FunctionMetadata]) -> String {
+    let mut rule_ids: Vec<&str> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.code.as_str())
+        .collect();
+    rule_ids.sort();
+    rule_ids.dedup();
+
+    let results: Vec<Value> = diagnostics
+        .iter()
+        .map(|diagnostic| sarif_result(diagnostic, functions))
+        .collect();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "typedb-meta",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rule_ids
+                        .iter()
+                        .map(|id| json!({ "id": id }))
+                        .collect::<Vec<_>>(),
+                }
+            },
+            "results": results,
+        }]
+    });
+    serde_json::to_string_pretty(&log).unwrap()
+}
+
+fn sarif_result(diagnostic: &Diagnostic, functions: &[FunctionMetadata]) -> Value {
+    let mut result = json!({
+        "ruleId": diagnostic.code,
+        "level": sarif_level(diagnostic.severity),
+        "message": { "text": diagnostic.message },
+    });
+
+    let file = diagnostic.function.as_ref().and_then(|name| {
+        functions
+            .iter()
+            .find(|function| &function.name == name)?
+            .source_file
+            .as_deref()
+    });
+    if let Some(file) = file {
+        let mut location = json!({ "artifactLocation": { "uri": file } });
+        if let Some(span) = diagnostic.span {
+            location["region"] = json!({
+                "startLine": span.line,
+                "startColumn": span.column,
+                "byteOffset": span.start,
+                "byteLength": span.end - span.start,
+            });
+        }
+        result["locations"] = json!([{ "physicalLocation": location }]);
+    }
+    if diagnostic.suppressed {
+        result["suppressions"] = json!([{ "kind": "inSource" }]);
+    }
+    if let Some(function) = &diagnostic.function {
+        result["properties"] = json!({ "function": function });
+    }
+
+    result
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "note",
+    }
+}
@@ -0,0 +1,159 @@
+//! Entity-relationship diagrams of a schema, to view next to the function docs.
+
+use std::fmt::Write;
+
+use crate::extract_schema_metadata::{SchemaKind, SchemaMetadata, TypeDefinition};
+
+impl SchemaMetadata {
+    /// Render the entities and relations in Graphviz DOT format.
+    ///
+    /// Each type is a box listing the attributes it owns with their value types; relations
+    /// are rounded and shaded. Relations point at the types playing their roles, labelled
+    /// with the role, and subtypes point at their supertypes with hollow arrows. Attribute
+    /// types appear only in the boxes of their owners.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "digraph schema {{").unwrap();
+        writeln!(out, "    rankdir=LR;").unwrap();
+        writeln!(out, "    node [shape=record, fontname=\"Helvetica\"];").unwrap();
+        writeln!(out, "    edge [fontname=\"Helvetica\", fontsize=10];").unwrap();
+
+        for type_def in self.things() {
+            let mut label = record_escape(&type_def.label);
+            if !type_def.owns.is_empty() {
+                label.push('|');
+                for attribute in &type_def.owns {
+                    write!(
+                        label,
+                        "{} : {}\\l",
+                        record_escape(attribute),
+                        record_escape(self.attribute_value_type(attribute))
+                    )
+                    .unwrap();
+                }
+            }
+            let style = match type_def.kind {
+                SchemaKind::Relation => ", style=\"rounded,filled\", fillcolor=\"#fff3c4\"",
+                _ => "",
+            };
+            writeln!(
+                out,
+                "    {} [label=\"{{{}}}\"{}];",
+                dot_id(&type_def.label),
+                label,
+                style
+            )
+            .unwrap();
+        }
+
+        for (relation, role, player) in self.role_players() {
+            writeln!(
+                out,
+                "    {} -> {} [label={}];",
+                dot_id(relation),
+                dot_id(player),
+                dot_id(role)
+            )
+            .unwrap();
+        }
+        for type_def in self.things() {
+            if let Some(supertype) = &type_def.supertype {
+                writeln!(
+                    out,
+                    "    {} -> {} [arrowhead=empty, style=dashed];",
+                    dot_id(&type_def.label),
+                    dot_id(supertype)
+                )
+                .unwrap();
+            }
+        }
+
+        writeln!(out, "}}").unwrap();
+        out
+    }
+
+    /// Render the entities and relations as a Mermaid ER diagram for embedding in
+    /// Markdown.
+    ///
+    /// Shows the same as [`SchemaMetadata::to_dot`]: owned attributes inside each type,
+    /// role players as relationships labelled with the role, and subtyping as a `sub`
+    /// relationship from subtype to supertype.
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "erDiagram").unwrap();
+
+        for type_def in self.things() {
+            if type_def.owns.is_empty() {
+                writeln!(out, "    {}", type_def.label).unwrap();
+                continue;
+            }
+            writeln!(out, "    {} {{", type_def.label).unwrap();
+            for attribute in &type_def.owns {
+                writeln!(
+                    out,
+                    "        {} {}",
+                    self.attribute_value_type(attribute),
+                    attribute
+                )
+                .unwrap();
+            }
+            writeln!(out, "    }}").unwrap();
+        }
+
+        for (relation, role, player) in self.role_players() {
+            writeln!(out, "    {} }}o--o{{ {} : \"{}\"", relation, player, role).unwrap();
+        }
+        for type_def in self.things() {
+            if let Some(supertype) = &type_def.supertype {
+                writeln!(out, "    {} |o--|| {} : \"sub\"", type_def.label, supertype).unwrap();
+            }
+        }
+        out
+    }
+
+    /// Entity and relation types, in schema order
+    fn things(&self) -> impl Iterator<Item = &TypeDefinition> {
+        self.types
+            .iter()
+            .filter(|type_def| type_def.kind != SchemaKind::Attribute)
+    }
+
+    /// `(relation, role, player)` for every role declared by a relation and played by a
+    /// type
+    fn role_players(&self) -> Vec<(&str, &str, &str)> {
+        let mut role_players = Vec::new();
+        for (relation, role) in self.roles() {
+            let scoped = format!("{}:{}", relation, role);
+            for player in self.things() {
+                if player.plays.contains(&scoped) {
+                    role_players.push((relation, role, player.label.as_str()));
+                }
+            }
+        }
+        role_players
+    }
+
+    /// The value type of the attribute type `label`, or `any` if it has none or is not
+    /// defined
+    fn attribute_value_type(&self, label: &str) -> &str {
+        self.get(label)
+            .and_then(|attribute| self.value_type(attribute))
+            .unwrap_or("any")
+    }
+}
+
+fn dot_id(label: &str) -> String {
+    format!("\"{}\"", label.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Escape the characters that structure DOT record labels
+fn record_escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if "{}|<>\"\\".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,186 @@
+//! Ranked search over the functions of a registry, for finding a function from a few
+//! words when its exact name is not known.
+//!
+//! Queries and functions are split into lowercase words, so `bracket rates` finds
+//! `get_tax_bracket_rate` as well as a function documented as computing the rate of a
+//! tax bracket. Words match exactly, as a prefix of a longer word or, in names and doc
+//! comments, within a small edit distance to tolerate typos. Words that occur in few
+//! functions count for more than common ones such as `get` or `person`.
+
+use std::collections::HashSet;
+
+use crate::extract_function_metadata::FunctionMetadata;
+use crate::registry::FunctionRegistry;
+use crate::validate::edit_distance;
+
+/// Words left out of queries as they say nothing about the function sought
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "by", "find", "for", "from", "function", "in", "is", "me", "of", "or",
+    "that", "the", "to", "which", "with",
+];
+
+/// A function matching a search, with how well it matched
+#[derive(Debug, Clone)]
+pub struct SearchHit<'a> {
+    pub function: &'a FunctionMetadata,
+    /// Higher is better; only comparable between hits of the same search
+    pub score: f64,
+    /// Query words found in the function, in query order; empty for
+    /// [`FunctionRegistry::search_semantic`]
+    pub terms: Vec<String>,
+}
+
+/// Where in a function a word was found, from most to least telling
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    Name,
+    Doc,
+    /// Parameter names and types, output types and schema types used in the body
+    Signature,
+    Body,
+}
+
+impl Field {
+    fn weight(self) -> f64 {
+        match self {
+            Field::Name => 3.0,
+            Field::Doc => 2.0,
+            Field::Signature => 1.5,
+            Field::Body => 1.0,
+        }
+    }
+
+    fn allows_typos(self) -> bool {
+        matches!(self, Field::Name | Field::Doc)
+    }
+}
+
+impl FunctionRegistry {
+    /// Functions matching the words of `query`, best first.
+    ///
+    /// Each query word scores the best of its matches in the function's name, doc comment,
+    /// signature and body, weighted by how rare the word is across the registry. Functions
+    /// matching no word are left out, and ties are broken by name.
+    pub fn search(&self, query: &str) -> Vec<SearchHit<'_>> {
+        let mut terms: Vec<String> = Vec::new();
+        for word in words(query) {
+            if !STOP_WORDS.contains(&word.as_str()) && !terms.contains(&word) {
+                terms.push(word);
+            }
+        }
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let documents: Vec<Vec<(Field, HashSet<String>)>> = self.iter().map(document).collect();
+        // Best match of each term in each function
+        let matches: Vec<Vec<f64>> = terms
+            .iter()
+            .map(|term| {
+                documents
+                    .iter()
+                    .map(|fields| best_match(term, fields))
+                    .collect()
+            })
+            .collect();
+        let rarity: Vec<f64> = matches
+            .iter()
+            .map(|scores| {
+                let found = scores.iter().filter(|score| **score > 0.0).count();
+                (1.0 + self.len() as f64 / found.max(1) as f64).ln()
+            })
+            .collect();
+
+        let mut hits: Vec<SearchHit<'_>> = self
+            .iter()
+            .enumerate()
+            .filter_map(|(position, function)| {
+                let mut score = 0.0;
+                let mut found = Vec::new();
+                for (term, (scores, rarity)) in terms.iter().zip(matches.iter().zip(&rarity)) {
+                    if scores[position] > 0.0 {
+                        score += scores[position] * rarity;
+                        found.push(term.clone());
+                    }
+                }
+                (score > 0.0).then_some(SearchHit {
+                    function,
+                    score,
+                    terms: found,
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.function.name.cmp(&b.function.name))
+        });
+        hits
+    }
+}
+
+/// The words of each field of `function`
+fn document(function: &FunctionMetadata) -> Vec<(Field, HashSet<String>)> {
+    let mut doc: HashSet<String> = function.doc.iter().flat_map(|doc| words(doc)).collect();
+    for tag in &function.doc_tags {
+        doc.extend(words(&tag.text));
+    }
+
+    let mut signature = HashSet::new();
+    for parameter in &function.parameters {
+        signature.extend(words(&parameter.name));
+        signature.extend(words(&parameter.type_name));
+    }
+    for output in function.output.types() {
+        signature.extend(words(&output.name));
+    }
+    for label in &function.referenced_types {
+        signature.extend(words(label));
+    }
+
+    vec![
+        (Field::Name, words(&function.name).collect()),
+        (Field::Doc, doc),
+        (Field::Signature, signature),
+        (Field::Body, words(&function.code_block).collect()),
+    ]
+}
+
+/// The weighted quality of the best match of `term` in any field, or 0 if none
+fn best_match(term: &str, fields: &[(Field, HashSet<String>)]) -> f64 {
+    let max_distance = (term.chars().count() / 4).max(1);
+    fields
+        .iter()
+        .map(|(field, words)| {
+            let quality = if words.contains(term) {
+                1.0
+            } else if term.len() >= 3 && words.iter().any(|word| word.starts_with(term)) {
+                0.8
+            } else if field.allows_typos()
+                && term.len() >= 4
+                && words
+                    .iter()
+                    .any(|word| edit_distance(term, word) <= max_distance)
+            {
+                0.6
+            } else {
+                0.0
+            };
+            quality * field.weight()
+        })
+        .fold(0.0, f64::max)
+}
+
+/// Lowercase words of `text`, split at anything but letters and digits, with a plural
+/// `s` dropped so `rates` and `rate` match
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let word = word.to_lowercase();
+            match word.strip_suffix('s') {
+                Some(stem) if stem.len() >= 3 && !stem.ends_with('s') => stem.to_string(),
+                _ => word,
+            }
+        })
+}
@@ -0,0 +1,170 @@
+//! Searching a registry by meaning, with embeddings from a model the caller supplies.
+//!
+//! Each function is embedded from its signature, doc comment and body, and the functions
+//! closest to the embedding of a description are returned first. Any closure from text
+//! to a vector is an [`Embedder`]:
+//!
+//! ```no_run
+//! use typedb_examples::{CachedEmbedder, FunctionRegistry};
+//!
+//! # fn call_embedding_model(text: &str) -> anyhow::Result<Vec<f32>> { unimplemented!() }
+//! # let registry = FunctionRegistry::new();
+//! // Embeds each function once, however many searches are run
+//! let embedder = CachedEmbedder::new(|text: &str| call_embedding_model(text));
+//! for hit in registry.search_semantic(&embedder, "tax owed on capital gains")?.iter().take(5) {
+//!     println!("{:.3} {}", hit.score, hit.function.name);
+//! }
+//! # Ok::<(), typedb_examples::ExtractError>(())
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+use crate::error::ExtractError;
+use crate::extract_function_metadata::FunctionMetadata;
+use crate::registry::FunctionRegistry;
+use crate::search::SearchHit;
+
+/// Turns text into vectors that are close when the texts mean similar things
+pub trait Embedder {
+    /// One embedding per text, in order. All embeddings must have the same length.
+    ///
+    /// Failures of the model are returned as [`ExtractError::Embedding`].
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, ExtractError>;
+}
+
+impl<F, E> Embedder for F
+where
+    F: Fn(&str) -> Result<Vec<f32>, E>,
+    E: Into<Box<dyn Error + Send + Sync>>,
+{
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, ExtractError> {
+        texts
+            .iter()
+            .map(|text| self(text).map_err(ExtractError::embedding))
+            .collect()
+    }
+}
+
+/// An [`Embedder`] remembering the embedding of every text it has seen, so functions are
+/// only embedded again after they change
+pub struct CachedEmbedder<E> {
+    embedder: E,
+    cache: Mutex<HashMap<String, Vec<f32>>>,
+}
+
+impl<E: Embedder> CachedEmbedder<E> {
+    pub fn new(embedder: E) -> Self {
+        CachedEmbedder {
+            embedder,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<E: Embedder> Embedder for CachedEmbedder<E> {
+    /// Texts not seen before are embedded in one batch
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, ExtractError> {
+        let mut cache = self.cache.lock().unwrap();
+        let mut seen = HashSet::new();
+        let missing: Vec<&str> = texts
+            .iter()
+            .copied()
+            .filter(|text| !cache.contains_key(*text) && seen.insert(*text))
+            .collect();
+        if !missing.is_empty() {
+            let embeddings = self.embedder.embed(&missing)?;
+            if embeddings.len() != missing.len() {
+                return Err(ExtractError::embedding(format!(
+                    "embedder returned {} embeddings for {} texts",
+                    embeddings.len(),
+                    missing.len()
+                )));
+            }
+            for (text, embedding) in missing.into_iter().zip(embeddings) {
+                cache.insert(text.to_string(), embedding);
+            }
+        }
+        Ok(texts.iter().map(|text| cache[*text].clone()).collect())
+    }
+}
+
+/// The text a function is embedded from: its signature, doc comment, doc tags and body
+pub fn embedding_text(function: &FunctionMetadata) -> String {
+    let mut text = function.signature();
+    if let Some(doc) = &function.doc {
+        write!(text, "\n{}", doc).unwrap();
+    }
+    for tag in &function.doc_tags {
+        write!(text, "\n@{} {}", tag.name, tag.text).unwrap();
+    }
+    write!(text, "\n{}", function.code_block).unwrap();
+    text
+}
+
+impl FunctionRegistry {
+    /// Every function, closest in meaning to `description` first.
+    ///
+    /// Scores are the cosine similarity between the embedding of `description` and that
+    /// of each function's [`embedding_text`], from -1 to 1. Hits have no `terms`.
+    pub fn search_semantic<'a>(
+        &'a self,
+        embedder: &dyn Embedder,
+        description: &str,
+    ) -> Result<Vec<SearchHit<'a>>, ExtractError> {
+        let texts: Vec<String> = self.iter().map(embedding_text).collect();
+        let mut inputs: Vec<&str> = vec![description];
+        inputs.extend(texts.iter().map(String::as_str));
+        let embeddings = embedder.embed(&inputs)?;
+        if embeddings.len() != inputs.len() {
+            return Err(ExtractError::embedding(format!(
+                "embedder returned {} embeddings for {} texts",
+                embeddings.len(),
+                inputs.len()
+            )));
+        }
+
+        let query = &embeddings[0];
+        let mut hits = Vec::new();
+        for (function, embedding) in self.iter().zip(&embeddings[1..]) {
+            if embedding.len() != query.len() {
+                return Err(ExtractError::embedding(format!(
+                    "embedding of `{}` has {} dimensions, the query's has {}",
+                    function.name,
+                    embedding.len(),
+                    query.len()
+                )));
+            }
+            hits.push(SearchHit {
+                function,
+                score: cosine_similarity(query, embedding),
+                terms: Vec::new(),
+            });
+        }
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.function.name.cmp(&b.function.name))
+        });
+        Ok(hits)
+    }
+}
+
+/// 0 when either vector is all zeros
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    let mut dot = 0.0;
+    let mut norm_a = 0.0;
+    let mut norm_b = 0.0;
+    for (x, y) in a.iter().zip(b) {
+        let (x, y) = (f64::from(*x), f64::from(*y));
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostic::Diagnostic;
+use crate::extract_function_metadata::{OutputType, Parameter, TypeKind, TypeName, VALUE_TYPES};
+use crate::lexer::{tokens, Token, TokenKind, Tokens};
+use crate::span::Span;
+
+/// The header of a function definition: its name, parameters and declared output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct FunctionSignature {
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub output: OutputType,
+}
+
+/// Extract only the signature of a TypeQL function definition.
+///
+/// Only the header up to the colon after the output is tokenized, without allocating
+/// tokens, and the body is never parsed, so this is much cheaper than
+/// [`extract_function_metadata`](crate::extract_function_metadata) when only names and
+/// types are needed, e.g. for indexing. The body is not checked for errors.
+pub fn extract_signature(function_text: &str) -> Result<FunctionSignature, Diagnostic> {
+    let mut parser = SignatureParser {
+        text: function_text,
+        rest: tokens(function_text),
+        previous_end: 0,
+    };
+
+    parser.expect_word("fun")?;
+    let name = parser.expect(TokenKind::Word, "a function name")?.text;
+    parser.expect_punct("(")?;
+    let mut parameters = Vec::new();
+    if !parser.eat_punct(")") {
+        loop {
+            let var = parser.expect(TokenKind::Variable, "a parameter variable")?;
+            parser.expect_punct(":")?;
+            let type_name = parser.type_name()?;
+            parameters.push(Parameter {
+                name: var.text.trim_start_matches('$').to_string(),
+                type_name: type_name.to_string(),
+                kind: type_name.kind,
+                span: Some(Span::new(
+                    function_text,
+                    var.range.start,
+                    parser.previous_end,
+                )),
+            });
+            if !parser.eat_punct(",") {
+                parser.expect_punct(")")?;
+                break;
+            }
+        }
+    }
+
+    parser.expect_punct("-")?;
+    parser.expect_punct(">")?;
+    let output = if parser.eat_punct("{") {
+        let types = parser.type_list()?;
+        parser.expect_punct("}")?;
+        OutputType::Stream(types)
+    } else {
+        OutputType::Single(parser.type_list()?)
+    };
+    parser.expect_punct(":")?;
+
+    Ok(FunctionSignature {
+        name: name.to_string(),
+        parameters,
+        output,
+    })
+}
+
+struct SignatureParser<'a> {
+    text: &'a str,
+    /// Tokens not yet consumed; comments are skipped
+    rest: Tokens<'a>,
+    /// End of the last consumed token
+    previous_end: usize,
+}
+
+impl<'a> SignatureParser<'a> {
+    /// The next two tokens, without consuming them
+    fn lookahead(&self) -> (Option<Token<'a>>, Option<Token<'a>>) {
+        let mut tokens = self
+            .rest
+            .clone()
+            .filter(|token| token.kind != TokenKind::Comment);
+        (tokens.next(), tokens.next())
+    }
+
+    fn peek(&self) -> Option<Token<'a>> {
+        self.lookahead().0
+    }
+
+    fn bump(&mut self) -> Option<Token<'a>> {
+        let token = self.rest.find(|token| token.kind != TokenKind::Comment)?;
+        self.previous_end = token.range.end;
+        Some(token)
+    }
+
+    fn eat_punct(&mut self, punct: &str) -> bool {
+        // `-` lexes as a word because labels may contain it, so compare text only
+        let found = self.peek().is_some_and(|token| token.text == punct);
+        if found {
+            self.bump();
+        }
+        found
+    }
+
+    fn expect(&mut self, kind: TokenKind, expected: &str) -> Result<Token<'a>, Diagnostic> {
+        match self.peek() {
+            Some(token) if token.kind == kind => {
+                self.bump();
+                Ok(token)
+            }
+            _ => Err(self.error(expected)),
+        }
+    }
+
+    fn expect_word(&mut self, word: &str) -> Result<(), Diagnostic> {
+        if self.peek().is_some_and(|token| token.is_word(word)) {
+            self.bump();
+            Ok(())
+        } else {
+            Err(self.error(&format!("`{}`", word)))
+        }
+    }
+
+    fn expect_punct(&mut self, punct: &str) -> Result<(), Diagnostic> {
+        if self.eat_punct(punct) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("`{}`", punct)))
+        }
+    }
+
+    /// `a, b, c` up to the `:` or `}` that ends an output
+    fn type_list(&mut self) -> Result<Vec<TypeName>, Diagnostic> {
+        let mut types = vec![self.type_name()?];
+        while self.eat_punct(",") {
+            types.push(self.type_name()?);
+        }
+        Ok(types)
+    }
+
+    /// A type such as `double`, `person[]`, `string?` or `employment:employee`
+    fn type_name(&mut self) -> Result<TypeName, Diagnostic> {
+        let label = self.expect(TokenKind::Word, "a type")?;
+        let mut name = label.text.to_string();
+        // A scoped role label is written without spaces; the colon ending the signature is
+        // followed by whitespace before the body
+        if let (Some(colon), Some(role)) = self.lookahead() {
+            if colon.is_punct(":")
+                && role.kind == TokenKind::Word
+                && colon.range.start == label.range.end
+                && role.range.start == colon.range.end
+            {
+                name = format!("{}:{}", name, role.text);
+                self.bump();
+                self.bump();
+            }
+        }
+
+        let kind = if VALUE_TYPES.contains(&name.as_str()) {
+            TypeKind::Builtin
+        } else {
+            TypeKind::Label
+        };
+        let list = self.eat_punct("[");
+        if list {
+            self.expect_punct("]")?;
+        }
+        let optional = !list && self.eat_punct("?");
+        Ok(TypeName {
+            name,
+            kind,
+            list,
+            optional,
+        })
+    }
+
+    fn error(&self, expected: &str) -> Diagnostic {
+        let (message, span) = match self.peek() {
+            Some(token) => (
+                format!("expected {}, found `{}`", expected, token.text),
+                Span::new(self.text, token.range.start, token.range.end),
+            ),
+            None => (
+                format!("expected {}, found end of input", expected),
+                Span::new(self.text, self.text.len(), self.text.len()),
+            ),
+        };
+        Diagnostic::error("parse-error", message).with_span(Some(span))
+    }
+}
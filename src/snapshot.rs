@@ -0,0 +1,171 @@
+//! Golden-file snapshots of metadata, for regression tests over a whole function library.
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use typedb_examples::{check_snapshot, extract_from_file, SnapshotMode};
+//!
+//! let functions = extract_from_file(Path::new("schema/tax.tql")).unwrap();
+//! check_snapshot(Path::new("tests/golden/tax.json"), &functions, SnapshotMode::from_env())
+//!     .unwrap();
+//! ```
+//!
+//! Run with `UPDATE_SNAPSHOTS=1` to write the golden files instead of comparing.
+
+use serde::Serialize;
+use std::fmt::{self, Write};
+use std::fs;
+use std::path::Path;
+
+use crate::error::ExtractError;
+
+/// Environment variable that switches [`SnapshotMode::from_env`] to updating
+pub const UPDATE_SNAPSHOTS_VAR: &str = "UPDATE_SNAPSHOTS";
+
+/// Lines of unchanged text shown around each change in a snapshot diff
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotMode {
+    /// Fail when the golden file is missing or differs
+    Compare,
+    /// Write the golden file when it is missing or differs
+    Update,
+}
+
+impl SnapshotMode {
+    /// [`SnapshotMode::Update`] when `UPDATE_SNAPSHOTS` is set to anything but `0` or an
+    /// empty string, [`SnapshotMode::Compare`] otherwise
+    pub fn from_env() -> Self {
+        match std::env::var(UPDATE_SNAPSHOTS_VAR) {
+            Ok(value) if !value.is_empty() && value != "0" => SnapshotMode::Update,
+            _ => SnapshotMode::Compare,
+        }
+    }
+}
+
+/// What [`check_snapshot`] did with the golden file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotOutcome {
+    Matched,
+    Created,
+    Updated,
+}
+
+impl fmt::Display for SnapshotOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outcome = match self {
+            SnapshotOutcome::Matched => "matched",
+            SnapshotOutcome::Created => "created",
+            SnapshotOutcome::Updated => "updated",
+        };
+        f.write_str(outcome)
+    }
+}
+
+/// `value` as pretty-printed JSON with object keys sorted, ending in a newline, so the
+/// same metadata always gives the same text
+pub fn snapshot_text<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<String> {
+    // Going through `Value` sorts the keys of maps serialized in hash order
+    let value = serde_json::to_value(value)?;
+    let mut text = serde_json::to_string_pretty(&value)?;
+    text.push('\n');
+    Ok(text)
+}
+
+/// Compare `value` with the golden file at `golden`, or write it there in
+/// [`SnapshotMode::Update`].
+///
+/// A mismatch in [`SnapshotMode::Compare`] is an [`ExtractError::SnapshotMismatch`] with a
+/// line diff from the golden file to the current text.
+pub fn check_snapshot<T: Serialize + ?Sized>(
+    golden: &Path,
+    value: &T,
+    mode: SnapshotMode,
+) -> Result<SnapshotOutcome, ExtractError> {
+    let actual = snapshot_text(value).map_err(|error| ExtractError::InvalidMetadata {
+        message: format!("cannot serialize the snapshot: {}", error),
+    })?;
+    let expected = match fs::read_to_string(golden) {
+        Ok(expected) => Some(expected),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+        Err(error) => return Err(ExtractError::io(golden, error)),
+    };
+
+    let outcome = match (&expected, mode) {
+        (Some(expected), _) if *expected == actual => return Ok(SnapshotOutcome::Matched),
+        (None, SnapshotMode::Compare) => {
+            return Err(ExtractError::SnapshotMissing {
+                golden: golden.to_path_buf(),
+            })
+        }
+        (Some(expected), SnapshotMode::Compare) => {
+            return Err(ExtractError::SnapshotMismatch {
+                golden: golden.to_path_buf(),
+                diff: line_diff(expected, &actual),
+            })
+        }
+        (None, SnapshotMode::Update) => SnapshotOutcome::Created,
+        (Some(_), SnapshotMode::Update) => SnapshotOutcome::Updated,
+    };
+    if let Some(parent) = golden.parent() {
+        fs::create_dir_all(parent).map_err(|error| ExtractError::io(parent, error))?;
+    }
+    fs::write(golden, &actual).map_err(|error| ExtractError::io(golden, error))?;
+    Ok(outcome)
+}
+
+/// A unified-style diff of the lines of `old` and `new`, with `-` for removed lines, `+`
+/// for added ones and a few unchanged lines around each change
+pub fn line_diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Longest common subsequence lengths of every pair of suffixes
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    // Marker, text, and the 1-based line numbers in `old` and `new` where the line falls
+    let mut lines: Vec<(char, &str, usize, usize)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i], i + 1, j + 1));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(('-', old[i], i + 1, j + 1));
+            i += 1;
+        } else {
+            lines.push(('+', new[j], i + 1, j + 1));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<usize> = (0..lines.len())
+        .filter(|&position| lines[position].0 != ' ')
+        .collect();
+    let mut out = String::new();
+    let mut next_shown = None;
+    for (position, (marker, line, old_line, new_line)) in lines.iter().enumerate() {
+        let near_change = changed.iter().any(|&change| {
+            position + CONTEXT_LINES >= change && position <= change + CONTEXT_LINES
+        });
+        if !near_change {
+            continue;
+        }
+        if next_shown != Some(position) {
+            writeln!(out, "@@ -{} +{} @@", old_line, new_line).unwrap();
+        }
+        writeln!(out, "{}{}", marker, line).unwrap();
+        next_shown = Some(position + 1);
+    }
+    out
+}
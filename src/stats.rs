@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use std::ops::AddAssign;
+use std::time::{Duration, Instant};
+
+use crate::error::ExtractError;
+use crate::extract_function_metadata::{extract_function_timed, FunctionMetadata};
+use crate::lexer::function_ranges;
+
+/// Where extraction spent its time, for tracking performance regressions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractionStats {
+    /// Bytes of TypeQL handed to the parser
+    pub input_bytes: usize,
+    pub function_count: usize,
+    /// Time spent in the typeql parser
+    pub parse_time: Duration,
+    /// Time spent building metadata from the parsed AST, including locating functions
+    pub extraction_time: Duration,
+}
+
+impl ExtractionStats {
+    pub fn total_time(&self) -> Duration {
+        self.parse_time + self.extraction_time
+    }
+}
+
+impl AddAssign for ExtractionStats {
+    fn add_assign(&mut self, other: Self) {
+        self.input_bytes += other.input_bytes;
+        self.function_count += other.function_count;
+        self.parse_time += other.parse_time;
+        self.extraction_time += other.extraction_time;
+    }
+}
+
+/// [`extract_function_metadata`](crate::extract_function_metadata) that also reports
+/// where the time went
+pub fn extract_function_metadata_with_stats(
+    function_text: &str,
+) -> Result<(FunctionMetadata, ExtractionStats), ExtractError> {
+    extract_function_timed(function_text, 0..function_text.len())
+        .map_err(|diagnostic| ExtractError::from_input(diagnostic, function_text))
+}
+
+/// [`extract_functions_from_source`](crate::extract_functions_from_source) that also
+/// reports where the time went, summed over every function in `source`.
+///
+/// `input_bytes` counts the whole source, not just the function definitions.
+pub fn extract_functions_from_source_with_stats(
+    source: &str,
+) -> Result<(Vec<FunctionMetadata>, ExtractionStats), ExtractError> {
+    let started = Instant::now();
+    let ranges = function_ranges(source);
+    let mut stats = ExtractionStats {
+        extraction_time: started.elapsed(),
+        ..ExtractionStats::default()
+    };
+
+    let mut functions = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        let (function, function_stats) = extract_function_timed(source, range)
+            .map_err(|diagnostic| ExtractError::from_input(diagnostic, source))?;
+        functions.push(function);
+        stats += function_stats;
+    }
+    stats.input_bytes = source.len();
+    Ok((functions, stats))
+}
@@ -0,0 +1,350 @@
+//! A SQLite cache of extracted functions (requires the `sqlite` feature).
+//!
+//! [`FunctionStore::refresh`] re-extracts only the files whose content changed since the
+//! last refresh, so tools working on a large corpus can skip parsing it on every run.
+//! Lookups by name, parameter type, output type and callee go through indexed tables
+//! instead of deserializing every function.
+
+use rusqlite::{params, Connection, OptionalExtension, Params, Transaction};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use crate::error::ExtractError;
+use crate::extract_function_metadata::{extract_functions_in, FunctionMetadata};
+use crate::ingest::expand_paths;
+use crate::metadata_json::METADATA_VERSION;
+use crate::registry::FunctionRegistry;
+
+const TABLES: &str = "
+    CREATE TABLE IF NOT EXISTS files (
+        path TEXT PRIMARY KEY,
+        hash TEXT NOT NULL,
+        text TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS functions (
+        position INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE,
+        file TEXT,
+        fingerprint TEXT NOT NULL,
+        metadata TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS functions_file ON functions (file);
+    CREATE TABLE IF NOT EXISTS parameter_types (function TEXT NOT NULL, type_name TEXT NOT NULL);
+    CREATE INDEX IF NOT EXISTS parameter_types_type_name ON parameter_types (type_name);
+    CREATE TABLE IF NOT EXISTS output_types (
+        function TEXT NOT NULL,
+        type_name TEXT NOT NULL,
+        stream INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS output_types_type_name ON output_types (type_name);
+    CREATE TABLE IF NOT EXISTS calls (caller TEXT NOT NULL, callee TEXT NOT NULL);
+    CREATE INDEX IF NOT EXISTS calls_callee ON calls (callee);
+";
+
+const DROP_TABLES: &str = "
+    DROP TABLE IF EXISTS files;
+    DROP TABLE IF EXISTS functions;
+    DROP TABLE IF EXISTS parameter_types;
+    DROP TABLE IF EXISTS output_types;
+    DROP TABLE IF EXISTS calls;
+";
+
+/// Functions, their source files and fingerprints persisted in a SQLite database.
+///
+/// The store is a cache: one written with a different [`METADATA_VERSION`] is emptied
+/// when opened. Names are unique, a later function replacing an earlier one of the same
+/// name as in a [`FunctionRegistry`]. Lookups return functions without a file first,
+/// then by file path and position in the file.
+pub struct FunctionStore {
+    connection: Connection,
+}
+
+/// Files looked at by [`FunctionStore::refresh`]
+#[derive(Debug, Clone, Default)]
+pub struct RefreshSummary {
+    /// Files that were new or changed, and were extracted again
+    pub extracted: Vec<String>,
+    /// Files whose content matched the store
+    pub unchanged: usize,
+    /// Files in the store that the patterns no longer match
+    pub removed: Vec<String>,
+}
+
+impl FunctionStore {
+    /// Open the store at `path`, creating it if needed
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ExtractError> {
+        let path = path.as_ref();
+        let connection = Connection::open(path).map_err(|source| ExtractError::Sqlite {
+            path: Some(path.to_path_buf()),
+            source,
+        })?;
+        Self::with_connection(connection)
+    }
+
+    /// A store that lives only as long as the value
+    pub fn open_in_memory() -> Result<Self, ExtractError> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self, ExtractError> {
+        let version: u32 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version != METADATA_VERSION {
+            connection.execute_batch(DROP_TABLES)?;
+            connection.pragma_update(None, "user_version", METADATA_VERSION)?;
+        }
+        connection.execute_batch(TABLES)?;
+        Ok(FunctionStore { connection })
+    }
+
+    /// Bring the store up to date with the files named by `patterns`, found as by
+    /// [`expand_paths`].
+    ///
+    /// Files whose content hash matches the stored one are skipped. The update runs in one
+    /// transaction, so a file that fails to parse leaves the store as it was.
+    pub fn refresh(&mut self, patterns: &[&str]) -> Result<RefreshSummary, ExtractError> {
+        let files = expand_paths(patterns)?;
+        let transaction = self.connection.transaction()?;
+        let mut summary = RefreshSummary::default();
+        let mut seen = BTreeSet::new();
+
+        for file in &files {
+            let path = file.display().to_string();
+            let text = fs::read_to_string(file).map_err(|error| ExtractError::io(file, error))?;
+            let hash = blake3::hash(text.as_bytes()).to_hex().to_string();
+            seen.insert(path.clone());
+            let stored: Option<String> = transaction
+                .query_row("SELECT hash FROM files WHERE path = ?1", [&path], |row| {
+                    row.get(0)
+                })
+                .optional()?;
+            if stored.as_deref() == Some(hash.as_str()) {
+                summary.unchanged += 1;
+                continue;
+            }
+
+            let functions = extract_functions_in(&text)
+                .map_err(|diagnostic| ExtractError::from_diagnostic(diagnostic, &path, &text))?;
+            remove_file(&transaction, &path)?;
+            transaction.execute(
+                "INSERT INTO files (path, hash, text) VALUES (?1, ?2, ?3)",
+                params![path, hash, text],
+            )?;
+            for mut function in functions {
+                function.source_file = Some(path.clone());
+                insert_function(&transaction, &function, Some(&path))?;
+            }
+            summary.extracted.push(path);
+        }
+
+        let stored_files = {
+            let mut statement = transaction.prepare("SELECT path FROM files ORDER BY path")?;
+            let paths = statement.query_map([], |row| row.get::<_, String>(0))?;
+            paths.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        for path in stored_files {
+            if !seen.contains(&path) {
+                remove_file(&transaction, &path)?;
+                summary.removed.push(path);
+            }
+        }
+
+        transaction.commit()?;
+        Ok(summary)
+    }
+
+    /// Replace the contents of the store with `registry`, its source files included
+    pub fn save(&mut self, registry: &FunctionRegistry) -> Result<(), ExtractError> {
+        let transaction = self.connection.transaction()?;
+        transaction.execute_batch(DROP_TABLES)?;
+        transaction.execute_batch(TABLES)?;
+        for source in registry.sources() {
+            let hash = blake3::hash(source.text.as_bytes()).to_hex().to_string();
+            transaction.execute(
+                "INSERT OR REPLACE INTO files (path, hash, text) VALUES (?1, ?2, ?3)",
+                params![source.path, hash, source.text],
+            )?;
+        }
+        for function in registry {
+            let file = registry
+                .source_of(&function.name)
+                .map(|source| source.path.as_str());
+            insert_function(&transaction, function, file)?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Every stored function in a registry, with the files they came from as its sources
+    pub fn load(&self) -> Result<FunctionRegistry, ExtractError> {
+        let mut registry = FunctionRegistry::new();
+        let mut statement = self
+            .connection
+            .prepare("SELECT path, text FROM files ORDER BY path")?;
+        let files = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        for file in files {
+            let (path, text): (String, String) = file?;
+            let functions = self.select(
+                "SELECT metadata FROM functions WHERE file = ?1 ORDER BY position",
+                [&path],
+            )?;
+            registry.add_extracted(path, text, functions);
+        }
+        registry.extend(self.select(
+            "SELECT metadata FROM functions WHERE file IS NULL ORDER BY position",
+            [],
+        )?);
+        Ok(registry)
+    }
+
+    /// Every stored function, those without a file first, then by file path and position
+    /// in the file
+    pub fn functions(&self) -> Result<Vec<FunctionMetadata>, ExtractError> {
+        self.select("SELECT metadata FROM functions ORDER BY file, position", [])
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<FunctionMetadata>, ExtractError> {
+        Ok(self
+            .select("SELECT metadata FROM functions WHERE name = ?1", [name])?
+            .pop())
+    }
+
+    /// The [`FunctionMetadata::fingerprint`] recorded for `name`
+    pub fn fingerprint(&self, name: &str) -> Result<Option<String>, ExtractError> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT fingerprint FROM functions WHERE name = ?1",
+                [name],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// The file `name` was extracted from, if it came from one
+    pub fn source_of(&self, name: &str) -> Result<Option<String>, ExtractError> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT file FROM functions WHERE name = ?1",
+                [name],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten())
+    }
+
+    /// Functions with at least one parameter of type `type_name`, ignoring list and
+    /// optional markers, as [`FunctionRegistry::with_parameter_type`]
+    pub fn with_parameter_type(
+        &self,
+        type_name: &str,
+    ) -> Result<Vec<FunctionMetadata>, ExtractError> {
+        self.select(
+            "SELECT metadata FROM functions WHERE name IN
+                (SELECT function FROM parameter_types WHERE type_name = ?1)
+            ORDER BY file, position",
+            [type_name],
+        )
+    }
+
+    /// Functions whose output includes `type_name`, streamed or single
+    pub fn returning(&self, type_name: &str) -> Result<Vec<FunctionMetadata>, ExtractError> {
+        self.select(
+            "SELECT metadata FROM functions WHERE name IN
+                (SELECT function FROM output_types WHERE type_name = ?1)
+            ORDER BY file, position",
+            [type_name],
+        )
+    }
+
+    /// Functions that call `name` directly
+    pub fn calling(&self, name: &str) -> Result<Vec<FunctionMetadata>, ExtractError> {
+        self.select(
+            "SELECT metadata FROM functions WHERE name IN
+                (SELECT caller FROM calls WHERE callee = ?1)
+            ORDER BY file, position",
+            [name],
+        )
+    }
+
+    /// Functions selected by a query for their metadata, decoded
+    fn select(
+        &self,
+        query: &str,
+        parameters: impl Params,
+    ) -> Result<Vec<FunctionMetadata>, ExtractError> {
+        let mut statement = self.connection.prepare(query)?;
+        let rows = statement.query_map(parameters, |row| row.get::<_, String>(0))?;
+        let mut functions = Vec::new();
+        for metadata in rows {
+            let function = serde_json::from_str(&metadata?).map_err(|error| {
+                ExtractError::InvalidMetadata {
+                    message: format!("stored metadata is not valid: {}", error),
+                }
+            })?;
+            functions.push(function);
+        }
+        Ok(functions)
+    }
+}
+
+fn insert_function(
+    transaction: &Transaction<'_>,
+    function: &FunctionMetadata,
+    file: Option<&str>,
+) -> Result<(), ExtractError> {
+    remove_function(transaction, &function.name)?;
+    let metadata =
+        serde_json::to_string(function).map_err(|error| ExtractError::InvalidMetadata {
+            message: format!("cannot serialize `{}`: {}", function.name, error),
+        })?;
+    transaction.execute(
+        "INSERT INTO functions (name, file, fingerprint, metadata) VALUES (?1, ?2, ?3, ?4)",
+        params![function.name, file, function.fingerprint(), metadata],
+    )?;
+    for parameter in &function.parameters {
+        transaction.execute(
+            "INSERT INTO parameter_types (function, type_name) VALUES (?1, ?2)",
+            params![
+                function.name,
+                parameter.type_name.trim_end_matches(['[', ']', '?'])
+            ],
+        )?;
+    }
+    for output in function.output.types() {
+        transaction.execute(
+            "INSERT INTO output_types (function, type_name, stream) VALUES (?1, ?2, ?3)",
+            params![function.name, output.name, function.output.is_stream()],
+        )?;
+    }
+    for callee in &function.referenced_functions {
+        transaction.execute(
+            "INSERT INTO calls (caller, callee) VALUES (?1, ?2)",
+            params![function.name, callee],
+        )?;
+    }
+    Ok(())
+}
+
+fn remove_function(transaction: &Transaction<'_>, name: &str) -> Result<(), ExtractError> {
+    transaction.execute("DELETE FROM functions WHERE name = ?1", [name])?;
+    transaction.execute("DELETE FROM parameter_types WHERE function = ?1", [name])?;
+    transaction.execute("DELETE FROM output_types WHERE function = ?1", [name])?;
+    transaction.execute("DELETE FROM calls WHERE caller = ?1", [name])?;
+    Ok(())
+}
+
+/// Remove a file and the functions extracted from it
+fn remove_file(transaction: &Transaction<'_>, path: &str) -> Result<(), ExtractError> {
+    let names = {
+        let mut statement = transaction.prepare("SELECT name FROM functions WHERE file = ?1")?;
+        let names = statement.query_map([path], |row| row.get::<_, String>(0))?;
+        names.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+    for name in names {
+        remove_function(transaction, &name)?;
+    }
+    transaction.execute("DELETE FROM files WHERE path = ?1", [path])?;
+    Ok(())
+}
@@ -0,0 +1,194 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::call_graph::CallGraph;
+use crate::diff::{diff_functions, FunctionDiff};
+use crate::extract_function_metadata::FunctionMetadata;
+
+/// Whether `sync_functions` runs its plan or only reports it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncMode {
+    /// Plan the statements without running them
+    DryRun,
+    /// Run the planned statements in one schema transaction
+    Apply,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncAction {
+    Define,
+    Redefine,
+    Undefine,
+}
+
+impl fmt::Display for SyncAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncAction::Define => write!(f, "define"),
+            SyncAction::Redefine => write!(f, "redefine"),
+            SyncAction::Undefine => write!(f, "undefine"),
+        }
+    }
+}
+
+/// One schema query in a sync plan
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncStatement {
+    pub action: SyncAction,
+    pub function: String,
+    /// The complete TypeQL query, e.g. `define\nfun ...;`
+    pub query: String,
+}
+
+/// Statements that bring a database's functions in line with a local set, in the order
+/// they must run: new and changed functions with callees before callers, then removals
+/// with callers before callees.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SyncPlan {
+    pub statements: Vec<SyncStatement>,
+}
+
+impl SyncPlan {
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+}
+
+impl fmt::Display for SyncPlan {
+    /// The plan as a TypeQL script, one query per statement
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (position, statement) in self.statements.iter().enumerate() {
+            if position > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "{}", statement.query)?;
+        }
+        Ok(())
+    }
+}
+
+/// Plan the queries that turn the `deployed` functions into the `local` ones.
+///
+/// Functions are compared with [`diff_functions`], so formatting and variable renames do
+/// not trigger a redefine. Deployed functions missing from `local` are undefined.
+pub fn plan_sync(local: &[FunctionMetadata], deployed: &[FunctionMetadata]) -> SyncPlan {
+    let deployed_named = |name: &str| deployed.iter().find(|function| function.name == name);
+    let mut statements = Vec::new();
+
+    for function in dependency_order(local) {
+        match deployed_named(&function.name) {
+            None => statements.push(SyncStatement {
+                action: SyncAction::Define,
+                function: function.name.clone(),
+                query: format!("define\n{}", function.to_typeql()),
+            }),
+            Some(current) if !diff_functions(current, function).is_empty() => {
+                statements.push(SyncStatement {
+                    action: SyncAction::Redefine,
+                    function: function.name.clone(),
+                    query: format!("redefine\n{}", function.to_typeql()),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+
+    for function in dependency_order(deployed).into_iter().rev() {
+        if !local.iter().any(|local| local.name == function.name) {
+            statements.push(SyncStatement {
+                action: SyncAction::Undefine,
+                function: function.name.clone(),
+                query: format!("undefine\nfun {};", function.name),
+            });
+        }
+    }
+
+    SyncPlan { statements }
+}
+
+/// Plan a migration script from one version of a function corpus to another.
+///
+/// This is [`plan_sync`] with `old` standing in for the deployed functions: new and
+/// changed functions are defined or redefined in a single pass with callees first, so
+/// each refers to the final signatures of the functions it calls, then removed ones are
+/// undefined with callers first, so no statement refers to an undefined function.
+pub fn plan_migration(old: &[FunctionMetadata], new: &[FunctionMetadata]) -> SyncPlan {
+    plan_sync(new, old)
+}
+
+/// Differences between local function definitions and those deployed to a database
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DriftReport {
+    /// Defined locally but not deployed
+    pub missing: Vec<String>,
+    /// Deployed but not defined locally
+    pub extra: Vec<String>,
+    /// Deployed with a definition that differs from the local one
+    pub changed: Vec<FunctionDiff>,
+}
+
+impl DriftReport {
+    /// Whether the database matches the local definitions
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for DriftReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for name in &self.missing {
+            writeln!(f, "missing: {}", name)?;
+        }
+        for name in &self.extra {
+            writeln!(f, "extra: {}", name)?;
+        }
+        for diff in &self.changed {
+            writeln!(f, "changed: {}", diff.name)?;
+            for line in diff.to_string().lines() {
+                writeln!(f, "    {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compare local functions with the `deployed` ones, matching them by name.
+///
+/// Changes are found with [`diff_functions`] from the deployed to the local version, so
+/// formatting and variable renames are not drift.
+pub fn detect_drift(local: &[FunctionMetadata], deployed: &[FunctionMetadata]) -> DriftReport {
+    let mut report = DriftReport::default();
+
+    for function in local {
+        match deployed.iter().find(|other| other.name == function.name) {
+            None => report.missing.push(function.name.clone()),
+            Some(current) => {
+                let diff = diff_functions(current, function);
+                if !diff.is_empty() {
+                    report.changed.push(diff);
+                }
+            }
+        }
+    }
+    report.extra = deployed
+        .iter()
+        .filter(|function| !local.iter().any(|other| other.name == function.name))
+        .map(|function| function.name.clone())
+        .collect();
+
+    report
+}
+
+/// Functions with callees first; mutually recursive functions keep their input order
+pub(crate) fn dependency_order(functions: &[FunctionMetadata]) -> Vec<&FunctionMetadata> {
+    let graph = CallGraph::from_functions(functions);
+    let order = graph
+        .topological_order()
+        .unwrap_or_else(|_| graph.functions().collect());
+    order
+        .into_iter()
+        .filter_map(|name| functions.iter().find(|function| function.name == name))
+        .collect()
+}
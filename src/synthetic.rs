@@ -0,0 +1,282 @@
+//! Random but schema-valid data, for exercising functions against something realistic.
+
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::data::quote;
+use crate::extract_schema_metadata::{Cardinality, SchemaKind, SchemaMetadata, TypeDefinition};
+
+/// How much data to generate and which values to draw from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyntheticConfig {
+    /// Seed for the random generator; the same seed and schema give the same data
+    #[serde(default)]
+    pub seed: u64,
+    /// Instances of each entity and relation type not listed in `counts`
+    #[serde(default = "default_count")]
+    pub default_count: usize,
+    /// Instances by type label
+    #[serde(default)]
+    pub counts: BTreeMap<String, usize>,
+    /// Values by attribute type label
+    #[serde(default)]
+    pub values: BTreeMap<String, ValueRange>,
+}
+
+impl Default for SyntheticConfig {
+    fn default() -> Self {
+        SyntheticConfig {
+            seed: 0,
+            default_count: default_count(),
+            counts: BTreeMap::new(),
+            values: BTreeMap::new(),
+        }
+    }
+}
+
+fn default_count() -> usize {
+    10
+}
+
+/// Values drawn for one attribute type
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValueRange {
+    /// Lower bound of numbers, or the first year of dates and datetimes
+    #[serde(default)]
+    pub min: Option<f64>,
+    /// Upper bound of numbers, or the last year of dates and datetimes
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// Strings to pick from instead of generated ones
+    #[serde(default)]
+    pub choices: Vec<String>,
+}
+
+impl SyntheticConfig {
+    fn count(&self, label: &str) -> usize {
+        self.counts
+            .get(label)
+            .copied()
+            .unwrap_or(self.default_count)
+    }
+}
+
+/// Generate an `insert` query creating instances of every entity and relation type in
+/// `schema`.
+///
+/// Each instance owns values of every attribute type its type owns, inherited ones
+/// included, drawn from the configured range for that attribute: one value, or as many
+/// as the minimum of the `@card` on the `owns`. Values of `@key` and `@unique` attributes
+/// differ between instances where the value type allows it. Each relation instance links
+/// one player per role, or the minimum of the role's `@card`, picked from the generated
+/// instances of the types playing it; relations with no possible players are left out.
+/// `@abstract` types get no instances. Entities are inserted first, then relations in
+/// schema order, so relations can play roles in later ones.
+pub fn generate_synthetic_data(schema: &SchemaMetadata, config: &SyntheticConfig) -> String {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    // Variables of the instances generated so far, by type label
+    let mut instances: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    // Values given so far to `@key` and `@unique` attributes, by type and attribute label
+    let mut unique_values: HashMap<(&str, &str), HashSet<String>> = HashMap::new();
+    let mut statements = Vec::new();
+
+    for type_def in schema.entities().chain(schema.relations()) {
+        if type_def.is_abstract() {
+            continue;
+        }
+        for position in 0..config.count(&type_def.label) {
+            let var = format!("${}-{}", type_def.label, position);
+            let mut statement = format!("{} isa {}", var, type_def.label);
+            if type_def.kind == SchemaKind::Relation {
+                let players = players(schema, type_def, &instances, &mut rng);
+                if players.is_empty() {
+                    break;
+                }
+                write!(statement, ", links ({})", players.join(", ")).unwrap();
+            }
+            for attribute in owned_attributes(schema, type_def) {
+                let range = config.values.get(&attribute.label);
+                let count = required(schema.owns_cardinality(type_def, &attribute.label));
+                let unique = schema.is_unique(type_def, &attribute.label);
+                for _ in 0..count {
+                    let mut literal = value(schema, attribute, range, &mut rng);
+                    if unique {
+                        let seen = unique_values
+                            .entry((&type_def.label, &attribute.label))
+                            .or_default();
+                        for _ in 0..UNIQUE_ATTEMPTS {
+                            if !seen.contains(&literal) {
+                                break;
+                            }
+                            literal = value(schema, attribute, range, &mut rng);
+                        }
+                        seen.insert(literal.clone());
+                    }
+                    write!(statement, ", has {} {}", attribute.label, literal).unwrap();
+                }
+            }
+            statements.push(statement);
+            instances.entry(&type_def.label).or_default().push(var);
+        }
+    }
+
+    let mut out = String::new();
+    if !statements.is_empty() {
+        writeln!(out, "insert").unwrap();
+        for statement in statements {
+            writeln!(out, "    {};", statement).unwrap();
+        }
+    }
+    out
+}
+
+/// Draws of a fresh value for a `@key` or `@unique` attribute before accepting a repeat,
+/// e.g. for a boolean key
+const UNIQUE_ATTEMPTS: usize = 100;
+
+/// How many values or players to generate: the minimum of `cardinality`, and at least
+/// one where it allows
+fn required(cardinality: Cardinality) -> usize {
+    let count = cardinality.min.max(1);
+    cardinality.max.map_or(count, |max| count.min(max)) as usize
+}
+
+/// `role: $var` for random players of each role of `relation`
+fn players(
+    schema: &SchemaMetadata,
+    relation: &TypeDefinition,
+    instances: &BTreeMap<&str, Vec<String>>,
+    rng: &mut StdRng,
+) -> Vec<String> {
+    let lineage = schema.lineage(relation);
+    let mut players = Vec::new();
+    let mut roles: Vec<&str> = Vec::new();
+    for ancestor in &lineage {
+        for role in &ancestor.relates {
+            if roles.contains(&role.as_str()) {
+                continue;
+            }
+            roles.push(role);
+            let scoped = format!("{}:{}", ancestor.label, role);
+            let candidates: Vec<&String> = instances
+                .iter()
+                .filter(|(label, _)| {
+                    schema.get(label).is_some_and(|player| {
+                        schema
+                            .lineage(player)
+                            .iter()
+                            .any(|player| player.plays.contains(&scoped))
+                    })
+                })
+                .flat_map(|(_, vars)| vars)
+                .collect();
+            let count = required(schema.relates_cardinality(relation, role));
+            for player in candidates.choose_multiple(rng, count) {
+                players.push(format!("{}: {}", role, player));
+            }
+        }
+    }
+    players
+}
+
+fn owned_attributes<'a>(
+    schema: &'a SchemaMetadata,
+    type_def: &'a TypeDefinition,
+) -> Vec<&'a TypeDefinition> {
+    let mut attributes: Vec<&TypeDefinition> = Vec::new();
+    for ancestor in schema.lineage(type_def) {
+        for label in &ancestor.owns {
+            if let Some(attribute) = schema.get(label) {
+                if !attributes.iter().any(|seen| seen.label == attribute.label) {
+                    attributes.push(attribute);
+                }
+            }
+        }
+    }
+    attributes
+}
+
+/// A random TypeQL literal of the attribute's value type
+fn value(
+    schema: &SchemaMetadata,
+    attribute: &TypeDefinition,
+    range: Option<&ValueRange>,
+    rng: &mut StdRng,
+) -> String {
+    let min = range.and_then(|range| range.min);
+    let max = range.and_then(|range| range.max);
+    let number = |rng: &mut StdRng, default_max: f64| {
+        let min = min.unwrap_or(0.0);
+        let max = max.unwrap_or(default_max).max(min);
+        rng.random_range(min..=max)
+    };
+    let date = |rng: &mut StdRng| {
+        let first = min.unwrap_or(2000.0) as i64;
+        let last = (max.unwrap_or(2030.0) as i64).max(first);
+        let start = days_from_civil(first, 1, 1);
+        let end = days_from_civil(last + 1, 1, 1);
+        let (year, month, day) = civil_from_days(rng.random_range(start..end));
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    };
+
+    let value_type = schema.value_type(attribute).unwrap_or("string");
+    match value_type {
+        "boolean" => rng.random_bool(0.5).to_string(),
+        "integer" => (number(rng, 1000.0).round() as i64).to_string(),
+        "double" => format!("{:?}", number(rng, 1000.0)),
+        "decimal" => format!("{:.2}dec", number(rng, 1000.0)),
+        "date" => date(rng),
+        "datetime" | "datetime-tz" => {
+            let time = format!(
+                "T{:02}:{:02}:{:02}",
+                rng.random_range(0..24),
+                rng.random_range(0..60),
+                rng.random_range(0..60)
+            );
+            let zone = if value_type == "datetime-tz" { "Z" } else { "" };
+            format!("{}{}{}", date(rng), time, zone)
+        }
+        "duration" => format!("P{}D", number(rng, 365.0).round() as i64),
+        _ => match range.map(|range| range.choices.as_slice()) {
+            Some(choices) if !choices.is_empty() => quote(choices.choose(rng).unwrap()),
+            _ => quote(&format!(
+                "{} {}",
+                attribute.label,
+                rng.random_range(0..10_000)
+            )),
+        },
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The proleptic Gregorian date `days` after 1970-01-01
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
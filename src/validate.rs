@@ -0,0 +1,350 @@
+use crate::body::ConstraintKind;
+use crate::diagnostic::Diagnostic;
+use crate::expression::Expr;
+use crate::extract_function_metadata::{FunctionMetadata, TypeKind};
+use crate::extract_schema_metadata::{SchemaMetadata, TypeDefinition};
+use crate::literals::literal_value_type;
+
+/// Report parameter, output and body types that the schema does not define
+pub fn validate_functions(
+    functions: &[FunctionMetadata],
+    schema: &SchemaMetadata,
+) -> Vec<Diagnostic> {
+    let known = known_labels(schema);
+    let known: Vec<&str> = known.iter().map(String::as_str).collect();
+    validate_functions_against_labels(functions, &known)
+}
+
+/// Every type and role label the schema defines, with roles both bare and scoped
+pub(crate) fn known_labels(schema: &SchemaMetadata) -> Vec<String> {
+    let mut known: Vec<String> = schema
+        .types
+        .iter()
+        .map(|type_def| type_def.label.clone())
+        .collect();
+    for (relation, role) in schema.roles() {
+        known.push(role.to_string());
+        known.push(format!("{}:{}", relation, role));
+    }
+    known
+}
+
+/// Same as [`validate_functions`] for callers that only have a list of type labels
+pub fn validate_functions_against_labels(
+    functions: &[FunctionMetadata],
+    known_labels: &[&str],
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for function in functions {
+        let mut check = |label: &str, what: String| {
+            if !known_labels.contains(&label) {
+                let mut message = format!("{} has unknown type `{}`", what, label);
+                if let Some(suggestion) = closest_label(label, known_labels) {
+                    message.push_str(&format!(", did you mean `{}`?", suggestion));
+                }
+                diagnostics
+                    .push(Diagnostic::error("unknown-type", message).in_function(&function.name));
+            }
+        };
+
+        for parameter in &function.parameters {
+            if let Some(label) = parameter.label() {
+                check(label, format!("parameter `${}`", parameter.name));
+            }
+        }
+        for output_type in function.output.types() {
+            if output_type.kind == TypeKind::Label {
+                check(&output_type.name, "output".to_string());
+            }
+        }
+        for label in &function.referenced_types {
+            check(label, "body".to_string());
+        }
+    }
+
+    diagnostics
+}
+
+/// Report parameters matched in the body in ways no instance of their type can satisfy.
+///
+/// Follows `sub` declarations: a `person` parameter may be matched as an `employee` or
+/// an `agent`, and may have the attributes and play the roles of its supertypes and of
+/// its subtypes, since instances of subtypes can be passed for it. Types and roles the
+/// schema does not define are left to [`validate_functions`].
+pub fn check_type_compatibility(
+    functions: &[FunctionMetadata],
+    schema: &SchemaMetadata,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for function in functions {
+        for parameter in &function.parameters {
+            let Some(label) = parameter.label() else {
+                continue;
+            };
+            if parameter.type_name.ends_with(']') || schema.get(label).is_none() {
+                continue;
+            }
+            let mut family: Vec<&TypeDefinition> = schema.ancestors(label);
+            family.extend(schema.get(label));
+            family.extend(schema.descendants(label));
+
+            let mut report = |message: String| {
+                diagnostics.push(
+                    Diagnostic::warning("incompatible-type", message)
+                        .in_function(&function.name)
+                        .with_span(parameter.span),
+                );
+            };
+            for constraint in &function.constraints {
+                match &constraint.kind {
+                    ConstraintKind::Isa {
+                        var,
+                        type_name,
+                        exact,
+                    } if *var == parameter.name && schema.get(type_name).is_some() => {
+                        let compatible = schema.is_subtype(type_name, label)
+                            || (!exact && schema.is_subtype(label, type_name));
+                        if !compatible {
+                            report(format!(
+                                "parameter `${}` is a `{}` but is matched as `{}`, which is \
+                                 not among its subtypes or supertypes",
+                                parameter.name, label, type_name
+                            ));
+                        }
+                    }
+                    ConstraintKind::Has {
+                        owner,
+                        attribute: Some(attribute),
+                        ..
+                    } if *owner == parameter.name && schema.get(attribute).is_some() => {
+                        let owned = family.iter().any(|type_def| {
+                            type_def
+                                .owns
+                                .iter()
+                                .any(|owned| schema.is_subtype(owned, attribute))
+                        });
+                        if !owned {
+                            report(format!(
+                                "parameter `${}` is a `{}`, which cannot own `{}`",
+                                parameter.name, label, attribute
+                            ));
+                        }
+                    }
+                    ConstraintKind::Links { role_players, .. } => {
+                        for role_player in role_players {
+                            let Some(role) = &role_player.role else {
+                                continue;
+                            };
+                            if role_player.player != parameter.name
+                                || !schema.roles().any(|(_, declared)| declared == role)
+                            {
+                                continue;
+                            }
+                            let plays = family.iter().any(|type_def| {
+                                type_def
+                                    .plays
+                                    .iter()
+                                    .any(|scoped| scoped.rsplit(':').next() == Some(role.as_str()))
+                            });
+                            if !plays {
+                                report(format!(
+                                    "parameter `${}` is a `{}`, which cannot play `{}`",
+                                    parameter.name, label, role
+                                ));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Report calls to functions that are neither in `functions` nor in `allowed`.
+///
+/// `allowed` names functions defined elsewhere, e.g. already deployed to the database.
+/// Calls to built-in functions such as `round` are not references, so never need it.
+pub fn check_function_references(
+    functions: &[FunctionMetadata],
+    allowed: &[&str],
+) -> Vec<Diagnostic> {
+    let defined: Vec<&str> = functions
+        .iter()
+        .map(|function| function.name.as_str())
+        .collect();
+    let mut diagnostics = Vec::new();
+
+    for function in functions {
+        for callee in &function.referenced_functions {
+            let callee = callee.as_str();
+            if defined.contains(&callee) || allowed.contains(&callee) {
+                continue;
+            }
+            let mut message = format!("call to undefined function `{}`", callee);
+            if let Some(suggestion) = closest_label(callee, &defined) {
+                message.push_str(&format!(", did you mean `{}`?", suggestion));
+            }
+            diagnostics
+                .push(Diagnostic::error("undefined-function", message).in_function(&function.name));
+        }
+    }
+
+    diagnostics
+}
+
+/// Report calls passing a different number of arguments than the called function has
+/// parameters.
+///
+/// Only calls to functions in `functions` are checked; calls to undefined functions are
+/// left to [`check_function_references`].
+pub fn check_call_arity(functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for function in functions {
+        for call in &function.call_sites {
+            let Some(callee) = functions.iter().find(|callee| callee.name == call.function) else {
+                continue;
+            };
+            let expected = callee.parameters.len();
+            if call.arguments.len() == expected {
+                continue;
+            }
+            let message = format!(
+                "`{}` takes {} argument{} but is called with {}",
+                callee.name,
+                expected,
+                if expected == 1 { "" } else { "s" },
+                call.arguments.len()
+            );
+            diagnostics.push(
+                Diagnostic::error("call-arity", message)
+                    .in_function(&function.name)
+                    .with_span(call.span),
+            );
+        }
+    }
+
+    diagnostics
+}
+
+/// Report arguments holding a value of a type their parameter does not accept.
+///
+/// An argument's value type is known when it is a literal, a value parameter of the
+/// calling function, or a variable assigned a literal or a value returned by a function in
+/// `functions`. An `integer` may be passed for a `double` or `decimal`. Only parameters of
+/// a value type are checked: whether a value or instance fits a schema type needs the
+/// schema.
+pub fn check_argument_types(functions: &[FunctionMetadata]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for function in functions {
+        for call in &function.call_sites {
+            let Some(callee) = functions.iter().find(|callee| callee.name == call.function) else {
+                continue;
+            };
+            for (argument, parameter) in call.arguments.iter().zip(&callee.parameters) {
+                if parameter.kind == TypeKind::Label {
+                    continue;
+                }
+                let Some(value_type) = argument_value_type(function, functions, argument) else {
+                    continue;
+                };
+                let expected = parameter.type_name.trim_end_matches('?');
+                let widened = value_type == "integer" && ["double", "decimal"].contains(&expected);
+                if expected.ends_with(']') || value_type == expected || widened {
+                    continue;
+                }
+                diagnostics.push(
+                    Diagnostic::warning(
+                        "argument-type",
+                        format!(
+                            "argument `{}` is of type `{}` but parameter `${}` of `{}` is `{}`",
+                            argument, value_type, parameter.name, callee.name, parameter.type_name
+                        ),
+                    )
+                    .in_function(&function.name)
+                    .with_span(call.span),
+                );
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// The value type of `argument` in a call made by `function`, when it is known
+fn argument_value_type<'a>(
+    function: &'a FunctionMetadata,
+    functions: &'a [FunctionMetadata],
+    argument: &Expr,
+) -> Option<&'a str> {
+    let var = match argument {
+        Expr::Literal { value } => return literal_value_type(value),
+        Expr::Variable { name } => name,
+        _ => return None,
+    };
+    if let Some(parameter) = function
+        .parameters
+        .iter()
+        .find(|parameter| parameter.name == *var)
+    {
+        let type_name = parameter.type_name.trim_end_matches('?');
+        return (parameter.kind == TypeKind::Builtin && !type_name.ends_with(']'))
+            .then_some(type_name);
+    }
+    function
+        .constraints
+        .iter()
+        .find_map(|constraint| match &constraint.kind {
+            ConstraintKind::Let { vars, tree, .. } | ConstraintKind::LetIn { vars, tree, .. } => {
+                let position = vars.iter().position(|assigned| assigned == var)?;
+                match tree {
+                    Expr::Literal { value } if vars.len() == 1 => literal_value_type(value),
+                    Expr::Call {
+                        function: callee,
+                        builtin: false,
+                        ..
+                    } => {
+                        let callee = functions.iter().find(|other| other.name == *callee)?;
+                        let output = callee.output.types().get(position)?;
+                        (output.kind == TypeKind::Builtin && !output.list)
+                            .then_some(output.name.as_str())
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+}
+
+/// The known label within a small edit distance of `label`, used to suggest typo fixes
+fn closest_label<'a>(label: &str, known_labels: &[&'a str]) -> Option<&'a str> {
+    let max_distance = (label.len() / 3).max(1);
+    known_labels
+        .iter()
+        .map(|known| (edit_distance(label, known), *known))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| known)
+}
+
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
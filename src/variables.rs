@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+
+use crate::body::ConstraintKind;
+use crate::extract_function_metadata::{FunctionMetadata, ReturnExpr};
+
+/// How a variable is used within a function
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VariableRole {
+    /// Declared in the signature
+    Parameter,
+    /// Bound by a pattern such as `isa`, `has`, `iid`, `is` or `let ... in`
+    Matched,
+    /// Plays a role in a relation
+    RolePlayer,
+    /// Assigned by `let $x = ...`
+    Assigned,
+    /// Appears in the `return` statement
+    Returned,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VariableInfo {
+    /// Name without the `$` prefix
+    pub name: String,
+    /// Distinct roles, in the order listed by [`VariableRole`]
+    pub roles: Vec<VariableRole>,
+    /// Number of body constraints plus the return statement that mention the variable;
+    /// the parameter declaration itself is not counted
+    pub usage_count: usize,
+}
+
+impl VariableInfo {
+    pub fn has_role(&self, role: VariableRole) -> bool {
+        self.roles.contains(&role)
+    }
+
+    /// Whether anything gives the variable a value: a parameter, a pattern or a `let`
+    pub fn is_bound(&self) -> bool {
+        self.roles
+            .iter()
+            .any(|role| *role != VariableRole::Returned)
+    }
+}
+
+impl FunctionMetadata {
+    /// Every named variable in the function, parameters first, then in order of appearance
+    pub fn variables(&self) -> Vec<VariableInfo> {
+        let mut inventory = Inventory::default();
+
+        for parameter in &self.parameters {
+            inventory.role(&parameter.name, VariableRole::Parameter);
+        }
+
+        for constraint in &self.constraints {
+            for var in &constraint.variables {
+                inventory.used(var);
+            }
+            match &constraint.kind {
+                ConstraintKind::Isa { var, .. }
+                | ConstraintKind::Iid { var, .. }
+                | ConstraintKind::Has { owner: var, .. } => {
+                    inventory.role(var, VariableRole::Matched)
+                }
+                ConstraintKind::Links {
+                    relation,
+                    role_players,
+                } => {
+                    inventory.role(relation, VariableRole::Matched);
+                    for role_player in role_players {
+                        inventory.role(&role_player.player, VariableRole::RolePlayer);
+                    }
+                }
+                ConstraintKind::Let { vars, .. } => {
+                    for var in vars {
+                        inventory.role(var, VariableRole::Assigned);
+                    }
+                }
+                ConstraintKind::LetIn { vars, .. } => {
+                    for var in vars {
+                        inventory.role(var, VariableRole::Matched);
+                    }
+                }
+                ConstraintKind::Is { lhs, rhs } => {
+                    inventory.role(lhs, VariableRole::Matched);
+                    inventory.role(rhs, VariableRole::Matched);
+                }
+                ConstraintKind::Comparison { .. } | ConstraintKind::Other { .. } => {}
+            }
+            // `has $value` binds the attribute variable too
+            if let ConstraintKind::Has { value, .. } = &constraint.kind {
+                if let Some(var) = value.strip_prefix('$') {
+                    inventory.role(var, VariableRole::Matched);
+                }
+            }
+        }
+
+        for var in self.return_expression.variables() {
+            inventory.used(var);
+            inventory.role(var, VariableRole::Returned);
+        }
+
+        inventory.variables
+    }
+}
+
+impl ReturnExpr {
+    /// Variables mentioned by the return statement, without `$`
+    pub fn variables(&self) -> Vec<&str> {
+        match self {
+            ReturnExpr::Single { vars, .. } | ReturnExpr::Stream { vars } => {
+                vars.iter().map(String::as_str).collect()
+            }
+            ReturnExpr::Reduce { reducers } => reducers
+                .iter()
+                .filter_map(|reduction| reduction.var.as_deref())
+                .collect(),
+            ReturnExpr::Check => Vec::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Inventory {
+    variables: Vec<VariableInfo>,
+}
+
+impl Inventory {
+    fn entry(&mut self, name: &str) -> Option<&mut VariableInfo> {
+        // Anonymous variables are not tracked
+        if name == "_" {
+            return None;
+        }
+        let position = match self.variables.iter().position(|info| info.name == name) {
+            Some(position) => position,
+            None => {
+                self.variables.push(VariableInfo {
+                    name: name.to_string(),
+                    roles: Vec::new(),
+                    usage_count: 0,
+                });
+                self.variables.len() - 1
+            }
+        };
+        Some(&mut self.variables[position])
+    }
+
+    fn role(&mut self, name: &str, role: VariableRole) {
+        if let Some(info) = self.entry(name) {
+            if !info.roles.contains(&role) {
+                info.roles.push(role);
+                info.roles.sort();
+            }
+        }
+    }
+
+    fn used(&mut self, name: &str) {
+        if let Some(info) = self.entry(name) {
+            info.usage_count += 1;
+        }
+    }
+}
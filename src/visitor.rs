@@ -0,0 +1,129 @@
+//! Walking the extracted function model with callbacks, for analyses that only care about
+//! some of its parts.
+//!
+//! ```
+//! use typedb_examples::{walk, FunctionMetadata, FunctionVisitor, Parameter};
+//!
+//! #[derive(Default)]
+//! struct LabelParameters(Vec<String>);
+//!
+//! impl<'a> FunctionVisitor<'a> for LabelParameters {
+//!     fn visit_parameter(&mut self, function: &'a FunctionMetadata, parameter: &'a Parameter) {
+//!         if let Some(label) = parameter.label() {
+//!             self.0.push(format!("{}: {}", function.name, label));
+//!         }
+//!     }
+//! }
+//!
+//! let function = FunctionMetadata::builder("get_income")
+//!     .parameter("taxpayer", "person")
+//!     .single_output(["double"])
+//!     .return_first(["income"])
+//!     .build()
+//!     .unwrap();
+//! let mut visitor = LabelParameters::default();
+//! walk(&mut visitor, &function);
+//! assert_eq!(visitor.0, ["get_income: person"]);
+//! ```
+
+use crate::body::{BodyPattern, Constraint, ConstraintKind};
+use crate::expression::Expr;
+use crate::extract_function_metadata::{FunctionMetadata, Parameter, ReturnExpr};
+
+/// Callbacks for the parts of a function, called by [`walk`] in source order.
+///
+/// Every callback does nothing by default, so a visitor only implements those it needs.
+/// Each gets the function being walked, for reporting against it. The `'a` lifetime lets
+/// a visitor keep references into the metadata it visits.
+pub trait FunctionVisitor<'a> {
+    /// Called first for each function
+    fn visit_function(&mut self, _function: &'a FunctionMetadata) {}
+
+    fn visit_parameter(&mut self, _function: &'a FunctionMetadata, _parameter: &'a Parameter) {}
+
+    /// Called for every body pattern, outer patterns before those nested in them
+    fn visit_pattern(&mut self, _function: &'a FunctionMetadata, _pattern: &'a BodyPattern) {}
+
+    /// Called for every constraint, after the pattern it is part of
+    fn visit_constraint(&mut self, _function: &'a FunctionMetadata, _constraint: &'a Constraint) {}
+
+    /// Called for every node of a `let` expression, after its constraint, parents before
+    /// children
+    fn visit_expression(&mut self, _function: &'a FunctionMetadata, _expression: &'a Expr) {}
+
+    /// Called for each function the body refers to, as in `referenced_functions`
+    fn visit_call(&mut self, _function: &'a FunctionMetadata, _callee: &'a str) {}
+
+    /// Called last, with the return statement
+    fn visit_return(&mut self, _function: &'a FunctionMetadata, _return: &'a ReturnExpr) {}
+}
+
+/// Call `visitor` for `function` and each of its parts.
+///
+/// Constraints are reached through the nested `patterns`; metadata saved before patterns
+/// were recorded has none, so its flat `constraints` are visited instead.
+pub fn walk<'a>(visitor: &mut impl FunctionVisitor<'a>, function: &'a FunctionMetadata) {
+    visitor.visit_function(function);
+    for parameter in &function.parameters {
+        visitor.visit_parameter(function, parameter);
+    }
+    if function.patterns.is_empty() {
+        for constraint in &function.constraints {
+            walk_constraint(visitor, function, constraint);
+        }
+    } else {
+        for pattern in &function.patterns {
+            walk_pattern(visitor, function, pattern);
+        }
+    }
+    for callee in &function.referenced_functions {
+        visitor.visit_call(function, callee);
+    }
+    visitor.visit_return(function, &function.return_expression);
+}
+
+/// [`walk`] each function in turn
+pub fn walk_all<'a>(visitor: &mut impl FunctionVisitor<'a>, functions: &'a [FunctionMetadata]) {
+    for function in functions {
+        walk(visitor, function);
+    }
+}
+
+fn walk_pattern<'a>(
+    visitor: &mut impl FunctionVisitor<'a>,
+    function: &'a FunctionMetadata,
+    pattern: &'a BodyPattern,
+) {
+    visitor.visit_pattern(function, pattern);
+    match pattern {
+        BodyPattern::Statement { constraints } => {
+            for constraint in constraints {
+                walk_constraint(visitor, function, constraint);
+            }
+        }
+        BodyPattern::Conjunction { patterns }
+        | BodyPattern::Negation { patterns }
+        | BodyPattern::Optional { patterns } => {
+            for pattern in patterns {
+                walk_pattern(visitor, function, pattern);
+            }
+        }
+        BodyPattern::Disjunction { branches } => {
+            for pattern in branches.iter().flatten() {
+                walk_pattern(visitor, function, pattern);
+            }
+        }
+    }
+}
+
+fn walk_constraint<'a>(
+    visitor: &mut impl FunctionVisitor<'a>,
+    function: &'a FunctionMetadata,
+    constraint: &'a Constraint,
+) {
+    visitor.visit_constraint(function, constraint);
+    if let ConstraintKind::Let { tree, .. } | ConstraintKind::LetIn { tree, .. } = &constraint.kind
+    {
+        tree.visit(&mut |expression| visitor.visit_expression(function, expression));
+    }
+}
@@ -0,0 +1,36 @@
+//! JavaScript bindings for browser tooling (requires the `wasm-bindgen` feature).
+//!
+//! Build with `wasm-pack build --target web --features wasm-bindgen`, then from
+//! JavaScript:
+//!
+//! ```js
+//! import init, { extractFunctionMetadata } from "./pkg/typedb_examples.js";
+//!
+//! await init();
+//! const metadata = JSON.parse(extractFunctionMetadata(text));
+//! ```
+//!
+//! Results are returned as JSON text in the same shape the CLI writes.
+
+use wasm_bindgen::prelude::*;
+
+use crate::extract_function_metadata::extract_function_metadata;
+use crate::lenient::extract_functions_from_source_lenient;
+
+/// [`extract_function_metadata`] as JSON; a parse error is thrown with the offending line
+#[wasm_bindgen(js_name = extractFunctionMetadata)]
+pub fn extract_function_metadata_json(function_text: &str) -> Result<String, JsError> {
+    let metadata = extract_function_metadata(function_text)
+        .map_err(|error| JsError::new(&error.to_string()))?;
+    Ok(serde_json::to_string(&metadata)?)
+}
+
+/// Every function in `source` as a JSON array of lenient extractions, for showing
+/// metadata while the text is being typed: functions that do not parse yet still give
+/// their name, raw signature and errors with line and column.
+#[wasm_bindgen(js_name = extractFunctionsLenient)]
+pub fn extract_functions_lenient_json(source: &str) -> Result<String, JsError> {
+    Ok(serde_json::to_string(
+        &extract_functions_from_source_lenient(source),
+    )?)
+}
@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::diff::{diff_functions, FunctionDiff};
+use crate::extract_function_metadata::FunctionMetadata;
+use crate::ingest::{expand_paths, extract_from_file};
+
+/// What a [`Watcher`] found when a watched file changed
+#[derive(Debug)]
+pub enum WatchEvent {
+    /// The file was re-extracted and some of its functions differ
+    Changed(FileChange),
+    /// The file could not be read or parsed; its previous functions are kept
+    Failed { path: PathBuf, error: String },
+}
+
+/// The functions of one file that changed since it was last extracted
+#[derive(Debug)]
+pub struct FileChange {
+    pub path: PathBuf,
+    /// Functions that are new or whose definition changed, in source order
+    pub updated: Vec<FunctionMetadata>,
+    /// How each previously known function in `updated` differs from its old version
+    pub diffs: Vec<FunctionDiff>,
+    /// Names of functions no longer in the file
+    pub removed: Vec<String>,
+}
+
+/// Polls TypeQL files for modifications and re-extracts only the files that changed.
+///
+/// Files are found as by [`expand_paths`] on every poll, so files created or deleted
+/// under a watched directory or glob are picked up. The first poll reports every
+/// function as updated.
+#[derive(Debug)]
+pub struct Watcher {
+    patterns: Vec<String>,
+    files: BTreeMap<PathBuf, WatchedFile>,
+}
+
+#[derive(Debug)]
+struct WatchedFile {
+    modified: Option<SystemTime>,
+    functions: Vec<FunctionMetadata>,
+}
+
+impl Watcher {
+    pub fn new(patterns: &[&str]) -> Self {
+        Watcher {
+            patterns: patterns.iter().map(|pattern| pattern.to_string()).collect(),
+            files: BTreeMap::new(),
+        }
+    }
+
+    /// Every function currently known, by file path and then source order
+    pub fn functions(&self) -> impl Iterator<Item = &FunctionMetadata> {
+        self.files.values().flat_map(|file| &file.functions)
+    }
+
+    /// Check every watched file once, returning an event per file that changed
+    pub fn poll(&mut self) -> Vec<WatchEvent> {
+        let mut events = Vec::new();
+        let paths = self.current_paths();
+
+        let deleted: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter(|path| !paths.contains(path))
+            .cloned()
+            .collect();
+        for path in deleted {
+            let file = self.files.remove(&path).unwrap();
+            if file.functions.is_empty() {
+                continue;
+            }
+            events.push(WatchEvent::Changed(FileChange {
+                path,
+                updated: Vec::new(),
+                diffs: Vec::new(),
+                removed: file
+                    .functions
+                    .into_iter()
+                    .map(|function| function.name)
+                    .collect(),
+            }));
+        }
+
+        for path in paths {
+            let modified = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            let previous = self.files.get(&path);
+            if previous.is_some_and(|file| file.modified == modified) {
+                continue;
+            }
+
+            let functions = match extract_from_file(&path) {
+                Ok(functions) => functions,
+                Err(error) => {
+                    // Keep the old functions, but retry once the file is saved again
+                    let file = self.files.entry(path.clone()).or_insert(WatchedFile {
+                        modified,
+                        functions: Vec::new(),
+                    });
+                    file.modified = modified;
+                    events.push(WatchEvent::Failed {
+                        path,
+                        error: format!("{:#}", error),
+                    });
+                    continue;
+                }
+            };
+
+            let old = previous.map_or(&[][..], |file| &file.functions);
+            let change = compare(path.clone(), old, &functions);
+            self.files.insert(
+                path,
+                WatchedFile {
+                    modified,
+                    functions,
+                },
+            );
+            if let Some(change) = change {
+                events.push(WatchEvent::Changed(change));
+            }
+        }
+
+        events
+    }
+
+    /// Watched files, or none for a pattern that currently matches nothing
+    fn current_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self
+            .patterns
+            .iter()
+            .filter_map(|pattern| expand_paths(&[pattern]).ok())
+            .flatten()
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+}
+
+/// The change from `old` to `new` for one file, `None` when no function differs.
+///
+/// A function counts as changed when its regenerated text differs, so edits to doc
+/// comments and variable names are reported even though [`diff_functions`] ignores them.
+fn compare(
+    path: PathBuf,
+    old: &[FunctionMetadata],
+    new: &[FunctionMetadata],
+) -> Option<FileChange> {
+    let mut updated = Vec::new();
+    let mut diffs = Vec::new();
+    for function in new {
+        match old.iter().find(|previous| previous.name == function.name) {
+            Some(previous) if previous.to_typeql() == function.to_typeql() => continue,
+            Some(previous) => diffs.push(diff_functions(previous, function)),
+            None => {}
+        }
+        updated.push(function.clone());
+    }
+    let removed: Vec<String> = old
+        .iter()
+        .filter(|previous| !new.iter().any(|function| function.name == previous.name))
+        .map(|previous| previous.name.clone())
+        .collect();
+
+    if updated.is_empty() && removed.is_empty() {
+        return None;
+    }
+    Some(FileChange {
+        path,
+        updated,
+        diffs,
+        removed,
+    })
+}
+
+/// Poll the files named by `patterns` every `interval`, calling `on_event` for each change
+/// until it returns [`ControlFlow::Break`].
+pub fn watch(
+    patterns: &[&str],
+    interval: Duration,
+    mut on_event: impl FnMut(&WatchEvent) -> ControlFlow<()>,
+) {
+    let mut watcher = Watcher::new(patterns);
+    loop {
+        for event in watcher.poll() {
+            if on_event(&event).is_break() {
+                return;
+            }
+        }
+        thread::sleep(interval);
+    }
+}